@@ -0,0 +1,302 @@
+// Table-driven checks of the pure time logic against hand-computed epoch
+// seconds, bypassing `Clock::get()` entirely.
+
+use crate::calendar::{eastern_to_utc, is_market_open, local_time};
+use crate::{get_eastern_time_info, get_nyse_market_state, MarketReason, MarketStatus};
+
+#[rustfmt::skip]
+const MARKET_STATE_CASES: &[(i64, MarketStatus, MarketReason)] = &[
+    // 2024-01-09 09:29:59 ET, Tuesday, one second before the open
+    (1_704_810_599, MarketStatus::AfterHours, MarketReason::PreMarket),
+    // 2024-01-09 09:30:00 ET, Tuesday open
+    (1_704_810_600, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2024-01-09 09:30:01 ET, first full second of the session
+    (1_704_810_601, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2024-01-09 15:59:00 ET, start of the last minute
+    (1_704_833_940, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2024-01-09 15:59:59 ET, last second of the session
+    (1_704_833_999, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2024-01-09 16:00:00 ET, close
+    (1_704_834_000, MarketStatus::AfterHours, MarketReason::AfterMarket),
+    // 2024-01-09 16:00:01 ET, just after the close
+    (1_704_834_001, MarketStatus::AfterHours, MarketReason::AfterMarket),
+    // 2024-01-09 00:00:00 ET, local midnight
+    (1_704_776_400, MarketStatus::Overnight, MarketReason::PreMarket),
+    // 2024-01-09 03:59:59 ET, last second before the pre-market
+    (1_704_790_799, MarketStatus::Overnight, MarketReason::PreMarket),
+    // 2024-01-09 04:00:00 ET, pre-market open
+    (1_704_790_800, MarketStatus::AfterHours, MarketReason::PreMarket),
+    // 2024-01-09 19:59:59 ET, last second of the post-market
+    (1_704_848_399, MarketStatus::AfterHours, MarketReason::AfterMarket),
+    // 2024-01-09 20:00:00 ET, post-market close
+    (1_704_848_400, MarketStatus::Overnight, MarketReason::AfterMarket),
+    // 2024-01-09 23:59:59 ET, last second of the day
+    (1_704_862_799, MarketStatus::Overnight, MarketReason::AfterMarket),
+    // 2024-01-12 15:59:59 ET, Friday before the weekend
+    (1_705_093_199, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2024-01-12 16:00:00 ET, Friday close
+    (1_705_093_200, MarketStatus::AfterHours, MarketReason::AfterMarket),
+    // 2024-01-13 00:00:00 ET, Saturday midnight
+    (1_705_122_000, MarketStatus::Weekend, MarketReason::Weekend),
+    // 2024-01-14 23:59:59 ET, Sunday last second
+    (1_705_294_799, MarketStatus::Weekend, MarketReason::Weekend),
+    // 2024-01-15 12:00:00 ET, MLK Day
+    (1_705_338_000, MarketStatus::Holiday, MarketReason::Holiday),
+    // 2024-01-16 09:30:00 ET, Tuesday after MLK Day
+    (1_705_415_400, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2024-03-08 09:30:00 EST, Friday before spring forward
+    (1_709_908_200, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2024-03-11 09:29:59 EDT, Monday after spring forward
+    (1_710_163_799, MarketStatus::AfterHours, MarketReason::PreMarket),
+    // 2024-03-11 09:30:00 EDT, first EDT open
+    (1_710_163_800, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2024-03-11 15:59:59 EDT, end of the first EDT session
+    (1_710_187_199, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2024-03-11 16:00:00 EDT, first EDT close
+    (1_710_187_200, MarketStatus::AfterHours, MarketReason::AfterMarket),
+    // 2024-03-29 12:00:00 ET, Good Friday
+    (1_711_728_000, MarketStatus::Holiday, MarketReason::Holiday),
+    // 2024-05-27 12:00:00 ET, Memorial Day
+    (1_716_825_600, MarketStatus::Holiday, MarketReason::Holiday),
+    // 2024-06-19 12:00:00 ET, Juneteenth
+    (1_718_812_800, MarketStatus::Holiday, MarketReason::Holiday),
+    // 2024-07-03 12:59:59 ET, July 3 half day before 1 PM
+    (1_720_025_999, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2024-07-03 13:00:00 ET, July 3 early close
+    (1_720_026_000, MarketStatus::AfterHours, MarketReason::EarlyClose),
+    // 2024-07-04 12:00:00 ET, Independence Day
+    (1_720_108_800, MarketStatus::Holiday, MarketReason::Holiday),
+    // 2024-09-02 12:00:00 ET, Labor Day
+    (1_725_292_800, MarketStatus::Holiday, MarketReason::Holiday),
+    // 2024-11-01 15:59:59 EDT, Friday before fall back
+    (1_730_491_199, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2024-11-04 09:29:59 EST, Monday after fall back
+    (1_730_730_599, MarketStatus::AfterHours, MarketReason::PreMarket),
+    // 2024-11-04 09:30:00 EST, first EST open
+    (1_730_730_600, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2024-11-28 12:00:00 ET, Thanksgiving
+    (1_732_813_200, MarketStatus::Holiday, MarketReason::Holiday),
+    // 2024-11-29 12:59:59 ET, day after Thanksgiving before 1 PM
+    (1_732_903_199, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2024-11-29 13:00:00 ET, day after Thanksgiving early close
+    (1_732_903_200, MarketStatus::AfterHours, MarketReason::EarlyClose),
+    // 2024-12-24 13:00:00 ET, Christmas Eve early close
+    (1_735_063_200, MarketStatus::AfterHours, MarketReason::EarlyClose),
+    // 2024-12-25 12:00:00 ET, Christmas
+    (1_735_146_000, MarketStatus::Holiday, MarketReason::Holiday),
+    // 2024-12-31 15:59:59 ET, New Year's Eve is a full day
+    (1_735_678_799, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2025-01-01 12:00:00 ET, New Year's Day
+    (1_735_750_800, MarketStatus::Holiday, MarketReason::Holiday),
+    // 2021-12-31 12:00:00 ET, open despite New Year's Day falling on Saturday
+    (1_640_970_000, MarketStatus::Open, MarketReason::RegularTradingHours),
+    // 2022-12-26 12:00:00 ET, Christmas observed on Monday
+    (1_672_074_000, MarketStatus::Holiday, MarketReason::Holiday),
+    // 2026-07-03 12:00:00 ET, Independence Day observed on Friday
+    (1_783_094_400, MarketStatus::Holiday, MarketReason::Holiday),
+];
+
+#[test]
+fn market_state_table() {
+    for &(timestamp, status, reason) in MARKET_STATE_CASES {
+        let state = get_nyse_market_state(timestamp, None, None).unwrap();
+        assert_eq!(
+            (state.status, state.reason),
+            (status, reason),
+            "timestamp {timestamp}"
+        );
+        assert_eq!(
+            state.is_open,
+            status == MarketStatus::Open,
+            "timestamp {timestamp}"
+        );
+    }
+}
+
+// (utc timestamp, local hour, minute, second, is_dst)
+#[rustfmt::skip]
+const DST_WEEKEND_CASES: &[(i64, u32, u32, u32, bool)] = &[
+    // 2024-03-10: 06:30 UTC is still 01:30 EST; 07:30 UTC is 03:30 EDT
+    (1_710_052_200, 1, 30, 0, false),
+    (1_710_055_800, 3, 30, 0, true),
+    // 2024-03-10: 01:59:59 EST is followed by 03:00:00 EDT
+    (1_710_053_999, 1, 59, 59, false),
+    (1_710_054_000, 3, 0, 0, true),
+    // 2024-11-03: 01:59:59 EDT is followed by a second 01:00:00, in EST
+    (1_730_613_599, 1, 59, 59, true),
+    (1_730_613_600, 1, 0, 0, false),
+];
+
+#[test]
+fn dst_weekend_table() {
+    for &(timestamp, hour, minute, second, is_dst) in DST_WEEKEND_CASES {
+        let local = get_eastern_time_info(timestamp).unwrap();
+        assert_eq!(
+            (local.hour, local.minute, local.second, local.is_dst),
+            (hour, minute, second, is_dst),
+            "timestamp {timestamp}"
+        );
+        assert_eq!(local.weekday, 0, "timestamp {timestamp}");
+    }
+}
+
+// (utc timestamp, ET day of month, ET weekday); each sits where the UTC
+// and ET dates differ, so a one-day slip would flip the weekend branch
+#[rustfmt::skip]
+const WEEKDAY_CASES: &[(i64, u32, u32)] = &[
+    // 2024-01-13 00:30 EST, Saturday; 05:30 UTC Saturday
+    (1_705_123_800, 13, 6),
+    // 2024-01-12 23:30 EST, Friday; already Saturday 04:30 UTC
+    (1_705_120_200, 12, 5),
+    // 2024-01-21 23:30 EST, Sunday; already Monday 04:30 UTC
+    (1_705_897_800, 21, 0),
+    // 2024-03-09 23:30 EST, Saturday before spring forward; Sunday in UTC
+    (1_710_045_000, 9, 6),
+    // 2024-03-11 00:30 EDT, first Monday after spring forward; 04:30 UTC
+    (1_710_131_400, 11, 1),
+    // 2024-06-15 00:30 EDT, Saturday; 04:30 UTC
+    (1_718_425_800, 15, 6),
+    // 2024-11-02 00:30 EDT, Saturday before fall back
+    (1_730_521_800, 2, 6),
+    // 2024-11-03 23:30 EST, Sunday after fall back; Monday in UTC
+    (1_730_694_600, 3, 0),
+];
+
+#[test]
+fn weekday_follows_the_eastern_date() {
+    for &(timestamp, day, weekday) in WEEKDAY_CASES {
+        let local = get_eastern_time_info(timestamp).unwrap();
+        assert_eq!(
+            (local.day, local.weekday),
+            (day, weekday),
+            "timestamp {timestamp}"
+        );
+
+        let state = get_nyse_market_state(timestamp, None, None).unwrap();
+        assert_eq!(
+            state.status == MarketStatus::Weekend,
+            weekday == 0 || weekday == 6,
+            "timestamp {timestamp}"
+        );
+    }
+}
+
+// (utc timestamp at 00:30 UTC, ET date, weekday, hour, is_dst, status):
+// the offset pulls each one back onto the previous ET day
+type UtcMidnightCase = (i64, (i32, u32, u32), u32, u32, bool, MarketStatus);
+
+#[rustfmt::skip]
+const UTC_MIDNIGHT_CASES: &[UtcMidnightCase] = &[
+    // 2023-12-31 19:30 EST, Sunday: back across the year
+    (1_704_069_000, (2023, 12, 31), 0, 19, false, MarketStatus::Weekend),
+    // 2024-01-12 19:30 EST, Friday, although UTC is already Saturday
+    (1_705_105_800, (2024, 1, 12), 5, 19, false, MarketStatus::AfterHours),
+    // 2024-01-14 19:30 EST, Sunday, although UTC is already Monday
+    (1_705_278_600, (2024, 1, 14), 0, 19, false, MarketStatus::Weekend),
+    // 2024-02-29 19:30 EST: back onto the leap day
+    (1_709_253_000, (2024, 2, 29), 4, 19, false, MarketStatus::AfterHours),
+    // 2024-03-09 19:30 EST, Saturday, hours before spring forward
+    (1_710_030_600, (2024, 3, 9), 6, 19, false, MarketStatus::Weekend),
+    // 2024-06-14 20:30 EDT, Friday night
+    (1_718_411_400, (2024, 6, 14), 5, 20, true, MarketStatus::Overnight),
+    // 2024-07-04 20:30 EDT, Independence Day, although UTC is the 5th
+    (1_720_139_400, (2024, 7, 4), 4, 20, true, MarketStatus::Holiday),
+    // 2024-11-03 19:30 EST, Sunday, hours after fall back
+    (1_730_680_200, (2024, 11, 3), 0, 19, false, MarketStatus::Weekend),
+];
+
+#[test]
+fn early_utc_morning_falls_on_the_previous_eastern_day() {
+    for &(timestamp, date, weekday, hour, is_dst, status) in UTC_MIDNIGHT_CASES {
+        let local = get_eastern_time_info(timestamp).unwrap();
+        assert_eq!(
+            ((local.year, local.month, local.day), local.weekday),
+            (date, weekday),
+            "timestamp {timestamp}"
+        );
+        assert_eq!(
+            (local.hour, local.minute, local.second, local.is_dst),
+            (hour, 30, 0, is_dst),
+            "timestamp {timestamp}"
+        );
+        assert_eq!(
+            get_nyse_market_state(timestamp, None, None).unwrap().status,
+            status,
+            "timestamp {timestamp}"
+        );
+    }
+
+    // Before 1970 the local timestamp itself is negative: one second before
+    // the epoch is 23:59:59 on Wednesday 1969-12-31, and at EST 00:30 UTC
+    // on 1970-01-01 is 19:30 the evening before
+    let local = local_time(-1, 0, false);
+    assert_eq!((local.year, local.month, local.day), (1969, 12, 31));
+    assert_eq!(
+        (local.hour, local.minute, local.second, local.weekday),
+        (23, 59, 59, 3)
+    );
+    let local = local_time(1800, -5 * 3600, false);
+    assert_eq!(
+        (local.day, local.hour, local.minute, local.weekday),
+        (31, 19, 30, 3)
+    );
+    let local = local_time(-86_400 * 366, 0, false);
+    assert_eq!(
+        (local.year, local.month, local.day, local.weekday),
+        (1968, 12, 31, 2)
+    );
+}
+
+#[test]
+fn eastern_wall_clock_resolves_dst_gaps() {
+    // 02:30 doesn't exist on 2024-03-10; it moves forward to 03:30 EDT
+    let skipped = eastern_to_utc(2024, 3, 10, 2 * 3600 + 30 * 60);
+    assert_eq!(skipped, 1_710_055_800);
+    let local = get_eastern_time_info(skipped).unwrap();
+    assert_eq!((local.hour, local.minute, local.is_dst), (3, 30, true));
+
+    // Either side of the gap maps back to itself
+    assert_eq!(eastern_to_utc(2024, 3, 10, 3600 + 30 * 60), 1_710_052_200);
+    assert_eq!(eastern_to_utc(2024, 3, 10, 3 * 3600), 1_710_054_000);
+
+    // 01:30 happens twice on 2024-11-03; the first (EDT) occurrence wins
+    assert_eq!(eastern_to_utc(2024, 11, 3, 3600 + 30 * 60), 1_730_611_800);
+    assert!(get_eastern_time_info(1_730_611_800).unwrap().is_dst);
+    assert_eq!(eastern_to_utc(2024, 11, 3, 2 * 3600), 1_730_617_200);
+
+    // Ordinary days round-trip through both offsets
+    for &(timestamp, _, _) in MARKET_STATE_CASES {
+        let local = get_eastern_time_info(timestamp).unwrap();
+        let seconds = local.hour * 3600 + local.minute * 60 + local.second;
+        assert_eq!(
+            eastern_to_utc(local.year, local.month, local.day, seconds),
+            timestamp,
+            "timestamp {timestamp}"
+        );
+    }
+}
+
+#[test]
+fn client_verdict_matches_hook() {
+    for &(timestamp, _, _) in MARKET_STATE_CASES {
+        let state = get_nyse_market_state(timestamp, None, None).unwrap();
+        assert_eq!(
+            is_market_open(timestamp),
+            state.is_open,
+            "timestamp {timestamp}"
+        );
+    }
+
+    // Every 15 minutes of 2024, plus one second either side
+    let start = 1_704_067_200;
+    for step in 0..366 * 96 {
+        for timestamp in [start + step * 900 - 1, start + step * 900] {
+            let state = get_nyse_market_state(timestamp, None, None).unwrap();
+            assert_eq!(
+                is_market_open(timestamp),
+                state.is_open,
+                "timestamp {timestamp}"
+            );
+        }
+    }
+}
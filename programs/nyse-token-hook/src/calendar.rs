@@ -0,0 +1,474 @@
+// Pure NYSE calendar and time-zone arithmetic shared by the transfer hook
+// and off-chain clients.
+//
+// Nothing in here touches Anchor, the Solana runtime or `std`: the file only
+// uses `core`, so a client crate can compile it on its own (e.g. through
+// `#[path]`) and get verdicts identical to the on-chain hook.
+
+/// Default NYSE regular session open, in minutes after midnight ET (9:30 AM)
+pub const MARKET_OPEN_MINUTES: u32 = 9 * 60 + 30;
+/// Default NYSE regular session close, in minutes after midnight ET (4:00 PM)
+pub const MARKET_CLOSE_MINUTES: u32 = 16 * 60;
+/// NYSE half-day close, in minutes after midnight ET (1:00 PM)
+pub const EARLY_CLOSE_MINUTES: u32 = 13 * 60;
+
+/// Earliest timestamp the hours check accepts (2020-01-01 00:00:00 UTC)
+pub const MIN_VALID_TIMESTAMP: i64 = 1_577_836_800;
+/// First timestamp past the accepted range (2100-01-01 00:00:00 UTC)
+pub const MAX_VALID_TIMESTAMP: i64 = 4_102_444_800;
+
+/// Wall-clock breakdown of a UTC timestamp in some exchange time zone
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LocalTime {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub weekday: u32, // 0 = Sunday, 1 = Monday, ..., 6 = Saturday
+    pub is_dst: bool,
+    pub utc_offset_seconds: i32,
+}
+
+/// Whether the default NYSE session (no per-mint config) is open at
+/// `timestamp`; matches `is_open` of the hook's verdict for unconfigured mints
+pub fn is_market_open(timestamp: i64) -> bool {
+    if !is_valid_timestamp(timestamp) {
+        return false;
+    }
+    let local = eastern_time(timestamp);
+    if local.weekday == 0
+        || local.weekday == 6
+        || is_nyse_holiday(local.year, local.month, local.day)
+    {
+        return false;
+    }
+    let (open_minutes, close_minutes, _) = session_bounds(
+        MARKET_OPEN_MINUTES,
+        MARKET_CLOSE_MINUTES,
+        local.year,
+        local.month,
+        local.day,
+    );
+    let current_seconds = local.hour * 3600 + local.minute * 60 + local.second;
+    (open_minutes * 60..close_minutes * 60).contains(&current_seconds)
+}
+
+/// Whether `timestamp` lies in the range the hours check accepts
+pub fn is_valid_timestamp(timestamp: i64) -> bool {
+    (MIN_VALID_TIMESTAMP..MAX_VALID_TIMESTAMP).contains(&timestamp)
+}
+
+/// Session `(open, close, is_early_close)` in local minutes for a trading
+/// day; half days close at 1:00 PM if the session would otherwise run later
+pub fn session_bounds(
+    open_minutes: u32,
+    regular_close_minutes: u32,
+    year: i32,
+    month: u32,
+    day: u32,
+) -> (u32, u32, bool) {
+    let early_close =
+        is_nyse_early_close(year, month, day) && regular_close_minutes > EARLY_CLOSE_MINUTES;
+    let close_minutes = if early_close {
+        EARLY_CLOSE_MINUTES
+    } else {
+        regular_close_minutes
+    };
+    (open_minutes, close_minutes, early_close)
+}
+
+/// US Eastern Time (EST/EDT) for a UTC timestamp
+pub fn eastern_time(utc_timestamp: i64) -> LocalTime {
+    local_time(
+        utc_timestamp,
+        -5 * 3600,
+        is_daylight_saving_time(utc_timestamp),
+    )
+}
+
+/// UTC instant of an Eastern wall-clock time, for placing local boundaries
+/// on the timeline. Every UTC instant has exactly one Eastern rendering, but
+/// not the reverse, so this resolves the DST gaps by convention:
+///
+/// - times skipped by the spring-forward jump move forward by the hour
+///   (02:30 on the second Sunday of March becomes 03:30 EDT)
+/// - times repeated after fall-back resolve to their first, EDT occurrence
+pub fn eastern_to_utc(year: i32, month: u32, day: u32, seconds_of_day: u32) -> i64 {
+    let local_timestamp = days_from_civil(year, month, day) * 86400 + seconds_of_day as i64;
+    // Reading the time as EDT picks the first occurrence of a repeated time;
+    // when EDT isn't in force at that instant, the time is EST or skipped,
+    // and reading it as EST lands an hour later
+    let as_edt = local_timestamp + 4 * 3600;
+    if is_daylight_saving_time(as_edt) {
+        as_edt
+    } else {
+        local_timestamp + 5 * 3600
+    }
+}
+
+/// Local time at a standard UTC offset, one hour ahead while `is_dst`
+pub fn local_time(utc_timestamp: i64, standard_offset_seconds: i32, is_dst: bool) -> LocalTime {
+    // Apply the local offset; saturating, so a clock at the i64 limits
+    // clamps instead of wrapping into a plausible date
+    let offset_seconds: i32 = standard_offset_seconds.saturating_add(if is_dst { 3600 } else { 0 });
+    let local_timestamp = utc_timestamp.saturating_add(offset_seconds as i64);
+
+    // Split into the local day and the seconds into it. Flooring division
+    // keeps the seconds in 0..86400 and moves an instant before a local
+    // midnight onto the day before, also ahead of 1970.
+    let days_since_epoch = local_timestamp.div_euclid(86400);
+    let seconds_in_day = local_timestamp.rem_euclid(86400);
+
+    // Calculate weekday (0 = Sunday, 1 = Monday, ..., 6 = Saturday)
+    // January 1, 1970 was a Thursday (4), so we adjust
+    let weekday = (days_since_epoch + 4).rem_euclid(7);
+
+    // Calculate time components
+    let hour = (seconds_in_day / 3600) as u32;
+    let minute = ((seconds_in_day % 3600) / 60) as u32;
+    let second = (seconds_in_day % 60) as u32;
+
+    // Calculate date components
+    let (year, month, day) = days_to_date(days_since_epoch);
+
+    LocalTime {
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        weekday: weekday as u32,
+        is_dst,
+        utc_offset_seconds: offset_seconds,
+    }
+}
+
+/// US Eastern DST check against the actual transition instants
+pub fn is_daylight_saving_time(utc_timestamp: i64) -> bool {
+    let days_since_epoch = utc_timestamp.div_euclid(86400);
+    let (year, _, _) = days_to_date(days_since_epoch);
+
+    if year >= 2007 {
+        // Post-2007 DST rules: 2:00 AM EST on the 2nd Sunday in March (07:00
+        // UTC) to 2:00 AM EDT on the 1st Sunday in November (06:00 UTC).
+        // Comparing UTC instants sidesteps the local-time gaps: the skipped
+        // spring hour is never produced, and each instant of the repeated
+        // fall hour maps to exactly one offset (EDT first, then EST).
+        let dst_start =
+            days_from_civil(year, 3, nth_weekday_of_month(year, 3, 0, 2)) * 86400 + 7 * 3600;
+        let dst_end =
+            days_from_civil(year, 11, nth_weekday_of_month(year, 11, 0, 1)) * 86400 + 6 * 3600;
+        (dst_start..dst_end).contains(&utc_timestamp)
+    } else {
+        // Pre-2007 DST rules (1987-2006): 2:00 AM EST on the 1st Sunday in
+        // April (07:00 UTC) to 2:00 AM EDT on the last Sunday in October
+        // (06:00 UTC)
+        let dst_start =
+            days_from_civil(year, 4, nth_weekday_of_month(year, 4, 0, 1)) * 86400 + 7 * 3600;
+        let dst_end =
+            days_from_civil(year, 10, last_weekday_of_month(year, 10, 0)) * 86400 + 6 * 3600;
+        (dst_start..dst_end).contains(&utc_timestamp)
+    }
+}
+
+/// EU summer time: last Sunday of March to last Sunday of October, switching
+/// at 01:00 UTC in every member timezone
+pub fn is_eu_summer_time(utc_timestamp: i64) -> bool {
+    let (year, _, _) = days_to_date(utc_timestamp.div_euclid(86400));
+    let summer_start = days_from_civil(year, 3, last_weekday_of_month(year, 3, 0)) * 86400 + 3600;
+    let summer_end = days_from_civil(year, 10, last_weekday_of_month(year, 10, 0)) * 86400 + 3600;
+    (summer_start..summer_end).contains(&utc_timestamp)
+}
+
+/// Exact proleptic Gregorian date for a day count since 1970-01-01 (Howard
+/// Hinnant's civil_from_days), including the century leap-year rules. Day
+/// counts beyond the `i32` years clamp to the first or last of them rather
+/// than wrapping.
+pub fn days_to_date(days_since_epoch: i64) -> (i32, u32, u32) {
+    let z = days_since_epoch.saturating_add(719468);
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = z - era * 146097; // [0, 146096]
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365; // [0, 399]
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100); // [0, 365], March-based
+    let mp = (5 * doy + 2) / 153; // [0, 11], March = 0
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (
+        year.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+        month,
+        day,
+    )
+}
+
+/// NYSE holiday check for fixed-date (actual and observed) and floating holidays
+pub fn is_nyse_holiday(year: i32, month: u32, day: u32) -> bool {
+    is_historical_closure(year, month, day)
+        || is_fixed_date_holiday(year, month, day)
+        || is_observed_fixed_date_holiday(year, month, day)
+        || match month {
+            1 => day == nth_weekday_of_month(year, 1, 1, 3), // MLK Day: 3rd Monday
+            2 => day == nth_weekday_of_month(year, 2, 1, 3), // Washington's Birthday: 3rd Monday
+            5 => day == last_weekday_of_month(year, 5, 1),   // Memorial Day: last Monday
+            9 => day == nth_weekday_of_month(year, 9, 1, 1), // Labor Day: 1st Monday
+            11 => day == thanksgiving(year),                 // Thanksgiving: 4th Thursday
+            3 | 4 => (month, day) == good_friday(year),
+            _ => false,
+        }
+}
+
+/// One-off NYSE closures no rule produces: national days of mourning and
+/// emergencies. Kept for historical accuracy when replaying past dates;
+/// future unscheduled closures belong in a mint's HolidayCalendar.
+pub const HISTORICAL_CLOSURES: [(i32, u32, u32); 11] = [
+    (1994, 4, 27), // President Nixon's funeral
+    (2001, 9, 11), // September 11 attacks, closed through the 14th
+    (2001, 9, 12),
+    (2001, 9, 13),
+    (2001, 9, 14),
+    (2004, 6, 11),  // President Reagan's funeral
+    (2007, 1, 2),   // President Ford's funeral
+    (2012, 10, 29), // Hurricane Sandy, two days
+    (2012, 10, 30),
+    (2018, 12, 5), // President George H. W. Bush's funeral
+    (2025, 1, 9),  // President Carter's funeral
+];
+
+/// Whether the date is one of the `HISTORICAL_CLOSURES`
+pub fn is_historical_closure(year: i32, month: u32, day: u32) -> bool {
+    HISTORICAL_CLOSURES.contains(&(year, month, day))
+}
+
+/// NASDAQ's one-off closures. NASDAQ closed on each of NYSE's, so this
+/// encodes no divergence; it is kept apart so that one can be added here.
+pub const NASDAQ_HISTORICAL_CLOSURES: [(i32, u32, u32); 11] = [
+    (1994, 4, 27), // President Nixon's funeral
+    (2001, 9, 11), // September 11 attacks, closed through the 14th
+    (2001, 9, 12),
+    (2001, 9, 13),
+    (2001, 9, 14),
+    (2004, 6, 11),  // President Reagan's funeral
+    (2007, 1, 2),   // President Ford's funeral
+    (2012, 10, 29), // Hurricane Sandy, two days
+    (2012, 10, 30),
+    (2018, 12, 5), // President George H. W. Bush's funeral
+    (2025, 1, 9),  // President Carter's funeral
+];
+
+/// NASDAQ holiday check, with its own table. No divergence from NYSE is
+/// encoded: the fixed-date holidays (Juneteenth from 2022, no Friday
+/// December 31st before a Saturday New Year's Day), the floating ones
+/// including Good Friday, and the one-off closures all match
+/// `is_nyse_holiday`. Unscheduled closures belong in a mint's
+/// HolidayCalendar.
+pub fn is_nasdaq_holiday(year: i32, month: u32, day: u32) -> bool {
+    NASDAQ_HISTORICAL_CLOSURES.contains(&(year, month, day))
+        || is_fixed_date_holiday(year, month, day)
+        || is_observed_fixed_date_holiday(year, month, day)
+        || match month {
+            1 => day == nth_weekday_of_month(year, 1, 1, 3), // MLK Day: 3rd Monday
+            2 => day == nth_weekday_of_month(year, 2, 1, 3), // Presidents Day: 3rd Monday
+            5 => day == last_weekday_of_month(year, 5, 1),   // Memorial Day: last Monday
+            9 => day == nth_weekday_of_month(year, 9, 1, 1), // Labor Day: 1st Monday
+            11 => day == thanksgiving(year),                 // Thanksgiving: 4th Thursday
+            3 | 4 => (month, day) == good_friday(year),
+            _ => false,
+        }
+}
+
+/// Days `next_holiday_after` looks ahead; every year has an observed
+/// Christmas, so a year and a day always reaches one
+pub const MAX_HOLIDAY_SCAN_DAYS: i64 = 366;
+
+/// First weekday NYSE holiday strictly after the Eastern date of
+/// `timestamp`, as `(year, month, day)`. Only closures that cost a trading
+/// day count: a Saturday Christmas reports the observed Friday, and a
+/// Saturday New Year's Day with no observance is skipped. Returns
+/// `(0, 0, 0)` if the bounded scan finds none, which the built-in rules
+/// never produce.
+pub fn next_holiday_after(timestamp: i64) -> (i32, u32, u32) {
+    let local = eastern_time(timestamp);
+    let today = days_from_civil(local.year, local.month, local.day);
+    (today + 1..=today + MAX_HOLIDAY_SCAN_DAYS)
+        .filter(|&days| (1..=5).contains(&day_of_week(days)))
+        .map(days_to_date)
+        .find(|&(year, month, day)| is_nyse_holiday(year, month, day))
+        .unwrap_or((0, 0, 0))
+}
+
+/// Actual calendar dates of the fixed-date holidays
+pub fn is_fixed_date_holiday(year: i32, month: u32, day: u32) -> bool {
+    match (month, day) {
+        (1, 1) => true,          // New Year's Day
+        (6, 19) => year >= 2022, // Juneteenth (NYSE closure since 2022)
+        (7, 4) => true,          // Independence Day
+        (12, 25) => true,        // Christmas Day
+        _ => false,
+    }
+}
+
+/// Weekday a fixed-date holiday is observed on when it lands on a weekend: a
+/// Saturday holiday closes the preceding Friday and a Sunday holiday the
+/// following Monday. NYSE does not observe a Saturday New Year's Day on the
+/// preceding December 31st, so that Friday stays a trading day. The weekend
+/// neighbour is found by day count rather than by adjusting the day of the
+/// month, so at the year boundary a Monday January 2nd looks up January 1st
+/// of its own year and a Friday December 31st the next year's.
+pub fn is_observed_fixed_date_holiday(year: i32, month: u32, day: u32) -> bool {
+    let days = days_from_civil(year, month, day);
+    match day_of_week(days) {
+        5 => {
+            let (year, month, day) = days_to_date(days + 1);
+            is_fixed_date_holiday(year, month, day) && (month, day) != (1, 1)
+        }
+        1 => {
+            let (year, month, day) = days_to_date(days - 1);
+            is_fixed_date_holiday(year, month, day)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the date is a fixed-date holiday's own date that the exchange
+/// observes on another day, or not at all (a Saturday New Year's Day)
+pub fn is_unobserved_actual_date(year: i32, month: u32, day: u32) -> bool {
+    is_fixed_date_holiday(year, month, day)
+        && observed_holiday(year, month, day) != Some((year, month, day))
+}
+
+/// Day of November Thanksgiving falls on, the 4th Thursday
+pub fn thanksgiving(year: i32) -> u32 {
+    nth_weekday_of_month(year, 11, 4, 4)
+}
+
+/// Date a fixed-date holiday closes the market: the holiday itself on a
+/// weekday, the Friday before a Saturday one and the Monday after a Sunday
+/// one. `None` for a Saturday New Year's Day, which NYSE doesn't observe.
+pub fn observed_holiday(year: i32, month: u32, day: u32) -> Option<(i32, u32, u32)> {
+    let days = days_from_civil(year, month, day);
+    match day_of_week(days) {
+        6 if (month, day) == (1, 1) => None,
+        6 => Some(days_to_date(days - 1)),
+        0 => Some(days_to_date(days + 1)),
+        _ => Some((year, month, day)),
+    }
+}
+
+/// NYSE half days closing at 1:00 PM ET, derived from the holiday dates: the
+/// day after Thanksgiving, and the session just before Independence Day and
+/// Christmas when the holiday is observed on its own date. A Monday holiday
+/// leaves the Friday before a full day, and a weekend one moves to a Friday
+/// or Monday without a half day.
+pub fn is_nyse_early_close(year: i32, month: u32, day: u32) -> bool {
+    let days = days_from_civil(year, month, day);
+    let weekday = day_of_week(days);
+    if weekday == 0 || weekday == 6 || is_nyse_holiday(year, month, day) {
+        return false;
+    }
+
+    let day_after_thanksgiving = days_from_civil(year, 11, thanksgiving(year)) + 1;
+    let eve_of = |holiday_month: u32, holiday_day: u32| {
+        let holiday = (year, holiday_month, holiday_day);
+        observed_holiday(year, holiday_month, holiday_day) == Some(holiday)
+            && days_from_civil(year, holiday_month, holiday_day) == days + 1
+    };
+    days == day_after_thanksgiving || eve_of(7, 4) || eve_of(12, 25)
+}
+
+/// Good Friday, two days before Easter Sunday
+pub fn good_friday(year: i32) -> (u32, u32) {
+    let (month, day) = easter_sunday(year);
+    let (_, month, day) = days_to_date(days_from_civil(year, month, day) - 2);
+    (month, day)
+}
+
+/// Easter Sunday (month, day) by the Anonymous Gregorian algorithm
+pub fn easter_sunday(year: i32) -> (u32, u32) {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    (month as u32, day as u32)
+}
+
+/// Number of trading days from January 1st through the given date (inclusive).
+///
+/// A date that is itself a weekend or holiday reports the count of the trading
+/// days before it, so the first trading day of a year is always `1`.
+pub fn trading_day_ordinal(year: i32, month: u32, day: u32) -> u32 {
+    let mut weekday = day_of_week(days_from_civil(year, 1, 1));
+    let mut ordinal = 0;
+
+    for m in 1..=month {
+        let last_day = if m == month {
+            day
+        } else {
+            days_in_month(year, m)
+        };
+        for d in 1..=last_day {
+            if weekday != 0 && weekday != 6 && !is_nyse_holiday(year, m, d) {
+                ordinal += 1;
+            }
+            weekday = (weekday + 1) % 7;
+        }
+    }
+
+    ordinal
+}
+
+/// Day of the month of the nth (1-based) given weekday (0 = Sunday)
+pub fn nth_weekday_of_month(year: i32, month: u32, weekday: u32, n: u32) -> u32 {
+    let first_weekday = day_of_week(days_from_civil(year, month, 1));
+    1 + (weekday + 7 - first_weekday) % 7 + 7 * (n - 1)
+}
+
+/// Day of the month of the last given weekday (0 = Sunday)
+pub fn last_weekday_of_month(year: i32, month: u32, weekday: u32) -> u32 {
+    let last_day = days_in_month(year, month);
+    let last_weekday = day_of_week(days_from_civil(year, month, last_day));
+    last_day - (last_weekday + 7 - weekday) % 7
+}
+
+/// 0 = Sunday, 1 = Monday, ..., 6 = Saturday (January 1, 1970 was a Thursday)
+pub fn day_of_week(days_since_epoch: i64) -> u32 {
+    (days_since_epoch.rem_euclid(7) as u32 + 4) % 7
+}
+
+pub fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's
+/// days_from_civil)
+pub fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let y = year as i64 - if month <= 2 { 1 } else { 0 };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
@@ -0,0 +1,34 @@
+// Checked duration math between timestamps, shared by every rule that
+// measures time: cooldowns, rolling windows, age checks and countdowns.
+//
+// Times are Unix seconds as the cluster clock reports them, which skips
+// leap seconds, so a duration never gains or loses one. The clock can also
+// read earlier than a time stored under it (validator skew, a stake-weighted
+// median moving back), and stored times can be anything a client wrote.
+// None of these helpers panics or wraps: a reading from before the stored
+// time counts as no time elapsed, and sums stop at the ends of `i64`.
+
+/// Seconds from `since` to `now`, or 0 when the clock reads before `since`
+pub fn elapsed(since: i64, now: i64) -> u64 {
+    if now <= since {
+        0
+    } else {
+        now.abs_diff(since)
+    }
+}
+
+/// Whether at least `duration` seconds have passed since `since`, with a
+/// clock reading before it counting as none
+pub fn has_elapsed(since: i64, now: i64, duration: u64) -> bool {
+    elapsed(since, now) >= duration
+}
+
+/// Seconds left from `now` until `until`, or 0 once it has passed
+pub fn remaining(now: i64, until: i64) -> u64 {
+    elapsed(now, until)
+}
+
+/// `timestamp` moved `seconds` later, stopping at `i64::MAX`
+pub fn after(timestamp: i64, seconds: u64) -> i64 {
+    timestamp.saturating_add(i64::try_from(seconds).unwrap_or(i64::MAX))
+}
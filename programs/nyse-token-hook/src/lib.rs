@@ -1,7 +1,43 @@
+// Anchor 0.31 `#[program]` expands to the deprecated `AccountInfo::realloc`
+#![allow(deprecated)]
+
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface;
-use spl_tlv_account_resolution::state::ExtraAccountMetaList;
+use anchor_lang::solana_program::program::{invoke_signed, set_return_data};
+use anchor_lang::solana_program::{system_instruction, sysvar};
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_hook::TransferHook as TransferHookExtension;
+use anchor_spl::token_interface::{self, get_mint_extension_data};
+use spl_tlv_account_resolution::{
+    account::ExtraAccountMeta, pubkey_data::PubkeyData, seeds::Seed, state::ExtraAccountMetaList,
+};
 use spl_transfer_hook_interface::instruction::{ExecuteInstruction, TransferHookInstruction};
+use spl_type_length_value::state::{TlvState, TlvStateBorrowed};
+
+#[cfg(test)]
+mod boundary_tests;
+pub mod calendar;
+pub mod interval;
+pub mod pda;
+pub mod state;
+#[cfg(feature = "testnet-calendar")]
+pub mod testnet_calendar;
+
+pub use calendar::{
+    next_holiday_after, trading_day_ordinal, MARKET_CLOSE_MINUTES, MARKET_OPEN_MINUTES,
+};
+pub use state::*;
+
+use calendar::*;
+use pda::*;
+
+// `msg!` for the detail lines only printed under `verbose_logging`, so
+// busy mints don't pay for the formatting and log space
+macro_rules! verbose_msg {
+    ($verbose:expr, $($arg:tt)+) => {
+        if $verbose {
+            msg!($($arg)+);
+        }
+    };
+}
 
 declare_id!("CUvtmRQZ6zikB7VijWzqS78orxrrkQhYkbhDL4PaPD6k");
 
@@ -15,13 +51,12 @@ pub mod nyse_token_hook {
     ) -> Result<()> {
         msg!("🏗️  Initializing NYSE Transfer Hook Extra Account Meta List");
 
-        // No extra accounts needed for NYSE hours checking
-        let account_metas = vec![];
+        let account_metas = extra_account_metas()?;
 
         // Get the account data
         let account_info = ctx.accounts.extra_account_meta_list.to_account_info();
 
-        // Initialize the extra account meta list with no extra accounts
+        // Register the extra accounts transfer_hook needs resolved
         ExtraAccountMetaList::init::<ExecuteInstruction>(
             &mut account_info.try_borrow_mut_data()?,
             &account_metas,
@@ -33,351 +68,13411 @@ pub mod nyse_token_hook {
         Ok(())
     }
 
-    /// Transfer hook execution - THIS RUNS ON EVERY TOKEN TRANSFER
-    pub fn transfer_hook(_ctx: Context<TransferHook>, amount: u64) -> Result<()> {
-        msg!("🚨 NYSE TRANSFER HOOK CALLED!");
-        msg!(
-            "🔍 NYSE Transfer Hook: Validating transfer of {} tokens",
-            amount
+    /// Re-runnable `initialize_extra_account_meta_list`: creates the list when
+    /// missing and otherwise only checks it, failing if it isn't exactly what
+    /// this program would register
+    pub fn ensure_extra_account_meta_list(ctx: Context<EnsureExtraAccountMetaList>) -> Result<()> {
+        let expected = extra_account_meta_list_data()?;
+        let account_info = ctx.accounts.extra_account_meta_list.to_account_info();
+
+        if account_info.owner == &crate::ID {
+            require!(
+                account_info.try_borrow_data()?[..] == expected[..],
+                NyseError::ExtraAccountMetaListMismatch
+            );
+            msg!("✅ Extra account meta list already initialized");
+            return Ok(());
+        }
+
+        let rent_due = Rent::get()?
+            .minimum_balance(expected.len())
+            .saturating_sub(account_info.lamports());
+        if rent_due > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: account_info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        let mint = ctx.accounts.mint.key();
+        allocate_and_assign(
+            &account_info,
+            &ctx.accounts.system_program.to_account_info(),
+            &[
+                EXTRA_ACCOUNT_METAS_SEED,
+                mint.as_ref(),
+                &[ctx.bumps.extra_account_meta_list],
+            ],
+            expected.len(),
+        )?;
+        account_info
+            .try_borrow_mut_data()?
+            .copy_from_slice(&expected);
+
+        msg!("✅ Extra account meta list initialized");
+        Ok(())
+    }
+
+    /// Re-register the extra accounts for a mint whose list predates newer
+    /// per-mint PDAs, resizing the list account (the payer covers any extra
+    /// rent). Only the mint's transfer-hook authority may do this.
+    pub fn update_extra_account_meta_list(ctx: Context<UpdateExtraAccountMetaList>) -> Result<()> {
+        require!(
+            transfer_hook_authority(&ctx.accounts.mint.to_account_info())?
+                == Some(ctx.accounts.authority.key()),
+            NyseError::Unauthorized
         );
 
-        // Get current time
-        let clock = Clock::get()?;
-        let current_timestamp = clock.unix_timestamp;
+        let account_metas = extra_account_metas()?;
+        let account_info = ctx.accounts.extra_account_meta_list.to_account_info();
+        let size = ExtraAccountMetaList::size_of(account_metas.len())?;
 
-        // Check NYSE market state
-        let market_result = get_nyse_market_state(current_timestamp)?;
+        let rent_due = Rent::get()?
+            .minimum_balance(size)
+            .saturating_sub(account_info.lamports());
+        if rent_due > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: account_info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        account_info.realloc(size, false)?;
+        ExtraAccountMetaList::update::<ExecuteInstruction>(
+            &mut account_info.try_borrow_mut_data()?,
+            &account_metas,
+        )?;
 
         msg!(
-            "📅 Current time: {} UTC (timestamp: {})",
-            format_timestamp(current_timestamp),
-            current_timestamp
+            "🔄 Extra account meta list updated: {} accounts",
+            account_metas.len()
         );
-        msg!("📊 Market state: {}", market_result.status);
+        Ok(())
+    }
 
-        // Block transfers based on market state
-        match market_result.is_open {
-            true => {
-                msg!("✅ NYSE OPEN: Transfer allowed during market hours");
-                Ok(())
-            }
-            false => {
-                msg!(
-                    "🚫 NYSE CLOSED: {} - Transfer BLOCKED",
-                    market_result.reason
-                );
-                msg!("💥 RETURNING ERROR TO BLOCK TRANSFER");
-                match market_result.status.as_str() {
-                    "WEEKEND" => {
-                        msg!("🛑 WEEKEND ERROR");
-                        Err(NyseError::MarketClosedWeekend.into())
-                    }
-                    "HOLIDAY" => {
-                        msg!("🛑 HOLIDAY ERROR");
-                        Err(NyseError::MarketClosedHoliday.into())
-                    }
-                    "AFTER_HOURS" => {
-                        msg!("🛑 AFTER HOURS ERROR");
-                        Err(NyseError::MarketClosedAfterHours.into())
-                    }
-                    _ => {
-                        msg!("🛑 DEFAULT AFTER HOURS ERROR");
-                        Err(NyseError::MarketClosedAfterHours.into())
-                    }
-                }
+    /// Close a retired mint's extra account meta list and send its rent to
+    /// `recipient`. Only the transfer-hook authority may do this, and only
+    /// once the mint no longer points its transfer hook at this program.
+    pub fn close_extra_account_meta_list(ctx: Context<CloseExtraAccountMetaList>) -> Result<()> {
+        let mint = ctx.accounts.mint.to_account_info();
+        require!(
+            transfer_hook_authority(&mint)? == Some(ctx.accounts.authority.key()),
+            NyseError::Unauthorized
+        );
+        require!(!hook_attached(&mint)?, NyseError::HookStillActive);
+
+        close_account(
+            &ctx.accounts.extra_account_meta_list,
+            &ctx.accounts.recipient,
+        )?;
+
+        msg!("🧹 Extra account meta list closed");
+        Ok(())
+    }
+
+    /// Transfer hook execution - THIS RUNS ON EVERY TOKEN TRANSFER
+    pub fn transfer_hook(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
+        run_transfer_checks(ctx.accounts, amount, true).map(|_| ())
+    }
+
+    /// Dry run of `transfer_hook` for front-ends: takes the same accounts
+    /// and runs the same checks (denylist, allowlist, limits, volume cap,
+    /// cooldown, market hours) but moves no tokens and records nothing.
+    /// Always succeeds, writing the verdict as `TransferVerdict` return data
+    /// so a UI can show the exact error before the user signs.
+    pub fn simulate_transfer(ctx: Context<TransferHook>, amount: u64) -> Result<()> {
+        let verdict = transfer_verdict(run_transfer_checks(ctx.accounts, amount, false));
+        msg!(
+            "🧪 SIMULATED: Transfer of {} would be {}",
+            amount,
+            if verdict.allowed {
+                "allowed"
+            } else {
+                "blocked"
             }
-        }
+        );
+        set_return_data(&verdict.try_to_vec()?);
+        Ok(())
     }
 
-    /// Fallback function for transfer hook interface
-    pub fn fallback<'info>(
-        _program_id: &Pubkey,
-        accounts: &'info [AccountInfo<'info>],
-        data: &[u8],
+    /// Read-only market state query for clients. Takes no token accounts, so
+    /// wallets can simulate it cheaply before building a transfer. Pass the
+    /// mint's MarketConfig and HolidayCalendar to evaluate its configured
+    /// hours and closures. A paused config reports `MarketState::HALTED`.
+    pub fn check_market_state(ctx: Context<CheckMarketState>) -> Result<()> {
+        let clock = Clock::get()?;
+        let config = ctx.accounts.market_config.as_deref();
+        let calendar = ctx.accounts.holiday_calendar.as_deref();
+        let timestamp = effective_timestamp(clock.unix_timestamp, config);
+        // A paused mint is halted whatever the hours say, as the hook sees it
+        let market_state = match ensure_not_halted(config) {
+            Ok(()) => get_nyse_market_state(timestamp, config, calendar)?,
+            Err(_) => MarketState::HALTED,
+        };
+        let countdown = market_countdown(timestamp, &market_state, config, calendar)?;
+
+        let mut data = market_state.try_to_vec()?;
+        data.extend_from_slice(&countdown.try_to_vec()?);
+        set_return_data(&data);
+        Ok(())
+    }
+
+    /// Whether `account` is on the mint's allowlist or denylist, for
+    /// compliance dashboards, written as an `AccountListStatus` byte of
+    /// return data. Pass whichever lists the mint has; a denylisted wallet
+    /// reports `Denylisted` even if it is also allowlisted, since the
+    /// denylist wins in the hook.
+    pub fn check_account_status(ctx: Context<CheckAccountStatus>, account: Pubkey) -> Result<()> {
+        let status = account_list_status(
+            ctx.accounts.allowlist.as_deref(),
+            ctx.accounts.denylist.as_deref(),
+            &account,
+        );
+        msg!("🔎 {} is {:?}", account, status);
+        set_return_data(&status.try_to_vec()?);
+        Ok(())
+    }
+
+    /// `check_account_status` for up to `MAX_STATUS_BATCH` accounts in one
+    /// call. Written as return data, two bits per account: bits
+    /// `2 * (i % 4)..` of byte `i / 4` hold the `AccountListStatus` of
+    /// account `i`.
+    pub fn check_accounts_status(
+        ctx: Context<CheckAccountStatus>,
+        accounts: Vec<Pubkey>,
     ) -> Result<()> {
-        msg!("🚨 FALLBACK CALLED!");
+        let packed = packed_account_statuses(
+            ctx.accounts.allowlist.as_deref(),
+            ctx.accounts.denylist.as_deref(),
+            &accounts,
+        )?;
+        msg!("🔎 Checked {} accounts", accounts.len());
+        set_return_data(&packed);
+        Ok(())
+    }
 
-        let instruction = TransferHookInstruction::unpack(data)?;
-        msg!("📦 Instruction unpacked successfully");
+    /// Trading schedule of one local date, for UIs to display and cache:
+    /// whether it trades and its effective open and close, honouring half
+    /// days, holidays and the mint's config and HolidayCalendar when passed.
+    /// Written as `DaySchedule` return data.
+    pub fn get_schedule_for_date(
+        ctx: Context<CheckMarketState>,
+        year: u16,
+        month: u8,
+        day: u8,
+    ) -> Result<()> {
+        let schedule = schedule_for_date(
+            ctx.accounts.market_config.as_deref(),
+            ctx.accounts.holiday_calendar.as_deref(),
+            HolidayDate { year, month, day },
+        )?;
+        set_return_data(&schedule.try_to_vec()?);
+        Ok(())
+    }
 
-        match instruction {
-            TransferHookInstruction::Execute { amount } => {
-                msg!("🎯 Execute instruction with amount: {}", amount);
+    /// Which of `count_days` consecutive local dates, from the first of
+    /// `start_month`, are trading days, for calendar widgets that would
+    /// otherwise call `get_schedule_for_date` once per day. Honours the
+    /// trading weekdays, holidays and the mint's HolidayCalendar when
+    /// passed. Written as return data, one bit per date with bit `i % 8` of
+    /// byte `i / 8` set when date `i` trades; at most `MAX_RANGE_DAYS` dates.
+    pub fn trading_days_in_range(
+        ctx: Context<CheckMarketState>,
+        start_year: u16,
+        start_month: u8,
+        count_days: u16,
+    ) -> Result<()> {
+        let bitmap = trading_days_bitmap(
+            ctx.accounts.market_config.as_deref(),
+            ctx.accounts.holiday_calendar.as_deref(),
+            HolidayDate {
+                year: start_year,
+                month: start_month,
+                day: 1,
+            },
+            count_days,
+        )?;
+        set_return_data(&bitmap);
+        Ok(())
+    }
 
-                // Call our transfer_hook function with proper discriminator
-                let amount_bytes = amount.to_le_bytes();
-                __private::__global::transfer_hook(_program_id, accounts, &amount_bytes)
-            }
-            _ => {
-                msg!("❌ Unknown instruction");
-                Ok(())
-            }
+    /// Typed market-state query for other programs. Anchor writes the
+    /// returned verdict as Borsh return data, so a caller built with this
+    /// crate's `cpi` feature reads it with
+    /// `nyse_token_hook::cpi::query_market_state(ctx)?.get()`, and a raw
+    /// `invoke`/`invoke_signed` caller decodes `get_return_data()` as a
+    /// `MarketState`. A halted config reports `MarketState::HALTED`. The
+    /// config and calendar must be `mint`'s own PDAs; callers gating on the
+    /// verdict should pass them whenever the mint has them, since an omitted
+    /// account means the NYSE defaults.
+    pub fn query_market_state(ctx: Context<CheckMarketState>) -> Result<MarketState> {
+        let clock = Clock::get()?;
+        let config = ctx.accounts.market_config.as_deref();
+        if ensure_not_halted(config).is_err() {
+            return Ok(MarketState::HALTED);
         }
+        let calendar = ctx.accounts.holiday_calendar.as_deref();
+        get_nyse_market_state(
+            effective_timestamp(clock.unix_timestamp, config),
+            config,
+            calendar,
+        )
     }
-}
 
-#[derive(Accounts)]
-pub struct InitializeExtraAccountMetaList<'info> {
-    #[account(mut)]
-    pub payer: Signer<'info>,
+    /// Identify the deployed build: its version, compiled-in default hours
+    /// and `FEATURE_*` bitmask, written as `ProgramInfo` return data so
+    /// tooling can check capabilities without reading the binary
+    pub fn get_program_info(_ctx: Context<GetProgramInfo>) -> Result<ProgramInfo> {
+        Ok(program_info())
+    }
 
-    /// The extra account metas list account
-    /// CHECK: This account is initialized as a TLV ExtraAccountMetaList
-    #[account(
-        init,
-        payer = payer,
-        space = ExtraAccountMetaList::size_of(0)?, // No extra accounts needed
-        seeds = [b"extra-account-metas", mint.key().as_ref()],
-        bump
-    )]
-    pub extra_account_meta_list: AccountInfo<'info>,
+    /// Pre-flight check for integrators: whether the mint's extra account
+    /// meta list exists, is sized for this build's extra accounts and
+    /// resolves exactly the accounts `transfer_hook` expects, and whether
+    /// the mint's transfer hook points at this program. Always succeeds,
+    /// writing a `HookSetupReport` as return data, so a misconfigured list
+    /// is found before a live transfer fails on it.
+    pub fn validate_hook_setup(ctx: Context<ValidateHookSetup>) -> Result<HookSetupReport> {
+        let report = hook_setup_report(&ctx.accounts.extra_account_meta_list, &ctx.accounts.mint)?;
+        msg!(
+            "🩺 Hook setup: {:?} ({} of {} bytes), hook attached: {}",
+            report.status,
+            report.actual_size,
+            report.expected_size,
+            report.hook_attached
+        );
+        Ok(report)
+    }
 
-    /// The mint account
-    /// CHECK: This account is passed in and used for PDA derivation
-    pub mint: AccountInfo<'info>,
+    /// Permissionless crank that announces the mint's open/close transitions.
+    /// Blocked transfers roll back, so the hook itself can't persist a close;
+    /// a keeper calling this after each boundary emits `MarketClosed`.
+    pub fn record_market_transition(ctx: Context<RecordMarketTransition>) -> Result<()> {
+        let (market_state, current_timestamp) =
+            crank_market_state(&ctx.accounts.market_config, &ctx.accounts.holiday_calendar)?;
 
-    pub system_program: Program<'info, System>,
-}
+        record_transition(
+            &ctx.accounts.market_transition,
+            MarketTransition::load(&ctx.accounts.market_transition)?,
+            &market_state,
+            current_timestamp,
+            true,
+        )?;
+        set_return_data(&market_state.try_to_vec()?);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct TransferHook<'info> {
-    /// Source token account
-    #[account(
-        token::mint = mint,
-        token::authority = owner
-    )]
-    pub source_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+    /// Permissionless crank that appends the current market state to the
+    /// mint's StateHistory, at most once a minute (`SnapshotTooSoon`
+    /// otherwise), overwriting the oldest snapshot when full. The snapshot is
+    /// also written as `StateSnapshot` return data.
+    pub fn record_state_snapshot(ctx: Context<RecordStateSnapshot>) -> Result<()> {
+        let (state, timestamp) =
+            crank_market_state(&ctx.accounts.market_config, &ctx.accounts.holiday_calendar)?;
+        let snapshot = StateSnapshot { timestamp, state };
+        ctx.accounts.state_history.record(snapshot)?;
 
-    /// Mint account
-    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+        msg!(
+            "🗂️ State snapshot: {} at {}",
+            state.status.as_str(),
+            timestamp
+        );
+        set_return_data(&snapshot.try_to_vec()?);
+        Ok(())
+    }
 
-    /// Destination token account
-    #[account(token::mint = mint)]
-    pub destination_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+    /// Create the mint's MarketConfig. Only the mint's transfer-hook authority
+    /// may do this, and it becomes the config authority.
+    pub fn initialize_market_config(
+        ctx: Context<InitializeMarketConfig>,
+        open_minute: u16,
+        close_minute: u16,
+    ) -> Result<()> {
+        require!(
+            transfer_hook_authority(&ctx.accounts.mint.to_account_info())?
+                == Some(ctx.accounts.authority.key()),
+            NyseError::Unauthorized
+        );
+        validate_market_hours(open_minute, close_minute)?;
 
-    /// Owner of the source token account
-    /// CHECK: This account is passed through from the transfer instruction
-    pub owner: UncheckedAccount<'info>,
+        let config = &mut ctx.accounts.market_config;
+        config.authority = ctx.accounts.authority.key();
+        config.pending_authority = None;
+        config.config_version = 0;
+        config.config_applied_slot = Clock::get()?.slot;
+        config.premarket_open_minute = EXTENDED_OPEN_MINUTES as u16;
+        config.open_minute = open_minute;
+        config.close_minute = close_minute;
+        config.sessions = Vec::new();
+        config.session_mode = SessionMode::Scheduled;
+        config.boundary_precision = BoundaryPrecision::Second;
+        config.trading_days_bitmask = MONDAY_TO_FRIDAY;
+        config.open_grace_seconds = 0;
+        config.close_grace_seconds = 0;
+        config.soft_open_minutes = 0;
+        config.opening_auction_minutes = 0;
+        config.closing_auction_minutes = 0;
+        config.allow_auction_transfers = true;
+        config.closing_cross_seconds = 0;
+        config.blocking_during_closing_cross = false;
+        config.pre_open_staging_minutes = 0;
+        config.staging_account = Pubkey::default();
+        config.treasury = Pubkey::default();
+        config.treasury_deposit_window = None;
+        config.treasury_withdraw_window = None;
+        config.early_close_override = None;
+        config.maintenance_start = 0;
+        config.maintenance_end = 0;
+        config.delayed_open_until = 0;
+        config.listing_timestamp = 0;
+        config.delisting_timestamp = 0;
+        config.paused = false;
+        config.halted_at_version = 0;
+        config.same_tx_halt_grace = false;
+        config.activated = false;
+        config.closure_message = [0; CLOSURE_MESSAGE_LEN];
+        config.timezone = TimezoneConfig::US_EASTERN;
+        config.dst_fold_policy = DstFoldPolicy::Earliest;
+        config.extended_hours = ExtendedHoursMode::RegularOnly;
+        config.holiday_mode = HolidayMode::BuiltInAndCalendar;
+        config.observance_mode = ObservanceMode::ObservedOnly;
+        config.exchange_preset = ExchangePreset::Nyse;
+        config.enforcement_mode = EnforcementMode::Enforce;
+        config.block_behavior = BlockBehavior::Error;
+        config.verbose_logging = false;
+        config.dedup_decision_events = false;
+        config.display_timezone = DisplayTimezone::Utc;
+        config.max_transfer_amount = 0;
+        config.limit_window_minutes = 0;
+        config.warmup_minutes = 0;
+        config.warmup_start_limit = 0;
+        config.min_transfer_amount = 0;
+        config.dust_exempt_below = 0;
+        config.limits_on_net_amount = false;
+        config.allow_zero_amount = true;
+        config.allow_incinerator = true;
+        config.pool_transfers_follow_hours = false;
+        config.allow_self_transfer = true;
+        config.short_circuit_no_ops = false;
+        config.max_volume_per_window = 0;
+        config.window_seconds = 0;
+        config.min_transfer_interval_seconds = 0;
+        config.max_transfers_per_day = 0;
+        config.allowlist_daily_cap = 0;
+        config.min_destination_age_seconds = 0;
+        config.breaker_threshold = 0;
+        config.breaker_window_seconds = 0;
+        config.breaker_cooldown_seconds = 0;
+        config.post_halt_cooldown_seconds = 0;
+        config.resumed_at = 0;
+        config.large_trade_threshold = 0;
+        config.compliance_authority = Pubkey::default();
+        config.round_amount_threshold = 0;
+        config.round_amount_unit = 0;
+        config.round_amount_policy = RoundAmountPolicy::Flag;
+        config.max_clock_rewind_seconds = 0;
+        config.last_seen_timestamp = 0;
+        config.test_time_override = None;
+        config.bump = ctx.bumps.market_config;
 
-    /// Extra account metas list
-    /// CHECK: This PDA is derived from the mint and validated by seeds constraint
-    #[account(seeds = [b"extra-account-metas", mint.key().as_ref()], bump)]
-    pub extra_account_meta_list: UncheckedAccount<'info>,
-}
+        msg!(
+            "⚙️  Market config initialized: {:02}:{:02} - {:02}:{:02}",
+            open_minute / 60,
+            open_minute % 60,
+            close_minute / 60,
+            close_minute % 60
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
 
-#[derive(Debug)]
-pub struct MarketState {
-    pub is_open: bool,
-    pub status: String,
-    pub reason: String,
-}
+    /// Close a retired mint's MarketConfig and send its rent to `recipient`
+    /// (config authority only). Refused while the mint's transfer hook still
+    /// points at this program.
+    pub fn close_market_config(ctx: Context<CloseMarketConfig>) -> Result<()> {
+        require!(
+            !hook_attached(&ctx.accounts.mint.to_account_info())?,
+            NyseError::HookStillActive
+        );
 
-#[error_code]
-pub enum NyseError {
-    #[msg("🚫 NYSE CLOSED: Market is closed for weekend")]
-    MarketClosedWeekend,
+        msg!("🧹 Market config closed");
+        emit!(MarketConfigClosed {
+            market_config: ctx.accounts.market_config.key(),
+            authority: ctx.accounts.authority.key(),
+            recipient: ctx.accounts.recipient.key(),
+        });
+        Ok(())
+    }
 
-    #[msg("🚫 NYSE CLOSED: Market is closed for holiday")]
-    MarketClosedHoliday,
+    /// Change the session open/close minutes (config authority only). A
+    /// close before the open runs overnight into the next morning.
+    pub fn update_market_hours(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        open_minute: u16,
+        close_minute: u16,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        validate_market_hours(open_minute, close_minute)?;
 
-    #[msg("🚫 NYSE CLOSED: Market is closed after hours")]
-    MarketClosedAfterHours,
+        let config = &mut ctx.accounts.market_config;
+        config.open_minute = open_minute;
+        config.close_minute = close_minute;
+        config.sessions.clear();
 
-    #[msg("Invalid timestamp")]
-    InvalidTimestamp,
-}
+        msg!(
+            "⚙️  Market hours updated: {:02}:{:02} - {:02}:{:02}",
+            open_minute / 60,
+            open_minute % 60,
+            close_minute / 60,
+            close_minute % 60
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
 
-// COMPREHENSIVE NYSE MARKET HOURS LOGIC
-fn get_nyse_market_state(timestamp: i64) -> Result<MarketState> {
-    // Simple and reliable Eastern Time conversion
-    let et_info = get_eastern_time_info(timestamp);
+    /// Split the trading day into several windows, e.g. morning and
+    /// afternoon sessions around a lunch break (config authority only).
+    /// `update_market_hours` goes back to a single session.
+    pub fn set_sessions(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        sessions: Vec<SessionWindow>,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        validate_sessions(&sessions)?;
 
-    msg!(
-        "🕐 Eastern Time: {}-{:02}-{:02} {:02}:{:02}:{:02} {} (Weekday: {})",
-        et_info.year,
-        et_info.month,
-        et_info.day,
-        et_info.hour,
-        et_info.minute,
-        et_info.second,
-        if et_info.is_dst { "EDT" } else { "EST" },
-        et_info.weekday
-    );
+        let config = &mut ctx.accounts.market_config;
+        config.open_minute = sessions[0].open_minute;
+        config.close_minute = sessions[sessions.len() - 1].close_minute;
+        config.sessions = sessions;
 
-    // 1. FIRST: Check weekend (Sunday = 0, Saturday = 6)
-    if et_info.weekday == 0 || et_info.weekday == 6 {
-        msg!("🚫 WEEKEND DETECTED: Weekday = {}", et_info.weekday);
-        return Ok(MarketState {
-            is_open: false,
-            status: "WEEKEND".to_string(),
-            reason: "Weekend".to_string(),
-        });
+        for session in &config.sessions {
+            msg!(
+                "⚙️  Session: {:02}:{:02} - {:02}:{:02}",
+                session.open_minute / 60,
+                session.open_minute % 60,
+                session.close_minute / 60,
+                session.close_minute % 60
+            );
+        }
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
     }
 
-    // 2. SECOND: Check major holidays (simplified list)
-    if is_nyse_holiday(et_info.year, et_info.month, et_info.day) {
-        return Ok(MarketState {
-            is_open: false,
-            status: "HOLIDAY".to_string(),
-            reason: "NYSE Holiday".to_string(),
-        });
+    /// Trade around the clock on every trading day (`Continuous`), or only
+    /// within the session minutes (`Scheduled`, the default); config
+    /// authority only
+    pub fn set_session_mode(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        mode: SessionMode,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.session_mode = mode;
+
+        msg!("🕰️  Session mode: {:?}", mode);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
     }
 
-    // 3. THIRD: Check market hours (9:30 AM - 4:00 PM ET)
-    let current_minutes = et_info.hour * 60 + et_info.minute;
-    let market_open_minutes = 9 * 60 + 30; // 9:30 AM
-    let market_close_minutes = 16 * 60; // 4:00 PM
+    /// Evaluate the boundaries to the second (`Second`, the default) or only
+    /// on whole minutes (`Minute`); config authority only
+    pub fn set_boundary_precision(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        precision: BoundaryPrecision,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.boundary_precision = precision;
 
-    if current_minutes >= market_open_minutes && current_minutes < market_close_minutes {
-        Ok(MarketState {
-            is_open: true,
-            status: "OPEN".to_string(),
-            reason: "Regular Trading Hours".to_string(),
-        })
-    } else {
-        let time_status = if current_minutes < market_open_minutes {
-            "Pre-Market"
-        } else {
-            "After-Market"
-        };
+        msg!("🎯 Boundary precision: {:?}", precision);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
 
-        Ok(MarketState {
-            is_open: false,
-            status: "AFTER_HOURS".to_string(),
-            reason: format!("{} (outside 9:30 AM - 4:00 PM ET)", time_status),
-        })
+    /// Interpret the session minutes in another exchange's local time
+    /// (config authority only)
+    pub fn set_timezone(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        timezone: TimezoneConfig,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        validate_timezone(&timezone)?;
+        ctx.accounts.market_config.timezone = timezone;
+
+        msg!(
+            "🌐 Timezone updated: UTC offset {}s, DST {:?}",
+            timezone.utc_offset_seconds,
+            timezone.dst_rule()?
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
     }
-}
 
-// Simplified Eastern Time structure
-#[derive(Debug)]
-struct EasternTimeInfo {
-    year: i32,
-    month: u32,
-    day: u32,
-    hour: u32,
-    minute: u32,
-    second: u32,
-    weekday: u32, // 0 = Sunday, 1 = Monday, ..., 6 = Saturday
-    is_dst: bool,
-}
+    /// Choose whether boundaries inside the hour repeated at the end of DST
+    /// take effect on its first pass (`Earliest`, the default) or its
+    /// second (`Latest`); config authority only
+    pub fn set_dst_fold_policy(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        policy: DstFoldPolicy,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.dst_fold_policy = policy;
 
-// Simple and reliable Eastern Time conversion
-fn get_eastern_time_info(utc_timestamp: i64) -> EasternTimeInfo {
-    // Determine DST status first (simplified rules)
-    let is_dst = is_daylight_saving_time(utc_timestamp);
+        msg!("🍂 DST fold policy: {:?}", policy);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
 
-    // Apply Eastern Time offset
-    let eastern_offset_seconds = if is_dst { -4 * 3600 } else { -5 * 3600 };
-    let eastern_timestamp = utc_timestamp + eastern_offset_seconds;
+    /// Allow or disallow pre-market and after-hours trading (config
+    /// authority only)
+    pub fn set_extended_hours_mode(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        mode: ExtendedHoursMode,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.extended_hours = mode;
 
-    // Convert to date/time components using simple arithmetic
-    let days_since_epoch = eastern_timestamp / 86400;
-    let seconds_in_day = eastern_timestamp % 86400;
+        msg!("🌙 Extended hours mode: {:?}", mode);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
 
-    // Handle negative seconds (wrap to previous day)
-    let (days_since_epoch, seconds_in_day) = if seconds_in_day < 0 {
-        (days_since_epoch - 1, seconds_in_day + 86400)
-    } else {
-        (days_since_epoch, seconds_in_day)
-    };
+    /// Move the start of the pre-market session, which is also where
+    /// extended-hours trading begins (config authority only)
+    pub fn set_premarket_open(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        premarket_open_minute: u16,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        let config = &mut ctx.accounts.market_config;
+        require!(
+            premarket_open_minute <= config.open_minute,
+            NyseError::InvalidMarketHours
+        );
+        config.premarket_open_minute = premarket_open_minute;
 
-    // Calculate weekday (0 = Sunday, 1 = Monday, ..., 6 = Saturday)
-    // January 1, 1970 was a Thursday (4), so we adjust
-    let weekday = ((days_since_epoch + 4) % 7 + 7) % 7;
+        msg!(
+            "🌅 Pre-market opens at {:02}:{:02}",
+            premarket_open_minute / 60,
+            premarket_open_minute % 60
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
 
-    // Calculate time components
-    let hour = (seconds_in_day / 3600) as u32;
-    let minute = ((seconds_in_day % 3600) / 60) as u32;
-    let second = (seconds_in_day % 60) as u32;
+    /// Choose the trading weekdays: bit `n` for weekday `n`, 0 = Sunday
+    /// (config authority only). 0 restores the Monday-Friday default.
+    pub fn set_trading_days(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        trading_days_bitmask: u8,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(trading_days_bitmask < 1 << 7, NyseError::InvalidTradingDays);
+        ctx.accounts.market_config.trading_days_bitmask = trading_days_bitmask;
+
+        msg!("📅 Trading days bitmask: {:#09b}", trading_days_bitmask);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
 
-    // Calculate date components (simplified - good enough for NYSE)
-    let (year, month, day) = days_to_date(days_since_epoch);
+    /// Close a weekend fixed-date holiday's own date as well as the weekday
+    /// it is observed on (`ActualAndObserved`), or only the observed day
+    /// (`ObservedOnly`, NYSE's practice); config authority only
+    pub fn set_observance_mode(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        mode: ObservanceMode,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.observance_mode = mode;
 
-    EasternTimeInfo {
-        year,
-        month,
-        day,
-        hour,
-        minute,
-        second,
-        weekday: weekday as u32,
-        is_dst,
+        msg!("📅 Observance mode: {:?}", mode);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
     }
-}
 
-// Simplified DST check (good enough for NYSE hours)
-fn is_daylight_saving_time(utc_timestamp: i64) -> bool {
-    // Convert to rough month estimate
-    let days_since_epoch = utc_timestamp / 86400;
-    let approx_year = 1970 + (days_since_epoch / 365);
-    let days_in_year = days_since_epoch % 365;
+    /// Choose between the built-in NYSE holidays, the HolidayCalendar, or
+    /// both (config authority only)
+    pub fn set_holiday_mode(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        mode: HolidayMode,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.holiday_mode = mode;
 
-    // DST roughly: March (day 60) to November (day 305)
-    // This is approximate but good enough for NYSE
-    if approx_year >= 2007 {
-        // Post-2007 DST rules: 2nd Sunday in March to 1st Sunday in November
-        days_in_year >= 70 && days_in_year <= 305
-    } else {
-        // Pre-2007 DST rules: 1st Sunday in April to last Sunday in October
-        days_in_year >= 90 && days_in_year <= 300
+        msg!("📅 Holiday mode: {:?}", mode);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
     }
-}
 
-// Simplified date calculation (good enough for NYSE)
-fn days_to_date(days_since_epoch: i64) -> (i32, u32, u32) {
-    // Very simplified - assumes average year length
-    let approx_year = 1970 + (days_since_epoch / 365);
-    let remaining_days = days_since_epoch % 365;
+    /// Pick which exchange's built-in holidays apply (config authority only)
+    pub fn set_exchange_preset(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        preset: ExchangePreset,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.exchange_preset = preset;
+
+        msg!("🏛️  Exchange preset: {:?}", preset);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
 
-    // Simplified month/day calculation
-    let months = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
-    let mut month = 1;
-    let mut days_left = remaining_days;
+    /// Log every step of the transfer checks, for debugging, or only the
+    /// reason a transfer is blocked (the default), which saves compute on
+    /// busy mints (config authority only)
+    pub fn set_verbose_logging(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        verbose_logging: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.verbose_logging = verbose_logging;
 
-    for &days_in_month in &months {
-        if days_left < days_in_month as i64 {
-            break;
-        }
-        days_left -= days_in_month as i64;
-        month += 1;
+        msg!("📣 Verbose logging: {}", verbose_logging);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
     }
 
-    let day = (days_left + 1).max(1) as u32;
+    /// Emit the full `TransferAllowed` or `TransferBlocked` only when a
+    /// transfer's status differs from the last one emitted, and the
+    /// lightweight `TransferStatusUnchanged` otherwise, for indexers that
+    /// only follow changes (config authority only). The last status lives in
+    /// the mint's `MarketTransition`; without one every event is full.
+    pub fn set_dedup_decision_events(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        dedup_decision_events: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.dedup_decision_events = dedup_decision_events;
+
+        msg!("🗜️  Decision event dedup: {}", dedup_decision_events);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
 
-    (approx_year as i32, month, day)
-}
+    /// Write log timestamps in UTC (the default) or in the exchange's local
+    /// time, so they read like the times the hours are checked against
+    /// (config authority only)
+    pub fn set_display_timezone(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        display_timezone: DisplayTimezone,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.display_timezone = display_timezone;
 
-// Simplified NYSE holiday check (major holidays only)
-fn is_nyse_holiday(_year: i32, month: u32, day: u32) -> bool {
-    match (month, day) {
-        (1, 1) => true,   // New Year's Day
-        (7, 4) => true,   // Independence Day
-        (12, 25) => true, // Christmas Day
-        // Add more holidays as needed
-        _ => false,
+        msg!("🕰️  Display timezone: {:?}", display_timezone);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
     }
-}
 
-// Keep existing helper functions for timestamp formatting
-fn format_timestamp(timestamp: i64) -> String {
-    // Simple UTC formatting
-    let days = timestamp / 86400;
-    let seconds = timestamp % 86400;
-    let hours = seconds / 3600;
-    let minutes = (seconds % 3600) / 60;
-    let secs = seconds % 60;
+    /// Switch between blocking closed-market transfers and only reporting
+    /// them with `TransferWouldBlock`, for a staged rollout (config authority
+    /// only)
+    pub fn set_enforcement_mode(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        mode: EnforcementMode,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.enforcement_mode = mode;
 
-    // Rough date calculation for display
-    let year = 1970 + days / 365;
-    let day_in_year = days % 365;
+        msg!("👮 Enforcement mode: {:?}", mode);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
 
-    format!(
-        "{}-01-{:02} {:02}:{:02}:{:02}",
-        year,
-        day_in_year + 1,
-        hours,
-        minutes,
-        secs
-    )
+    /// Choose whether closed-market transfers fail with the verdict's own
+    /// error or uniformly with `MarketClosedRejected` (config authority
+    /// only). Either way the transfer fails; see `BlockBehavior`.
+    pub fn set_block_behavior(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        behavior: BlockBehavior,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.block_behavior = behavior;
+
+        msg!("🤫 Block behavior: {:?}", behavior);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Keep accepting transfers a few seconds either side of the session so
+    /// block-time lag doesn't fail a transfer sent at 3:59 PM (config
+    /// authority only). Grace never reaches into a weekend or holiday.
+    pub fn set_grace_periods(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        open_grace_seconds: u16,
+        close_grace_seconds: u16,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            open_grace_seconds <= MAX_GRACE_SECONDS && close_grace_seconds <= MAX_GRACE_SECONDS,
+            NyseError::InvalidMarketHours
+        );
+        let config = &mut ctx.accounts.market_config;
+        config.open_grace_seconds = open_grace_seconds;
+        config.close_grace_seconds = close_grace_seconds;
+
+        msg!(
+            "⏳ Grace periods: {}s before open, {}s after close",
+            open_grace_seconds,
+            close_grace_seconds
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Block new position changes in the final `closing_cross_seconds`
+    /// before the close while `blocking_during_closing_cross` is set; the
+    /// allowlisted accounts that settle the auction keep going (config
+    /// authority only)
+    pub fn set_closing_cross(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        closing_cross_seconds: u16,
+        blocking_during_closing_cross: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            closing_cross_seconds <= MAX_CLOSING_CROSS_SECONDS,
+            NyseError::InvalidMarketHours
+        );
+        let config = &mut ctx.accounts.market_config;
+        config.closing_cross_seconds = closing_cross_seconds;
+        config.blocking_during_closing_cross = blocking_during_closing_cross;
+
+        msg!(
+            "⚖️  Closing cross: last {}s before close, transfers {}",
+            closing_cross_seconds,
+            if blocking_during_closing_cross {
+                "blocked"
+            } else {
+                "allowed"
+            }
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Accept transfers in the `soft_open_minutes` before the open but flag
+    /// each with a `SoftOpenTransfer` event, so early activity shows up
+    /// without being blocked (config authority only). Zero disables it.
+    pub fn set_soft_open(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        soft_open_minutes: u16,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            soft_open_minutes <= MAX_SOFT_OPEN_MINUTES,
+            NyseError::InvalidMarketHours
+        );
+        ctx.accounts.market_config.soft_open_minutes = soft_open_minutes;
+
+        msg!(
+            "🌤️  Soft open: {} minutes before the open",
+            soft_open_minutes
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Accept transfers into `staging_account` (an owner, like allowlist
+    /// entries) in the `pre_open_staging_minutes` before the open, while the
+    /// market is still closed, so orders can be staged for the opening
+    /// auction (config authority only). Zero minutes disables it.
+    pub fn set_pre_open_staging(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        pre_open_staging_minutes: u16,
+        staging_account: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            pre_open_staging_minutes <= MAX_STAGING_MINUTES,
+            NyseError::InvalidMarketHours
+        );
+        let config = &mut ctx.accounts.market_config;
+        config.pre_open_staging_minutes = pre_open_staging_minutes;
+        config.staging_account = staging_account;
+
+        msg!(
+            "📥 Pre-open staging: {} min before open into {}",
+            pre_open_staging_minutes,
+            staging_account
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Give deposits into and withdrawals from `treasury` (an owner, like
+    /// allowlist entries) their own window on trading days, in place of the
+    /// market hours (config authority only). A `None` window leaves that
+    /// direction on the market hours.
+    pub fn set_treasury_windows(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        treasury: Pubkey,
+        deposit_window: Option<SessionWindow>,
+        withdraw_window: Option<SessionWindow>,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        for window in deposit_window.iter().chain(&withdraw_window) {
+            validate_sessions(std::slice::from_ref(window))?;
+        }
+        let config = &mut ctx.accounts.market_config;
+        config.treasury = treasury;
+        config.treasury_deposit_window = deposit_window;
+        config.treasury_withdraw_window = withdraw_window;
+
+        msg!(
+            "🏦 Treasury {}: deposits {:?}, withdrawals {:?}",
+            treasury,
+            deposit_window,
+            withdraw_window
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Flag the first and last minutes of the regular session as the opening
+    /// and closing auctions, reported with status `AUCTION`, and choose
+    /// whether transfers go through in them (config authority only). Zero
+    /// minutes disables either window.
+    pub fn set_auction_windows(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        opening_auction_minutes: u16,
+        closing_auction_minutes: u16,
+        allow_auction_transfers: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            opening_auction_minutes <= MAX_AUCTION_MINUTES
+                && closing_auction_minutes <= MAX_AUCTION_MINUTES,
+            NyseError::InvalidMarketHours
+        );
+        let config = &mut ctx.accounts.market_config;
+        config.opening_auction_minutes = opening_auction_minutes;
+        config.closing_auction_minutes = closing_auction_minutes;
+        config.allow_auction_transfers = allow_auction_transfers;
+
+        msg!(
+            "🔔 Auctions: {} min after open, {} min before close, transfers {}",
+            opening_auction_minutes,
+            closing_auction_minutes,
+            if allow_auction_transfers {
+                "allowed"
+            } else {
+                "blocked"
+            }
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Cap single transfers in the final minutes before the close; zero for
+    /// either value disables the cap (config authority only)
+    pub fn set_transfer_limit(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        max_transfer_amount: u64,
+        limit_window_minutes: u16,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        let config = &mut ctx.accounts.market_config;
+        config.max_transfer_amount = max_transfer_amount;
+        config.limit_window_minutes = limit_window_minutes;
+
+        msg!(
+            "📏 Transfer limit: {} in the last {} minutes before close",
+            max_transfer_amount,
+            limit_window_minutes
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Cap single transfers in the first `warmup_minutes` after the open, at
+    /// `warmup_start_limit` in the first minute and another
+    /// `warmup_start_limit` each minute after, until the cap lifts when the
+    /// ramp ends; zero for either value disables it (config authority only)
+    pub fn set_warmup_ramp(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        warmup_minutes: u16,
+        warmup_start_limit: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            warmup_minutes <= MAX_WARMUP_MINUTES,
+            NyseError::InvalidMarketHours
+        );
+        let config = &mut ctx.accounts.market_config;
+        config.warmup_minutes = warmup_minutes;
+        config.warmup_start_limit = warmup_start_limit;
+
+        msg!(
+            "🌅 Warmup ramp: from {} over the first {} minutes after open",
+            warmup_start_limit,
+            warmup_minutes
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Reject transfers smaller than `min_transfer_amount` while the market
+    /// is open; zero removes the floor (config authority only)
+    pub fn set_min_transfer_amount(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        min_transfer_amount: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.min_transfer_amount = min_transfer_amount;
+
+        msg!("📏 Minimum transfer: {}", min_transfer_amount);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Let nonzero transfers below `dust_exempt_below` through without
+    /// running any check, at any hour, for micropayments; zero turns it off
+    /// (config authority only)
+    pub fn set_dust_exempt_below(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        dust_exempt_below: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.dust_exempt_below = dust_exempt_below;
+
+        msg!("🪶 Dust exempt below: {}", dust_exempt_below);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Apply the size limits (large trade threshold, warmup and closing
+    /// window caps, minimum, circuit breaker and volume cap) to the amount
+    /// delivered after the mint's transfer fee (`true`), or to the gross
+    /// amount sent (`false`, the default); config authority only
+    pub fn set_limits_on_net_amount(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        limits_on_net_amount: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.limits_on_net_amount = limits_on_net_amount;
+
+        msg!("💸 Limits on net amount: {}", limits_on_net_amount);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Let zero-amount transfers through regardless of market hours, or
+    /// apply the normal rules to them (config authority only)
+    pub fn set_allow_zero_amount(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        allow_zero_amount: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.allow_zero_amount = allow_zero_amount;
+
+        msg!("🫙 Allow zero-amount transfers: {}", allow_zero_amount);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Let transfers into the SPL incinerator through regardless of market
+    /// hours, so holders can destroy tokens off-hours, or apply the normal
+    /// rules to them (config authority only)
+    pub fn set_allow_incinerator(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        allow_incinerator: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.allow_incinerator = allow_incinerator;
+
+        msg!(
+            "🔥 Allow transfers to the incinerator: {}",
+            allow_incinerator
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Make transfers to and from `LiquidityPool` allowlist entries follow
+    /// the market hours again, for deployments that want liquidity moves
+    /// blocked off-hours too (config authority only)
+    pub fn set_pool_transfers_follow_hours(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        pool_transfers_follow_hours: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.pool_transfers_follow_hours = pool_transfers_follow_hours;
+
+        msg!(
+            "🌊 Pool transfers follow market hours: {}",
+            pool_transfers_follow_hours
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Allow or block transfers between two token accounts of the same
+    /// owner, which can be used for wash trading (config authority only)
+    pub fn set_allow_self_transfer(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        allow_self_transfer: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.allow_self_transfer = allow_self_transfer;
+
+        msg!("🔁 Allow self-transfers: {}", allow_self_transfer);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Let transfers that move nothing (a zero amount, or an empty source
+    /// in `simulate_transfer`) skip every check, writing a `NoOpHint` as
+    /// return data so the client learns why instead of reading a
+    /// market-hours verdict (config authority only)
+    pub fn set_short_circuit_no_ops(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        short_circuit_no_ops: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.short_circuit_no_ops = short_circuit_no_ops;
+
+        msg!("💤 Short-circuit no-op transfers: {}", short_circuit_no_ops);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Cap how much one owner may send per rolling window; zero for either
+    /// value disables the cap (config authority only). Capped mints need a
+    /// funded RentReserve to create each owner's VolumeTracker.
+    pub fn set_volume_limit(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        max_volume_per_window: u64,
+        window_seconds: u32,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            cfg!(feature = "volume-tracking") || max_volume_per_window == 0,
+            NyseError::FeatureDisabled
+        );
+        let config = &mut ctx.accounts.market_config;
+        config.max_volume_per_window = max_volume_per_window;
+        config.window_seconds = window_seconds;
+
+        msg!(
+            "📊 Volume limit: {} per {} seconds per owner",
+            max_volume_per_window,
+            window_seconds
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Make each owner wait `min_transfer_interval_seconds` between
+    /// transfers; zero disables it (config authority only). Like the volume
+    /// cap, this needs a funded RentReserve to create each owner's
+    /// LastTransfer.
+    pub fn set_transfer_cooldown(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        min_transfer_interval_seconds: u32,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            cfg!(feature = "cooldown") || min_transfer_interval_seconds == 0,
+            NyseError::FeatureDisabled
+        );
+        ctx.accounts.market_config.min_transfer_interval_seconds = min_transfer_interval_seconds;
+
+        msg!(
+            "⏱️  Transfer cooldown: {} seconds per owner",
+            min_transfer_interval_seconds
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Cap how many transfers each owner may make per trading day; zero
+    /// disables it (config authority only). Needs a funded RentReserve to
+    /// create each owner's DailyCounter.
+    pub fn set_daily_transfer_limit(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        max_transfers_per_day: u32,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            cfg!(feature = "daily-limit") || max_transfers_per_day == 0,
+            NyseError::FeatureDisabled
+        );
+        ctx.accounts.market_config.max_transfers_per_day = max_transfers_per_day;
+
+        msg!(
+            "🔢 Daily transfer limit: {} per owner",
+            max_transfers_per_day
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Cap what each source owner may send per trading day through the
+    /// allowlist exemption, so even exempt wallets have a worst-case
+    /// exposure (config authority only; zero means unlimited)
+    pub fn set_allowlist_daily_cap(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        allowlist_daily_cap: u64,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            cfg!(feature = "allowlist") || allowlist_daily_cap == 0,
+            NyseError::FeatureDisabled
+        );
+        ctx.accounts.market_config.allowlist_daily_cap = allowlist_daily_cap;
+
+        msg!("🧢 Allowlist daily cap: {} per owner", allowlist_daily_cap);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Halt the mint automatically once its aggregate volume within
+    /// `breaker_window_seconds` exceeds `breaker_threshold`, for
+    /// `breaker_cooldown_seconds`; a zero threshold or window disables it.
+    /// Needs the mint's CircuitBreaker (config authority only).
+    pub fn set_circuit_breaker(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        breaker_threshold: u64,
+        breaker_window_seconds: u32,
+        breaker_cooldown_seconds: u32,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        let config = &mut ctx.accounts.market_config;
+        config.breaker_threshold = breaker_threshold;
+        config.breaker_window_seconds = breaker_window_seconds;
+        config.breaker_cooldown_seconds = breaker_cooldown_seconds;
+
+        msg!(
+            "🧯 Circuit breaker: {} per {} seconds, {} second cooldown",
+            breaker_threshold,
+            breaker_window_seconds,
+            breaker_cooldown_seconds
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Only let transfers reach token accounts registered at least
+    /// `min_destination_age_seconds` ago with `register_token_account`,
+    /// against routing through brand-new accounts; zero disables it (config
+    /// authority only)
+    pub fn set_min_destination_age(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        min_destination_age_seconds: u32,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            cfg!(feature = "destination-age") || min_destination_age_seconds == 0,
+            NyseError::FeatureDisabled
+        );
+        ctx.accounts.market_config.min_destination_age_seconds = min_destination_age_seconds;
+
+        msg!(
+            "🐣 Minimum destination age: {} seconds",
+            min_destination_age_seconds
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Require `compliance_authority` to co-sign the transaction of any
+    /// transfer of at least `large_trade_threshold`; zero disables the check
+    /// (config authority only)
+    pub fn set_large_trade_approval(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        large_trade_threshold: u64,
+        compliance_authority: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        let config = &mut ctx.accounts.market_config;
+        config.large_trade_threshold = large_trade_threshold;
+        config.compliance_authority = compliance_authority;
+
+        msg!(
+            "🛂 Transfers of {} or more need {} to co-sign",
+            large_trade_threshold,
+            compliance_authority
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Treat transfers above `round_amount_threshold` whole tokens that are
+    /// an exact multiple of `round_amount_unit` whole tokens, such as
+    /// exactly 10,000.000000, as possible structuring: flag them in
+    /// `TransferAllowed` or block them (config authority only). A zero
+    /// threshold or unit disables it.
+    pub fn set_round_amount_policy(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        round_amount_threshold: u64,
+        round_amount_unit: u64,
+        round_amount_policy: RoundAmountPolicy,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        let config = &mut ctx.accounts.market_config;
+        config.round_amount_threshold = round_amount_threshold;
+        config.round_amount_unit = round_amount_unit;
+        config.round_amount_policy = round_amount_policy;
+
+        msg!(
+            "🔍 Round amounts above {} in multiples of {}: {:?}",
+            round_amount_threshold,
+            round_amount_unit,
+            round_amount_policy
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Schedule an ad-hoc early close (e.g. a national day of mourning) for
+    /// one date, or clear it with `None` (config authority only). Once the
+    /// date has passed the override has no effect.
+    pub fn set_early_close_override(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        early_close_override: Option<EarlyCloseOverride>,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        if let Some(early_close) = &early_close_override {
+            validate_holiday_date(&early_close.date)?;
+            require!(
+                early_close.close_minute <= 24 * 60,
+                NyseError::InvalidMarketHours
+            );
+            msg!(
+                "⏰ Early close on {}-{:02}-{:02} at {:02}:{:02}",
+                early_close.date.year,
+                early_close.date.month,
+                early_close.date.day,
+                early_close.close_minute / 60,
+                early_close.close_minute % 60
+            );
+        } else {
+            msg!("⏰ Early close override cleared");
+        }
+
+        ctx.accounts.market_config.early_close_override = early_close_override;
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Block every non-allowlisted transfer from `maintenance_start` until
+    /// `maintenance_end` (UTC timestamps), whatever the hours say; zero for
+    /// both clears it (config authority only)
+    pub fn set_maintenance_window(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        maintenance_start: i64,
+        maintenance_end: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            maintenance_start <= maintenance_end,
+            NyseError::InvalidTimestamp
+        );
+        let config = &mut ctx.accounts.market_config;
+        config.maintenance_start = maintenance_start;
+        config.maintenance_end = maintenance_end;
+
+        let config = Some(&*ctx.accounts.market_config);
+        msg!(
+            "🔧 Maintenance window: {} - {}",
+            format_display_timestamp(maintenance_start, config),
+            format_display_timestamp(maintenance_end, config)
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Delay this mint's open until `delayed_open_until` (a UTC timestamp)
+    /// without touching the recurring schedule, as NYSE does for an IPO or
+    /// pending news; the delay lapses by itself and zero cancels it
+    /// (config authority only)
+    pub fn set_delayed_open(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        delayed_open_until: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(delayed_open_until >= 0, NyseError::InvalidTimestamp);
+        ctx.accounts.market_config.delayed_open_until = delayed_open_until;
+
+        msg!(
+            "⏳ Open delayed until {}",
+            format_display_timestamp(delayed_open_until, Some(&ctx.accounts.market_config))
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Block every transfer before `listing_timestamp` and from
+    /// `delisting_timestamp` on (UTC timestamps), whatever the hours say;
+    /// zero disables either side (config authority only)
+    pub fn set_listing_window(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        listing_timestamp: i64,
+        delisting_timestamp: i64,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            listing_timestamp >= 0
+                && (delisting_timestamp == 0 || delisting_timestamp > listing_timestamp),
+            NyseError::InvalidTimestamp
+        );
+        let config = &mut ctx.accounts.market_config;
+        config.listing_timestamp = listing_timestamp;
+        config.delisting_timestamp = delisting_timestamp;
+
+        let config = Some(&*ctx.accounts.market_config);
+        msg!(
+            "📜 Listed from {}, delisted {}",
+            format_display_timestamp(listing_timestamp, config),
+            if delisting_timestamp == 0 {
+                "never".to_string()
+            } else {
+                format!(
+                    "at {}",
+                    format_display_timestamp(delisting_timestamp, config)
+                )
+            }
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Refuse to evaluate transfers when the cluster clock reads more than
+    /// `max_clock_rewind_seconds` behind the latest time the hook has seen,
+    /// so a stale validator clock can't reopen a closed market; 0 turns the
+    /// check off (config authority only)
+    pub fn set_clock_tolerance(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        max_clock_rewind_seconds: u32,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.max_clock_rewind_seconds = max_clock_rewind_seconds;
+
+        msg!("⏱️  Clock rewind tolerance: {}s", max_clock_rewind_seconds);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Pin the time the hook evaluates transfers at, so QA can exercise the
+    /// weekend, holiday and after-hours paths on demand; `None` goes back to
+    /// the cluster clock. Only builds with the `time-override` feature
+    /// (never mainnet) accept this (config authority only).
+    pub fn set_test_time_override(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        test_time_override: Option<i64>,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        require!(
+            cfg!(feature = "time-override"),
+            NyseError::TimeOverrideDisabled
+        );
+        if let Some(timestamp) = test_time_override {
+            require!(is_valid_timestamp(timestamp), NyseError::InvalidTimestamp);
+        }
+        ctx.accounts.market_config.test_time_override = test_time_override;
+
+        msg!("🧪 Test time override: {:?}", test_time_override);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Open the mint for transfers once it is configured (config authority
+    /// only). New configs block every transfer until this is called.
+    pub fn activate(ctx: Context<UpdateMarketConfig>, expected_version: u64) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.activated = true;
+
+        msg!("🟢 Hook activated: transfers follow the market hours");
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Freeze or unfreeze all transfers of the mint (config authority only)
+    pub fn set_paused(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        paused: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        let config = &mut ctx.accounts.market_config;
+        if config.paused && !paused {
+            config.resumed_at = effective_timestamp(Clock::get()?.unix_timestamp, Some(config));
+            config.halted_at_version = 0;
+        }
+        if !config.paused && paused {
+            config.halted_at_version = config.config_version;
+        }
+        config.paused = paused;
+
+        if paused {
+            msg!("⛔ Market HALTED: all transfers are blocked until unpaused");
+        } else {
+            msg!("▶️  Market halt lifted: normal trading hours apply");
+        }
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Let transfers later in the transaction that halts the mint through as
+    /// if it were still running, for issuers who batch a last transfer with
+    /// the halt (config authority only). Only a `set_paused` sent as a
+    /// top-level instruction is seen; one made through a CPI still blocks.
+    pub fn set_same_tx_halt_grace(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        same_tx_halt_grace: bool,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.same_tx_halt_grace = same_tx_halt_grace;
+
+        msg!("⏮️  Same-transaction halt grace: {}", same_tx_halt_grace);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Let only allowlisted wallets transfer for `post_halt_cooldown_seconds`
+    /// after a halt is lifted or the circuit breaker resets, so the reopen
+    /// isn't a stampede (config authority only; zero disables it)
+    pub fn set_post_halt_cooldown(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        post_halt_cooldown_seconds: u32,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.post_halt_cooldown_seconds = post_halt_cooldown_seconds;
+
+        msg!(
+            "🐢 Post-halt cooldown: {} seconds",
+            post_halt_cooldown_seconds
+        );
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Explain an unexpected halt or maintenance window to users, e.g.
+    /// "Closed: system-wide outage" (at most 64 bytes; empty clears it).
+    /// Wallets read it from `TransferBlocked` and the return data (config
+    /// authority only).
+    pub fn set_closure_message(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        message: String,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.closure_message = closure_message_bytes(&message)?;
+
+        msg!("📝 Closure message: {:?}", message);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Start handing the config to `new_authority` (config authority only).
+    /// Nothing changes until that key signs `accept_authority`; proposing
+    /// again replaces the pending key.
+    pub fn propose_authority(
+        ctx: Context<UpdateMarketConfig>,
+        expected_version: u64,
+        new_authority: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        ctx.accounts.market_config.pending_authority = Some(new_authority);
+
+        msg!("🔑 Config authority handoff proposed to {}", new_authority);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Take over the config as the proposed authority
+    pub fn accept_authority(ctx: Context<AcceptAuthority>, expected_version: u64) -> Result<()> {
+        ctx.accounts
+            .market_config
+            .advance_version(expected_version)?;
+        let new_authority = ctx.accounts.new_authority.key();
+        ctx.accounts
+            .market_config
+            .accept_authority(&new_authority)?;
+
+        msg!("🔑 Config authority is now {}", new_authority);
+        emit!(ConfigUpdated::new(
+            &ctx.accounts.market_config,
+            new_authority
+        ));
+        Ok(())
+    }
+
+    /// Create the mint's exempt-wallet allowlist. Only the mint's
+    /// transfer-hook authority may do this, and it becomes the list authority.
+    pub fn initialize_allowlist(ctx: Context<InitializeAllowlist>) -> Result<()> {
+        require!(cfg!(feature = "allowlist"), NyseError::FeatureDisabled);
+        require!(
+            transfer_hook_authority(&ctx.accounts.mint.to_account_info())?
+                == Some(ctx.accounts.authority.key()),
+            NyseError::Unauthorized
+        );
+
+        let allowlist = &mut ctx.accounts.allowlist;
+        allowlist.authority = ctx.accounts.authority.key();
+        allowlist.entries = Vec::new();
+        allowlist.programs = Vec::new();
+        allowlist.bump = ctx.bumps.allowlist;
+
+        msg!("📋 Allowlist initialized");
+        Ok(())
+    }
+
+    /// Exempt a wallet from the market-hours check when it sends, receives
+    /// or both, per `direction` (list authority only)
+    pub fn add_to_allowlist(
+        ctx: Context<UpdateAllowlist>,
+        wallet: Pubkey,
+        direction: ExemptDirection,
+    ) -> Result<()> {
+        let allowlist = &mut ctx.accounts.allowlist;
+        require!(!allowlist.contains(&wallet), NyseError::AlreadyAllowlisted);
+        require!(
+            allowlist.entries.len() < MAX_ALLOWLIST_ENTRIES,
+            NyseError::AllowlistFull
+        );
+        allowlist.entries.push(AllowlistEntry { wallet, direction });
+
+        msg!("➕ Allowlisted {} ({:?})", wallet, direction);
+        emit!(AllowlistUpdated::new(
+            &ctx.accounts.allowlist,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Replace the whole allowlist in one call, for onboarding a roster of
+    /// market makers (list authority only). Lists created when the cap was
+    /// lower are grown to the current capacity, the payer covering the rent.
+    /// A transaction has room for about 30 entries; add the rest with
+    /// `add_to_allowlist`.
+    pub fn set_allowlist(
+        ctx: Context<ReplaceAllowlist>,
+        entries: Vec<AllowlistEntry>,
+    ) -> Result<()> {
+        validate_allowlist(&entries)?;
+        ctx.accounts.allowlist.entries = entries;
+
+        msg!(
+            "📋 Allowlist replaced: {} wallets",
+            ctx.accounts.allowlist.entries.len()
+        );
+        emit!(AllowlistUpdated::new(
+            &ctx.accounts.allowlist,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Remove a wallet's exemption (list authority only)
+    pub fn remove_from_allowlist(ctx: Context<UpdateAllowlist>, wallet: Pubkey) -> Result<()> {
+        let allowlist = &mut ctx.accounts.allowlist;
+        let index = allowlist
+            .entries
+            .iter()
+            .position(|entry| entry.wallet == wallet)
+            .ok_or(NyseError::NotAllowlisted)?;
+        allowlist.entries.swap_remove(index);
+
+        msg!("➖ Removed {} from allowlist", wallet);
+        emit!(AllowlistUpdated::new(
+            &ctx.accounts.allowlist,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Exempt sends from any token account owned by a PDA of `program_id`
+    /// (list authority only), instead of listing each of the program's
+    /// vaults. Lists created before this are grown to fit, the payer
+    /// covering the rent.
+    pub fn add_allowlisted_program(
+        ctx: Context<ReplaceAllowlist>,
+        program_id: Pubkey,
+    ) -> Result<()> {
+        let allowlist = &mut ctx.accounts.allowlist;
+        require!(
+            !allowlist.lists_program(&program_id),
+            NyseError::AlreadyAllowlisted
+        );
+        require!(
+            allowlist.programs.len() < MAX_ALLOWLISTED_PROGRAMS,
+            NyseError::AllowlistFull
+        );
+        allowlist.programs.push(program_id);
+
+        msg!("➕ Allowlisted PDAs of program {}", program_id);
+        emit!(AllowlistUpdated::new(
+            &ctx.accounts.allowlist,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Withdraw a program's PDA exemption (list authority only)
+    pub fn remove_allowlisted_program(
+        ctx: Context<UpdateAllowlist>,
+        program_id: Pubkey,
+    ) -> Result<()> {
+        let allowlist = &mut ctx.accounts.allowlist;
+        let index = allowlist
+            .programs
+            .iter()
+            .position(|listed| *listed == program_id)
+            .ok_or(NyseError::NotAllowlisted)?;
+        allowlist.programs.swap_remove(index);
+
+        msg!("➖ Removed program {} from allowlist", program_id);
+        emit!(AllowlistUpdated::new(
+            &ctx.accounts.allowlist,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Create the mint's blocked-wallet denylist. Only the mint's
+    /// transfer-hook authority may do this, and it becomes the list authority.
+    pub fn initialize_denylist(ctx: Context<InitializeDenylist>) -> Result<()> {
+        require!(cfg!(feature = "denylist"), NyseError::FeatureDisabled);
+        require!(
+            transfer_hook_authority(&ctx.accounts.mint.to_account_info())?
+                == Some(ctx.accounts.authority.key()),
+            NyseError::Unauthorized
+        );
+
+        let denylist = &mut ctx.accounts.denylist;
+        denylist.authority = ctx.accounts.authority.key();
+        denylist.entries = Vec::new();
+        denylist.bump = ctx.bumps.denylist;
+
+        msg!("📋 Denylist initialized");
+        Ok(())
+    }
+
+    /// Block a wallet from sending or receiving (list authority only)
+    pub fn add_to_denylist(ctx: Context<UpdateDenylist>, wallet: Pubkey) -> Result<()> {
+        let denylist = &mut ctx.accounts.denylist;
+        require!(!denylist.contains(&wallet), NyseError::AlreadyDenylisted);
+        require!(
+            denylist.entries.len() < MAX_DENYLIST_ENTRIES,
+            NyseError::DenylistFull
+        );
+        denylist.entries.push(wallet);
+
+        msg!("⛔ Denylisted {}", wallet);
+        emit!(DenylistUpdated::new(
+            &ctx.accounts.denylist,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Unblock a wallet (list authority only)
+    pub fn remove_from_denylist(ctx: Context<UpdateDenylist>, wallet: Pubkey) -> Result<()> {
+        let denylist = &mut ctx.accounts.denylist;
+        let index = denylist
+            .entries
+            .iter()
+            .position(|entry| entry == &wallet)
+            .ok_or(NyseError::NotDenylisted)?;
+        denylist.entries.swap_remove(index);
+
+        msg!("➖ Removed {} from denylist", wallet);
+        emit!(DenylistUpdated::new(
+            &ctx.accounts.denylist,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Create the mint's holiday calendar for closures outside the built-in
+    /// rules. Only the mint's transfer-hook authority may do this, and it
+    /// becomes the calendar authority.
+    pub fn initialize_holiday_calendar(ctx: Context<InitializeHolidayCalendar>) -> Result<()> {
+        require!(
+            cfg!(feature = "holiday-calendar"),
+            NyseError::FeatureDisabled
+        );
+        require!(
+            transfer_hook_authority(&ctx.accounts.mint.to_account_info())?
+                == Some(ctx.accounts.authority.key()),
+            NyseError::Unauthorized
+        );
+
+        let calendar = &mut ctx.accounts.holiday_calendar;
+        calendar.authority = ctx.accounts.authority.key();
+        calendar.dates = Vec::new();
+        calendar.early_closes = Vec::new();
+        calendar.bump = ctx.bumps.holiday_calendar;
+
+        msg!("📅 Holiday calendar initialized");
+        Ok(())
+    }
+
+    /// Close the market for a full local day (calendar authority only)
+    pub fn add_holiday(ctx: Context<UpdateHolidayCalendar>, date: HolidayDate) -> Result<()> {
+        validate_holiday_date(&date)?;
+        let calendar = &mut ctx.accounts.holiday_calendar;
+        let index = match calendar.dates.binary_search(&date) {
+            Ok(_) => return err!(NyseError::HolidayAlreadyListed),
+            Err(index) => index,
+        };
+        require!(
+            calendar.dates.len() < MAX_CALENDAR_ENTRIES,
+            NyseError::HolidayCalendarFull
+        );
+        calendar.dates.insert(index, date);
+
+        msg!(
+            "➕ Holiday added: {}-{:02}-{:02}",
+            date.year,
+            date.month,
+            date.day
+        );
+        emit!(CalendarUpdated::new(
+            &ctx.accounts.holiday_calendar,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// List a half day closing at `close_minute` local, e.g. to encode the
+    /// exchange's published half-day schedule (calendar authority only)
+    pub fn add_early_close(
+        ctx: Context<UpdateHolidayCalendar>,
+        early_close: EarlyCloseOverride,
+    ) -> Result<()> {
+        validate_holiday_date(&early_close.date)?;
+        require!(
+            early_close.close_minute <= 24 * 60,
+            NyseError::InvalidMarketHours
+        );
+        let calendar = &mut ctx.accounts.holiday_calendar;
+        let index = match calendar
+            .early_closes
+            .binary_search_by_key(&early_close.date, |listed| listed.date)
+        {
+            Ok(_) => return err!(NyseError::HolidayAlreadyListed),
+            Err(index) => index,
+        };
+        require!(
+            calendar.early_closes.len() < MAX_EARLY_CLOSE_ENTRIES,
+            NyseError::HolidayCalendarFull
+        );
+        calendar.early_closes.insert(index, early_close);
+
+        msg!(
+            "➕ Early close added: {}-{:02}-{:02} at {:02}:{:02}",
+            early_close.date.year,
+            early_close.date.month,
+            early_close.date.day,
+            early_close.close_minute / 60,
+            early_close.close_minute % 60
+        );
+        emit!(CalendarUpdated::new(
+            &ctx.accounts.holiday_calendar,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Drop a listed half day (calendar authority only)
+    pub fn remove_early_close(
+        ctx: Context<UpdateHolidayCalendar>,
+        date: HolidayDate,
+    ) -> Result<()> {
+        let calendar = &mut ctx.accounts.holiday_calendar;
+        let index = calendar
+            .early_closes
+            .binary_search_by_key(&date, |listed| listed.date)
+            .map_err(|_| NyseError::HolidayNotListed)?;
+        calendar.early_closes.remove(index);
+
+        msg!(
+            "➖ Early close removed: {}-{:02}-{:02}",
+            date.year,
+            date.month,
+            date.day
+        );
+        emit!(CalendarUpdated::new(
+            &ctx.accounts.holiday_calendar,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Reopen a previously added date (calendar authority only)
+    pub fn remove_holiday(ctx: Context<UpdateHolidayCalendar>, date: HolidayDate) -> Result<()> {
+        let calendar = &mut ctx.accounts.holiday_calendar;
+        let index = calendar
+            .dates
+            .binary_search(&date)
+            .map_err(|_| NyseError::HolidayNotListed)?;
+        calendar.dates.remove(index);
+
+        msg!(
+            "➖ Holiday removed: {}-{:02}-{:02}",
+            date.year,
+            date.month,
+            date.day
+        );
+        emit!(CalendarUpdated::new(
+            &ctx.accounts.holiday_calendar,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Create the mint's rent reserve, which pays for the per-owner accounts
+    /// the hook creates. Only the mint's transfer-hook authority may do this.
+    pub fn initialize_rent_reserve(ctx: Context<InitializeRentReserve>) -> Result<()> {
+        require!(
+            transfer_hook_authority(&ctx.accounts.mint.to_account_info())?
+                == Some(ctx.accounts.authority.key()),
+            NyseError::Unauthorized
+        );
+        ctx.accounts.rent_reserve.bump = ctx.bumps.rent_reserve;
+
+        msg!("💰 Rent reserve initialized; fund it with a SOL transfer");
+        Ok(())
+    }
+
+    /// Create the mint's transition tracker, starting closed so the first
+    /// open verdict announces `MarketOpened`. Only the mint's transfer-hook
+    /// authority may do this.
+    pub fn initialize_market_transition(ctx: Context<InitializeMarketTransition>) -> Result<()> {
+        require!(
+            transfer_hook_authority(&ctx.accounts.mint.to_account_info())?
+                == Some(ctx.accounts.authority.key()),
+            NyseError::Unauthorized
+        );
+
+        let transition = &mut ctx.accounts.market_transition;
+        transition.is_open = false;
+        transition.timestamp = 0;
+        transition.last_decision_status = None;
+        transition.bump = ctx.bumps.market_transition;
+
+        msg!("🔔 Market transition tracker initialized");
+        Ok(())
+    }
+
+    /// Create the mint's market-state history, empty. Only the mint's
+    /// transfer-hook authority may do this; anyone can then append to it
+    /// with `record_state_snapshot`.
+    pub fn initialize_state_history(ctx: Context<InitializeStateHistory>) -> Result<()> {
+        require!(
+            transfer_hook_authority(&ctx.accounts.mint.to_account_info())?
+                == Some(ctx.accounts.authority.key()),
+            NyseError::Unauthorized
+        );
+
+        let history = &mut ctx.accounts.state_history;
+        history.next = 0;
+        history.snapshots = Vec::new();
+        history.bump = ctx.bumps.state_history;
+
+        msg!("🗂️ State history initialized");
+        Ok(())
+    }
+
+    /// Create the mint's transfer counters. Only the mint's transfer-hook
+    /// authority may do this, and it becomes the stats authority.
+    pub fn initialize_hook_stats(ctx: Context<InitializeHookStats>) -> Result<()> {
+        require!(cfg!(feature = "hook-stats"), NyseError::FeatureDisabled);
+        require!(
+            transfer_hook_authority(&ctx.accounts.mint.to_account_info())?
+                == Some(ctx.accounts.authority.key()),
+            NyseError::Unauthorized
+        );
+
+        let stats = &mut ctx.accounts.hook_stats;
+        stats.authority = ctx.accounts.authority.key();
+        stats.reset();
+        stats.bump = ctx.bumps.hook_stats;
+
+        msg!("📈 Hook stats initialized");
+        Ok(())
+    }
+
+    /// Create the mint's circuit breaker, which `set_circuit_breaker`
+    /// configures. Only the mint's transfer-hook authority may do this.
+    pub fn initialize_circuit_breaker(ctx: Context<InitializeCircuitBreaker>) -> Result<()> {
+        require!(
+            cfg!(feature = "circuit-breaker"),
+            NyseError::FeatureDisabled
+        );
+        require!(
+            transfer_hook_authority(&ctx.accounts.mint.to_account_info())?
+                == Some(ctx.accounts.authority.key()),
+            NyseError::Unauthorized
+        );
+
+        let breaker = &mut ctx.accounts.circuit_breaker;
+        breaker.volume = 0;
+        breaker.last_update = 0;
+        breaker.tripped_at = 0;
+        breaker.bump = ctx.bumps.circuit_breaker;
+
+        msg!("🧯 Circuit breaker initialized");
+        Ok(())
+    }
+
+    /// Zero the transfer counters (stats authority only)
+    pub fn reset_stats(ctx: Context<ResetHookStats>) -> Result<()> {
+        ctx.accounts.hook_stats.reset();
+
+        msg!("📈 Hook stats reset");
+        Ok(())
+    }
+
+    /// Create the deployment-wide settings. Only the program's upgrade
+    /// authority may do this, and it becomes the settings authority.
+    pub fn initialize_program_settings(
+        ctx: Context<InitializeProgramSettings>,
+        require_config: bool,
+    ) -> Result<()> {
+        let settings = &mut ctx.accounts.program_settings;
+        settings.authority = ctx.accounts.authority.key();
+        settings.require_config = require_config;
+        settings.frozen = false;
+        settings.super_authority = ctx.accounts.authority.key();
+        settings.bump = ctx.bumps.program_settings;
+
+        msg!(
+            "🌍 Program settings initialized: require config {}",
+            require_config
+        );
+        Ok(())
+    }
+
+    /// Reject transfers of mints with no MarketConfig (`true`), or run them
+    /// on the built-in NYSE defaults (`false`, the behaviour without
+    /// settings); settings authority only
+    pub fn set_require_config(
+        ctx: Context<UpdateProgramSettings>,
+        require_config: bool,
+    ) -> Result<()> {
+        ctx.accounts.program_settings.require_config = require_config;
+
+        msg!("🌍 Require config: {}", require_config);
+        emit!(ProgramSettingsUpdated::new(
+            &ctx.accounts.program_settings,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Hand the global freeze to another key, such as an incident-response
+    /// multisig; settings authority only
+    pub fn set_super_authority(
+        ctx: Context<UpdateProgramSettings>,
+        super_authority: Pubkey,
+    ) -> Result<()> {
+        ctx.accounts.program_settings.super_authority = super_authority;
+
+        msg!("🌍 Super authority: {}", super_authority);
+        emit!(ProgramSettingsUpdated::new(
+            &ctx.accounts.program_settings,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Stop (`true`) or resume (`false`) transfers of every mint at once, for
+    /// catastrophic incidents. Overrides each mint's own config, including
+    /// its allowlist; the super authority or the settings authority only.
+    pub fn set_global_freeze(ctx: Context<SetGlobalFreeze>, frozen: bool) -> Result<()> {
+        ctx.accounts.program_settings.frozen = frozen;
+
+        msg!("🧊 Global freeze: {}", frozen);
+        emit!(ProgramSettingsUpdated::new(
+            &ctx.accounts.program_settings,
+            ctx.accounts.authority.key()
+        ));
+        Ok(())
+    }
+
+    /// Start the age clock of one of the mint's token accounts, for
+    /// `set_min_destination_age`. Permissionless: the payer covers the rent,
+    /// and the age counts from now, so registering never backdates an
+    /// account.
+    pub fn register_token_account(ctx: Context<RegisterTokenAccount>) -> Result<()> {
+        let age = &mut ctx.accounts.account_age;
+        age.registered_at = Clock::get()?.unix_timestamp;
+        age.bump = ctx.bumps.account_age;
+
+        msg!(
+            "🐣 Token account {} registered",
+            ctx.accounts.token_account.key()
+        );
+        Ok(())
+    }
+
+    /// Create the mint's market-state cache so busy mints evaluate the
+    /// calendar at most once per minute. Only the mint's transfer-hook
+    /// authority may do this.
+    pub fn initialize_market_state_cache(ctx: Context<InitializeMarketStateCache>) -> Result<()> {
+        require!(cfg!(feature = "state-cache"), NyseError::FeatureDisabled);
+        require!(
+            transfer_hook_authority(&ctx.accounts.mint.to_account_info())?
+                == Some(ctx.accounts.authority.key()),
+            NyseError::Unauthorized
+        );
+
+        let cache = &mut ctx.accounts.market_state_cache;
+        cache.minute = i64::MIN;
+        cache.state = MarketState::HALTED;
+        cache.bump = ctx.bumps.market_state_cache;
+        cache.config_version = 0;
+
+        msg!("🗄️  Market state cache initialized");
+        Ok(())
+    }
+
+    /// Let one transfer of exactly `amount` from the `source` to the
+    /// `destination` token account through while the market is closed,
+    /// until `expires_at` (UTC timestamp). The hook closes the voucher when
+    /// it uses it. Config authority only.
+    pub fn issue_transfer_voucher(
+        ctx: Context<IssueTransferVoucher>,
+        source: Pubkey,
+        destination: Pubkey,
+        amount: u64,
+        expires_at: i64,
+    ) -> Result<()> {
+        require!(
+            cfg!(feature = "transfer-vouchers"),
+            NyseError::FeatureDisabled
+        );
+        require!(
+            expires_at > Clock::get()?.unix_timestamp,
+            NyseError::InvalidTimestamp
+        );
+        let voucher = &mut ctx.accounts.transfer_voucher;
+        voucher.mint = ctx.accounts.mint.key();
+        voucher.source = source;
+        voucher.destination = destination;
+        voucher.amount = amount;
+        voucher.expires_at = expires_at;
+        voucher.bump = ctx.bumps.transfer_voucher;
+
+        msg!(
+            "🎟️  Voucher: {} from {} to {} until {}",
+            amount,
+            source,
+            destination,
+            format_display_timestamp(expires_at, Some(&ctx.accounts.market_config))
+        );
+        Ok(())
+    }
+
+    /// Withdraw an unused or expired voucher, sending its rent to
+    /// `recipient` (config authority only)
+    pub fn revoke_transfer_voucher(ctx: Context<RevokeTransferVoucher>) -> Result<()> {
+        msg!(
+            "🎟️  Voucher from {} to {} revoked",
+            ctx.accounts.transfer_voucher.source,
+            ctx.accounts.transfer_voucher.destination
+        );
+        Ok(())
+    }
+
+    /// Report the next NYSE holiday after today's Eastern date via return
+    /// data, Borsh-encoded as `(year: i32, month: u32, day: u32)`
+    pub fn get_next_holiday(_ctx: Context<GetNextHoliday>) -> Result<()> {
+        let clock = Clock::get()?;
+        let timestamp = effective_timestamp(clock.unix_timestamp, None);
+        require!(is_valid_timestamp(timestamp), NyseError::InvalidTimestamp);
+        let holiday = next_holiday_after(timestamp);
+        set_return_data(&holiday.try_to_vec()?);
+        Ok(())
+    }
+
+    /// Report the Eastern Time breakdown of the current clock via return data
+    pub fn get_local_time(_ctx: Context<GetLocalTime>) -> Result<EasternTimeInfo> {
+        let clock = Clock::get()?;
+        get_eastern_time_info(effective_timestamp(clock.unix_timestamp, None))
+    }
+
+    /// Fallback function for transfer hook interface
+    pub fn fallback<'info>(
+        _program_id: &Pubkey,
+        accounts: &'info [AccountInfo<'info>],
+        data: &[u8],
+    ) -> Result<()> {
+        let instruction = decode_hook_instruction(data)?;
+
+        match instruction {
+            // Run transfer_hook the way Anchor's dispatcher would, minus its
+            // "Instruction:" log line, which every transfer would pay for
+            TransferHookInstruction::Execute { amount } => {
+                let mut bumps = TransferHookBumps::default();
+                let mut remaining_accounts = accounts;
+                let mut hook = TransferHook::try_accounts(
+                    _program_id,
+                    &mut remaining_accounts,
+                    &amount.to_le_bytes(),
+                    &mut bumps,
+                    &mut std::collections::BTreeSet::new(),
+                )?;
+                transfer_hook(
+                    Context::new(_program_id, &mut hook, remaining_accounts, bumps),
+                    amount,
+                )?;
+                hook.exit(_program_id)
+            }
+            // The interface initializer takes caller-chosen metas in a
+            // different account order; this program registers its own list
+            // through the Anchor instructions instead
+            TransferHookInstruction::InitializeExtraAccountMetaList { .. }
+            | TransferHookInstruction::UpdateExtraAccountMetaList { .. } => {
+                msg!("❌ Use initialize_extra_account_meta_list or update_extra_account_meta_list");
+                err!(NyseError::UnsupportedHookInstruction)
+            }
+        }
+    }
+}
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// The extra account metas list account
+    /// CHECK: This account is initialized as a TLV ExtraAccountMetaList
+    #[account(
+        init,
+        payer = payer,
+        space = ExtraAccountMetaList::size_of(extra_account_metas()?.len())?,
+        seeds = [EXTRA_ACCOUNT_METAS_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub extra_account_meta_list: AccountInfo<'info>,
+
+    /// The mint account
+    /// CHECK: This account is passed in and used for PDA derivation
+    pub mint: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EnsureExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: PDA validated by seeds; created or checked as a TLV
+    /// ExtraAccountMetaList
+    #[account(mut, seeds = [EXTRA_ACCOUNT_METAS_SEED, mint.key().as_ref()], bump)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    /// CHECK: This account is passed in and used for PDA derivation
+    pub mint: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Transfer-hook authority of the mint
+    pub authority: Signer<'info>,
+
+    /// CHECK: PDA validated by seeds; rewritten as a TLV ExtraAccountMetaList
+    #[account(mut, seeds = [EXTRA_ACCOUNT_METAS_SEED, mint.key().as_ref()], bump)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseExtraAccountMetaList<'info> {
+    /// Transfer-hook authority of the mint
+    pub authority: Signer<'info>,
+
+    /// CHECK: Only receives the list's lamports
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    /// CHECK: PDA validated by seeds; closed
+    #[account(mut, seeds = [EXTRA_ACCOUNT_METAS_SEED, mint.key().as_ref()], bump)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+}
+
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    /// Source token account
+    #[account(
+        token::mint = mint,
+        token::authority = owner
+    )]
+    pub source_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Mint account
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    /// Destination token account
+    #[account(token::mint = mint)]
+    pub destination_token: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    /// Owner of the source token account
+    /// CHECK: This account is passed through from the transfer instruction
+    pub owner: UncheckedAccount<'info>,
+
+    /// Extra account metas list
+    /// CHECK: This PDA is derived from the mint and validated by seeds constraint
+    #[account(seeds = [EXTRA_ACCOUNT_METAS_SEED, mint.key().as_ref()], bump)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    // The PDAs below aren't derived here. Each one that exists is checked
+    // against the bump it stored, one hash instead of a bump search, and an
+    // empty one reads as missing. In a transfer Token-2022 resolves every
+    // address from the extra account list, so only `simulate_transfer` can
+    // be handed others.
+    /// Per-mint market config; left uninitialized to use the NYSE defaults.
+    /// Writable so the hook can track the last clock reading.
+    /// CHECK: Checked against its stored bump when loaded
+    #[account(mut)]
+    pub market_config: UncheckedAccount<'info>,
+
+    // The optional subsystems' accounts only exist in builds with the
+    // feature; extra_account_metas registers the same set
+    /// Per-mint exempt wallets; left uninitialized when nobody is exempt
+    /// CHECK: Checked against its stored bump when loaded
+    #[cfg(feature = "allowlist")]
+    pub allowlist: UncheckedAccount<'info>,
+
+    /// Per-mint extra closures; left uninitialized to use the built-in rules
+    /// CHECK: Checked against its stored bump when loaded
+    #[cfg(feature = "holiday-calendar")]
+    pub holiday_calendar: UncheckedAccount<'info>,
+
+    /// Per-mint verdict cache; left uninitialized to compute every time
+    /// CHECK: Checked against its stored bump when loaded
+    #[cfg(feature = "state-cache")]
+    #[account(mut)]
+    pub market_state_cache: UncheckedAccount<'info>,
+
+    /// Per-mint blocked wallets; left uninitialized when nobody is blocked
+    /// CHECK: Checked against its stored bump when loaded
+    #[cfg(feature = "denylist")]
+    pub denylist: UncheckedAccount<'info>,
+
+    /// Pays for the per-owner PDAs the hook creates
+    /// CHECK: Checked against its stored bump before it's debited
+    #[cfg(feature = "rent-reserve")]
+    #[account(mut)]
+    pub rent_reserve: UncheckedAccount<'info>,
+
+    /// Source owner's rolling volume; created on the first capped transfer
+    /// CHECK: Checked against its stored bump when loaded
+    #[cfg(feature = "volume-tracking")]
+    #[account(mut)]
+    pub volume_tracker: UncheckedAccount<'info>,
+
+    #[cfg(any(
+        feature = "volume-tracking",
+        feature = "cooldown",
+        feature = "daily-counter"
+    ))]
+    pub system_program: Program<'info, System>,
+
+    /// Per-mint open/close tracker; left uninitialized to skip transition events
+    /// CHECK: Checked against its stored bump when loaded
+    #[cfg(feature = "transition-events")]
+    #[account(mut)]
+    pub market_transition: UncheckedAccount<'info>,
+
+    /// Per-mint transfer counters; left uninitialized to skip counting
+    /// CHECK: Checked against its stored bump when loaded
+    #[cfg(feature = "hook-stats")]
+    #[account(mut)]
+    pub hook_stats: UncheckedAccount<'info>,
+
+    /// Lists the transaction's signers for the large-trade approval
+    /// CHECK: Address checked; read with load_instruction_at_checked
+    #[account(address = sysvar::instructions::ID)]
+    pub instructions_sysvar: UncheckedAccount<'info>,
+
+    /// Source owner's last transfer; created on the first cooled-down transfer
+    /// CHECK: Checked against its stored bump when loaded
+    #[cfg(feature = "cooldown")]
+    #[account(mut)]
+    pub last_transfer: UncheckedAccount<'info>,
+
+    /// Deployment-wide policy; left uninitialized to allow defaults
+    /// CHECK: Checked against its stored bump when loaded
+    pub program_settings: UncheckedAccount<'info>,
+
+    /// When the destination token account was registered; only read when
+    /// the config sets a minimum destination age
+    /// CHECK: Checked against its stored bump when loaded
+    #[cfg(feature = "destination-age")]
+    pub destination_age: UncheckedAccount<'info>,
+
+    /// Mint-wide volume breaker; left uninitialized to skip it
+    /// CHECK: Checked against its stored bump when loaded
+    #[cfg(feature = "circuit-breaker")]
+    #[account(mut)]
+    pub circuit_breaker: UncheckedAccount<'info>,
+
+    /// One-off exemption for this exact transfer; left uninitialized when
+    /// none was issued. Closed into the rent reserve once used.
+    /// CHECK: Checked against its stored bump when loaded
+    #[cfg(feature = "transfer-vouchers")]
+    #[account(mut)]
+    pub transfer_voucher: UncheckedAccount<'info>,
+
+    /// Source owner's transfers this trading day; created on the first
+    /// transfer under a daily limit
+    /// CHECK: Checked against its stored bump when loaded
+    #[cfg(feature = "daily-counter")]
+    #[account(mut)]
+    pub daily_counter: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMarketConfig<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Transfer-hook authority of the mint
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MarketConfig::INIT_SPACE,
+        seeds = [MARKET_CONFIG_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseMarketConfig<'info> {
+    pub authority: Signer<'info>,
+
+    /// CHECK: Only receives the config's lamports
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [MARKET_CONFIG_SEED, mint.key().as_ref()],
+        bump = market_config.bump,
+        has_one = authority @ NyseError::Unauthorized,
+        close = recipient
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateMarketConfig<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, has_one = authority @ NyseError::Unauthorized)]
+    pub market_config: Account<'info, MarketConfig>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    /// Key named by `propose_authority`
+    pub new_authority: Signer<'info>,
+
+    #[account(mut)]
+    pub market_config: Account<'info, MarketConfig>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeAllowlist<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Transfer-hook authority of the mint
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Allowlist::INIT_SPACE,
+        seeds = [ALLOWLIST_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub allowlist: Account<'info, Allowlist>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAllowlist<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, has_one = authority @ NyseError::Unauthorized)]
+    pub allowlist: Account<'info, Allowlist>,
+}
+
+#[derive(Accounts)]
+pub struct ReplaceAllowlist<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        has_one = authority @ NyseError::Unauthorized,
+        realloc = 8 + Allowlist::INIT_SPACE,
+        realloc::payer = payer,
+        realloc::zero = false
+    )]
+    pub allowlist: Account<'info, Allowlist>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeDenylist<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Transfer-hook authority of the mint
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Denylist::INIT_SPACE,
+        seeds = [DENYLIST_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub denylist: Account<'info, Denylist>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateDenylist<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, has_one = authority @ NyseError::Unauthorized)]
+    pub denylist: Account<'info, Denylist>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeHolidayCalendar<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Transfer-hook authority of the mint
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + HolidayCalendar::INIT_SPACE,
+        seeds = [HOLIDAY_CALENDAR_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub holiday_calendar: Account<'info, HolidayCalendar>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateHolidayCalendar<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, has_one = authority @ NyseError::Unauthorized)]
+    pub holiday_calendar: Account<'info, HolidayCalendar>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRentReserve<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Transfer-hook authority of the mint
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + RentReserve::INIT_SPACE,
+        seeds = [RENT_RESERVE_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub rent_reserve: Account<'info, RentReserve>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeHookStats<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Transfer-hook authority of the mint
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + HookStats::INIT_SPACE,
+        seeds = [HOOK_STATS_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub hook_stats: Account<'info, HookStats>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeCircuitBreaker<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Transfer-hook authority of the mint
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + CircuitBreaker::INIT_SPACE,
+        seeds = [CIRCUIT_BREAKER_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub circuit_breaker: Account<'info, CircuitBreaker>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ResetHookStats<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(mut, has_one = authority @ NyseError::Unauthorized)]
+    pub hook_stats: Account<'info, HookStats>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeProgramSettings<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Upgrade authority of this program
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + ProgramSettings::INIT_SPACE,
+        seeds = [PROGRAM_SETTINGS_SEED],
+        bump
+    )]
+    pub program_settings: Account<'info, ProgramSettings>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()))]
+    pub program: Program<'info, crate::program::NyseTokenHook>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key())
+            @ NyseError::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProgramSettings<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SETTINGS_SEED],
+        bump = program_settings.bump,
+        has_one = authority @ NyseError::Unauthorized
+    )]
+    pub program_settings: Account<'info, ProgramSettings>,
+}
+
+#[derive(Accounts)]
+pub struct SetGlobalFreeze<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [PROGRAM_SETTINGS_SEED],
+        bump = program_settings.bump,
+        constraint = authority.key() == program_settings.super_authority
+            || authority.key() == program_settings.authority
+            @ NyseError::Unauthorized
+    )]
+    pub program_settings: Account<'info, ProgramSettings>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterTokenAccount<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(token::mint = mint)]
+    pub token_account: InterfaceAccount<'info, token_interface::TokenAccount>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + AccountAge::INIT_SPACE,
+        seeds = [ACCOUNT_AGE_SEED, mint.key().as_ref(), token_account.key().as_ref()],
+        bump
+    )]
+    pub account_age: Account<'info, AccountAge>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMarketStateCache<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Transfer-hook authority of the mint
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MarketStateCache::INIT_SPACE,
+        seeds = [MARKET_STATE_CACHE_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub market_state_cache: Account<'info, MarketStateCache>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(source: Pubkey, destination: Pubkey)]
+pub struct IssueTransferVoucher<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Config authority of the mint
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, mint.key().as_ref()],
+        bump = market_config.bump,
+        has_one = authority @ NyseError::Unauthorized
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + TransferVoucher::INIT_SPACE,
+        seeds = [
+            TRANSFER_VOUCHER_SEED,
+            mint.key().as_ref(),
+            source.as_ref(),
+            destination.as_ref()
+        ],
+        bump
+    )]
+    pub transfer_voucher: Account<'info, TransferVoucher>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeTransferVoucher<'info> {
+    /// Config authority of the mint
+    pub authority: Signer<'info>,
+
+    /// CHECK: Only receives the voucher's lamports
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [MARKET_CONFIG_SEED, mint.key().as_ref()],
+        bump = market_config.bump,
+        has_one = authority @ NyseError::Unauthorized
+    )]
+    pub market_config: Account<'info, MarketConfig>,
+
+    #[account(mut, has_one = mint @ NyseError::MintMismatch, close = recipient)]
+    pub transfer_voucher: Account<'info, TransferVoucher>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeMarketTransition<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Transfer-hook authority of the mint
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + MarketTransition::INIT_SPACE,
+        seeds = [MARKET_TRANSITION_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub market_transition: Account<'info, MarketTransition>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordMarketTransition<'info> {
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    /// CHECK: PDA validated by seeds and deserialized by MarketConfig::load
+    #[account(seeds = [MARKET_CONFIG_SEED, mint.key().as_ref()], bump)]
+    pub market_config: UncheckedAccount<'info>,
+
+    /// CHECK: PDA validated by seeds and deserialized by HolidayCalendar::load
+    #[account(seeds = [HOLIDAY_CALENDAR_SEED, mint.key().as_ref()], bump)]
+    pub holiday_calendar: UncheckedAccount<'info>,
+
+    /// CHECK: PDA validated by seeds and deserialized by MarketTransition::load
+    #[account(mut, seeds = [MARKET_TRANSITION_SEED, mint.key().as_ref()], bump)]
+    pub market_transition: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeStateHistory<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// Transfer-hook authority of the mint
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + StateHistory::INIT_SPACE,
+        seeds = [STATE_HISTORY_SEED, mint.key().as_ref()],
+        bump
+    )]
+    pub state_history: Account<'info, StateHistory>,
+
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RecordStateSnapshot<'info> {
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    /// CHECK: PDA validated by seeds and deserialized by MarketConfig::load
+    #[account(seeds = [MARKET_CONFIG_SEED, mint.key().as_ref()], bump)]
+    pub market_config: UncheckedAccount<'info>,
+
+    /// CHECK: PDA validated by seeds and deserialized by HolidayCalendar::load
+    #[account(seeds = [HOLIDAY_CALENDAR_SEED, mint.key().as_ref()], bump)]
+    pub holiday_calendar: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [STATE_HISTORY_SEED, mint.key().as_ref()],
+        bump = state_history.bump
+    )]
+    pub state_history: Account<'info, StateHistory>,
+}
+
+#[derive(Accounts)]
+pub struct CheckMarketState<'info> {
+    /// CHECK: Only used for PDA derivation, so another mint's config or
+    /// calendar can't stand in for this one's
+    pub mint: UncheckedAccount<'info>,
+
+    #[account(seeds = [MARKET_CONFIG_SEED, mint.key().as_ref()], bump)]
+    pub market_config: Option<Account<'info, MarketConfig>>,
+
+    #[account(seeds = [HOLIDAY_CALENDAR_SEED, mint.key().as_ref()], bump)]
+    pub holiday_calendar: Option<Account<'info, HolidayCalendar>>,
+}
+
+#[derive(Accounts)]
+pub struct CheckAccountStatus<'info> {
+    pub mint: InterfaceAccount<'info, token_interface::Mint>,
+
+    #[account(seeds = [ALLOWLIST_SEED, mint.key().as_ref()], bump)]
+    pub allowlist: Option<Account<'info, Allowlist>>,
+
+    #[account(seeds = [DENYLIST_SEED, mint.key().as_ref()], bump)]
+    pub denylist: Option<Account<'info, Denylist>>,
+}
+
+#[derive(Accounts)]
+pub struct GetLocalTime<'info> {
+    /// CHECK: Never read; the CPI client Anchor generates needs an account
+    pub mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetNextHoliday<'info> {
+    /// CHECK: Never read; the CPI client Anchor generates needs an account
+    pub mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct GetProgramInfo<'info> {
+    /// CHECK: Never read; the CPI client Anchor generates needs an account
+    pub mint: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ValidateHookSetup<'info> {
+    /// CHECK: PDA validated by seeds; only read, and may not exist yet
+    #[account(seeds = [EXTRA_ACCOUNT_METAS_SEED, mint.key().as_ref()], bump)]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+
+    /// CHECK: Used for PDA derivation; its transfer-hook extension is read
+    /// if it has one
+    pub mint: UncheckedAccount<'info>,
+}
+
+/// One date's trading hours, written as return data by
+/// `get_schedule_for_date`.
+///
+/// Borsh layout (6 bytes): `[is_trading_day: u8 (0|1)][open_minute: u16 LE]
+/// [close_minute: u16 LE][early_close: u8 (0|1)]`, minutes after local
+/// midnight. Closed dates report zeros; continuous mints report 0 and 1440.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DaySchedule {
+    pub is_trading_day: bool,
+    /// First open of the day
+    pub open_minute: u16,
+    /// Last close of the day, after any half-day or scheduled early close
+    pub close_minute: u16,
+    pub early_close: bool,
+}
+
+/// Time until the market next opens or closes, appended to the
+/// `check_market_state` return data for "closes in 12 minutes" displays.
+///
+/// Borsh layout (9 bytes): `[seconds_until_next_transition: i64 LE]
+/// [next_transition_is_open: u8 (0|1)]`. Unlike `MarketState`'s
+/// `next_transition`, this skips phase changes that leave the market as
+/// open or as closed as it is (a pre-market start, say). Zero seconds when
+/// no open or close falls in the next two weeks.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MarketCountdown {
+    pub seconds_until_next_transition: i64,
+    /// Whether the market is open after that transition
+    pub next_transition_is_open: bool,
+}
+
+/// Outcome of `simulate_transfer`, written as its return data.
+///
+/// Borsh layout: `[allowed: u8 (0|1)][allow_reason: Option<AllowReason>]
+/// [error_code: u64 LE]`. An allowed transfer carries the `AllowReason`,
+/// or none when only the WarnOnly rollout lets it through. A blocked one
+/// carries the code `transfer_hook` would fail with: the custom error code
+/// (6020 for `VolumeLimitExceeded`) or the runtime's builtin error code.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransferVerdict {
+    pub allowed: bool,
+    pub allow_reason: Option<AllowReason>,
+    pub error_code: u64,
+}
+
+/// Build identity returned by `get_program_info`.
+///
+/// Borsh layout: `[version: u32 LE length + UTF-8 semver]
+/// [default_open_minute: u16 LE][default_close_minute: u16 LE]
+/// [features: u32 LE]`, the minutes being the NYSE session used by mints
+/// without a MarketConfig and `features` a set of `FEATURE_*` bits.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ProgramInfo {
+    pub version: String,
+    pub default_open_minute: u16,
+    pub default_close_minute: u16,
+    pub features: u32,
+}
+
+/// Verdict on a mint's extra account meta list, from `validate_hook_setup`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookSetupStatus {
+    /// The list resolves exactly the accounts `transfer_hook` expects
+    Ready,
+    /// The list PDA hasn't been created; run
+    /// `initialize_extra_account_meta_list`
+    Missing,
+    /// The list PDA exists but isn't owned by this program
+    WrongOwner,
+    /// The list is sized for a different set of extra accounts, usually one
+    /// registered by an older build; run `update_extra_account_meta_list`
+    WrongSize,
+    /// The list is the right size but resolves different accounts; run
+    /// `update_extra_account_meta_list`
+    WrongAccounts,
+}
+
+/// Setup diagnostic returned by `validate_hook_setup`.
+///
+/// Borsh layout (10 bytes): `[status: u8 (HookSetupStatus)]
+/// [hook_attached: u8 (0|1)][expected_size: u32 LE][actual_size: u32 LE]`,
+/// the sizes being the list's bytes this build registers and the bytes the
+/// list account holds (0 when missing).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HookSetupReport {
+    pub status: HookSetupStatus,
+    /// Whether the mint's transfer-hook extension names this program
+    pub hook_attached: bool,
+    pub expected_size: u32,
+    pub actual_size: u32,
+}
+
+/// `ProgramInfo::features` bit: exempt-wallet allowlists
+pub const FEATURE_ALLOWLIST: u32 = 1 << 0;
+/// `ProgramInfo::features` bit: per-mint MarketConfig
+pub const FEATURE_MARKET_CONFIG: u32 = 1 << 1;
+/// `ProgramInfo::features` bit: pre-market and after-hours trading
+pub const FEATURE_EXTENDED_HOURS: u32 = 1 << 2;
+/// `ProgramInfo::features` bit: built with `testnet-calendar`
+pub const FEATURE_TESTNET_CALENDAR: u32 = 1 << 3;
+/// `ProgramInfo::features` bit: built with `time-override`
+pub const FEATURE_TIME_OVERRIDE: u32 = 1 << 4;
+/// `ProgramInfo::features` bit: wallets blocked at any hour
+pub const FEATURE_DENYLIST: u32 = 1 << 5;
+/// `ProgramInfo::features` bit: per-owner rolling volume caps
+pub const FEATURE_VOLUME_TRACKING: u32 = 1 << 6;
+/// `ProgramInfo::features` bit: the mint-wide circuit breaker
+pub const FEATURE_CIRCUIT_BREAKER: u32 = 1 << 7;
+/// `ProgramInfo::features` bit: per-mint closures and half days on top of
+/// the built-in NYSE holidays
+pub const FEATURE_HOLIDAY_CALENDAR: u32 = 1 << 8;
+/// `ProgramInfo::features` bit: the per-minute market-state cache
+pub const FEATURE_STATE_CACHE: u32 = 1 << 9;
+/// `ProgramInfo::features` bit: MarketOpened/MarketClosed from transfers
+pub const FEATURE_TRANSITION_EVENTS: u32 = 1 << 10;
+/// `ProgramInfo::features` bit: per-mint transfer counters
+pub const FEATURE_HOOK_STATS: u32 = 1 << 11;
+/// `ProgramInfo::features` bit: per-owner transfer cooldowns
+pub const FEATURE_COOLDOWN: u32 = 1 << 12;
+/// `ProgramInfo::features` bit: minimum destination account age
+pub const FEATURE_DESTINATION_AGE: u32 = 1 << 13;
+/// `ProgramInfo::features` bit: one-off transfer vouchers
+pub const FEATURE_TRANSFER_VOUCHERS: u32 = 1 << 14;
+/// `ProgramInfo::features` bit: per-owner daily transfer limits
+pub const FEATURE_DAILY_LIMIT: u32 = 1 << 15;
+
+/// Verdict of the hours check, written as return data by `transfer_hook`.
+///
+/// Borsh layout (11 bytes): `[is_open: u8 (0|1)][status: u8][reason: u8]
+/// [next_transition: i64 LE]`, where `status` and `reason` are the
+/// `MarketStatus` and `MarketReason` discriminants below.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct MarketState {
+    pub is_open: bool,
+    pub status: MarketStatus,
+    pub reason: MarketReason,
+    /// UTC timestamp of the next phase change (pre-market open, open, close,
+    /// post-market close), for countdowns; 0 when unknown, e.g. while halted
+    pub next_transition: i64,
+}
+
+impl MarketState {
+    /// Verdict while the config authority has paused the mint
+    pub const HALTED: Self = Self {
+        is_open: false,
+        status: MarketStatus::Halted,
+        reason: MarketReason::Halted,
+        next_transition: 0,
+    };
+}
+
+/// Emitted by `transfer_hook` whenever a transfer is rejected
+#[event]
+pub struct TransferBlocked {
+    pub amount: u64,
+    pub timestamp: i64,
+    /// `MarketStatus` discriminant
+    pub status: u8,
+    /// `MarketReason` discriminant
+    pub reason_code: u8,
+    /// The config's `closure_message` while halted or in maintenance,
+    /// otherwise zeros
+    pub closure_message: [u8; CLOSURE_MESSAGE_LEN],
+    /// UTC timestamp of the next open when the market hours blocked the
+    /// transfer, otherwise 0
+    pub next_open: i64,
+}
+
+impl TransferBlocked {
+    pub fn new(amount: u64, timestamp: i64, state: &MarketState) -> Self {
+        Self {
+            amount,
+            timestamp,
+            status: state.status as u8,
+            reason_code: state.reason as u8,
+            closure_message: [0; CLOSURE_MESSAGE_LEN],
+            next_open: 0,
+        }
+    }
+
+    pub fn with_closure_message(mut self, closure_message: [u8; CLOSURE_MESSAGE_LEN]) -> Self {
+        self.closure_message = closure_message;
+        self
+    }
+
+    pub fn with_next_open(mut self, next_open: i64) -> Self {
+        self.next_open = next_open;
+        self
+    }
+}
+
+/// Emitted by `transfer_hook` in `WarnOnly` mode for a transfer that the
+/// market state would have rejected; the transfer still goes through
+#[event]
+pub struct TransferWouldBlock {
+    pub amount: u64,
+    pub timestamp: i64,
+    /// `MarketStatus` discriminant
+    pub status: u8,
+    /// `MarketReason` discriminant
+    pub reason_code: u8,
+}
+
+impl TransferWouldBlock {
+    pub fn new(amount: u64, timestamp: i64, state: &MarketState) -> Self {
+        Self {
+            amount,
+            timestamp,
+            status: state.status as u8,
+            reason_code: state.reason as u8,
+        }
+    }
+}
+
+/// Emitted by `transfer_hook` whenever a transfer goes through, including
+/// exempt transfers outside market hours
+#[event]
+pub struct TransferAllowed {
+    pub amount: u64,
+    pub timestamp: i64,
+    /// `MarketStatus` discriminant
+    pub status: u8,
+    /// `MarketReason` discriminant
+    pub reason_code: u8,
+    pub allow_reason: AllowReason,
+    /// Whether the amount matched the config's round-number pattern
+    pub suspicious: bool,
+}
+
+impl TransferAllowed {
+    pub fn new(
+        amount: u64,
+        timestamp: i64,
+        state: &MarketState,
+        allow_reason: AllowReason,
+    ) -> Self {
+        Self {
+            amount,
+            timestamp,
+            status: state.status as u8,
+            reason_code: state.reason as u8,
+            allow_reason,
+            suspicious: false,
+        }
+    }
+
+    pub fn with_suspicious(mut self, suspicious: bool) -> Self {
+        self.suspicious = suspicious;
+        self
+    }
+}
+
+/// Emitted by `transfer_hook` in place of `TransferAllowed` or
+/// `TransferBlocked` under `dedup_decision_events`, when the status is the
+/// one the last full event carried
+#[event]
+pub struct TransferStatusUnchanged {
+    pub amount: u64,
+    pub timestamp: i64,
+    pub allowed: bool,
+}
+
+/// Emitted alongside `TransferAllowed` for a transfer let through in the
+/// config's soft pre-open window, flagging the early activity
+#[event]
+pub struct SoftOpenTransfer {
+    pub amount: u64,
+    pub timestamp: i64,
+    /// Owner of the source token account
+    pub source_owner: Pubkey,
+}
+
+/// Emitted by every instruction that creates or changes a MarketConfig,
+/// with the config as the instruction left it, so the logs replay as an
+/// audit trail of edits
+#[event]
+pub struct ConfigUpdated {
+    pub market_config: Pubkey,
+    /// Signer that made the change
+    pub authority: Pubkey,
+    pub config: MarketConfig,
+}
+
+impl ConfigUpdated {
+    pub fn new(market_config: &Account<MarketConfig>, authority: Pubkey) -> Self {
+        Self {
+            market_config: market_config.key(),
+            authority,
+            config: (**market_config).clone(),
+        }
+    }
+}
+
+/// Emitted by every allowlist edit, with the entries that result
+#[event]
+pub struct AllowlistUpdated {
+    pub allowlist: Pubkey,
+    /// Signer that made the change
+    pub authority: Pubkey,
+    pub entries: Vec<AllowlistEntry>,
+    pub programs: Vec<Pubkey>,
+}
+
+impl AllowlistUpdated {
+    pub fn new(allowlist: &Account<Allowlist>, authority: Pubkey) -> Self {
+        Self {
+            allowlist: allowlist.key(),
+            authority,
+            entries: allowlist.entries.clone(),
+            programs: allowlist.programs.clone(),
+        }
+    }
+}
+
+/// Emitted by every denylist edit, with the entries that result
+#[event]
+pub struct DenylistUpdated {
+    pub denylist: Pubkey,
+    /// Signer that made the change
+    pub authority: Pubkey,
+    pub entries: Vec<Pubkey>,
+}
+
+impl DenylistUpdated {
+    pub fn new(denylist: &Account<Denylist>, authority: Pubkey) -> Self {
+        Self {
+            denylist: denylist.key(),
+            authority,
+            entries: denylist.entries.clone(),
+        }
+    }
+}
+
+/// Emitted by every holiday calendar edit, with the closures that result
+#[event]
+pub struct CalendarUpdated {
+    pub holiday_calendar: Pubkey,
+    /// Signer that made the change
+    pub authority: Pubkey,
+    pub dates: Vec<HolidayDate>,
+    pub early_closes: Vec<EarlyCloseOverride>,
+}
+
+impl CalendarUpdated {
+    pub fn new(calendar: &Account<HolidayCalendar>, authority: Pubkey) -> Self {
+        Self {
+            holiday_calendar: calendar.key(),
+            authority,
+            dates: calendar.dates.clone(),
+            early_closes: calendar.early_closes.clone(),
+        }
+    }
+}
+
+/// Emitted by `close_market_config`; the mint runs on the NYSE defaults
+/// from then on
+#[event]
+pub struct MarketConfigClosed {
+    pub market_config: Pubkey,
+    /// Signer that closed it
+    pub authority: Pubkey,
+    /// Account that received the config's lamports
+    pub recipient: Pubkey,
+}
+
+/// Emitted by every change to the deployment-wide settings, with the
+/// settings as the instruction left them
+#[event]
+pub struct ProgramSettingsUpdated {
+    pub program_settings: Pubkey,
+    /// Signer that made the change
+    pub authority: Pubkey,
+    pub require_config: bool,
+    pub frozen: bool,
+    pub super_authority: Pubkey,
+}
+
+impl ProgramSettingsUpdated {
+    pub fn new(settings: &Account<ProgramSettings>, authority: Pubkey) -> Self {
+        Self {
+            program_settings: settings.key(),
+            authority,
+            require_config: settings.require_config,
+            frozen: settings.frozen,
+            super_authority: settings.super_authority,
+        }
+    }
+}
+
+/// Emitted the first time a transfer or the transition crank sees the
+/// market open after it was closed
+#[event]
+pub struct MarketOpened {
+    pub timestamp: i64,
+    /// `MarketStatus` discriminant (OPEN or EXTENDED)
+    pub status: u8,
+}
+
+/// Emitted by the transition crank the first time it sees the market
+/// closed after it was open
+#[event]
+pub struct MarketClosed {
+    pub timestamp: i64,
+    /// `MarketStatus` discriminant
+    pub status: u8,
+    /// `MarketReason` discriminant
+    pub reason_code: u8,
+}
+
+/// List membership of a wallet, written as return data by
+/// `check_account_status`
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountListStatus {
+    /// On neither list: the normal market-hours rules apply
+    Unlisted = 0,
+    /// Exempt from the market-hours check
+    Allowlisted = 1,
+    /// Blocked at any hour
+    Denylisted = 2,
+}
+
+/// Why `transfer_hook` let a transfer through
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AllowReason {
+    /// The market state was open
+    MarketOpen = 0,
+    /// The source or destination owner is allowlisted
+    Allowlisted = 1,
+    /// The mint authority is issuing or redeeming tokens
+    MintBurn = 2,
+    /// Nothing was transferred and the config lets zero amounts through
+    ZeroAmount = 3,
+    /// The market was in an opted-in pre-market or after-hours session
+    ExtendedHours = 4,
+    /// The destination is the staging account and the open is minutes away
+    PreOpenStaging = 5,
+    /// A transfer voucher issued for exactly this transfer was used
+    Voucher = 6,
+    /// Nothing would move, so the config skips the checks
+    NoOp = 7,
+    /// The source owner is a PDA of an allowlisted protocol program
+    ProtocolAccount = 8,
+    /// The destination is the SPL incinerator, so the tokens are burnt
+    Incinerator = 9,
+    /// The source or destination owner is a tagged liquidity pool
+    LiquidityOperation = 10,
+    /// The amount was below the config's dust threshold, so no check ran
+    DustExempt = 11,
+}
+
+/// Why a transfer was short-circuited under `short_circuit_no_ops`,
+/// written by `transfer_hook` as its return data in place of the
+/// `MarketState`.
+///
+/// Borsh layout (1 byte): the variant's index.
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NoOpHint {
+    /// The transfer amount is zero
+    ZeroAmount = 0,
+    /// The source token account holds nothing (only seen by
+    /// `simulate_transfer`: by the time the hook runs, Token-2022 has
+    /// already refused to overdraw the source)
+    EmptySource = 1,
+}
+
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum MarketStatus {
+    Open = 0,
+    Weekend = 1,
+    Holiday = 2,
+    AfterHours = 3,
+    Halted = 4,
+    Extended = 5,
+    Overnight = 6,
+    Auction = 7,
+}
+
+impl MarketStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketStatus::Open => "OPEN",
+            MarketStatus::Weekend => "WEEKEND",
+            MarketStatus::Holiday => "HOLIDAY",
+            MarketStatus::AfterHours => "AFTER_HOURS",
+            MarketStatus::Halted => "HALTED",
+            MarketStatus::Extended => "EXTENDED",
+            MarketStatus::Overnight => "CLOSED_OVERNIGHT",
+            MarketStatus::Auction => "AUCTION",
+        }
+    }
+}
+
+/// Why the market is in its state. The discriminant is the locale-free
+/// code carried as `reason` in return data and `reason_code` in events;
+/// clients match it and localize their own text, `as_str` being only for
+/// logs. Codes are fixed: new reasons take the next free number.
+#[repr(u8)]
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum MarketReason {
+    RegularTradingHours = 0,
+    Weekend = 1,
+    Holiday = 2,
+    PreMarket = 3,
+    AfterMarket = 4,
+    EarlyClose = 5,
+    Halted = 6,
+    LunchBreak = 7,
+    GracePeriod = 8,
+    OpeningAuction = 9,
+    ClosingAuction = 10,
+    SoftPreOpen = 11,
+}
+
+impl MarketReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketReason::RegularTradingHours => "Regular Trading Hours",
+            MarketReason::Weekend => "Weekend",
+            MarketReason::Holiday => "NYSE Holiday",
+            MarketReason::PreMarket => "Pre-Market (before the open)",
+            MarketReason::AfterMarket => "After-Market (after the close)",
+            MarketReason::EarlyClose => "Early close (half day or scheduled)",
+            MarketReason::Halted => "Trading halted by the market operator",
+            MarketReason::LunchBreak => "Lunch break (between sessions)",
+            MarketReason::GracePeriod => "Grace period around the open or close",
+            MarketReason::OpeningAuction => "Opening auction",
+            MarketReason::ClosingAuction => "Closing auction",
+            MarketReason::SoftPreOpen => "Soft pre-open (allowed, flagged)",
+        }
+    }
+}
+
+/// Program errors. Clients should match the numeric code (6000 plus the
+/// discriminant, e.g. 6003 for `InvalidTimestamp`) or the variant name rather
+/// than the human-readable message. Discriminants are fixed: new variants
+/// take the next free number and existing ones are never renumbered.
+#[error_code]
+pub enum NyseError {
+    #[msg("🚫 NYSE CLOSED: Market is closed for weekend")]
+    MarketClosedWeekend = 0,
+
+    #[msg("🚫 NYSE CLOSED: Market is closed for holiday")]
+    MarketClosedHoliday = 1,
+
+    #[msg("🚫 NYSE CLOSED: Market has not opened yet")]
+    MarketClosedPreMarket = 2,
+
+    #[msg("Invalid timestamp")]
+    InvalidTimestamp = 3,
+
+    #[msg("Signer is not the authority for this mint")]
+    Unauthorized = 4,
+
+    #[msg("Invalid market hours: open must be before close within one day")]
+    InvalidMarketHours = 5,
+
+    #[msg("⛔ NYSE HALTED: Trading is halted by the market operator")]
+    MarketHalted = 6,
+
+    #[msg("Allowlist is full")]
+    AllowlistFull = 7,
+
+    #[msg("Wallet is already on the allowlist")]
+    AlreadyAllowlisted = 8,
+
+    #[msg("Wallet is not on the allowlist")]
+    NotAllowlisted = 9,
+
+    #[msg("Invalid timezone: UTC offset must be within 14 hours")]
+    InvalidTimezone = 10,
+
+    #[msg("Holiday calendar is full")]
+    HolidayCalendarFull = 11,
+
+    #[msg("Date is already in the holiday calendar")]
+    HolidayAlreadyListed = 12,
+
+    #[msg("Date is not in the holiday calendar")]
+    HolidayNotListed = 13,
+
+    #[msg("Invalid calendar date")]
+    InvalidDate = 14,
+
+    #[msg("Transfer exceeds the limit for the closing window")]
+    TransferExceedsLimit = 15,
+
+    #[msg("🚫 Source or destination wallet is blocked for this token")]
+    AddressBlocked = 16,
+
+    #[msg("Denylist is full")]
+    DenylistFull = 17,
+
+    #[msg("Wallet is already on the denylist")]
+    AlreadyDenylisted = 18,
+
+    #[msg("Wallet is not on the denylist")]
+    NotDenylisted = 19,
+
+    #[msg("Transfer exceeds the owner's rolling volume limit")]
+    VolumeLimitExceeded = 20,
+
+    #[msg("Rent reserve cannot fund a new account; top it up")]
+    RentReserveDepleted = 21,
+
+    #[msg("🚫 NYSE CLOSED: Market is closed for the day")]
+    MarketClosedPostMarket = 22,
+
+    #[msg("Trading days bitmask only has bits for seven weekdays")]
+    InvalidTradingDays = 23,
+
+    #[msg("Transfer is below the minimum transfer amount")]
+    TransferBelowMinimum = 24,
+
+    #[msg("🔧 Transfers are paused for scheduled maintenance")]
+    MaintenanceWindow = 25,
+
+    #[msg("🧾 Extra account meta list is stale or malformed; run update_extra_account_meta_list")]
+    ExtraAccountMetaListMismatch = 26,
+
+    #[msg("🧪 This build doesn't support a test time override")]
+    TimeOverrideDisabled = 27,
+
+    #[msg("🛂 Large transfer needs the compliance authority's signature")]
+    LargeTradeRequiresApproval = 28,
+
+    #[msg("🔗 The mint's transfer hook still points at this program")]
+    HookStillActive = 29,
+
+    #[msg("📝 Closure message is longer than 64 bytes")]
+    ClosureMessageTooLong = 30,
+
+    #[msg("🔔 NYSE AUCTION: Transfers are blocked during the opening and closing auctions")]
+    AuctionWindow = 31,
+
+    #[msg(
+        "🧩 Transfer-hook interface instruction not supported; use the program's own instruction"
+    )]
+    UnsupportedHookInstruction = 32,
+
+    #[msg("⏱️ Owner must wait out the transfer cooldown")]
+    TransferCooldown = 33,
+
+    #[msg("⚙️ This deployment requires the mint to have a MarketConfig")]
+    ConfigMissing = 34,
+
+    #[msg("🔀 The config or allowlist belongs to a different mint")]
+    MintMismatch = 35,
+
+    #[msg("🔢 The config changed since this update was built; re-read its version")]
+    StaleConfigVersion = 36,
+
+    #[msg("🐣 The destination token account is too new to receive transfers")]
+    DestinationTooNew = 37,
+
+    #[msg("🧯 Circuit breaker tripped: transfers are paused after a volume spike")]
+    CircuitBreakerTripped = 38,
+
+    #[msg("🚧 Hook not activated: the config authority hasn't called activate yet")]
+    HookNotActivated = 39,
+
+    #[msg("🔁 Self-transfer blocked: source and destination have the same owner")]
+    SelfTransferBlocked = 40,
+
+    #[msg("📜 The token hasn't listed yet")]
+    NotYetListed = 41,
+
+    #[msg("📜 The token has been delisted")]
+    Delisted = 42,
+
+    #[msg("🧊 Globally frozen: every transfer is stopped program-wide")]
+    GloballyFrozen = 43,
+
+    #[msg("📦 Malformed transfer-hook instruction data")]
+    MalformedHookInstruction = 44,
+
+    #[msg("🌅 Transfer exceeds the limit while trading warms up after the open")]
+    WarmupLimitExceeded = 45,
+
+    #[msg("🗂️ A state snapshot was already recorded in the last minute")]
+    SnapshotTooSoon = 46,
+
+    #[msg("🌐 The config's DST ruleset isn't supported by this program version")]
+    UnsupportedTimezoneConfig = 47,
+
+    #[msg("🔢 Owner reached the daily transfer limit")]
+    DailyTransferLimitExceeded = 48,
+
+    #[msg("🧾 Extra account meta list data is corrupted; run update_extra_account_meta_list")]
+    CorruptedMetaList = 49,
+
+    #[msg("⚖️ Transfers are blocked during the closing cross")]
+    ClosingCrossBlocked = 50,
+
+    #[msg("📚 Too many accounts in one status check")]
+    StatusBatchTooLarge = 51,
+
+    #[msg("🚫 Market closed: transfer rejected")]
+    MarketClosedRejected = 52,
+
+    #[msg("🧢 Owner reached the allowlist's daily volume cap")]
+    AllowlistCapExceeded = 53,
+
+    #[msg("🐢 Trading just resumed; only allowlisted wallets may transfer yet")]
+    PostHaltCooldown = 54,
+
+    #[msg("⏳ The open is delayed for this security")]
+    OpeningDelayed = 55,
+
+    #[msg("🧩 This build of the program leaves out that feature")]
+    FeatureDisabled = 56,
+
+    #[msg("🕵️ The hook only runs inside a Token-2022 transfer")]
+    NotTransferring = 57,
+
+    #[msg("🔍 Large round-number transfers are blocked for this mint")]
+    SuspiciousRoundAmount = 58,
+}
+
+// Longest grace period either side of the session
+const MAX_GRACE_SECONDS: u16 = 3600;
+
+// Longest opening or closing auction window
+const MAX_AUCTION_MINUTES: u16 = 60;
+
+// Longest closing cross
+const MAX_CLOSING_CROSS_SECONDS: u16 = 600;
+
+// Longest soft pre-open window
+const MAX_SOFT_OPEN_MINUTES: u16 = 60;
+
+// Longest pre-open staging window
+const MAX_STAGING_MINUTES: u16 = 60;
+
+// Longest warmup ramp after the open
+const MAX_WARMUP_MINUTES: u16 = 120;
+
+// Pre-market and post-market sessions, where mints that opt in keep
+// trading; outside them the market is closed overnight
+const EXTENDED_OPEN_MINUTES: u32 = 4 * 60; // 4:00 AM
+const EXTENDED_CLOSE_MINUTES: u32 = 20 * 60; // 8:00 PM
+
+// Days `next_transition` searches for the next trading day
+const MAX_LOOKAHEAD_DAYS: i64 = 14;
+
+/// Most dates one `trading_days_in_range` call covers, about a quarter
+pub const MAX_RANGE_DAYS: u16 = 92;
+
+/// Most accounts one `check_accounts_status` call takes, about what fits
+/// in a transaction
+pub const MAX_STATUS_BATCH: usize = 32;
+
+// Extra accounts resolved for every transfer, in TransferHook field order
+// after `extra_account_meta_list`; a build leaves out the accounts of the
+// subsystems it doesn't include
+fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    let mut metas = vec![
+        // market_config: ["market-config", mint], written for the clock check
+        ExtraAccountMeta::new_with_seeds(
+            &[
+                Seed::Literal {
+                    bytes: MARKET_CONFIG_SEED.to_vec(),
+                },
+                Seed::AccountKey { index: 1 },
+            ],
+            false,
+            true,
+        )?,
+    ];
+    // allowlist: ["allowlist", mint]
+    #[cfg(feature = "allowlist")]
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: ALLOWLIST_SEED.to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        false,
+    )?);
+    // holiday_calendar: ["holiday-calendar", mint]
+    #[cfg(feature = "holiday-calendar")]
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: HOLIDAY_CALENDAR_SEED.to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        false,
+    )?);
+    // market_state_cache: ["market-state-cache", mint], writable
+    #[cfg(feature = "state-cache")]
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: MARKET_STATE_CACHE_SEED.to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        true,
+    )?);
+    // denylist: ["denylist", mint]
+    #[cfg(feature = "denylist")]
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: DENYLIST_SEED.to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        false,
+    )?);
+    // rent_reserve: ["rent-reserve", mint], writable
+    #[cfg(feature = "rent-reserve")]
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: RENT_RESERVE_SEED.to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        true,
+    )?);
+    // volume_tracker: ["volume", mint, source owner], writable; the owner
+    // is bytes 32..64 of the source token account
+    #[cfg(feature = "volume-tracking")]
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: VOLUME_TRACKER_SEED.to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+            Seed::AccountData {
+                account_index: 0,
+                data_index: 32,
+                length: 32,
+            },
+        ],
+        false,
+        true,
+    )?);
+    // system_program, for creating per-owner PDAs
+    #[cfg(any(
+        feature = "volume-tracking",
+        feature = "cooldown",
+        feature = "daily-counter"
+    ))]
+    metas.push(ExtraAccountMeta::new_with_pubkey(
+        &System::id(),
+        false,
+        false,
+    )?);
+    // market_transition: ["market-transition", mint], writable
+    #[cfg(feature = "transition-events")]
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: MARKET_TRANSITION_SEED.to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        true,
+    )?);
+    // hook_stats: ["hook-stats", mint], writable
+    #[cfg(feature = "hook-stats")]
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: HOOK_STATS_SEED.to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        true,
+    )?);
+    // instructions_sysvar: the Instructions sysvar
+    metas.push(ExtraAccountMeta::new_with_pubkey(
+        &sysvar::instructions::ID,
+        false,
+        false,
+    )?);
+    // last_transfer: ["last-transfer", mint, source owner], writable
+    #[cfg(feature = "cooldown")]
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: LAST_TRANSFER_SEED.to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+            Seed::AccountData {
+                account_index: 0,
+                data_index: 32,
+                length: 32,
+            },
+        ],
+        false,
+        true,
+    )?);
+    // program_settings: ["program-settings"], shared by every mint
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[Seed::Literal {
+            bytes: PROGRAM_SETTINGS_SEED.to_vec(),
+        }],
+        false,
+        false,
+    )?);
+    // destination_age: ["account-age", mint, destination token account]
+    #[cfg(feature = "destination-age")]
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: ACCOUNT_AGE_SEED.to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+            Seed::AccountKey { index: 2 },
+        ],
+        false,
+        false,
+    )?);
+    // circuit_breaker: ["circuit-breaker", mint], writable
+    #[cfg(feature = "circuit-breaker")]
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: CIRCUIT_BREAKER_SEED.to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        true,
+    )?);
+    // transfer_voucher: ["transfer-voucher", mint, source, destination],
+    // writable so the hook can close it
+    #[cfg(feature = "transfer-vouchers")]
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: TRANSFER_VOUCHER_SEED.to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+            Seed::AccountKey { index: 0 },
+            Seed::AccountKey { index: 2 },
+        ],
+        false,
+        true,
+    )?);
+    // daily_counter: ["daily-counter", mint, source owner], writable
+    #[cfg(feature = "daily-counter")]
+    metas.push(ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal {
+                bytes: DAILY_COUNTER_SEED.to_vec(),
+            },
+            Seed::AccountKey { index: 1 },
+            Seed::AccountData {
+                account_index: 0,
+                data_index: 32,
+                length: 32,
+            },
+        ],
+        false,
+        true,
+    )?);
+    Ok(metas)
+}
+
+// Bytes of a freshly initialized extra account meta list
+fn extra_account_meta_list_data() -> Result<Vec<u8>> {
+    let account_metas = extra_account_metas()?;
+    let mut data = vec![0; ExtraAccountMetaList::size_of(account_metas.len())?];
+    ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &account_metas)?;
+    Ok(data)
+}
+
+// Compare the account at the list PDA with the list this build registers;
+// a mint without a transfer-hook extension reports `hook_attached` false
+fn hook_setup_report(list: &AccountInfo, mint: &AccountInfo) -> Result<HookSetupReport> {
+    let expected = extra_account_meta_list_data()?;
+    let data = list.try_borrow_data()?;
+    let status = if list.lamports() == 0 && data.is_empty() {
+        HookSetupStatus::Missing
+    } else if list.owner != &crate::ID {
+        HookSetupStatus::WrongOwner
+    } else if data.len() != expected.len() {
+        HookSetupStatus::WrongSize
+    } else if data[..] != expected[..] {
+        HookSetupStatus::WrongAccounts
+    } else {
+        HookSetupStatus::Ready
+    };
+    Ok(HookSetupReport {
+        status,
+        hook_attached: hook_attached(mint).unwrap_or(false),
+        expected_size: expected.len() as u32,
+        actual_size: data.len() as u32,
+    })
+}
+
+// An empty list PDA was never registered; anything else has to parse as the
+// Execute list, down to every entry's address config
+fn check_meta_list(list: &AccountInfo) -> Result<()> {
+    let data = list.try_borrow_data()?;
+    require!(
+        data.is_empty() || meta_list_parses(&data),
+        NyseError::CorruptedMetaList
+    );
+    Ok(())
+}
+
+fn meta_list_parses(data: &[u8]) -> bool {
+    let Ok(tlv) = TlvStateBorrowed::unpack(data) else {
+        return false;
+    };
+    // PodSlice trusts its length prefix, so check it fits before reading
+    let Some((count, entries)) = tlv
+        .get_first_bytes::<ExecuteInstruction>()
+        .ok()
+        .and_then(|value| value.split_first_chunk::<4>())
+    else {
+        return false;
+    };
+    let fits = (u32::from_le_bytes(*count) as usize)
+        .checked_mul(std::mem::size_of::<ExtraAccountMeta>())
+        .is_some_and(|len| len <= entries.len());
+    fits && ExtraAccountMetaList::unpack_with_tlv_state::<ExecuteInstruction>(&tlv).is_ok_and(
+        |metas| {
+            metas.data().iter().all(|meta| match meta.discriminator {
+                0 => true,
+                1 | 128.. => Seed::unpack_address_config(&meta.address_config).is_ok(),
+                2 => PubkeyData::unpack(&meta.address_config).is_ok(),
+                _ => false,
+            })
+        },
+    )
+}
+
+// The authority recorded in the mint's transfer-hook extension
+fn transfer_hook_authority(mint: &AccountInfo) -> Result<Option<Pubkey>> {
+    let extension = get_mint_extension_data::<TransferHookExtension>(mint)?;
+    Ok(Option::<Pubkey>::from(extension.authority))
+}
+
+// Amount the size limits see: the gross `amount` unless the config measures
+// them net of the mint's transfer fee for `epoch`. Mints without the fee
+// extension deliver the gross amount either way.
+fn limited_amount(
+    config: Option<&MarketConfig>,
+    mint: &AccountInfo,
+    epoch: u64,
+    amount: u64,
+) -> Result<u64> {
+    use anchor_spl::token_2022::spl_token_2022::extension::{
+        transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions,
+    };
+    use anchor_spl::token_2022::spl_token_2022::state::Mint;
+
+    if !config.is_some_and(|config| config.limits_on_net_amount) {
+        return Ok(amount);
+    }
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    let Ok(fee_config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(amount);
+    };
+    let fee = fee_config
+        .calculate_epoch_fee(epoch, amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    Ok(amount.saturating_sub(fee))
+}
+
+// Token-2022 raises the `transferring` flag on the source account only for
+// the duration of its CPI into the hook, so a direct call never has it
+fn ensure_transferring(token_account: &AccountInfo) -> Result<()> {
+    use anchor_spl::token_2022::spl_token_2022::extension::{
+        transfer_hook::TransferHookAccount, BaseStateWithExtensions, StateWithExtensions,
+    };
+    use anchor_spl::token_2022::spl_token_2022::state::Account;
+
+    let data = token_account.try_borrow_data()?;
+    let state = StateWithExtensions::<Account>::unpack(&data)?;
+    let transferring = state
+        .get_extension::<TransferHookAccount>()
+        .is_ok_and(|extension| bool::from(extension.transferring));
+    require!(transferring, NyseError::NotTransferring);
+    Ok(())
+}
+
+// Drain a program-owned account into `recipient` and hand it back to the
+// system program, as Anchor's `close` constraint does for typed accounts
+fn close_account(info: &AccountInfo, recipient: &AccountInfo) -> Result<()> {
+    let lamports = info.lamports();
+    **recipient.try_borrow_mut_lamports()? += lamports;
+    **info.try_borrow_mut_lamports()? = 0;
+    info.assign(&System::id());
+    info.realloc(0, false)?;
+    Ok(())
+}
+
+// Whether the mint's transfer hook still points at this program
+fn hook_attached(mint: &AccountInfo) -> Result<bool> {
+    let extension = get_mint_extension_data::<TransferHookExtension>(mint)?;
+    Ok(Option::<Pubkey>::from(extension.program_id) == Some(crate::ID))
+}
+
+// A close before the open is an overnight session ending the next morning
+fn validate_market_hours(open_minute: u16, close_minute: u16) -> Result<()> {
+    require!(
+        open_minute != close_minute && open_minute < 24 * 60 && close_minute <= 24 * 60,
+        NyseError::InvalidMarketHours
+    );
+    Ok(())
+}
+
+// Sessions must be valid windows in order, without overlaps
+fn validate_sessions(sessions: &[SessionWindow]) -> Result<()> {
+    require!(
+        (1..=MAX_SESSIONS).contains(&sessions.len()),
+        NyseError::InvalidMarketHours
+    );
+    for session in sessions {
+        require!(
+            session.open_minute < session.close_minute && session.close_minute <= 24 * 60,
+            NyseError::InvalidMarketHours
+        );
+    }
+    require!(
+        sessions
+            .windows(2)
+            .all(|pair| pair[0].close_minute <= pair[1].open_minute),
+        NyseError::InvalidMarketHours
+    );
+    Ok(())
+}
+
+fn validate_holiday_date(date: &HolidayDate) -> Result<()> {
+    require!(
+        (1..=12).contains(&date.month)
+            && date.day >= 1
+            && date.day as u32 <= days_in_month(date.year as i32, date.month as u32),
+        NyseError::InvalidDate
+    );
+    Ok(())
+}
+
+fn validate_timezone(timezone: &TimezoneConfig) -> Result<()> {
+    require!(
+        timezone.utc_offset_seconds.abs() <= 14 * 3600,
+        NyseError::InvalidTimezone
+    );
+    timezone.dst_rule()?;
+    Ok(())
+}
+
+// The transfer-hook interface instruction `fallback` received. Anything it
+// can't decode fails with MalformedHookInstruction, logging the length and
+// leading bytes so integrators can see what actually arrived.
+fn decode_hook_instruction(data: &[u8]) -> Result<TransferHookInstruction> {
+    let leading = &data[..data.len().min(16)];
+    let execute = TransferHookInstruction::Execute { amount: 0 }.pack();
+    let (discriminator, amount) = execute.split_at(8);
+    if data.starts_with(discriminator) && data.len() < execute.len() {
+        msg!(
+            "❌ Execute carries {} of {} amount bytes: {:02x?}",
+            data.len() - discriminator.len(),
+            amount.len(),
+            leading
+        );
+        return err!(NyseError::MalformedHookInstruction);
+    }
+    TransferHookInstruction::unpack(data).map_err(|_| {
+        msg!(
+            "❌ Not a transfer-hook instruction: {} bytes, starting {:02x?}",
+            data.len(),
+            leading
+        );
+        error!(NyseError::MalformedHookInstruction)
+    })
+}
+
+// A replacement allowlist must fit and name each wallet once
+fn validate_allowlist(entries: &[AllowlistEntry]) -> Result<()> {
+    require!(
+        entries.len() <= MAX_ALLOWLIST_ENTRIES,
+        NyseError::AllowlistFull
+    );
+    for (index, entry) in entries.iter().enumerate() {
+        require!(
+            !entries[..index]
+                .iter()
+                .any(|earlier| earlier.wallet == entry.wallet),
+            NyseError::AlreadyAllowlisted
+        );
+    }
+    Ok(())
+}
+
+// Whether `key` is this program's PDA of `seeds` and `bump`, the bump the
+// account stored when it was created. That is a single hash, where
+// `find_program_address` tries bumps down from 255 until one fits.
+fn is_stored_pda(key: &Pubkey, seeds: &[&[u8]], bump: u8) -> bool {
+    #[cfg(test)]
+    PDA_HASHES.set(PDA_HASHES.get() + 1);
+    let bump = [bump];
+    let seeds = [seeds, &[&bump[..]]].concat();
+    Pubkey::create_program_address(&seeds, &crate::ID).is_ok_and(|address| address == *key)
+}
+
+// Off chain the hashes never reach the runtime, so tests count them here
+#[cfg(test)]
+thread_local! {
+    static PDA_HASHES: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+}
+
+// Fail with `mismatch` when a loaded account (`bump` is what it stored)
+// isn't at its PDA of `seeds`, for example another mint's config; an
+// account that was never created has no address to check
+fn check_stored_pda(
+    info: &AccountInfo,
+    seeds: &[&[u8]],
+    bump: Option<u8>,
+    mismatch: impl Into<Error>,
+) -> Result<()> {
+    match bump {
+        Some(bump) if !is_stored_pda(info.key, seeds, bump) => Err(mismatch.into()),
+        _ => Ok(()),
+    }
+}
+
+// The deployment-wide emergency stop, checked before anything per-mint
+fn ensure_not_frozen(settings: Option<&ProgramSettings>) -> Result<()> {
+    require!(
+        !settings.is_some_and(|settings| settings.frozen),
+        NyseError::GloballyFrozen
+    );
+    Ok(())
+}
+
+// Missing configs run on the NYSE defaults unless the deployment requires one
+fn check_config_policy(
+    settings: Option<&ProgramSettings>,
+    config: Option<&MarketConfig>,
+) -> Result<()> {
+    let require_config = settings.is_some_and(|settings| settings.require_config);
+    require!(
+        config.is_some() || !require_config,
+        NyseError::ConfigMissing
+    );
+    Ok(())
+}
+
+// A config blocks everything until its authority activates it; mints
+// without one run on the defaults
+fn ensure_activated(config: Option<&MarketConfig>) -> Result<()> {
+    require!(
+        config.map_or(true, |config| config.activated),
+        NyseError::HookNotActivated
+    );
+    Ok(())
+}
+
+// Mints log every step of their checks only when the config asks to
+fn verbose_logging(config: Option<&MarketConfig>) -> bool {
+    config.is_some_and(|config| config.verbose_logging)
+}
+
+// Whether the mint allows same-transaction grace and its halt was begun by
+// a `set_paused` an earlier top-level instruction of this transaction sent
+// to `market_config`. Each change bumps the version, so the halt's version
+// names the one instruction that began it.
+fn halted_earlier_in_transaction(
+    config: Option<&MarketConfig>,
+    market_config: &Pubkey,
+    instructions_sysvar: &AccountInfo,
+) -> Result<bool> {
+    let Some(config) = config.filter(|config| config.same_tx_halt_grace) else {
+        return Ok(false);
+    };
+    let current = sysvar::instructions::load_current_index_checked(instructions_sysvar)?;
+    for index in 0..current {
+        let instruction =
+            sysvar::instructions::load_instruction_at_checked(index as usize, instructions_sysvar)?;
+        let Some(args) = instruction
+            .data
+            .strip_prefix(instruction::SetPaused::DISCRIMINATOR)
+            .filter(|_| instruction.program_id == crate::ID)
+            .and_then(|mut data| instruction::SetPaused::deserialize(&mut data).ok())
+        else {
+            continue;
+        };
+        // UpdateMarketConfig's accounts are the authority, then the config
+        let this_mint = instruction
+            .accounts
+            .get(1)
+            .is_some_and(|meta| meta.pubkey == *market_config);
+        if this_mint
+            && args.paused
+            && args.expected_version.wrapping_add(1) == config.halted_at_version
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn ensure_not_halted(config: Option<&MarketConfig>) -> Result<()> {
+    require!(
+        !config.is_some_and(|config| config.paused),
+        NyseError::MarketHalted
+    );
+    Ok(())
+}
+
+// Error for a closed verdict: pre-market (opens later today) is told apart
+// from post-market (closed for the day)
+fn closed_error(state: &MarketState) -> NyseError {
+    match (state.status, state.reason) {
+        (MarketStatus::Weekend, _) => NyseError::MarketClosedWeekend,
+        (MarketStatus::Holiday, _) => NyseError::MarketClosedHoliday,
+        (MarketStatus::Halted, _) => NyseError::MarketHalted,
+        (MarketStatus::Auction, _) => NyseError::AuctionWindow,
+        (_, MarketReason::PreMarket | MarketReason::LunchBreak) => NyseError::MarketClosedPreMarket,
+        _ => NyseError::MarketClosedPostMarket,
+    }
+}
+
+// Outcome of a closed verdict: its error when enforcing (one shared code
+// when the config rejects quietly), Ok when the config only warns
+fn enforce_closed(config: Option<&MarketConfig>, state: &MarketState) -> Result<()> {
+    match config.map_or(EnforcementMode::Enforce, |config| config.enforcement_mode) {
+        EnforcementMode::Enforce if rejects_quietly(config) => {
+            err!(NyseError::MarketClosedRejected)
+        }
+        EnforcementMode::Enforce => Err(closed_error(state).into()),
+        EnforcementMode::WarnOnly => Ok(()),
+    }
+}
+
+fn rejects_quietly(config: Option<&MarketConfig>) -> bool {
+    config.is_some_and(|config| config.block_behavior == BlockBehavior::RejectQuietly)
+}
+
+// TransferHook's PDAs as the checks read them, each checked against the
+// bump it stored. A build without a subsystem has no account for it and
+// runs as if the mint never created it.
+impl TransferHook<'_> {
+    fn load_market_config(&self) -> Result<Option<MarketConfig>> {
+        let config = MarketConfig::load(&self.market_config)?;
+        check_stored_pda(
+            &self.market_config,
+            &[MARKET_CONFIG_SEED, self.mint.key().as_ref()],
+            config.as_ref().map(|config| config.bump),
+            NyseError::MintMismatch,
+        )?;
+        Ok(config)
+    }
+
+    fn load_program_settings(&self) -> Result<Option<ProgramSettings>> {
+        let settings = ProgramSettings::load(&self.program_settings)?;
+        check_stored_pda(
+            &self.program_settings,
+            &[PROGRAM_SETTINGS_SEED],
+            settings.as_ref().map(|settings| settings.bump),
+            ErrorCode::ConstraintSeeds,
+        )?;
+        Ok(settings)
+    }
+
+    #[cfg(feature = "allowlist")]
+    fn load_allowlist(&self) -> Result<Option<Allowlist>> {
+        let allowlist = Allowlist::load(&self.allowlist)?;
+        check_stored_pda(
+            &self.allowlist,
+            &[ALLOWLIST_SEED, self.mint.key().as_ref()],
+            allowlist.as_ref().map(|allowlist| allowlist.bump),
+            NyseError::MintMismatch,
+        )?;
+        Ok(allowlist)
+    }
+
+    #[cfg(not(feature = "allowlist"))]
+    fn load_allowlist(&self) -> Result<Option<Allowlist>> {
+        Ok(None)
+    }
+
+    #[cfg(feature = "denylist")]
+    fn load_denylist(&self) -> Result<Option<Denylist>> {
+        let denylist = Denylist::load(&self.denylist)?;
+        check_stored_pda(
+            &self.denylist,
+            &[DENYLIST_SEED, self.mint.key().as_ref()],
+            denylist.as_ref().map(|denylist| denylist.bump),
+            ErrorCode::ConstraintSeeds,
+        )?;
+        Ok(denylist)
+    }
+
+    #[cfg(not(feature = "denylist"))]
+    fn load_denylist(&self) -> Result<Option<Denylist>> {
+        Ok(None)
+    }
+
+    #[cfg(feature = "holiday-calendar")]
+    fn load_holiday_calendar(&self) -> Result<Option<HolidayCalendar>> {
+        let calendar = HolidayCalendar::load(&self.holiday_calendar)?;
+        check_stored_pda(
+            &self.holiday_calendar,
+            &[HOLIDAY_CALENDAR_SEED, self.mint.key().as_ref()],
+            calendar.as_ref().map(|calendar| calendar.bump),
+            ErrorCode::ConstraintSeeds,
+        )?;
+        Ok(calendar)
+    }
+
+    #[cfg(not(feature = "holiday-calendar"))]
+    fn load_holiday_calendar(&self) -> Result<Option<HolidayCalendar>> {
+        Ok(None)
+    }
+
+    #[cfg(feature = "state-cache")]
+    fn load_state_cache(&self) -> Result<Option<MarketStateCache>> {
+        let cache = MarketStateCache::load(&self.market_state_cache)?;
+        check_stored_pda(
+            &self.market_state_cache,
+            &[MARKET_STATE_CACHE_SEED, self.mint.key().as_ref()],
+            cache.as_ref().map(|cache| cache.bump),
+            ErrorCode::ConstraintSeeds,
+        )?;
+        Ok(cache)
+    }
+
+    #[cfg(not(feature = "state-cache"))]
+    fn load_state_cache(&self) -> Result<Option<MarketStateCache>> {
+        Ok(None)
+    }
+
+    #[cfg(feature = "circuit-breaker")]
+    fn load_circuit_breaker(&self) -> Result<Option<CircuitBreaker>> {
+        let breaker = CircuitBreaker::load(&self.circuit_breaker)?;
+        check_stored_pda(
+            &self.circuit_breaker,
+            &[CIRCUIT_BREAKER_SEED, self.mint.key().as_ref()],
+            breaker.as_ref().map(|breaker| breaker.bump),
+            ErrorCode::ConstraintSeeds,
+        )?;
+        Ok(breaker)
+    }
+
+    #[cfg(not(feature = "circuit-breaker"))]
+    fn load_circuit_breaker(&self) -> Result<Option<CircuitBreaker>> {
+        Ok(None)
+    }
+
+    #[cfg(feature = "transition-events")]
+    fn load_market_transition(&self) -> Result<Option<MarketTransition>> {
+        let transition = MarketTransition::load(&self.market_transition)?;
+        check_stored_pda(
+            &self.market_transition,
+            &[MARKET_TRANSITION_SEED, self.mint.key().as_ref()],
+            transition.as_ref().map(|transition| transition.bump),
+            ErrorCode::ConstraintSeeds,
+        )?;
+        Ok(transition)
+    }
+
+    // Count a transfer in the mint's stats; a no-op for mints without them
+    #[cfg(feature = "hook-stats")]
+    fn record_stats(&self, state: &MarketState, allowed: bool) -> Result<()> {
+        let Some(mut stats) = HookStats::load(&self.hook_stats)? else {
+            return Ok(());
+        };
+        check_stored_pda(
+            &self.hook_stats,
+            &[HOOK_STATS_SEED, self.mint.key().as_ref()],
+            Some(stats.bump),
+            ErrorCode::ConstraintSeeds,
+        )?;
+        stats.record(state, allowed);
+        stats.store(&self.hook_stats)
+    }
+
+    #[cfg(not(feature = "hook-stats"))]
+    fn record_stats(&self, _state: &MarketState, _allowed: bool) -> Result<()> {
+        Ok(())
+    }
+
+    // Whether a decision gets the full event: always, unless the config
+    // dedups them and the last one noted carried the same status
+    #[cfg(feature = "transition-events")]
+    fn is_new_decision(&self, config: Option<&MarketConfig>, state: &MarketState) -> Result<bool> {
+        if !config.is_some_and(|config| config.dedup_decision_events) {
+            return Ok(true);
+        }
+        let Some(mut transition) = self.load_market_transition()? else {
+            return Ok(true);
+        };
+        if !transition.observe_decision(state.status) {
+            return Ok(false);
+        }
+        transition.store(&self.market_transition)?;
+        Ok(true)
+    }
+
+    #[cfg(not(feature = "transition-events"))]
+    fn is_new_decision(
+        &self,
+        _config: Option<&MarketConfig>,
+        _state: &MarketState,
+    ) -> Result<bool> {
+        Ok(true)
+    }
+
+    #[cfg(feature = "volume-tracking")]
+    fn load_volume_tracker(&self) -> Result<Option<VolumeTracker>> {
+        let tracker = VolumeTracker::load(&self.volume_tracker)?;
+        check_stored_pda(
+            &self.volume_tracker,
+            &[
+                VOLUME_TRACKER_SEED,
+                self.mint.key().as_ref(),
+                self.source_token.owner.as_ref(),
+            ],
+            tracker.as_ref().map(|tracker| tracker.bump),
+            ErrorCode::ConstraintSeeds,
+        )?;
+        Ok(tracker)
+    }
+
+    #[cfg(feature = "cooldown")]
+    fn load_last_transfer(&self) -> Result<Option<LastTransfer>> {
+        let last = LastTransfer::load(&self.last_transfer)?;
+        check_stored_pda(
+            &self.last_transfer,
+            &[
+                LAST_TRANSFER_SEED,
+                self.mint.key().as_ref(),
+                self.source_token.owner.as_ref(),
+            ],
+            last.as_ref().map(|last| last.bump),
+            ErrorCode::ConstraintSeeds,
+        )?;
+        Ok(last)
+    }
+
+    #[cfg(feature = "daily-counter")]
+    fn load_daily_counter(&self) -> Result<Option<DailyCounter>> {
+        let counter = DailyCounter::load(&self.daily_counter)?;
+        check_stored_pda(
+            &self.daily_counter,
+            &[
+                DAILY_COUNTER_SEED,
+                self.mint.key().as_ref(),
+                self.source_token.owner.as_ref(),
+            ],
+            counter.as_ref().map(|counter| counter.bump),
+            ErrorCode::ConstraintSeeds,
+        )?;
+        Ok(counter)
+    }
+
+    #[cfg(feature = "destination-age")]
+    fn load_destination_age(&self) -> Result<Option<AccountAge>> {
+        let age = AccountAge::load(&self.destination_age)?;
+        check_stored_pda(
+            &self.destination_age,
+            &[
+                ACCOUNT_AGE_SEED,
+                self.mint.key().as_ref(),
+                self.destination_token.key().as_ref(),
+            ],
+            age.as_ref().map(|age| age.bump),
+            ErrorCode::ConstraintSeeds,
+        )?;
+        Ok(age)
+    }
+
+    #[cfg(feature = "transfer-vouchers")]
+    fn load_transfer_voucher(&self) -> Result<Option<TransferVoucher>> {
+        let voucher = TransferVoucher::load(&self.transfer_voucher)?;
+        check_stored_pda(
+            &self.transfer_voucher,
+            &[
+                TRANSFER_VOUCHER_SEED,
+                self.mint.key().as_ref(),
+                self.source_token.key().as_ref(),
+                self.destination_token.key().as_ref(),
+            ],
+            voucher.as_ref().map(|voucher| voucher.bump),
+            ErrorCode::ConstraintSeeds,
+        )?;
+        Ok(voucher)
+    }
+}
+
+// The checks behind `transfer_hook`. With `commit` false (the dry run of
+// `simulate_transfer`) the verdict is the same but nothing is stored,
+// created, counted or emitted. Returns why the transfer passed, or `None`
+// when only the WarnOnly rollout let it through.
+//
+// `market_config` is read fresh from the account on every call, never kept
+// across instructions. The runtime runs a slot's transactions, and a
+// transaction's instructions, one after another, so a transfer sees every
+// config change committed before it, including a `set_paused` earlier in
+// the same transaction. The state cache is keyed by minute and
+// `config_version`, so a config change also voids that minute's verdict.
+//
+// When several rules would block a transfer, the first in this order
+// decides, so the error is the most lasting and most specific reason:
+//
+//  1. a call from outside a Token-2022 transfer, a corrupted meta list
+//     (dust-exempt amounts pass right after it, ahead of every rule below),
+//     the global freeze, a missing config, and a config that isn't
+//     activated yet
+//  2. the listing window (not yet listed, delisted), then an operator halt
+//  3. maintenance, unless an exemption from step 5 applies
+//  4. the denylist and self-transfers, which no exemption waives
+//  5. exemptions: mint/burn, the allowlist (up to its daily cap), pools
+//     and protocol programs
+//  6. the block-trade co-signature, then blocked round amounts, which the
+//     remaining exemptions (zero amount, incinerator, staging, vouchers)
+//     don't waive
+//  7. the market hours: weekend, then holiday, then pre- and post-market,
+//     lunch breaks and auctions
+//  8. rules that only apply while the hours allow trading: the post-halt
+//     cooldown, the delayed open, the warmup, closing-window and closing
+//     cross limits, then the dust, breaker, volume, account-age, cooldown
+//     and daily limits
+fn run_transfer_checks(
+    accounts: &TransferHook,
+    amount: u64,
+    commit: bool,
+) -> Result<Option<AllowReason>> {
+    // Anyone can invoke the hook directly with made-up amounts. Only a real
+    // transfer may touch the breaker, the owner's limits or the reserve.
+    if commit {
+        if let Err(direct) = ensure_transferring(&accounts.source_token.to_account_info()) {
+            msg!("🕵️ NOT A TRANSFER: The hook was called directly - Transfer BLOCKED");
+            return Err(direct);
+        }
+    }
+
+    // A damaged list resolves the wrong accounts or none; name it
+    if let Err(corrupted) = check_meta_list(&accounts.extra_account_meta_list) {
+        msg!("🧾 CORRUPTED META LIST: The extra account list doesn't parse - Transfer BLOCKED");
+        return Err(corrupted);
+    }
+
+    // Check NYSE market state against the mint's config, if it has one
+    let mut config = accounts.load_market_config()?;
+    let verbose = verbose_logging(config.as_ref());
+
+    // Micropayments skip the whole pipeline, ahead of any other work
+    if is_dust_exempt(config.as_ref(), amount) {
+        verbose_msg!(
+            verbose,
+            "🪶 DUST_EXEMPT: Transfer of {} is below the dust threshold - Checks skipped",
+            amount
+        );
+        return Ok(Some(AllowReason::DustExempt));
+    }
+    if commit {
+        verbose_msg!(verbose, "🚨 NYSE TRANSFER HOOK CALLED!");
+        verbose_msg!(
+            verbose,
+            "🔍 NYSE Transfer Hook: Validating transfer of {} tokens",
+            amount
+        );
+    }
+
+    // A deployment-wide freeze stops every mint, whatever its config says
+    let settings = accounts.load_program_settings()?;
+    if let Err(frozen) = ensure_not_frozen(settings.as_ref()) {
+        msg!("🧊 GLOBALLY FROZEN: Every transfer is stopped - Transfer BLOCKED");
+        return Err(frozen);
+    }
+
+    // Deployments can refuse to run a mint on the defaults by accident
+    if let Err(missing) = check_config_policy(settings.as_ref(), config.as_ref()) {
+        msg!("🚫 NO CONFIG: This deployment requires a MarketConfig - Transfer BLOCKED");
+        return Err(missing);
+    }
+    if let Err(inactive) = ensure_activated(config.as_ref()) {
+        msg!("🚧 NOT ACTIVATED: The mint is still being configured - Transfer BLOCKED");
+        return Err(inactive);
+    }
+    if let Err(unsupported) = check_build_supports(config.as_ref()) {
+        msg!(
+            "🧩 MISSING FEATURE: The config sets a limit this build leaves out - Transfer BLOCKED"
+        );
+        return Err(unsupported);
+    }
+
+    // Transfers that move nothing don't need the rest of the pipeline. The
+    // hook runs after Token-2022 debited the source, the dry run before.
+    let balance_before = if commit {
+        accounts.source_token.amount.saturating_add(amount)
+    } else {
+        accounts.source_token.amount
+    };
+    if let Some(hint) = no_op_hint(config.as_ref(), amount, balance_before) {
+        msg!("💤 NO-OP: {:?} - Checks skipped", hint);
+        set_return_data(&hint.try_to_vec()?);
+        return Ok(Some(AllowReason::NoOp));
+    }
+
+    // Get current time
+    let clock = Clock::get()?;
+    let current_timestamp = effective_timestamp(clock.unix_timestamp, config.as_ref());
+
+    // A clock that went backwards past the tolerance isn't trusted
+    if let Some(config) = config.as_mut() {
+        if config.observe_clock(clock.unix_timestamp)? && commit {
+            config.store(&accounts.market_config)?;
+        }
+    }
+
+    // Before listing and after delisting there is no market at all, which
+    // outlasts any halt
+    if let Err(unlisted) = check_listing(config.as_ref(), current_timestamp) {
+        msg!("📜 NOT LISTED: Outside the token's listing window - Transfer BLOCKED");
+        return Err(unlisted);
+    }
+
+    // An operator halt overrides the calendar entirely, unless the mint lets
+    // the rest of the transaction that halted it finish
+    let halt = ensure_not_halted(config.as_ref());
+    let halt_graced = halt.is_err()
+        && halted_earlier_in_transaction(
+            config.as_ref(),
+            accounts.market_config.key,
+            &accounts.instructions_sysvar,
+        )?;
+    if halt_graced {
+        verbose_msg!(
+            verbose,
+            "⏮️ SAME-TX HALT: The halt began earlier in this transaction - Checked as before it"
+        );
+    } else if let Err(halted) = halt {
+        let market_result = MarketState::HALTED;
+        let closure_message = closure_message(config.as_ref());
+        set_closure_return_data(&market_result, &closure_message)?;
+        if commit {
+            emit!(
+                TransferBlocked::new(amount, current_timestamp, &market_result)
+                    .with_closure_message(closure_message)
+            );
+        }
+        msg!("⛔ MARKET HALTED: Transfers frozen by the config authority - Transfer BLOCKED");
+        return Err(halted);
+    }
+
+    // Reuse this minute's verdict if another transfer already computed it
+    let mut cache = accounts.load_state_cache()?;
+    let config_version = config.as_ref().map_or(0, |config| config.config_version);
+    let mut market_result = cached_market_state(
+        cache.as_mut(),
+        current_timestamp,
+        config_version,
+        verbose,
+        || {
+            let calendar = accounts.load_holiday_calendar()?;
+            get_nyse_market_state(current_timestamp, config.as_ref(), calendar.as_ref())
+        },
+    )?;
+    if commit {
+        #[cfg(feature = "state-cache")]
+        if let Some(cache) = &cache {
+            cache.store(&accounts.market_state_cache)?;
+        }
+        #[cfg(feature = "transition-events")]
+        record_transition(
+            &accounts.market_transition,
+            accounts.load_market_transition()?,
+            &market_result,
+            current_timestamp,
+            verbose,
+        )?;
+    }
+
+    // Deposits into and withdrawals from the treasury can keep their own
+    // hours; the cache and transition log stay on the market's
+    let treasury_state = treasury_market_state(
+        current_timestamp,
+        config.as_ref(),
+        &market_result,
+        &accounts.source_token.owner,
+        &accounts.destination_token.owner,
+    );
+    if let Some(treasury_state) = treasury_state {
+        verbose_msg!(
+            verbose,
+            "🏦 TREASURY: Checked against the treasury's own window"
+        );
+        market_result = treasury_state;
+    }
+
+    // Publish the verdict on both the allowed and blocked paths
+    set_return_data(&market_result.try_to_vec()?);
+
+    verbose_msg!(
+        verbose,
+        "📅 Current time: {} (timestamp: {})",
+        format_display_timestamp(current_timestamp, config.as_ref()),
+        current_timestamp
+    );
+    verbose_msg!(
+        verbose,
+        "📊 Market state: {}",
+        market_result.status.as_str()
+    );
+
+    // Large round amounts are flagged in the event, or blocked further down
+    let suspicious = is_suspicious_round_amount(config.as_ref(), accounts.mint.decimals, amount);
+
+    // Announce and count a transfer that goes through; a flagged one always
+    // gets the full event
+    let allow = |allow_reason: AllowReason| -> Result<Option<AllowReason>> {
+        if commit {
+            if suspicious || accounts.is_new_decision(config.as_ref(), &market_result)? {
+                emit!(TransferAllowed::new(
+                    amount,
+                    current_timestamp,
+                    &market_result,
+                    allow_reason
+                )
+                .with_suspicious(suspicious));
+            } else {
+                emit!(TransferStatusUnchanged {
+                    amount,
+                    timestamp: current_timestamp,
+                    allowed: true,
+                });
+            }
+            accounts.record_stats(&market_result, true)?;
+        }
+        Ok(Some(allow_reason))
+    };
+
+    // Planned maintenance blocks everyone, even in regular hours and ahead of
+    // the denylist, except the transfers the exemptions below let through
+    let allowlist = accounts.load_allowlist()?;
+    if let Err(maintenance) = check_maintenance(config.as_ref(), current_timestamp) {
+        if !waives_maintenance(accounts, config.as_ref(), allowlist.as_ref()) {
+            let closure_message = closure_message(config.as_ref());
+            set_closure_return_data(&market_result, &closure_message)?;
+            if commit {
+                emit!(
+                    TransferBlocked::new(amount, current_timestamp, &market_result)
+                        .with_closure_message(closure_message)
+                );
+            }
+            msg!("🔧 MAINTENANCE: Scheduled maintenance window - Transfer BLOCKED");
+            return Err(maintenance);
+        }
+    }
+
+    // Sanctioned or frozen wallets are blocked at any hour, exemptions included
+    let denylist = accounts.load_denylist()?;
+    if is_denied(
+        denylist.as_ref(),
+        &accounts.source_token.owner,
+        &accounts.destination_token.owner,
+    ) {
+        msg!("🚫 DENYLISTED: Source or destination wallet is blocked - Transfer BLOCKED");
+        return err!(NyseError::AddressBlocked);
+    }
+
+    // Round trips between one owner's accounts, at any hour
+    if let Err(self_transfer) = check_self_transfer(
+        config.as_ref(),
+        &accounts.source_token.owner,
+        &accounts.destination_token.owner,
+    ) {
+        msg!("🔁 SELF-TRANSFER: Source and destination share an owner - Transfer BLOCKED");
+        return Err(self_transfer);
+    }
+
+    // Issuance and redemption aren't secondary-market trades
+    if is_mint_burn(
+        Option::<Pubkey>::from(accounts.mint.mint_authority).as_ref(),
+        &accounts.source_token.owner,
+        &accounts.destination_token.owner,
+        &accounts.owner.key(),
+    ) {
+        verbose_msg!(
+            verbose,
+            "🏦 MINT/BURN: Issuer operation - Transfer allowed regardless of market hours"
+        );
+        return allow(AllowReason::MintBurn);
+    }
+
+    // Allowlisted wallets (treasury, market makers) trade around the clock
+    if is_exempt(
+        allowlist.as_ref(),
+        &accounts.source_token.owner,
+        &accounts.destination_token.owner,
+    ) {
+        #[cfg(feature = "allowlist")]
+        if let Err(capped) =
+            record_allowlist_volume(accounts, config.as_ref(), current_timestamp, amount, commit)
+        {
+            msg!("🧢 ALLOWLIST CAP: Owner reached today's exempt volume - Transfer BLOCKED");
+            return Err(capped);
+        }
+        verbose_msg!(
+            verbose,
+            "⭐ EXEMPT: Allowlisted wallet - Transfer allowed regardless of market hours"
+        );
+        return allow(AllowReason::Allowlisted);
+    }
+
+    // Adding and removing liquidity isn't a peer-to-peer trade
+    if is_liquidity_operation(
+        config.as_ref(),
+        allowlist.as_ref(),
+        &accounts.source_token.owner,
+        &accounts.destination_token.owner,
+    ) {
+        verbose_msg!(
+            verbose,
+            "🌊 LIQUIDITY: Transfer to or from a pool vault - Transfer allowed regardless of market hours"
+        );
+        return allow(AllowReason::LiquidityOperation);
+    }
+
+    // Vaults of allowlisted protocol programs (vesting, escrow) send at any hour
+    if is_protocol_account(
+        allowlist.as_ref(),
+        &accounts.owner,
+        &accounts.source_token.owner,
+    ) {
+        verbose_msg!(
+            verbose,
+            "🏛️ PROTOCOL: Source owned by an allowlisted program's PDA - Transfer allowed regardless of market hours"
+        );
+        return allow(AllowReason::ProtocolAccount);
+    }
+
+    // Size limits can measure what the destination receives after the fee
+    let limited_amount = limited_amount(
+        config.as_ref(),
+        &accounts.mint.to_account_info(),
+        clock.epoch,
+        amount,
+    )?;
+
+    // Block trades need the compliance authority's co-signature
+    if let Err(unapproved) = check_large_trade(
+        config.as_ref(),
+        limited_amount,
+        &accounts.instructions_sysvar,
+    ) {
+        msg!(
+            "🛂 LARGE TRADE: Transfer of {} lacks compliance approval - Transfer BLOCKED",
+            amount
+        );
+        return Err(unapproved);
+    }
+
+    // Possible structuring, when the mint blocks rather than flags it
+    let blocks_round_amounts = config
+        .as_ref()
+        .is_some_and(|config| config.round_amount_policy == RoundAmountPolicy::Block);
+    if suspicious && blocks_round_amounts {
+        msg!(
+            "🔍 ROUND AMOUNT: Transfer of {} matches the structuring pattern - Transfer BLOCKED",
+            amount
+        );
+        return err!(NyseError::SuspiciousRoundAmount);
+    }
+
+    // Wallets send zero-amount probes to initialize accounts; they move
+    // no value, so they don't need the market open
+    if is_zero_amount_exempt(config.as_ref(), amount) {
+        verbose_msg!(
+            verbose,
+            "🫙 ZERO AMOUNT: No value moved - Transfer allowed regardless of market hours"
+        );
+        return allow(AllowReason::ZeroAmount);
+    }
+
+    // Sending to the incinerator destroys the tokens rather than trading them
+    if is_incinerator_exempt(
+        config.as_ref(),
+        &accounts.destination_token.key(),
+        &accounts.destination_token.owner,
+    ) {
+        verbose_msg!(
+            verbose,
+            "🔥 INCINERATOR: Tokens are being burnt - Transfer allowed regardless of market hours"
+        );
+        return allow(AllowReason::Incinerator);
+    }
+
+    // Right after a halt or breaker reset only exempt wallets go first;
+    // while closed, the hours give the better reason
+    let post_halt_enabled = config
+        .as_ref()
+        .is_some_and(|config| config.post_halt_cooldown_seconds > 0);
+    if market_result.is_open && post_halt_enabled {
+        let breaker = accounts.load_circuit_breaker()?;
+        if let Err(cooling) =
+            check_post_halt_cooldown(config.as_ref(), breaker.as_ref(), current_timestamp)
+        {
+            msg!("🐢 POST-HALT COOLDOWN: Trading just resumed - Transfer BLOCKED");
+            return Err(cooling);
+        }
+    }
+
+    // A security-specific late open holds back the hours that would allow it
+    if market_result.is_open {
+        if let Err(delayed) = check_opening_delay(config.as_ref(), current_timestamp) {
+            msg!("⏳ OPENING DELAYED: The open is delayed for this security - Transfer BLOCKED");
+            return Err(delayed);
+        }
+    }
+
+    // Size cap in the minutes before the close; continuous mints have none
+    let limit_enabled = config.as_ref().is_some_and(|config| {
+        config.max_transfer_amount > 0
+            && config.limit_window_minutes > 0
+            && config.session_mode == SessionMode::Scheduled
+    });
+    let in_regular_session = market_result.is_open
+        && matches!(
+            market_result.status,
+            MarketStatus::Open | MarketStatus::Auction
+        );
+    let warmup_enabled = config.as_ref().is_some_and(|config| {
+        config.warmup_minutes > 0
+            && config.warmup_start_limit > 0
+            && config.session_mode == SessionMode::Scheduled
+    });
+    if in_regular_session && warmup_enabled {
+        if let Err(limit) = check_warmup_limit(current_timestamp, config.as_ref(), limited_amount) {
+            msg!(
+                "🚫 WARMUP: Transfer of {} exceeds the limit after the open - Transfer BLOCKED",
+                amount
+            );
+            return Err(limit);
+        }
+    }
+    if in_regular_session && limit_enabled {
+        // The calendar's half days move the close the window counts back from
+        let calendar = accounts.load_holiday_calendar()?;
+        if let Err(limit) = check_transfer_limit(
+            current_timestamp,
+            config.as_ref(),
+            calendar.as_ref(),
+            limited_amount,
+        ) {
+            msg!("🚫 CLOSING WINDOW: Transfer of {} exceeds the per-transfer limit - Transfer BLOCKED", amount);
+            return Err(limit);
+        }
+    }
+    let cross_enabled = config.as_ref().is_some_and(|config| {
+        config.blocking_during_closing_cross
+            && config.closing_cross_seconds > 0
+            && config.session_mode == SessionMode::Scheduled
+    });
+    if in_regular_session && cross_enabled {
+        let calendar = accounts.load_holiday_calendar()?;
+        if let Err(cross) =
+            check_closing_cross(current_timestamp, config.as_ref(), calendar.as_ref())
+        {
+            msg!(
+                "🚫 CLOSING CROSS: New transfers wait for the auction to settle - Transfer BLOCKED"
+            );
+            return Err(cross);
+        }
+    }
+
+    // Dust floor, against spam and price-painting
+    if market_result.is_open {
+        if let Err(dust) = check_transfer_minimum(config.as_ref(), limited_amount) {
+            msg!(
+                "🚫 DUST: Transfer of {} is below the minimum - Transfer BLOCKED",
+                amount
+            );
+            return Err(dust);
+        }
+    }
+
+    // Mint-wide halt after a volume spike
+    #[cfg(feature = "circuit-breaker")]
+    if market_result.is_open {
+        if let Err(tripped) = record_circuit_breaker(
+            accounts,
+            config.as_ref(),
+            current_timestamp,
+            limited_amount,
+            commit,
+        ) {
+            msg!("🧯 CIRCUIT BREAKER: Tripped by a volume spike - Transfer BLOCKED");
+            return Err(tripped);
+        }
+    }
+
+    // Rolling per-owner volume cap
+    #[cfg(feature = "volume-tracking")]
+    if market_result.is_open {
+        if let Err(limit) = record_volume(
+            accounts,
+            config.as_ref(),
+            current_timestamp,
+            limited_amount,
+            commit,
+        ) {
+            msg!("🚫 VOLUME CAP: Owner's rolling volume limit reached - Transfer BLOCKED");
+            return Err(limit);
+        }
+    }
+
+    // Fresh destination accounts are a common routing trick
+    #[cfg(feature = "destination-age")]
+    if market_result.is_open {
+        let min_age = config
+            .as_ref()
+            .map_or(0, |config| config.min_destination_age_seconds);
+        let age = match min_age {
+            0 => None,
+            _ => accounts.load_destination_age()?,
+        };
+        if let Err(too_new) = check_destination_age(age.as_ref(), current_timestamp, min_age) {
+            msg!("🐣 NEW ACCOUNT: Destination was registered too recently - Transfer BLOCKED");
+            return Err(too_new);
+        }
+    }
+
+    // Per-owner cooldown against rapid-fire trading
+    #[cfg(feature = "cooldown")]
+    if market_result.is_open {
+        if let Err(cooldown) =
+            record_last_transfer(accounts, config.as_ref(), current_timestamp, commit)
+        {
+            msg!("🚫 COOLDOWN: Owner transferred too recently - Transfer BLOCKED");
+            return Err(cooldown);
+        }
+    }
+
+    // Per-owner transfer count for the trading day
+    #[cfg(feature = "daily-limit")]
+    if market_result.is_open {
+        if let Err(limit) = record_daily_count(accounts, config.as_ref(), current_timestamp, commit)
+        {
+            msg!("🚫 DAILY LIMIT: Owner made too many transfers today - Transfer BLOCKED");
+            return Err(limit);
+        }
+    }
+
+    // Block transfers based on market state
+    match market_result.is_open {
+        true if market_result.status == MarketStatus::Extended => {
+            verbose_msg!(
+                verbose,
+                "🌙 EXTENDED HOURS: Transfer allowed in the pre-market or after-hours session"
+            );
+            allow(AllowReason::ExtendedHours)
+        }
+        true if market_result.reason == MarketReason::SoftPreOpen => {
+            msg!("🌤️ SOFT OPEN: Transfer allowed ahead of the open and flagged");
+            if commit {
+                emit!(SoftOpenTransfer {
+                    amount,
+                    timestamp: current_timestamp,
+                    source_owner: accounts.source_token.owner,
+                });
+            }
+            allow(AllowReason::MarketOpen)
+        }
+        true => {
+            verbose_msg!(
+                verbose,
+                "✅ NYSE OPEN: Transfer allowed during market hours"
+            );
+            allow(AllowReason::MarketOpen)
+        }
+        false => {
+            // Orders staged into the holding account ahead of the open
+            if is_pre_open_staging(
+                config.as_ref(),
+                || accounts.load_holiday_calendar(),
+                &market_result,
+                current_timestamp,
+                &accounts.destination_token.owner,
+            )? {
+                verbose_msg!(
+                    verbose,
+                    "📥 PRE-OPEN STAGING: Transfer into the staging account allowed before the open"
+                );
+                return allow(AllowReason::PreOpenStaging);
+            }
+
+            let verdict = enforce_closed(config.as_ref(), &market_result);
+            if verdict.is_ok() {
+                if commit {
+                    emit!(TransferWouldBlock::new(
+                        amount,
+                        current_timestamp,
+                        &market_result
+                    ));
+                    accounts.record_stats(&market_result, true)?;
+                }
+                verbose_msg!(
+                    verbose,
+                    "👀 WARN ONLY: {} - Transfer would be BLOCKED, allowed during rollout",
+                    market_result.reason.as_str()
+                );
+                return Ok(None);
+            }
+
+            // An operator's voucher for exactly this transfer
+            #[cfg(feature = "transfer-vouchers")]
+            if redeem_voucher(accounts, amount, current_timestamp, commit)? {
+                verbose_msg!(
+                    verbose,
+                    "🎟️  VOUCHER: One-off exemption used - Transfer allowed while the market is closed"
+                );
+                return allow(AllowReason::Voucher);
+            }
+
+            // Tell the client when to retry; a treasury window only reopens
+            // within reach later the same day
+            let next_open = match treasury_state {
+                Some(state) if state.reason == MarketReason::PreMarket => state.next_transition,
+                Some(_) => 0,
+                None => {
+                    let calendar = accounts.load_holiday_calendar()?;
+                    next_open(
+                        current_timestamp,
+                        &market_result,
+                        config.as_ref(),
+                        calendar.as_ref(),
+                    )?
+                }
+            };
+            set_blocked_return_data(&market_result, next_open)?;
+
+            if rejects_quietly(config.as_ref()) {
+                msg!("🚫 Transfer rejected");
+                if commit {
+                    accounts.record_stats(&market_result, false)?;
+                }
+                return verdict.map(|()| None);
+            }
+            if commit {
+                if accounts.is_new_decision(config.as_ref(), &market_result)? {
+                    emit!(
+                        TransferBlocked::new(amount, current_timestamp, &market_result)
+                            .with_next_open(next_open)
+                    );
+                } else {
+                    emit!(TransferStatusUnchanged {
+                        amount,
+                        timestamp: current_timestamp,
+                        allowed: false,
+                    });
+                }
+            }
+            msg!(
+                "🚫 NYSE CLOSED: {} - Transfer BLOCKED",
+                market_result.reason.as_str()
+            );
+            verbose_msg!(verbose, "💥 RETURNING ERROR TO BLOCK TRANSFER");
+            if commit {
+                accounts.record_stats(&market_result, false)?;
+            }
+            verdict.map(|()| None)
+        }
+    }
+}
+
+// Verdict of a dry run, as `simulate_transfer` reports it
+fn transfer_verdict(result: Result<Option<AllowReason>>) -> TransferVerdict {
+    let (allow_reason, error_code) = match result {
+        Ok(allow_reason) => {
+            return TransferVerdict {
+                allowed: true,
+                allow_reason,
+                error_code: 0,
+            }
+        }
+        Err(Error::AnchorError(error)) => (None, error.error_code_number as u64),
+        Err(Error::ProgramError(error)) => (None, u64::from(error.program_error)),
+    };
+    TransferVerdict {
+        allowed: false,
+        allow_reason,
+        error_code,
+    }
+}
+
+// Market state for the permissionless cranks, from the mint's config and
+// calendar PDAs (either may be empty), and the time it was evaluated at
+fn crank_market_state(
+    market_config: &AccountInfo,
+    holiday_calendar: &AccountInfo,
+) -> Result<(MarketState, i64)> {
+    let clock = Clock::get()?;
+    let config = MarketConfig::load(market_config)?;
+    let current_timestamp = effective_timestamp(clock.unix_timestamp, config.as_ref());
+    let calendar = HolidayCalendar::load(holiday_calendar)?;
+    let market_state = match ensure_not_halted(config.as_ref()) {
+        Ok(()) => get_nyse_market_state(current_timestamp, config.as_ref(), calendar.as_ref())?,
+        Err(_) => MarketState::HALTED,
+    };
+    Ok((market_state, current_timestamp))
+}
+
+// Emit MarketOpened/MarketClosed if `state` flips the mint's recorded
+// transition, as loaded from `info`; a no-op for mints without a tracker
+fn record_transition(
+    info: &AccountInfo,
+    transition: Option<MarketTransition>,
+    state: &MarketState,
+    timestamp: i64,
+    verbose: bool,
+) -> Result<()> {
+    let Some(mut transition) = transition else {
+        return Ok(());
+    };
+    match transition.observe(state, timestamp) {
+        Some(true) => emit!(MarketOpened {
+            timestamp,
+            status: state.status as u8,
+        }),
+        Some(false) => emit!(MarketClosed {
+            timestamp,
+            status: state.status as u8,
+            reason_code: state.reason as u8,
+        }),
+        None => return Ok(()),
+    }
+    verbose_msg!(verbose, "🔔 Market transition: {}", state.status.as_str());
+    transition.store(info)
+}
+
+// Create the program-owned PDA of `seeds` funded by the mint's rent reserve
+// and return its bump, found only here since creation happens once per
+// owner. The reserve is debited directly (this program owns it) and the PDA
+// allocates and assigns itself through the system program.
+#[cfg(any(
+    feature = "volume-tracking",
+    feature = "cooldown",
+    feature = "daily-counter"
+))]
+fn create_from_reserve<'info>(
+    accounts: &TransferHook<'info>,
+    target: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    space: usize,
+) -> Result<u8> {
+    let (address, bump) = Pubkey::find_program_address(seeds, &crate::ID);
+    // One attempt per bump tried, from 255 down
+    #[cfg(test)]
+    PDA_HASHES.set(PDA_HASHES.get() + 256 - bump as u64);
+    require_keys_eq!(*target.key, address, ErrorCode::ConstraintSeeds);
+    let rent_reserve = &accounts.rent_reserve;
+    let needed = reserve_rent(accounts, target, space)?;
+    **rent_reserve.try_borrow_mut_lamports()? -= needed;
+    **target.try_borrow_mut_lamports()? += needed;
+    let bump_seed = [bump];
+    let signer_seeds = [seeds, &[&bump_seed[..]]].concat();
+    allocate_and_assign(target, &accounts.system_program, &signer_seeds, space)?;
+    Ok(bump)
+}
+
+// Lamports the mint's rent reserve must move into `target` to make `space`
+// bytes rent-exempt; fails when its spare balance can't cover them. Only a
+// reserve at the address it stored can be spent from.
+#[cfg(any(
+    feature = "volume-tracking",
+    feature = "cooldown",
+    feature = "daily-counter"
+))]
+fn reserve_rent(accounts: &TransferHook, target: &AccountInfo, space: usize) -> Result<u64> {
+    let rent = Rent::get()?;
+    let needed = rent
+        .minimum_balance(space)
+        .saturating_sub(target.lamports());
+    let rent_reserve = &accounts.rent_reserve;
+    let reserve = RentReserve::load(rent_reserve)?;
+    check_stored_pda(
+        rent_reserve,
+        &[RENT_RESERVE_SEED, accounts.mint.key().as_ref()],
+        reserve.as_ref().map(|reserve| reserve.bump),
+        ErrorCode::ConstraintSeeds,
+    )?;
+    let spare = match reserve {
+        Some(_) => rent_reserve
+            .lamports()
+            .saturating_sub(rent.minimum_balance(rent_reserve.data_len())),
+        None => 0,
+    };
+    require!(spare >= needed, NyseError::RentReserveDepleted);
+    Ok(needed)
+}
+
+// Give an already funded PDA its space and hand it to this program
+fn allocate_and_assign<'info>(
+    target: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+    space: usize,
+) -> Result<()> {
+    invoke_signed(
+        &system_instruction::allocate(target.key, space as u64),
+        &[target.clone(), system_program.clone()],
+        &[signer_seeds],
+    )?;
+    invoke_signed(
+        &system_instruction::assign(target.key, &crate::ID),
+        &[target.clone(), system_program.clone()],
+        &[signer_seeds],
+    )?;
+    Ok(())
+}
+
+// Charge `amount` against the source owner's rolling volume, creating their
+// tracker on the first capped transfer
+#[cfg(feature = "volume-tracking")]
+fn record_volume(
+    accounts: &TransferHook,
+    config: Option<&MarketConfig>,
+    now: i64,
+    amount: u64,
+    commit: bool,
+) -> Result<()> {
+    let Some(config) =
+        config.filter(|config| config.max_volume_per_window > 0 && config.window_seconds > 0)
+    else {
+        return Ok(());
+    };
+
+    let tracker_info = accounts.volume_tracker.to_account_info();
+    let mut tracker = match accounts.load_volume_tracker()? {
+        Some(tracker) => tracker,
+        None => {
+            let space = 8 + VolumeTracker::INIT_SPACE;
+            // The dry run never stores the tracker, so its bump goes unused
+            let bump = if commit {
+                let mint = accounts.mint.key();
+                let owner = accounts.source_token.owner;
+                create_from_reserve(
+                    accounts,
+                    &tracker_info,
+                    &[VOLUME_TRACKER_SEED, mint.as_ref(), owner.as_ref()],
+                    space,
+                )?
+            } else {
+                reserve_rent(accounts, &tracker_info, space)?;
+                0
+            };
+            VolumeTracker {
+                last_update: now,
+                volume: 0,
+                bump,
+            }
+        }
+    };
+
+    accumulate_volume(
+        &mut tracker,
+        now,
+        amount,
+        config.max_volume_per_window,
+        config.window_seconds,
+    )?;
+    if !commit {
+        return Ok(());
+    }
+    tracker.store(&tracker_info)
+}
+
+// Enforce the per-owner cooldown and stamp the owner's LastTransfer, creating
+// it from the rent reserve on their first cooled-down transfer
+#[cfg(feature = "cooldown")]
+fn record_last_transfer(
+    accounts: &TransferHook,
+    config: Option<&MarketConfig>,
+    now: i64,
+    commit: bool,
+) -> Result<()> {
+    let Some(interval) = config
+        .map(|config| config.min_transfer_interval_seconds)
+        .filter(|&interval| interval > 0)
+    else {
+        return Ok(());
+    };
+
+    let last_info = accounts.last_transfer.to_account_info();
+    let last = accounts.load_last_transfer()?;
+    check_cooldown(last.as_ref(), now, interval)?;
+    if !commit {
+        if last.is_none() {
+            reserve_rent(accounts, &last_info, 8 + LastTransfer::INIT_SPACE)?;
+        }
+        return Ok(());
+    }
+    let mut last = match last {
+        Some(last) => last,
+        None => {
+            let mint = accounts.mint.key();
+            let owner = accounts.source_token.owner;
+            let bump = create_from_reserve(
+                accounts,
+                &last_info,
+                &[LAST_TRANSFER_SEED, mint.as_ref(), owner.as_ref()],
+                8 + LastTransfer::INIT_SPACE,
+            )?;
+            LastTransfer {
+                timestamp: now,
+                bump,
+            }
+        }
+    };
+    last.timestamp = now;
+    last.store(&last_info)
+}
+
+// Count the transfer against the owner's DailyCounter
+#[cfg(feature = "daily-limit")]
+fn record_daily_count(
+    accounts: &TransferHook,
+    config: Option<&MarketConfig>,
+    now: i64,
+    commit: bool,
+) -> Result<()> {
+    let Some(config) = config.filter(|config| config.max_transfers_per_day > 0) else {
+        return Ok(());
+    };
+
+    update_daily_counter(accounts, config, now, commit, |counter| {
+        counter.count = next_daily_count(
+            Some(counter),
+            counter.trading_day,
+            config.max_transfers_per_day,
+        )?;
+        Ok(())
+    })
+}
+
+// Add an allowlist-exempt transfer to the source owner's DailyCounter
+#[cfg(feature = "allowlist")]
+fn record_allowlist_volume(
+    accounts: &TransferHook,
+    config: Option<&MarketConfig>,
+    now: i64,
+    amount: u64,
+    commit: bool,
+) -> Result<()> {
+    let Some(config) = config.filter(|config| config.allowlist_daily_cap > 0) else {
+        return Ok(());
+    };
+
+    update_daily_counter(accounts, config, now, commit, |counter| {
+        counter.allowlist_volume =
+            next_allowlist_volume(counter, amount, config.allowlist_daily_cap)?;
+        Ok(())
+    })
+}
+
+// Apply `update` to the owner's DailyCounter for the current trading day,
+// creating it from the rent reserve on their first counted transfer
+#[cfg(feature = "daily-counter")]
+fn update_daily_counter(
+    accounts: &TransferHook,
+    config: &MarketConfig,
+    now: i64,
+    commit: bool,
+    update: impl FnOnce(&mut DailyCounter) -> Result<()>,
+) -> Result<()> {
+    let counter_info = accounts.daily_counter.to_account_info();
+    let stored = accounts.load_daily_counter()?;
+    let today = trading_day(now, config);
+    let mut counter = match &stored {
+        Some(counter) if counter.trading_day == today => counter.clone(),
+        _ => DailyCounter {
+            trading_day: today,
+            count: 0,
+            allowlist_volume: 0,
+            bump: stored.as_ref().map_or(0, |counter| counter.bump),
+        },
+    };
+    update(&mut counter)?;
+    if !commit {
+        if stored.is_none() {
+            reserve_rent(accounts, &counter_info, 8 + DailyCounter::INIT_SPACE)?;
+        }
+        return Ok(());
+    }
+    if stored.is_none() {
+        let mint = accounts.mint.key();
+        let owner = accounts.source_token.owner;
+        counter.bump = create_from_reserve(
+            accounts,
+            &counter_info,
+            &[DAILY_COUNTER_SEED, mint.as_ref(), owner.as_ref()],
+            8 + DailyCounter::INIT_SPACE,
+        )?;
+    }
+
+    counter.store(&counter_info)
+}
+
+// The owner's count including this transfer; a counter from an earlier
+// trading day starts over
+#[cfg(feature = "daily-limit")]
+fn next_daily_count(counter: Option<&DailyCounter>, today: i64, max_per_day: u32) -> Result<u32> {
+    let so_far = counter
+        .filter(|counter| counter.trading_day == today)
+        .map_or(0, |counter| counter.count);
+    require!(so_far < max_per_day, NyseError::DailyTransferLimitExceeded);
+    Ok(so_far + 1)
+}
+
+// Today's exempt volume including this transfer
+#[cfg(feature = "allowlist")]
+fn next_allowlist_volume(counter: &DailyCounter, amount: u64, cap: u64) -> Result<u64> {
+    counter
+        .allowlist_volume
+        .checked_add(amount)
+        .filter(|volume| *volume <= cap)
+        .ok_or_else(|| NyseError::AllowlistCapExceeded.into())
+}
+
+// Local date of the session `timestamp` belongs to, as days since the epoch;
+// the early hours of an overnight session count towards the evening it opened
+#[cfg(feature = "daily-counter")]
+fn trading_day(timestamp: i64, config: &MarketConfig) -> i64 {
+    let local = get_local_time_info(timestamp, &config.timezone);
+    let today = days_from_civil(local.year, local.month, local.day);
+    let seconds = session_seconds(timestamp, &local, Some(config), &config.timezone);
+    if is_overnight(config) && seconds < config.close_minute as u32 * 60 {
+        today - 1
+    } else {
+        today
+    }
+}
+
+// Unregistered destinations count as brand new
+#[cfg(feature = "destination-age")]
+fn check_destination_age(age: Option<&AccountAge>, now: i64, min_age_seconds: u32) -> Result<()> {
+    if min_age_seconds == 0 {
+        return Ok(());
+    }
+    let old_enough = age
+        .is_some_and(|age| interval::has_elapsed(age.registered_at, now, min_age_seconds as u64));
+    require!(old_enough, NyseError::DestinationTooNew);
+    Ok(())
+}
+
+// An owner's first transfer always passes; later ones wait out the interval
+#[cfg(feature = "cooldown")]
+fn check_cooldown(last: Option<&LastTransfer>, now: i64, interval_seconds: u32) -> Result<()> {
+    let Some(last) = last else {
+        return Ok(());
+    };
+    require!(
+        interval::has_elapsed(last.timestamp, now, interval_seconds as u64),
+        NyseError::TransferCooldown
+    );
+    Ok(())
+}
+
+// What is left of `volume` recorded at `last_update`, decaying linearly to
+// zero over the window
+#[cfg(any(feature = "volume-tracking", feature = "circuit-breaker"))]
+fn decayed_volume(volume: u64, last_update: i64, now: i64, window_seconds: u32) -> u64 {
+    let elapsed = interval::elapsed(last_update, now) as u128;
+    let window = window_seconds as u128;
+    if elapsed >= window {
+        0
+    } else {
+        (volume as u128 * (window - elapsed) / window) as u64
+    }
+}
+
+// Count `amount` in the mint's circuit breaker, refusing it while the breaker
+// is tripped; a no-op for mints without one or with it disabled
+#[cfg(feature = "circuit-breaker")]
+fn record_circuit_breaker(
+    accounts: &TransferHook,
+    config: Option<&MarketConfig>,
+    now: i64,
+    amount: u64,
+    commit: bool,
+) -> Result<()> {
+    let Some(config) =
+        config.filter(|config| config.breaker_threshold > 0 && config.breaker_window_seconds > 0)
+    else {
+        return Ok(());
+    };
+    let breaker_info = accounts.circuit_breaker.to_account_info();
+    let Some(mut breaker) = accounts.load_circuit_breaker()? else {
+        return Ok(());
+    };
+
+    if observe_breaker(&mut breaker, config, now, amount)? {
+        verbose_msg!(
+            verbose_logging(Some(config)),
+            "🧯 Circuit breaker tripped at {}",
+            now
+        );
+    }
+    if !commit {
+        return Ok(());
+    }
+    breaker.store(&breaker_info)
+}
+
+// Fail while tripped and cooling down, then re-arm; otherwise add `amount`
+// and trip once the volume exceeds the threshold. Returns whether this
+// transfer tripped it.
+#[cfg(feature = "circuit-breaker")]
+fn observe_breaker(
+    breaker: &mut CircuitBreaker,
+    config: &MarketConfig,
+    now: i64,
+    amount: u64,
+) -> Result<bool> {
+    if breaker.tripped_at != 0 {
+        require!(
+            interval::has_elapsed(
+                breaker.tripped_at,
+                now,
+                config.breaker_cooldown_seconds as u64
+            ),
+            NyseError::CircuitBreakerTripped
+        );
+        breaker.tripped_at = 0;
+        breaker.volume = 0;
+    }
+
+    breaker.volume = decayed_volume(
+        breaker.volume,
+        breaker.last_update,
+        now,
+        config.breaker_window_seconds,
+    )
+    .saturating_add(amount);
+    breaker.last_update = now;
+    let tripped = breaker.volume > config.breaker_threshold;
+    if tripped {
+        breaker.tripped_at = now;
+    }
+    Ok(tripped)
+}
+
+// Decay the tracked volume linearly over the window, then add `amount` if
+// it fits under the cap. A full window of inactivity resets it to zero.
+#[cfg(feature = "volume-tracking")]
+fn accumulate_volume(
+    tracker: &mut VolumeTracker,
+    now: i64,
+    amount: u64,
+    max_volume: u64,
+    window_seconds: u32,
+) -> Result<()> {
+    let remaining = decayed_volume(tracker.volume, tracker.last_update, now, window_seconds);
+    let volume = remaining.saturating_add(amount);
+    require!(volume <= max_volume, NyseError::VolumeLimitExceeded);
+    tracker.volume = volume;
+    tracker.last_update = now;
+    Ok(())
+}
+
+// Token-2022 doesn't run the hook for MintTo/Burn themselves, so issuance
+// shows up as the mint authority distributing tokens and redemption as
+// holders returning them to it ahead of a burn
+fn is_mint_burn(
+    mint_authority: Option<&Pubkey>,
+    source_owner: &Pubkey,
+    destination_owner: &Pubkey,
+    transfer_authority: &Pubkey,
+) -> bool {
+    mint_authority.is_some_and(|authority| {
+        [source_owner, destination_owner, transfer_authority].contains(&authority)
+    })
+}
+
+// Either side of the transfer being denylisted blocks it outright
+fn is_denied(
+    denylist: Option<&Denylist>,
+    source_owner: &Pubkey,
+    destination_owner: &Pubkey,
+) -> bool {
+    denylist.is_some_and(|list| list.contains(source_owner) || list.contains(destination_owner))
+}
+
+// Same-owner transfers are allowed unless the config turns them off
+fn check_self_transfer(
+    config: Option<&MarketConfig>,
+    source_owner: &Pubkey,
+    destination_owner: &Pubkey,
+) -> Result<()> {
+    require!(
+        source_owner != destination_owner
+            || config.map_or(true, |config| config.allow_self_transfer),
+        NyseError::SelfTransferBlocked
+    );
+    Ok(())
+}
+
+// The denylist is checked first in the hook, so it wins here too
+fn account_list_status(
+    allowlist: Option<&Allowlist>,
+    denylist: Option<&Denylist>,
+    account: &Pubkey,
+) -> AccountListStatus {
+    if denylist.is_some_and(|list| list.contains(account)) {
+        AccountListStatus::Denylisted
+    } else if allowlist.is_some_and(|list| list.contains(account)) {
+        AccountListStatus::Allowlisted
+    } else {
+        AccountListStatus::Unlisted
+    }
+}
+
+// Two-bit `AccountListStatus` codes of `accounts`, four to a byte from the
+// low bits up
+fn packed_account_statuses(
+    allowlist: Option<&Allowlist>,
+    denylist: Option<&Denylist>,
+    accounts: &[Pubkey],
+) -> Result<Vec<u8>> {
+    require!(
+        accounts.len() <= MAX_STATUS_BATCH,
+        NyseError::StatusBatchTooLarge
+    );
+    let mut packed = vec![0u8; accounts.len().div_ceil(4)];
+    for (index, account) in accounts.iter().enumerate() {
+        let status = account_list_status(allowlist, denylist, account) as u8;
+        packed[index / 4] |= status << (2 * (index % 4));
+    }
+    Ok(packed)
+}
+
+// Nonzero amounts under the config's dust threshold; zero amounts keep
+// their own setting
+fn is_dust_exempt(config: Option<&MarketConfig>, amount: u64) -> bool {
+    config.is_some_and(|config| amount > 0 && amount < config.dust_exempt_below)
+}
+
+// Zero-amount transfers skip the hours check unless the config opts out;
+// unconfigured mints let them through
+fn is_zero_amount_exempt(config: Option<&MarketConfig>, amount: u64) -> bool {
+    amount == 0 && config.map_or(true, |config| config.allow_zero_amount)
+}
+
+// Transfers into the incinerator, as the token account or its owner, skip
+// the hours check unless the config opts out; unconfigured mints let them
+// through
+fn is_incinerator_exempt(
+    config: Option<&MarketConfig>,
+    destination: &Pubkey,
+    destination_owner: &Pubkey,
+) -> bool {
+    use anchor_lang::solana_program::incinerator;
+    (*destination == incinerator::ID || *destination_owner == incinerator::ID)
+        && config.map_or(true, |config| config.allow_incinerator)
+}
+
+// A transfer into the staging account in the configured minutes before
+// the open of a trading day, while the market still reads pre-market. The
+// calendar is only loaded once the rest matches.
+fn is_pre_open_staging(
+    config: Option<&MarketConfig>,
+    load_calendar: impl FnOnce() -> Result<Option<HolidayCalendar>>,
+    market_state: &MarketState,
+    timestamp: i64,
+    destination_owner: &Pubkey,
+) -> Result<bool> {
+    let Some(config) = config.filter(|config| {
+        config.pre_open_staging_minutes > 0 && &config.staging_account == destination_owner
+    }) else {
+        return Ok(false);
+    };
+    if market_state.is_open || market_state.reason != MarketReason::PreMarket {
+        return Ok(false);
+    }
+    let local = get_local_time_info(timestamp, &config.timezone);
+    let current_seconds = session_seconds(timestamp, &local, Some(config), &config.timezone);
+    let (open_minutes, _, _) = session_hours(
+        Some(config),
+        load_calendar()?.as_ref(),
+        local.year,
+        local.month,
+        local.day,
+    );
+    let staging_start = open_minutes.saturating_sub(config.pre_open_staging_minutes as u32) * 60;
+    Ok((staging_start..open_minutes * 60).contains(&current_seconds))
+}
+
+// Verdict of a deposit into or withdrawal from the treasury whose direction
+// has its own window: open inside it, closed before or after it. Days the
+// market doesn't trade, and halts, close the treasury as well.
+fn treasury_market_state(
+    timestamp: i64,
+    config: Option<&MarketConfig>,
+    market_state: &MarketState,
+    source_owner: &Pubkey,
+    destination_owner: &Pubkey,
+) -> Option<MarketState> {
+    let config = config.filter(|config| config.treasury != Pubkey::default())?;
+    let window = if source_owner == &config.treasury {
+        config.treasury_withdraw_window
+    } else if destination_owner == &config.treasury {
+        config.treasury_deposit_window
+    } else {
+        None
+    }?;
+    if matches!(
+        market_state.status,
+        MarketStatus::Weekend | MarketStatus::Holiday | MarketStatus::Halted
+    ) {
+        return None;
+    }
+
+    let local = get_local_time_info(timestamp, &config.timezone);
+    let current_seconds = session_seconds(timestamp, &local, Some(config), &config.timezone);
+    let boundary = |minute: u16| {
+        local_to_utc(
+            &config.timezone,
+            config.dst_fold_policy,
+            local.year,
+            local.month,
+            local.day,
+            minute as u32 * 60,
+        )
+    };
+    let (open_seconds, close_seconds) = (
+        window.open_minute as u32 * 60,
+        window.close_minute as u32 * 60,
+    );
+    Some(if current_seconds < open_seconds {
+        MarketState {
+            is_open: false,
+            status: MarketStatus::AfterHours,
+            reason: MarketReason::PreMarket,
+            next_transition: boundary(window.open_minute),
+        }
+    } else if current_seconds < close_seconds {
+        MarketState {
+            is_open: true,
+            status: MarketStatus::Open,
+            reason: MarketReason::RegularTradingHours,
+            next_transition: boundary(window.close_minute),
+        }
+    } else {
+        MarketState {
+            is_open: false,
+            status: MarketStatus::AfterHours,
+            reason: MarketReason::AfterMarket,
+            next_transition: market_state.next_transition,
+        }
+    })
+}
+
+// The exemptions that also let a transfer through a maintenance window:
+// mint/burn, the allowlist, pools and protocol programs
+fn waives_maintenance(
+    accounts: &TransferHook,
+    config: Option<&MarketConfig>,
+    allowlist: Option<&Allowlist>,
+) -> bool {
+    let (source_owner, destination_owner) = (
+        &accounts.source_token.owner,
+        &accounts.destination_token.owner,
+    );
+    is_mint_burn(
+        Option::<Pubkey>::from(accounts.mint.mint_authority).as_ref(),
+        source_owner,
+        destination_owner,
+        &accounts.owner.key(),
+    ) || is_exempt(allowlist, source_owner, destination_owner)
+        || is_liquidity_operation(config, allowlist, source_owner, destination_owner)
+        || is_protocol_account(allowlist, &accounts.owner, source_owner)
+}
+
+// Either side of the transfer being allowlisted exempts it from the hours check
+fn is_exempt(
+    allowlist: Option<&Allowlist>,
+    source_owner: &Pubkey,
+    destination_owner: &Pubkey,
+) -> bool {
+    allowlist.is_some_and(|list| {
+        list.direction(source_owner)
+            .is_some_and(ExemptDirection::exempts_sending)
+            || list
+                .direction(destination_owner)
+                .is_some_and(ExemptDirection::exempts_receiving)
+    })
+}
+
+// Either side is a tagged pool's vault authority, unless the config makes
+// pool transfers follow the hours. A swap and a deposit look the same here.
+fn is_liquidity_operation(
+    config: Option<&MarketConfig>,
+    allowlist: Option<&Allowlist>,
+    source_owner: &Pubkey,
+    destination_owner: &Pubkey,
+) -> bool {
+    let is_pool = |owner| {
+        allowlist.is_some_and(|list| list.direction(owner) == Some(ExemptDirection::LiquidityPool))
+    };
+    !config.is_some_and(|config| config.pool_transfers_follow_hours)
+        && (is_pool(source_owner) || is_pool(destination_owner))
+}
+
+// Whether the source token account's owner is a PDA of an allowlisted
+// program: `owner` must be that account, off the ed25519 curve (so no key
+// can sign for it) and owned by the listed program
+fn is_protocol_account(
+    allowlist: Option<&Allowlist>,
+    owner: &AccountInfo,
+    source_owner: &Pubkey,
+) -> bool {
+    allowlist.is_some_and(|list| {
+        owner.key == source_owner && list.lists_program(owner.owner) && is_off_curve(owner.key)
+    })
+}
+
+fn is_off_curve(key: &Pubkey) -> bool {
+    #[cfg(target_os = "solana")]
+    {
+        // 0 = CURVE25519_EDWARDS; the syscall returns 0 for a point on it
+        let mut point = 0u8;
+        #[allow(deprecated)]
+        let result = unsafe {
+            anchor_lang::solana_program::syscalls::sol_curve_validate_point(
+                0,
+                key.as_ref().as_ptr(),
+                &mut point,
+            )
+        };
+        result != 0
+    }
+    #[cfg(not(target_os = "solana"))]
+    {
+        !key.is_on_curve()
+    }
+}
+
+// Time the market rules are evaluated at. Testnet builds can pin it with the
+// config's `test_time_override` or replay a compressed calendar; every other
+// build uses the cluster clock as-is.
+fn effective_timestamp(unix_timestamp: i64, config: Option<&MarketConfig>) -> i64 {
+    #[cfg(feature = "time-override")]
+    if let Some(timestamp) = config.and_then(|config| config.test_time_override) {
+        return timestamp;
+    }
+    #[cfg(not(feature = "time-override"))]
+    let _ = config;
+
+    #[cfg(feature = "testnet-calendar")]
+    {
+        testnet_calendar::compress(unix_timestamp)
+    }
+    #[cfg(not(feature = "testnet-calendar"))]
+    {
+        unix_timestamp
+    }
+}
+
+// COMPREHENSIVE NYSE MARKET HOURS LOGIC
+/// Whether the hook would let a transfer of a mint without a MarketConfig
+/// or HolidayCalendar through its hours check at `timestamp`. Out-of-range
+/// timestamps count as closed.
+///
+/// ```
+/// use nyse_token_hook::{is_market_open_at, MARKET_OPEN_MINUTES};
+///
+/// // Tuesday 2024-01-09 09:30:00 EST, exactly at the open
+/// let open = 1_704_810_600;
+/// assert_eq!(MARKET_OPEN_MINUTES, 9 * 60 + 30);
+/// assert!(is_market_open_at(open));
+/// assert!(!is_market_open_at(open - 1));
+/// ```
+pub fn is_market_open_at(timestamp: i64) -> bool {
+    get_nyse_market_state(timestamp, None, None).is_ok_and(|state| state.is_open)
+}
+
+pub(crate) fn get_nyse_market_state(
+    timestamp: i64,
+    config: Option<&MarketConfig>,
+    calendar: Option<&HolidayCalendar>,
+) -> Result<MarketState> {
+    // A cluster clock outside this range is broken, not a closed market
+    require!(is_valid_timestamp(timestamp), NyseError::InvalidTimestamp);
+
+    // Local exchange time; Eastern Time for mints without a config. A DST
+    // ruleset from newer tooling fails rather than reading as no DST.
+    let timezone = config.map_or(TimezoneConfig::US_EASTERN, |config| config.timezone);
+    timezone.dst_rule()?;
+    let et_info = get_local_time_info(timestamp, &timezone);
+    let verbose = verbose_logging(config);
+
+    verbose_msg!(
+        verbose,
+        "🕐 Local Time: {}-{:02}-{:02} {:02}:{:02}:{:02} UTC{:+}s{} (Weekday: {})",
+        et_info.year,
+        et_info.month,
+        et_info.day,
+        et_info.hour,
+        et_info.minute,
+        et_info.second,
+        et_info.utc_offset_seconds,
+        if et_info.is_dst { " DST" } else { "" },
+        et_info.weekday
+    );
+
+    let next_transition = next_transition(timestamp, config, calendar, &timezone);
+
+    if let Some(config) = config.filter(|config| is_overnight(config)) {
+        return Ok(overnight_market_state(
+            session_seconds(timestamp, &et_info, Some(config), &timezone),
+            &et_info,
+            config,
+            calendar,
+            next_transition,
+        ));
+    }
+
+    // 1. FIRST: Check weekend (Saturday and Sunday unless configured otherwise)
+    if !is_trading_day(config, et_info.weekday) {
+        verbose_msg!(
+            verbose,
+            "🚫 WEEKEND DETECTED: Weekday = {}",
+            et_info.weekday
+        );
+        return Ok(MarketState {
+            is_open: false,
+            status: MarketStatus::Weekend,
+            reason: MarketReason::Weekend,
+            next_transition,
+        });
+    }
+
+    // 2. SECOND: Check holidays (built-in NYSE rules and/or the mint's calendar)
+    if is_market_holiday(config, calendar, et_info.year, et_info.month, et_info.day) {
+        return Ok(MarketState {
+            is_open: false,
+            status: MarketStatus::Holiday,
+            reason: MarketReason::Holiday,
+            next_transition,
+        });
+    }
+
+    // Continuous ("24/5") mints are open for the whole of any trading day
+    if is_continuous(config) {
+        return Ok(MarketState {
+            is_open: true,
+            status: MarketStatus::Open,
+            reason: MarketReason::RegularTradingHours,
+            next_transition,
+        });
+    }
+
+    // 3. THIRD: Check market hours (configured, or 9:30 AM - 4:00 PM ET),
+    // to the second unless the config asks for whole minutes: the open is
+    // inclusive and the close exclusive, so 09:29:59 is pre-market,
+    // 09:30:00 is open and 16:00:00 is closed
+    let current_seconds = session_seconds(timestamp, &et_info, config, &timezone);
+    let (market_open_minutes, market_close_minutes, early_close) =
+        session_hours(config, calendar, et_info.year, et_info.month, et_info.day);
+    let market_open_seconds = market_open_minutes * 60;
+    let market_close_seconds = market_close_minutes * 60;
+
+    let in_session = |open_minutes: u32, close_minutes: u32| {
+        (open_minutes * 60..close_minutes.min(market_close_minutes) * 60).contains(&current_seconds)
+    };
+    let sessions = config.map_or(&[][..], |config| &config.sessions[..]);
+    let is_open = if sessions.is_empty() {
+        in_session(market_open_minutes, market_close_minutes)
+    } else {
+        sessions
+            .iter()
+            .any(|session| in_session(session.open_minute as u32, session.close_minute as u32))
+    };
+
+    // Configured grace just outside the day's open and close; weekends and
+    // holidays returned above, so it can't spill into them
+    let (open_grace, close_grace) = config.map_or((0, 0), |config| {
+        (
+            config.open_grace_seconds as u32,
+            config.close_grace_seconds as u32,
+        )
+    });
+    let in_grace = (market_open_seconds.saturating_sub(open_grace)..market_open_seconds)
+        .contains(&current_seconds)
+        || (market_close_seconds..market_close_seconds + close_grace).contains(&current_seconds);
+
+    // Opted-in mints accept the minutes before the open, but flag them
+    let soft_open = config.map_or(0, |config| config.soft_open_minutes as u32 * 60);
+    let in_soft_open = (market_open_seconds.saturating_sub(soft_open)..market_open_seconds)
+        .contains(&current_seconds);
+
+    // Outside the pre-market and post-market sessions the market is closed
+    // overnight
+    let premarket_seconds = premarket_open_minutes(config) * 60;
+    let in_extended_window =
+        (premarket_seconds..EXTENDED_CLOSE_MINUTES * 60).contains(&current_seconds);
+    let closed_status = if in_extended_window {
+        MarketStatus::AfterHours
+    } else {
+        MarketStatus::Overnight
+    };
+
+    // The first and last minutes of the day's session are the auctions
+    let (opening_auction, closing_auction) = auction_seconds(config);
+    let auction = if (market_open_seconds..market_open_seconds + opening_auction)
+        .contains(&current_seconds)
+    {
+        Some(MarketReason::OpeningAuction)
+    } else if (market_close_seconds.saturating_sub(closing_auction)..market_close_seconds)
+        .contains(&current_seconds)
+    {
+        Some(MarketReason::ClosingAuction)
+    } else {
+        None
+    };
+
+    if let (true, Some(reason)) = (is_open, auction) {
+        Ok(MarketState {
+            is_open: config.map_or(true, |config| config.allow_auction_transfers),
+            status: MarketStatus::Auction,
+            reason,
+            next_transition,
+        })
+    } else if is_open {
+        Ok(MarketState {
+            is_open: true,
+            status: MarketStatus::Open,
+            reason: MarketReason::RegularTradingHours,
+            next_transition,
+        })
+    } else if in_soft_open {
+        Ok(MarketState {
+            is_open: true,
+            status: MarketStatus::Open,
+            reason: MarketReason::SoftPreOpen,
+            next_transition,
+        })
+    } else if in_grace {
+        Ok(MarketState {
+            is_open: true,
+            status: MarketStatus::Open,
+            reason: MarketReason::GracePeriod,
+            next_transition,
+        })
+    } else if early_close && current_seconds >= market_close_seconds {
+        Ok(MarketState {
+            is_open: false,
+            status: closed_status,
+            reason: MarketReason::EarlyClose,
+            next_transition,
+        })
+    } else {
+        let reason = if current_seconds < market_open_seconds {
+            MarketReason::PreMarket
+        } else if current_seconds >= market_close_seconds {
+            MarketReason::AfterMarket
+        } else {
+            // Between two of the configured sessions; no extended trading
+            return Ok(MarketState {
+                is_open: false,
+                status: MarketStatus::AfterHours,
+                reason: MarketReason::LunchBreak,
+                next_transition,
+            });
+        };
+
+        // Opted-in mints keep trading in the pre-market and after-hours sessions
+        let extended_hours =
+            config.is_some_and(|config| config.extended_hours == ExtendedHoursMode::ExtendedHours);
+        if extended_hours && in_extended_window {
+            return Ok(MarketState {
+                is_open: true,
+                status: MarketStatus::Extended,
+                reason,
+                next_transition,
+            });
+        }
+
+        Ok(MarketState {
+            is_open: false,
+            status: closed_status,
+            reason,
+            next_transition,
+        })
+    }
+}
+
+// Whether the session runs past midnight: a close minute before the open
+// minute means the session opens on a trading day and closes the next
+// morning. Continuous mode ignores the session minutes.
+fn is_overnight(config: &MarketConfig) -> bool {
+    config.session_mode == SessionMode::Scheduled && config.close_minute < config.open_minute
+}
+
+// Verdict under an overnight session. The hours before the close belong to
+// the session that opened the previous evening, so they follow that day's
+// weekday and holiday status: a Friday 20:00-04:00 session is still open
+// early on Saturday. Grace, auctions, extended hours and early closes
+// don't apply to overnight sessions.
+fn overnight_market_state(
+    current_seconds: u32,
+    local: &EasternTimeInfo,
+    config: &MarketConfig,
+    calendar: Option<&HolidayCalendar>,
+    next_transition: i64,
+) -> MarketState {
+    let open_seconds = config.open_minute as u32 * 60;
+    let close_seconds = config.close_minute as u32 * 60;
+
+    let today = days_from_civil(local.year, local.month, local.day);
+    let session_day = if current_seconds < close_seconds {
+        today - 1
+    } else {
+        today
+    };
+    let (year, month, day) = days_to_date(session_day);
+    let closed = |status, reason| MarketState {
+        is_open: false,
+        status,
+        reason,
+        next_transition,
+    };
+
+    if !is_trading_day(Some(config), day_of_week(session_day)) {
+        closed(MarketStatus::Weekend, MarketReason::Weekend)
+    } else if is_market_holiday(Some(config), calendar, year, month, day) {
+        closed(MarketStatus::Holiday, MarketReason::Holiday)
+    } else if current_seconds < close_seconds || current_seconds >= open_seconds {
+        MarketState {
+            is_open: true,
+            status: MarketStatus::Open,
+            reason: MarketReason::RegularTradingHours,
+            next_transition,
+        }
+    } else {
+        // Between the morning close and the evening open
+        closed(MarketStatus::AfterHours, MarketReason::PreMarket)
+    }
+}
+
+fn program_info() -> ProgramInfo {
+    let mut features = FEATURE_MARKET_CONFIG | FEATURE_EXTENDED_HOURS;
+    for (enabled, bit) in [
+        (cfg!(feature = "allowlist"), FEATURE_ALLOWLIST),
+        (cfg!(feature = "denylist"), FEATURE_DENYLIST),
+        (cfg!(feature = "volume-tracking"), FEATURE_VOLUME_TRACKING),
+        (cfg!(feature = "circuit-breaker"), FEATURE_CIRCUIT_BREAKER),
+        (cfg!(feature = "holiday-calendar"), FEATURE_HOLIDAY_CALENDAR),
+        (cfg!(feature = "state-cache"), FEATURE_STATE_CACHE),
+        (
+            cfg!(feature = "transition-events"),
+            FEATURE_TRANSITION_EVENTS,
+        ),
+        (cfg!(feature = "hook-stats"), FEATURE_HOOK_STATS),
+        (cfg!(feature = "cooldown"), FEATURE_COOLDOWN),
+        (cfg!(feature = "destination-age"), FEATURE_DESTINATION_AGE),
+        (
+            cfg!(feature = "transfer-vouchers"),
+            FEATURE_TRANSFER_VOUCHERS,
+        ),
+        (cfg!(feature = "daily-limit"), FEATURE_DAILY_LIMIT),
+    ] {
+        if enabled {
+            features |= bit;
+        }
+    }
+    if cfg!(feature = "testnet-calendar") {
+        features |= FEATURE_TESTNET_CALENDAR;
+    }
+    if cfg!(feature = "time-override") {
+        features |= FEATURE_TIME_OVERRIDE;
+    }
+    ProgramInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        default_open_minute: MARKET_OPEN_MINUTES as u16,
+        default_close_minute: MARKET_CLOSE_MINUTES as u16,
+        features,
+    }
+}
+
+// Verdict for `timestamp`, served from the cache when it already holds this
+// minute under the same config version; otherwise computed and written into
+// the cache
+fn cached_market_state(
+    cache: Option<&mut MarketStateCache>,
+    timestamp: i64,
+    config_version: u64,
+    verbose: bool,
+    compute: impl FnOnce() -> Result<MarketState>,
+) -> Result<MarketState> {
+    let minute = timestamp.div_euclid(60);
+    match cache {
+        Some(cache) if cache.minute == minute && cache.config_version == config_version => {
+            verbose_msg!(verbose, "🗄️  Using cached market state for this minute");
+            Ok(cache.state)
+        }
+        Some(cache) => {
+            let state = compute()?;
+            cache.minute = minute;
+            cache.state = state;
+            cache.config_version = config_version;
+            Ok(state)
+        }
+        None => compute(),
+    }
+}
+
+// Session (open, close, is_early_close) in local minutes for the mint's
+// configured hours, or the NYSE defaults
+fn session_hours(
+    config: Option<&MarketConfig>,
+    calendar: Option<&HolidayCalendar>,
+    year: i32,
+    month: u32,
+    day: u32,
+) -> (u32, u32, bool) {
+    let (open_minutes, regular_close_minutes) = match config {
+        Some(config) => (config.open_minute as u32, config.close_minute as u32),
+        None => (MARKET_OPEN_MINUTES, MARKET_CLOSE_MINUTES),
+    };
+
+    // A scheduled one-off early close wins over the calendar's half days,
+    // which win over the recurring built-in ones
+    let date = local_date(year, month, day);
+    let scheduled = config
+        .and_then(|config| config.early_close_override)
+        .filter(|early_close| Some(early_close.date) == date)
+        .map(|early_close| early_close.close_minute);
+    let mode = config.map_or(HolidayMode::BuiltIn, |config| config.holiday_mode);
+    let listed = calendar
+        .filter(|_| mode != HolidayMode::BuiltIn)
+        .zip(date)
+        .and_then(|(calendar, date)| calendar.early_close(&date));
+    if let Some(close_minute) = scheduled.or(listed) {
+        let close_minutes = (close_minute as u32).min(regular_close_minutes);
+        return (open_minutes, close_minutes, true);
+    }
+
+    session_bounds(open_minutes, regular_close_minutes, year, month, day)
+}
+
+// Most phase changes `market_countdown` steps through looking for an open or
+// close; four a trading day, and next_transition skips closed days
+const MAX_COUNTDOWN_STEPS: usize = 8;
+
+// Walk the phase changes after `timestamp` to the next one that flips
+// whether the market is open
+fn market_countdown(
+    timestamp: i64,
+    state: &MarketState,
+    config: Option<&MarketConfig>,
+    calendar: Option<&HolidayCalendar>,
+) -> Result<MarketCountdown> {
+    let mut at = state.next_transition;
+    for _ in 0..MAX_COUNTDOWN_STEPS {
+        if at <= timestamp || !crate::calendar::is_valid_timestamp(at) {
+            break;
+        }
+        let next = get_nyse_market_state(at, config, calendar)?;
+        if next.is_open != state.is_open {
+            return Ok(MarketCountdown {
+                seconds_until_next_transition: interval::remaining(timestamp, at) as i64,
+                next_transition_is_open: next.is_open,
+            });
+        }
+        at = next.next_transition;
+    }
+    Ok(MarketCountdown {
+        seconds_until_next_transition: 0,
+        next_transition_is_open: state.is_open,
+    })
+}
+
+// UTC timestamp at which a closed market next lets transfers through on
+// the hours alone, as the countdown finds it; 0 when none is in reach
+fn next_open(
+    timestamp: i64,
+    state: &MarketState,
+    config: Option<&MarketConfig>,
+    calendar: Option<&HolidayCalendar>,
+) -> Result<i64> {
+    let countdown = market_countdown(timestamp, state, config, calendar)?;
+    Ok(match countdown.next_transition_is_open && !state.is_open {
+        true => interval::after(timestamp, countdown.seconds_until_next_transition as u64),
+        false => 0,
+    })
+}
+
+// Effective schedule of a local date under the mint's rules
+fn schedule_for_date(
+    config: Option<&MarketConfig>,
+    calendar: Option<&HolidayCalendar>,
+    date: HolidayDate,
+) -> Result<DaySchedule> {
+    validate_holiday_date(&date)?;
+    let (year, month, day) = (date.year as i32, date.month as u32, date.day as u32);
+
+    let weekday = day_of_week(days_from_civil(year, month, day));
+    if !is_trading_day(config, weekday) || is_market_holiday(config, calendar, year, month, day) {
+        return Ok(DaySchedule {
+            is_trading_day: false,
+            open_minute: 0,
+            close_minute: 0,
+            early_close: false,
+        });
+    }
+    if is_continuous(config) {
+        return Ok(DaySchedule {
+            is_trading_day: true,
+            open_minute: 0,
+            close_minute: 24 * 60,
+            early_close: false,
+        });
+    }
+
+    let (open_minutes, close_minutes, early_close) =
+        session_hours(config, calendar, year, month, day);
+    Ok(DaySchedule {
+        is_trading_day: true,
+        open_minute: open_minutes as u16,
+        close_minute: close_minutes as u16,
+        early_close,
+    })
+}
+
+// One bit per date from `start`, set on trading days, least significant
+// bit first
+fn trading_days_bitmap(
+    config: Option<&MarketConfig>,
+    calendar: Option<&HolidayCalendar>,
+    start: HolidayDate,
+    count_days: u16,
+) -> Result<Vec<u8>> {
+    validate_holiday_date(&start)?;
+    require!(
+        (1..=MAX_RANGE_DAYS).contains(&count_days),
+        NyseError::InvalidDate
+    );
+    let first = days_from_civil(start.year as i32, start.month as u32, start.day as u32);
+    let mut bitmap = vec![0u8; (count_days as usize).div_ceil(8)];
+    for offset in 0..count_days as usize {
+        let days = first + offset as i64;
+        let (year, month, day) = days_to_date(days);
+        if is_trading_day(config, day_of_week(days))
+            && !is_market_holiday(config, calendar, year, month, day)
+        {
+            bitmap[offset / 8] |= 1 << (offset % 8);
+        }
+    }
+    Ok(bitmap)
+}
+
+// Whether the market trades on `weekday` (0 = Sunday); Monday-Friday for
+// mints without a config
+fn is_trading_day(config: Option<&MarketConfig>, weekday: u32) -> bool {
+    match config {
+        Some(config) => config.trades_on(weekday),
+        None => weekday != 0 && weekday != 6,
+    }
+}
+
+// Start of the pre-market session in local minutes, never after the open
+fn premarket_open_minutes(config: Option<&MarketConfig>) -> u32 {
+    config.map_or(EXTENDED_OPEN_MINUTES, |config| {
+        config.premarket_open_minute.min(config.open_minute) as u32
+    })
+}
+
+// Local seconds of the day at which the market can change phase: pre-market
+// open, grace edges, session opens and closes, and the end of the
+// post-market session
+fn phase_boundaries(
+    config: Option<&MarketConfig>,
+    calendar: Option<&HolidayCalendar>,
+    year: i32,
+    month: u32,
+    day: u32,
+) -> Vec<u32> {
+    let (open_minutes, close_minutes, _) = session_hours(config, calendar, year, month, day);
+    let (open_grace, close_grace) = config.map_or((0, 0), |config| {
+        (
+            config.open_grace_seconds as u32,
+            config.close_grace_seconds as u32,
+        )
+    });
+
+    let soft_open = config.map_or(0, |config| config.soft_open_minutes as u32 * 60);
+    let (opening_auction, closing_auction) = auction_seconds(config);
+
+    let mut boundaries = vec![
+        premarket_open_minutes(config) * 60,
+        (open_minutes * 60).saturating_sub(open_grace),
+        (open_minutes * 60).saturating_sub(soft_open),
+        open_minutes * 60,
+        open_minutes * 60 + opening_auction,
+        (close_minutes * 60).saturating_sub(closing_auction),
+        close_minutes * 60,
+        close_minutes * 60 + close_grace,
+        EXTENDED_CLOSE_MINUTES * 60,
+    ];
+    for session in config.map_or(&[][..], |config| &config.sessions[..]) {
+        boundaries.push(session.open_minute as u32 * 60);
+        boundaries.push((session.close_minute as u32).min(close_minutes) * 60);
+    }
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    boundaries
+}
+
+fn is_continuous(config: Option<&MarketConfig>) -> bool {
+    config.is_some_and(|config| config.session_mode == SessionMode::Continuous)
+}
+
+// Lengths of the configured opening and closing auctions, in seconds
+fn auction_seconds(config: Option<&MarketConfig>) -> (u32, u32) {
+    config.map_or((0, 0), |config| {
+        (
+            config.opening_auction_minutes as u32 * 60,
+            config.closing_auction_minutes as u32 * 60,
+        )
+    })
+}
+
+// UTC timestamp of the next phase change after `timestamp`, looking up to
+// MAX_LOOKAHEAD_DAYS ahead for the next trading day; 0 when none is found
+fn next_transition(
+    timestamp: i64,
+    config: Option<&MarketConfig>,
+    calendar: Option<&HolidayCalendar>,
+    timezone: &TimezoneConfig,
+) -> i64 {
+    let local = get_local_time_info(timestamp, timezone);
+    let today = days_from_civil(local.year, local.month, local.day);
+    let fold = dst_fold_policy(config);
+    let is_open_day = |days: i64| {
+        let (year, month, day) = days_to_date(days);
+        is_trading_day(config, day_of_week(days))
+            && !is_market_holiday(config, calendar, year, month, day)
+    };
+
+    // Continuous mints only change state at midnight between an open and a
+    // closed day
+    if is_continuous(config) {
+        let open_today = is_open_day(today);
+        return (today + 1..=today + MAX_LOOKAHEAD_DAYS)
+            .find(|&days| is_open_day(days) != open_today)
+            .map_or(0, |days| {
+                let (year, month, day) = days_to_date(days);
+                local_to_utc(timezone, fold, year, month, day, 0)
+            });
+    }
+
+    // Overnight sessions open in the evening of each open day and close the
+    // next morning
+    if let Some(config) = config.filter(|config| is_overnight(config)) {
+        let seconds_on = |days: i64, minute: u16| {
+            let (year, month, day) = days_to_date(days);
+            local_to_utc(timezone, fold, year, month, day, minute as u32 * 60)
+        };
+        return (today - 1..=today + MAX_LOOKAHEAD_DAYS)
+            .filter(|&days| is_open_day(days))
+            .flat_map(|days| {
+                [
+                    seconds_on(days, config.open_minute),
+                    seconds_on(days + 1, config.close_minute),
+                ]
+            })
+            .find(|&boundary| boundary > timestamp)
+            .unwrap_or(0);
+    }
+
+    for days in today..=today + MAX_LOOKAHEAD_DAYS {
+        if !is_open_day(days) {
+            continue;
+        }
+        let (year, month, day) = days_to_date(days);
+        let next = phase_boundaries(config, calendar, year, month, day)
+            .into_iter()
+            .map(|seconds| match boundary_precision(config) {
+                BoundaryPrecision::Second => seconds,
+                // A mid-minute boundary only shows at the next whole minute
+                BoundaryPrecision::Minute => seconds.div_ceil(60) * 60,
+            })
+            .map(|seconds| local_to_utc(timezone, fold, year, month, day, seconds))
+            .find(|&boundary| boundary > timestamp);
+        if let Some(next) = next {
+            return next;
+        }
+    }
+    0
+}
+
+fn boundary_precision(config: Option<&MarketConfig>) -> BoundaryPrecision {
+    config.map_or(BoundaryPrecision::Second, |config| {
+        config.boundary_precision
+    })
+}
+
+fn dst_fold_policy(config: Option<&MarketConfig>) -> DstFoldPolicy {
+    config.map_or(DstFoldPolicy::Earliest, |config| config.dst_fold_policy)
+}
+
+// Local standard-time reading at which the end of DST repeats an hour: the
+// US switch is at 06:00 UTC (2:00 AM EDT), the EU one at 01:00 UTC
+fn fold_start_seconds(timezone: &TimezoneConfig) -> Option<u32> {
+    let switch_utc_seconds = match timezone.dst_rule().ok()?? {
+        DstRule::UnitedStates => 6 * 3600,
+        DstRule::EuropeanUnion => 3600,
+    };
+    Some((switch_utc_seconds + timezone.utc_offset_seconds as i64).rem_euclid(86400) as u32)
+}
+
+// Seconds into the local day that the session boundaries are compared
+// against, at the config's precision. In the hour DST's end repeats, the
+// reading moves to the far side of the hour's boundaries: past them on
+// the second pass under `Earliest`, before them on the first under `Latest`.
+fn session_seconds(
+    timestamp: i64,
+    local: &EasternTimeInfo,
+    config: Option<&MarketConfig>,
+    timezone: &TimezoneConfig,
+) -> u32 {
+    let seconds = match boundary_precision(config) {
+        BoundaryPrecision::Second => local.hour * 3600 + local.minute * 60 + local.second,
+        BoundaryPrecision::Minute => local.hour * 3600 + local.minute * 60,
+    };
+    let Some(fold_start) = fold_start_seconds(timezone) else {
+        return seconds;
+    };
+    let is_dst = |timestamp| get_local_time_info(timestamp, timezone).is_dst;
+    match dst_fold_policy(config) {
+        DstFoldPolicy::Earliest if !local.is_dst && is_dst(timestamp - 3600) => {
+            (fold_start + 3599).min(86_399)
+        }
+        DstFoldPolicy::Latest if local.is_dst && !is_dst(timestamp + 3600) => {
+            fold_start.saturating_sub(1)
+        }
+        _ => seconds,
+    }
+}
+
+// UTC timestamp of a local wall-clock time, resolved like `eastern_to_utc`:
+// skipped times move forward, and repeated times take the occurrence the
+// fold policy names
+fn local_to_utc(
+    timezone: &TimezoneConfig,
+    fold: DstFoldPolicy,
+    year: i32,
+    month: u32,
+    day: u32,
+    seconds_of_day: u32,
+) -> i64 {
+    let local_timestamp = days_from_civil(year, month, day) * 86400 + seconds_of_day as i64;
+    let standard = local_timestamp - timezone.utc_offset_seconds as i64;
+    let as_dst = standard - 3600;
+    let is_dst = |timestamp| get_local_time_info(timestamp, timezone).is_dst;
+    let repeated = is_dst(as_dst) && !is_dst(standard);
+    if is_dst(as_dst) && !(repeated && fold == DstFoldPolicy::Latest) {
+        as_dst
+    } else {
+        standard
+    }
+}
+
+// Operator text for halts and maintenance; zeros without a config
+fn closure_message(config: Option<&MarketConfig>) -> [u8; CLOSURE_MESSAGE_LEN] {
+    config.map_or([0; CLOSURE_MESSAGE_LEN], |config| config.closure_message)
+}
+
+// Zero-padded `closure_message` field for `text`
+fn closure_message_bytes(text: &str) -> Result<[u8; CLOSURE_MESSAGE_LEN]> {
+    require!(
+        text.len() <= CLOSURE_MESSAGE_LEN,
+        NyseError::ClosureMessageTooLong
+    );
+    let mut bytes = [0; CLOSURE_MESSAGE_LEN];
+    bytes[..text.len()].copy_from_slice(text.as_bytes());
+    Ok(bytes)
+}
+
+// Return data for a market-hours block: the verdict followed by the next
+// open as an i64 LE UTC timestamp
+fn set_blocked_return_data(state: &MarketState, next_open: i64) -> Result<()> {
+    let mut data = state.try_to_vec()?;
+    data.extend_from_slice(&next_open.to_le_bytes());
+    set_return_data(&data);
+    Ok(())
+}
+
+// Return data for a halt or maintenance block: the verdict followed by the
+// 64-byte closure message
+fn set_closure_return_data(
+    state: &MarketState,
+    closure_message: &[u8; CLOSURE_MESSAGE_LEN],
+) -> Result<()> {
+    let mut data = state.try_to_vec()?;
+    data.extend_from_slice(closure_message);
+    set_return_data(&data);
+    Ok(())
+}
+
+// A zero amount, or nothing to send it from, when the config opts in
+fn no_op_hint(config: Option<&MarketConfig>, amount: u64, balance: u64) -> Option<NoOpHint> {
+    if !config.is_some_and(|config| config.short_circuit_no_ops) {
+        return None;
+    }
+    if amount == 0 {
+        Some(NoOpHint::ZeroAmount)
+    } else if balance == 0 {
+        Some(NoOpHint::EmptySource)
+    } else {
+        None
+    }
+}
+
+// The listing window; zeroed ends leave that side open
+fn check_listing(config: Option<&MarketConfig>, timestamp: i64) -> Result<()> {
+    let Some(config) = config else {
+        return Ok(());
+    };
+    require!(
+        timestamp >= config.listing_timestamp,
+        NyseError::NotYetListed
+    );
+    require!(
+        config.delisting_timestamp == 0 || timestamp < config.delisting_timestamp,
+        NyseError::Delisted
+    );
+    Ok(())
+}
+
+// Use up a voucher covering this transfer, closing it into the rent reserve
+// so it can't let a second transfer through; the dry run leaves it open
+#[cfg(feature = "transfer-vouchers")]
+fn redeem_voucher(
+    accounts: &TransferHook,
+    amount: u64,
+    timestamp: i64,
+    commit: bool,
+) -> Result<bool> {
+    let Some(voucher) = accounts.load_transfer_voucher()? else {
+        return Ok(false);
+    };
+    if !voucher.covers(amount, timestamp) {
+        return Ok(false);
+    }
+    if commit {
+        close_account(&accounts.transfer_voucher, &accounts.rent_reserve)?;
+    }
+    Ok(true)
+}
+
+// Limits the config sets that this build leaves out. Skipping them would
+// let through what the mint asked to stop, so the transfer is refused.
+fn check_build_supports(config: Option<&MarketConfig>) -> Result<()> {
+    let Some(config) = config else {
+        return Ok(());
+    };
+    let missing = [
+        (
+            cfg!(feature = "volume-tracking"),
+            config.max_volume_per_window > 0,
+        ),
+        (
+            cfg!(feature = "circuit-breaker"),
+            config.breaker_threshold > 0,
+        ),
+        (
+            cfg!(feature = "cooldown"),
+            config.min_transfer_interval_seconds > 0,
+        ),
+        (
+            cfg!(feature = "daily-limit"),
+            config.max_transfers_per_day > 0,
+        ),
+        (
+            cfg!(feature = "destination-age"),
+            config.min_destination_age_seconds > 0,
+        ),
+    ]
+    .into_iter()
+    .any(|(built_in, configured)| configured && !built_in);
+    require!(!missing, NyseError::FeatureDisabled);
+    Ok(())
+}
+
+// Scheduled maintenance; an empty window (including the zeroed default)
+// never matches
+fn check_maintenance(config: Option<&MarketConfig>, timestamp: i64) -> Result<()> {
+    let in_window = config.is_some_and(|config| {
+        (config.maintenance_start..config.maintenance_end).contains(&timestamp)
+    });
+    require!(!in_window, NyseError::MaintenanceWindow);
+    Ok(())
+}
+
+// Fail within `post_halt_cooldown_seconds` of the latest resumption: the
+// halt being lifted, or a tripped breaker's cooldown running out. The
+// breaker forgets its trip once a transfer re-arms it, which can only
+// happen after this window.
+fn check_post_halt_cooldown(
+    config: Option<&MarketConfig>,
+    breaker: Option<&CircuitBreaker>,
+    now: i64,
+) -> Result<()> {
+    let Some(config) = config.filter(|config| config.post_halt_cooldown_seconds > 0) else {
+        return Ok(());
+    };
+
+    // A breaker still cooling down resets later, and blocks on its own until
+    // then. A resume the clock reads as still ahead happened just now.
+    let breaker_reset = breaker
+        .filter(|breaker| breaker.tripped_at != 0)
+        .map(|breaker| interval::after(breaker.tripped_at, config.breaker_cooldown_seconds as u64))
+        .filter(|&reset| reset <= now);
+    let resumed = Some(config.resumed_at).filter(|&resumed| resumed != 0);
+    let cooling = resumed.into_iter().chain(breaker_reset).any(|resumed| {
+        !interval::has_elapsed(resumed, now, config.post_halt_cooldown_seconds as u64)
+    });
+    require!(!cooling, NyseError::PostHaltCooldown);
+    Ok(())
+}
+
+// Before a delayed open; the zeroed default never matches
+fn check_opening_delay(config: Option<&MarketConfig>, timestamp: i64) -> Result<()> {
+    require!(
+        config.map_or(true, |config| timestamp >= config.delayed_open_until),
+        NyseError::OpeningDelayed
+    );
+    Ok(())
+}
+
+// Large transfers need the compliance authority's signature somewhere in
+// the transaction. The Instructions sysvar carries the message's signer
+// flags, which the runtime only sets for verified signatures.
+fn check_large_trade(
+    config: Option<&MarketConfig>,
+    amount: u64,
+    instructions_sysvar: &AccountInfo,
+) -> Result<()> {
+    let Some(config) = config.filter(|config| {
+        config.large_trade_threshold > 0 && amount >= config.large_trade_threshold
+    }) else {
+        return Ok(());
+    };
+    // The sysvar data starts with the instruction count
+    let count = match instructions_sysvar.try_borrow_data()?.get(..2) {
+        Some(count) => u16::from_le_bytes([count[0], count[1]]) as usize,
+        None => 0,
+    };
+    for index in 0..count {
+        let instruction =
+            sysvar::instructions::load_instruction_at_checked(index, instructions_sysvar)?;
+        if instruction
+            .accounts
+            .iter()
+            .any(|meta| meta.is_signer && meta.pubkey == config.compliance_authority)
+        {
+            return Ok(());
+        }
+    }
+    err!(NyseError::LargeTradeRequiresApproval)
+}
+
+// Above the threshold and an exact multiple of the unit, both counted in
+// whole tokens at the mint's decimals. Amounts a threshold or unit too large
+// for a u64 would need never match.
+fn is_suspicious_round_amount(config: Option<&MarketConfig>, decimals: u8, amount: u64) -> bool {
+    let Some(config) =
+        config.filter(|config| config.round_amount_threshold > 0 && config.round_amount_unit > 0)
+    else {
+        return false;
+    };
+    let whole = |tokens: u64| {
+        10u64
+            .checked_pow(decimals as u32)
+            .and_then(|one| tokens.checked_mul(one))
+    };
+    match (
+        whole(config.round_amount_threshold),
+        whole(config.round_amount_unit),
+    ) {
+        (Some(threshold), Some(unit)) => amount > threshold && amount % unit == 0,
+        _ => false,
+    }
+}
+
+// Floor on the transfer size; zero means none
+fn check_transfer_minimum(config: Option<&MarketConfig>, amount: u64) -> Result<()> {
+    let min_transfer_amount = config.map_or(0, |config| config.min_transfer_amount);
+    require!(
+        amount >= min_transfer_amount,
+        NyseError::TransferBelowMinimum
+    );
+    Ok(())
+}
+
+// Per-transfer cap in the final `limit_window_minutes` of the session
+fn check_transfer_limit(
+    timestamp: i64,
+    config: Option<&MarketConfig>,
+    calendar: Option<&HolidayCalendar>,
+    amount: u64,
+) -> Result<()> {
+    let Some(config) = config else {
+        return Ok(());
+    };
+    if config.max_transfer_amount == 0 || config.limit_window_minutes == 0 {
+        return Ok(());
+    }
+
+    let local = get_local_time_info(timestamp, &config.timezone);
+    let current_seconds = local.hour * 3600 + local.minute * 60 + local.second;
+    let (_, close_minutes, _) =
+        session_hours(Some(config), calendar, local.year, local.month, local.day);
+    let window_start = close_minutes.saturating_sub(config.limit_window_minutes as u32) * 60;
+    // The cap keeps applying through the close grace period
+    let window_end = close_minutes * 60 + config.close_grace_seconds as u32;
+
+    if (window_start..window_end).contains(&current_seconds) {
+        require!(
+            amount <= config.max_transfer_amount,
+            NyseError::TransferExceedsLimit
+        );
+    }
+    Ok(())
+}
+
+// Block the final `closing_cross_seconds` before the day's close, to the
+// second whatever the config's boundary precision; overnight sessions have
+// no closing cross
+fn check_closing_cross(
+    timestamp: i64,
+    config: Option<&MarketConfig>,
+    calendar: Option<&HolidayCalendar>,
+) -> Result<()> {
+    let Some(config) = config else {
+        return Ok(());
+    };
+    if !config.blocking_during_closing_cross || is_overnight(config) {
+        return Ok(());
+    }
+
+    let local = get_local_time_info(timestamp, &config.timezone);
+    let current_seconds = local.hour * 3600 + local.minute * 60 + local.second;
+    let (_, close_minutes, _) =
+        session_hours(Some(config), calendar, local.year, local.month, local.day);
+    let cross_start = (close_minutes * 60).saturating_sub(config.closing_cross_seconds as u32);
+    require!(
+        !(cross_start..close_minutes * 60).contains(&current_seconds),
+        NyseError::ClosingCrossBlocked
+    );
+    Ok(())
+}
+
+// Per-transfer cap in the first `warmup_minutes` after the open: the start
+// limit in the first minute, growing by as much each minute until it lifts.
+// Transfers in the opening grace period count as the first minute.
+fn check_warmup_limit(timestamp: i64, config: Option<&MarketConfig>, amount: u64) -> Result<()> {
+    let Some(config) = config else {
+        return Ok(());
+    };
+    if config.warmup_minutes == 0 || config.warmup_start_limit == 0 {
+        return Ok(());
+    }
+    // Overnight sessions have no morning open to ramp from
+    if config.close_minute < config.open_minute {
+        return Ok(());
+    }
+
+    let local = get_local_time_info(timestamp, &config.timezone);
+    let current_minutes = local.hour * 60 + local.minute;
+    let elapsed = current_minutes.saturating_sub(config.open_minute as u32);
+    if elapsed < config.warmup_minutes as u32 {
+        let limit = config.warmup_start_limit.saturating_mul(elapsed as u64 + 1);
+        require!(amount <= limit, NyseError::WarmupLimitExceeded);
+    }
+    Ok(())
+}
+
+/// Local-time breakdown of a UTC timestamp, as the hours check sees it
+/// (Eastern Time unless the mint configures another timezone).
+/// Returned by `get_local_time` so clients can verify the interpretation.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct EasternTimeInfo {
+    pub year: i32,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub weekday: u32, // 0 = Sunday, 1 = Monday, ..., 6 = Saturday
+    pub is_dst: bool,
+    pub utc_offset_seconds: i32,
+}
+
+impl From<LocalTime> for EasternTimeInfo {
+    fn from(local: LocalTime) -> Self {
+        Self {
+            year: local.year,
+            month: local.month,
+            day: local.day,
+            hour: local.hour,
+            minute: local.minute,
+            second: local.second,
+            weekday: local.weekday,
+            is_dst: local.is_dst,
+            utc_offset_seconds: local.utc_offset_seconds,
+        }
+    }
+}
+
+// Eastern Time for a UTC instant. Going this way there is nothing to
+// resolve: the skipped spring hour is never produced and each instant of the
+// repeated fall hour has a single offset (see `calendar::eastern_to_utc` for
+// the convention going the other way). Clocks outside the accepted range
+// are refused rather than rendered as a clamped date.
+pub(crate) fn get_eastern_time_info(utc_timestamp: i64) -> Result<EasternTimeInfo> {
+    require!(
+        is_valid_timestamp(utc_timestamp),
+        NyseError::InvalidTimestamp
+    );
+    Ok(get_local_time_info(
+        utc_timestamp,
+        &TimezoneConfig::US_EASTERN,
+    ))
+}
+
+// Local time in the given exchange timezone. An unknown DST ruleset reads
+// as none here; `get_nyse_market_state` refuses such configs up front.
+fn get_local_time_info(utc_timestamp: i64, timezone: &TimezoneConfig) -> EasternTimeInfo {
+    let is_dst = match timezone.dst_rule() {
+        Ok(Some(DstRule::UnitedStates)) => is_daylight_saving_time(utc_timestamp),
+        Ok(Some(DstRule::EuropeanUnion)) => is_eu_summer_time(utc_timestamp),
+        Ok(None) | Err(_) => false,
+    };
+    local_time(utc_timestamp, timezone.utc_offset_seconds, is_dst).into()
+}
+
+// Built-in holiday rules of the preset's exchange
+pub fn is_exchange_holiday(preset: ExchangePreset, year: i32, month: u32, day: u32) -> bool {
+    match preset {
+        ExchangePreset::Nyse => is_nyse_holiday(year, month, day),
+        ExchangePreset::Nasdaq => is_nasdaq_holiday(year, month, day),
+    }
+}
+
+// Holiday check honouring the mint's HolidayMode and ObservanceMode;
+// unconfigured mints use the built-in rules only, on their observed dates
+fn is_market_holiday(
+    config: Option<&MarketConfig>,
+    calendar: Option<&HolidayCalendar>,
+    year: i32,
+    month: u32,
+    day: u32,
+) -> bool {
+    let mode = config.map_or(HolidayMode::BuiltIn, |config| config.holiday_mode);
+    let preset = config.map_or(ExchangePreset::Nyse, |config| config.exchange_preset);
+    let observance = config.map_or(ObservanceMode::ObservedOnly, |config| {
+        config.observance_mode
+    });
+    let built_in = mode != HolidayMode::CalendarOnly
+        && is_exchange_holiday(preset, year, month, day)
+        && (observance == ObservanceMode::ActualAndObserved
+            || !is_unobserved_actual_date(year, month, day));
+    let listed = mode != HolidayMode::BuiltIn
+        && calendar
+            .zip(local_date(year, month, day))
+            .is_some_and(|(calendar, date)| calendar.contains(&date));
+    built_in || listed
+}
+
+// Calendar key for a local date; None for years a HolidayDate can't hold
+fn local_date(year: i32, month: u32, day: u32) -> Option<HolidayDate> {
+    Some(HolidayDate {
+        year: u16::try_from(year).ok()?,
+        month: month as u8,
+        day: day as u8,
+    })
+}
+
+// Render a UTC timestamp as "YYYY-MM-DD HH:MM:SS UTC"
+fn format_timestamp(timestamp: i64) -> String {
+    let days = timestamp.div_euclid(86400);
+    let seconds = timestamp.rem_euclid(86400);
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    let secs = seconds % 60;
+
+    let (year, month, day) = days_to_date(days);
+
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year, month, day, hours, minutes, secs
+    )
+}
+
+// Render a timestamp in the config's display zone. The exchange zone reads
+// the same local time the hours are checked against.
+fn format_display_timestamp(timestamp: i64, config: Option<&MarketConfig>) -> String {
+    let Some(timezone) = config
+        .filter(|config| config.display_timezone == DisplayTimezone::Exchange)
+        .map(|config| &config.timezone)
+    else {
+        return format_timestamp(timestamp);
+    };
+
+    let local = get_local_time_info(timestamp, timezone);
+    format!(
+        "{}-{:02}-{:02} {:02}:{:02}:{:02} {}",
+        local.year,
+        local.month,
+        local.day,
+        local.hour,
+        local.minute,
+        local.second,
+        zone_label(timezone, &local)
+    )
+}
+
+// EST/EDT for New York; other zones show the offset in force, "UTC+01:00"
+fn zone_label(timezone: &TimezoneConfig, local: &EasternTimeInfo) -> String {
+    if *timezone == TimezoneConfig::US_EASTERN {
+        return if local.is_dst { "EDT" } else { "EST" }.to_string();
+    }
+    let offset = local.utc_offset_seconds;
+    let sign = if offset < 0 { '-' } else { '+' };
+    let minutes = offset.unsigned_abs() / 60;
+    format!("UTC{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trading_day_ordinal_starts_on_first_trading_day() {
+        // 2024-01-01 is a Monday holiday, so the 2nd is the first session
+        assert_eq!(trading_day_ordinal(2024, 1, 1), 0);
+        assert_eq!(trading_day_ordinal(2024, 1, 2), 1);
+    }
+
+    #[test]
+    fn trading_day_ordinal_mid_year() {
+        assert_eq!(trading_day_ordinal(2024, 6, 28), 124);
+        // Independence Day is skipped
+        assert_eq!(trading_day_ordinal(2024, 7, 5), 128);
+    }
+
+    #[test]
+    fn days_to_date_handles_leap_years() {
+        // 2024-02-29 00:00:00 UTC
+        assert_eq!(days_to_date(1_709_164_800 / 86400), (2024, 2, 29));
+        assert_eq!(days_to_date(1_709_251_200 / 86400), (2024, 3, 1));
+        // 2000 is a leap year by the 400-year rule
+        assert_eq!(days_to_date(946_684_800 / 86400), (2000, 1, 1));
+        assert_eq!(days_to_date(951_782_400 / 86400), (2000, 2, 29));
+        // 2100 is not a leap year
+        assert_eq!(days_to_date(4_107_542_400 / 86400), (2100, 3, 1));
+        assert_eq!(days_to_date(1_703_980_800 / 86400), (2023, 12, 31));
+        assert_eq!(days_to_date(0), (1970, 1, 1));
+    }
+
+    #[test]
+    fn days_to_date_inverts_days_from_civil() {
+        for days in 0..(131 * 366) {
+            let (year, month, day) = days_to_date(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn juneteenth_closes_from_2022() {
+        assert!(!is_nyse_holiday(2021, 6, 19));
+        assert!(is_nyse_holiday(2022, 6, 19));
+        assert!(is_nyse_holiday(2023, 6, 19));
+        // 2023-06-19 14:00 UTC is 10:00 AM EDT on a Monday
+        let state = get_nyse_market_state(1_687_183_200, None, None).unwrap();
+        assert_eq!(state.status, MarketStatus::Holiday);
+    }
+
+    #[test]
+    fn floating_holidays_2024() {
+        assert!(is_nyse_holiday(2024, 1, 15)); // MLK Day
+        assert!(is_nyse_holiday(2024, 2, 19)); // Washington's Birthday
+        assert!(is_nyse_holiday(2024, 5, 27)); // Memorial Day
+        assert!(is_nyse_holiday(2024, 9, 2)); // Labor Day
+        assert!(is_nyse_holiday(2024, 11, 28)); // Thanksgiving
+        assert!(!is_nyse_holiday(2024, 1, 8));
+        assert!(!is_nyse_holiday(2024, 11, 29));
+    }
+
+    #[test]
+    fn nyse_and_nasdaq_presets_share_holidays() {
+        // Shared closures, including Good Friday and Juneteenth
+        for (year, month, day) in [(2024, 1, 1), (2024, 3, 29), (2024, 6, 19), (2024, 12, 25)] {
+            assert!(is_exchange_holiday(ExchangePreset::Nyse, year, month, day));
+            assert!(is_exchange_holiday(
+                ExchangePreset::Nasdaq,
+                year,
+                month,
+                day
+            ));
+        }
+        // Neither closes Friday 2021-12-31 for the Saturday New Year's Day
+        assert!(!is_exchange_holiday(ExchangePreset::Nasdaq, 2021, 12, 31));
+
+        // No divergence is encoded: every day agrees, one-off closures too
+        assert!(is_exchange_holiday(ExchangePreset::Nasdaq, 2012, 10, 29));
+        let start = days_from_civil(1990, 1, 1);
+        for days in start..days_from_civil(2100, 1, 1) {
+            let (year, month, day) = days_to_date(days);
+            assert_eq!(
+                is_exchange_holiday(ExchangePreset::Nyse, year, month, day),
+                is_exchange_holiday(ExchangePreset::Nasdaq, year, month, day),
+                "{year}-{month:02}-{day:02}"
+            );
+        }
+
+        // A NASDAQ-configured mint is closed on Good Friday 2024 at noon
+        let mut config = config_with_hours(570, 960);
+        config.exchange_preset = ExchangePreset::Nasdaq;
+        let state = get_nyse_market_state(1_711_728_000, Some(&config), None).unwrap();
+        assert_eq!(state.status, MarketStatus::Holiday);
+    }
+
+    #[test]
+    fn floating_holiday_edge_weeks() {
+        // November 2023 has five Thursdays: Thanksgiving is the 4th, not the last
+        assert!(is_nyse_holiday(2023, 11, 23));
+        assert!(!is_nyse_holiday(2023, 11, 30));
+        // Memorial Day at both ends of its range
+        assert!(is_nyse_holiday(2021, 5, 31));
+        assert!(is_nyse_holiday(2026, 5, 25));
+        assert!(!is_nyse_holiday(2021, 5, 24));
+    }
+
+    #[test]
+    fn good_friday_follows_easter() {
+        assert_eq!(good_friday(2024), (3, 29));
+        assert_eq!(good_friday(2025), (4, 18));
+        assert!(is_nyse_holiday(2024, 3, 29));
+        assert!(is_nyse_holiday(2025, 4, 18));
+        assert!(!is_nyse_holiday(2025, 3, 29));
+        // Across decades, including a Good Friday in March of an early Easter
+        assert_eq!(easter_sunday(2000), (4, 23));
+        assert_eq!(easter_sunday(2008), (3, 23));
+        assert_eq!(easter_sunday(2038), (4, 25));
+        assert_eq!(good_friday(2008), (3, 21));
+    }
+
+    #[test]
+    fn weekend_holidays_shift_to_observed_weekday() {
+        // Independence Day 2026 is a Saturday: Friday July 3 is closed
+        assert!(is_nyse_holiday(2026, 7, 3));
+        assert!(!is_nyse_holiday(2026, 7, 6));
+        // 2026-07-03 14:00 UTC is 10:00 AM EDT
+        let state = get_nyse_market_state(1_783_087_200, None, None).unwrap();
+        assert_eq!(state.status, MarketStatus::Holiday);
+        // 2026-07-04 is reported as a weekend
+        let state = get_nyse_market_state(1_783_173_600, None, None).unwrap();
+        assert_eq!(state.status, MarketStatus::Weekend);
+
+        // Sunday holidays move to Monday
+        assert!(is_nyse_holiday(2022, 12, 26));
+        assert!(is_nyse_holiday(2027, 7, 5));
+        assert!(!is_nyse_holiday(2022, 12, 23));
+    }
+
+    #[test]
+    fn saturday_new_years_day_is_not_observed() {
+        // 2022-01-01 is a Saturday; Friday 2021-12-31 trades normally
+        assert!(!is_nyse_holiday(2021, 12, 31));
+        // 2021-12-31 15:00 UTC is 10:00 AM EST
+        let state = get_nyse_market_state(1_640_962_800, None, None).unwrap();
+        assert!(state.is_open);
+        let state = get_nyse_market_state(1_641_049_200, None, None).unwrap();
+        assert_eq!(state.status, MarketStatus::Weekend);
+        assert!(!is_nyse_holiday(2022, 1, 3));
+    }
+
+    #[test]
+    fn sunday_new_years_day_closes_the_following_monday() {
+        // 2023-01-01 is a Sunday: Monday 2023-01-02 is closed, while Friday
+        // 2022-12-30 before it is a full session
+        assert!(is_nyse_holiday(2023, 1, 2));
+        assert!(!is_nyse_holiday(2022, 12, 30));
+        assert!(!is_nyse_early_close(2022, 12, 30));
+        let state = get_nyse_market_state(1_672_678_800, None, None).unwrap();
+        assert_eq!(state.status, MarketStatus::Holiday);
+        assert!(
+            get_nyse_market_state(1_672_419_600, None, None)
+                .unwrap()
+                .is_open
+        );
+        assert!(
+            get_nyse_market_state(1_672_765_200, None, None)
+                .unwrap()
+                .is_open
+        );
+
+        // Friday 9:00 PM EST counts down to the Tuesday pre-market, 4:00 AM
+        let state = get_nyse_market_state(1_672_452_000, None, None).unwrap();
+        assert_eq!(state.next_transition, 1_672_736_400);
+        assert_eq!(next_holiday_after(1_672_419_600), (2023, 1, 2));
+
+        // Same shape across 2016 -> 2017, before the hook's accepted clock
+        // range, so only the calendar rules are checked
+        assert!(is_nyse_holiday(2017, 1, 2));
+        assert!(!is_nyse_holiday(2016, 12, 30));
+        assert!(!is_nyse_holiday(2017, 1, 3));
+        // Friday 2016-12-30 12:00 PM EST
+        assert_eq!(next_holiday_after(1_483_117_200), (2017, 1, 2));
+    }
+
+    #[test]
+    fn schedule_for_normal_holiday_and_half_days() {
+        let date = |year, month, day| HolidayDate { year, month, day };
+        let schedule =
+            |config: Option<&MarketConfig>, date| schedule_for_date(config, None, date).unwrap();
+
+        // Tuesday 2024-01-09: the full 9:30 AM - 4:00 PM session
+        assert_eq!(
+            schedule(None, date(2024, 1, 9)),
+            DaySchedule {
+                is_trading_day: true,
+                open_minute: 570,
+                close_minute: 960,
+                early_close: false,
+            }
+        );
+        // Good Friday 2024 and a Saturday are closed
+        assert!(!schedule(None, date(2024, 3, 29)).is_trading_day);
+        assert!(!schedule(None, date(2024, 1, 6)).is_trading_day);
+        // The day after Thanksgiving 2024 closes at 1:00 PM
+        assert_eq!(
+            schedule(None, date(2024, 11, 29)),
+            DaySchedule {
+                is_trading_day: true,
+                open_minute: 570,
+                close_minute: 780,
+                early_close: true,
+            }
+        );
+
+        // A scheduled early close on an otherwise normal day
+        let mut config = config_with_hours(570, 960);
+        config.early_close_override = Some(EarlyCloseOverride {
+            date: date(2024, 1, 9),
+            close_minute: 720,
+        });
+        let overridden = schedule(Some(&config), date(2024, 1, 9));
+        assert_eq!(
+            (overridden.close_minute, overridden.early_close),
+            (720, true)
+        );
+
+        assert_eq!(
+            schedule_for_date(None, None, date(2024, 2, 30)).unwrap_err(),
+            NyseError::InvalidDate.into()
+        );
+    }
+
+    #[test]
+    fn independence_day_half_day_follows_the_observed_date() {
+        // July 4th midweek: the session before it closes early
+        assert!(is_nyse_early_close(2024, 7, 3)); // Wednesday, before Thursday
+        assert!(is_nyse_early_close(2023, 7, 3)); // Monday, before Tuesday
+        assert!(!is_nyse_early_close(2024, 7, 2));
+
+        // July 4th on a Monday: the Friday before is a full day
+        assert_eq!(observed_holiday(2022, 7, 4), Some((2022, 7, 4)));
+        assert!(!is_nyse_early_close(2022, 7, 1));
+        assert!(!is_nyse_early_close(2022, 7, 3));
+
+        // Weekend July 4ths move to a Friday or Monday with no half day
+        assert_eq!(observed_holiday(2020, 7, 4), Some((2020, 7, 3)));
+        assert!(!is_nyse_early_close(2020, 7, 2));
+        assert_eq!(observed_holiday(2021, 7, 4), Some((2021, 7, 5)));
+        assert!(!is_nyse_early_close(2021, 7, 2));
+
+        // Christmas and Thanksgiving come from the same dates
+        assert!(is_nyse_early_close(2018, 12, 24)); // Monday, before Tuesday
+        assert!(!is_nyse_early_close(2023, 12, 22)); // Friday before a Monday
+        assert_eq!(thanksgiving(2025), 27);
+        assert!(is_nyse_early_close(2025, 11, 28));
+        assert_eq!(observed_holiday(2022, 1, 1), None);
+    }
+
+    #[test]
+    fn half_days_close_at_one_pm() {
+        assert!(is_nyse_early_close(2024, 11, 29)); // Day after Thanksgiving
+        assert!(is_nyse_early_close(2024, 12, 24)); // Tuesday
+        assert!(is_nyse_early_close(2024, 7, 3)); // Wednesday
+        assert!(!is_nyse_early_close(2021, 12, 24)); // Observed Christmas holiday
+        assert!(!is_nyse_early_close(2026, 7, 3)); // Observed Independence Day
+        assert!(!is_nyse_early_close(2023, 12, 24)); // Sunday
+
+        // 2024-11-29 17:30 UTC is 12:30 PM EST, 18:30 UTC is 1:30 PM EST
+        assert!(
+            get_nyse_market_state(1_732_901_400, None, None)
+                .unwrap()
+                .is_open
+        );
+        let state = get_nyse_market_state(1_732_905_000, None, None).unwrap();
+        assert!(!state.is_open);
+        assert_eq!(state.status, MarketStatus::AfterHours);
+        assert_eq!(state.reason, MarketReason::EarlyClose);
+    }
+
+    #[test]
+    fn format_timestamp_renders_calendar_date() {
+        assert_eq!(format_timestamp(1_709_227_845), "2024-02-29 17:30:45 UTC");
+        assert_eq!(format_timestamp(1_735_689_599), "2024-12-31 23:59:59 UTC");
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00:00 UTC");
+    }
+
+    #[test]
+    fn display_timestamp_matches_the_time_the_hours_use() {
+        let mut config = config_with_hours(570, 960);
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        assert_eq!(
+            format_display_timestamp(open, Some(&config)),
+            "2024-01-09 14:30:00 UTC"
+        );
+        assert_eq!(format_display_timestamp(open, None), format_timestamp(open));
+
+        config.display_timezone = DisplayTimezone::Exchange;
+        for timestamp in [open, 1_720_539_000, 1_704_670_200] {
+            // The rendering is the local time the decision was made on
+            let local = get_local_time_info(timestamp, &config.timezone);
+            let state = get_nyse_market_state(timestamp, Some(&config), None).unwrap();
+            let shown = format_display_timestamp(timestamp, Some(&config));
+            assert!(shown.starts_with(&format!(
+                "{}-{:02}-{:02} {:02}:{:02}",
+                local.year, local.month, local.day, local.hour, local.minute
+            )));
+            assert_eq!(
+                state.is_open,
+                local.weekday != 0 && (570..960).contains(&(local.hour * 60 + local.minute))
+            );
+        }
+        assert_eq!(
+            format_display_timestamp(open, Some(&config)),
+            "2024-01-09 09:30:00 EST"
+        );
+        // 2024-07-09 11:30 EDT, and 2024-01-07 18:30 EST on the Sunday
+        // before, already Monday in UTC
+        assert_eq!(
+            format_display_timestamp(1_720_539_000, Some(&config)),
+            "2024-07-09 11:30:00 EDT"
+        );
+        assert_eq!(
+            format_display_timestamp(1_704_670_200, Some(&config)),
+            "2024-01-07 18:30:00 EST"
+        );
+
+        // Other exchanges show their offset
+        config.timezone = TimezoneConfig::LONDON;
+        assert_eq!(
+            format_display_timestamp(1_720_539_000, Some(&config)),
+            "2024-07-09 16:30:00 UTC+01:00"
+        );
+        config.timezone = TimezoneConfig::new(-(3 * 3600 + 1800), None);
+        assert_eq!(
+            format_display_timestamp(open, Some(&config)),
+            "2024-01-09 11:00:00 UTC-03:30"
+        );
+    }
+
+    #[test]
+    fn market_state_return_data_layout() {
+        let bytes = |is_open: u8, status: u8, reason: u8, next_transition: i64| {
+            let mut bytes = vec![is_open, status, reason];
+            bytes.extend_from_slice(&next_transition.to_le_bytes());
+            bytes
+        };
+
+        // 2024-01-06 is a Saturday; the pre-market next opens Monday 4:00 AM EST
+        let weekend = get_nyse_market_state(1_704_549_600, None, None).unwrap();
+        assert_eq!(weekend.try_to_vec().unwrap(), bytes(0, 1, 1, 1_704_704_400));
+
+        // 2024-01-09 19:00 UTC is 2:00 PM EST on a Tuesday, closing at 4:00 PM
+        let open = get_nyse_market_state(1_704_826_800, None, None).unwrap();
+        assert_eq!(open.try_to_vec().unwrap(), bytes(1, 0, 0, 1_704_834_000));
+        assert_eq!(
+            MarketState::try_from_slice(&bytes(1, 0, 0, 1_704_834_000)).unwrap(),
+            open
+        );
+
+        // 2024-01-09 22:00 UTC is 5:00 PM EST, post-market until 8:00 PM
+        let closed = get_nyse_market_state(1_704_837_600, None, None).unwrap();
+        assert_eq!(closed.try_to_vec().unwrap(), bytes(0, 3, 4, 1_704_848_400));
+    }
+
+    #[test]
+    fn monday_overnight_reports_next_open() {
+        // 2024-01-08 03:00 EST, Monday: overnight until the 4:00 AM pre-market
+        let overnight = get_nyse_market_state(1_704_700_800, None, None).unwrap();
+        assert_eq!(overnight.status, MarketStatus::Overnight);
+        assert_eq!(overnight.reason, MarketReason::PreMarket);
+        assert_eq!(overnight.next_transition, 1_704_704_400);
+
+        // 04:00 EST: pre-market, next change is the 9:30 AM open
+        let premarket = get_nyse_market_state(1_704_704_400, None, None).unwrap();
+        assert_eq!(premarket.status, MarketStatus::AfterHours);
+        assert_eq!(premarket.reason, MarketReason::PreMarket);
+        assert_eq!(premarket.next_transition, 1_704_724_200);
+
+        // A pre-market configured to start at 7:00 AM moves the countdown
+        let mut config = config_with_hours(570, 960);
+        config.premarket_open_minute = 7 * 60;
+        let state = get_nyse_market_state(1_704_700_800, Some(&config), None).unwrap();
+        assert_eq!(state.status, MarketStatus::Overnight);
+        assert_eq!(state.next_transition, 1_704_715_200);
+        let state = get_nyse_market_state(1_704_704_400, Some(&config), None).unwrap();
+        assert_eq!(state.status, MarketStatus::Overnight);
+
+        // 2024-03-11 03:00 EDT, the Monday after spring forward
+        let state = get_nyse_market_state(1_710_140_400, None, None).unwrap();
+        assert_eq!(state.next_transition, 1_710_144_000);
+    }
+
+    #[test]
+    fn countdown_from_friday_close_to_the_next_open() {
+        let countdown = |timestamp| {
+            let state = get_nyse_market_state(timestamp, None, None).unwrap();
+            market_countdown(timestamp, &state, None, None).unwrap()
+        };
+
+        // Friday 2024-01-19 5:00 PM EST to Monday's 9:30 AM open, past the
+        // post-market close and the Monday pre-market start
+        let friday = 1_705_701_600;
+        let monday_open = 1_705_933_800;
+        assert_eq!(
+            countdown(friday),
+            MarketCountdown {
+                seconds_until_next_transition: monday_open - friday,
+                next_transition_is_open: true,
+            }
+        );
+
+        // Across Martin Luther King Jr. Day to Tuesday 2024-01-16
+        let long_weekend = 1_705_096_800;
+        assert_eq!(
+            countdown(long_weekend).seconds_until_next_transition,
+            1_705_415_400 - long_weekend
+        );
+
+        // Twelve minutes before Monday's close
+        let closing = monday_open + 6 * 3600 + 18 * 60;
+        assert_eq!(
+            countdown(closing),
+            MarketCountdown {
+                seconds_until_next_transition: 12 * 60,
+                next_transition_is_open: false,
+            }
+        );
+    }
+
+    #[test]
+    fn next_transition_skips_weekend_and_holiday() {
+        // Friday 2024-01-12 9:00 PM EST; Monday is MLK Day, so the next
+        // change is the Tuesday 4:00 AM EST pre-market open
+        let state = get_nyse_market_state(1_705_111_200, None, None).unwrap();
+        assert_eq!(state.status, MarketStatus::Overnight);
+        assert_eq!(state.reason, MarketReason::AfterMarket);
+        assert_eq!(state.next_transition, 1_705_395_600);
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn check_market_state_reports_a_paused_mint_as_halted() {
+        let open = 1_704_810_600 + 3600; // Tuesday 2024-01-09 10:30 EST
+        let mut config = config_with_hours(570, 960);
+        let (state, countdown) = check_market_state_at(open, Some(&config));
+        assert!(state.is_open);
+        assert_eq!(countdown.seconds_until_next_transition, 5 * 3600 + 1800);
+
+        // Whatever the hours say, as the hook and query_market_state see it;
+        // no reopening is scheduled, so there is nothing to count down to
+        config.paused = true;
+        assert_eq!(
+            check_market_state_at(open, Some(&config)),
+            (
+                MarketState::HALTED,
+                MarketCountdown {
+                    seconds_until_next_transition: 0,
+                    next_transition_is_open: false,
+                }
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn check_market_state_weekend_decodes_closed() {
+        // 2024-01-06 14:00 UTC, a Saturday morning
+        let (state, countdown) = check_market_state_at(1_704_549_600, None);
+        assert!(!state.is_open);
+        assert_eq!(state.status, MarketStatus::Weekend);
+        assert_eq!(state.reason, MarketReason::Weekend);
+        // Monday's pre-market at 4:00 AM EST comes first; the countdown
+        // runs on to the 9:30 open
+        assert_eq!(state.next_transition, 1_704_704_400);
+        assert_eq!(
+            countdown,
+            MarketCountdown {
+                seconds_until_next_transition: 1_704_724_200 - 1_704_549_600,
+                next_transition_is_open: true,
+            }
+        );
+    }
+
+    // An allowlist entry exempt in both directions
+    fn both_ways(wallet: Pubkey) -> AllowlistEntry {
+        AllowlistEntry {
+            wallet,
+            direction: ExemptDirection::BothExempt,
+        }
+    }
+
+    fn config_with_hours(open_minute: u16, close_minute: u16) -> MarketConfig {
+        MarketConfig {
+            authority: Pubkey::new_unique(),
+            premarket_open_minute: EXTENDED_OPEN_MINUTES as u16,
+            pending_authority: None,
+            config_version: 0,
+            config_applied_slot: 0,
+            open_minute,
+            close_minute,
+            sessions: Vec::new(),
+            session_mode: SessionMode::Scheduled,
+            boundary_precision: BoundaryPrecision::Second,
+            trading_days_bitmask: MONDAY_TO_FRIDAY,
+            open_grace_seconds: 0,
+            close_grace_seconds: 0,
+            soft_open_minutes: 0,
+            opening_auction_minutes: 0,
+            closing_auction_minutes: 0,
+            allow_auction_transfers: true,
+            closing_cross_seconds: 0,
+            blocking_during_closing_cross: false,
+            pre_open_staging_minutes: 0,
+            staging_account: Pubkey::default(),
+            treasury: Pubkey::default(),
+            treasury_deposit_window: None,
+            treasury_withdraw_window: None,
+            early_close_override: None,
+            maintenance_start: 0,
+            maintenance_end: 0,
+            delayed_open_until: 0,
+            listing_timestamp: 0,
+            delisting_timestamp: 0,
+            paused: false,
+            halted_at_version: 0,
+            same_tx_halt_grace: false,
+            activated: true,
+            closure_message: [0; CLOSURE_MESSAGE_LEN],
+            timezone: TimezoneConfig::US_EASTERN,
+            dst_fold_policy: DstFoldPolicy::Earliest,
+            extended_hours: ExtendedHoursMode::RegularOnly,
+            holiday_mode: HolidayMode::BuiltInAndCalendar,
+            observance_mode: ObservanceMode::ObservedOnly,
+            exchange_preset: ExchangePreset::Nyse,
+            enforcement_mode: EnforcementMode::Enforce,
+            block_behavior: BlockBehavior::Error,
+            verbose_logging: false,
+            dedup_decision_events: false,
+            display_timezone: DisplayTimezone::Utc,
+            max_transfer_amount: 0,
+            limit_window_minutes: 0,
+            warmup_minutes: 0,
+            warmup_start_limit: 0,
+            min_transfer_amount: 0,
+            dust_exempt_below: 0,
+            limits_on_net_amount: false,
+            allow_zero_amount: true,
+            allow_incinerator: true,
+            pool_transfers_follow_hours: false,
+            allow_self_transfer: true,
+            short_circuit_no_ops: false,
+            max_volume_per_window: 0,
+            window_seconds: 0,
+            min_transfer_interval_seconds: 0,
+            max_transfers_per_day: 0,
+            allowlist_daily_cap: 0,
+            min_destination_age_seconds: 0,
+            breaker_threshold: 0,
+            breaker_window_seconds: 0,
+            breaker_cooldown_seconds: 0,
+            post_halt_cooldown_seconds: 0,
+            resumed_at: 0,
+            large_trade_threshold: 0,
+            compliance_authority: Pubkey::default(),
+            round_amount_threshold: 0,
+            round_amount_unit: 0,
+            round_amount_policy: RoundAmountPolicy::Flag,
+            max_clock_rewind_seconds: 0,
+            last_seen_timestamp: 0,
+            test_time_override: None,
+            bump: 255,
+        }
+    }
+
+    #[test]
+    fn juneteenth_and_historical_closures() {
+        // NYSE first closed for Juneteenth in 2022: the Saturday holiday of
+        // 2021 wasn't observed, while the Sunday one of 2022 moved to Monday
+        assert!(!is_nyse_holiday(2021, 6, 18));
+        assert!(!is_nyse_holiday(2021, 6, 19));
+        assert!(is_nyse_holiday(2022, 6, 19));
+        assert!(is_nyse_holiday(2022, 6, 20));
+
+        // One-off closures come from the table, not the rules
+        assert!(is_nyse_holiday(2018, 12, 5));
+        assert!(!is_nyse_holiday(2018, 12, 4) && !is_nyse_holiday(2018, 12, 6));
+        assert!(is_nyse_holiday(2012, 10, 29) && is_nyse_holiday(2012, 10, 30));
+        assert!(!is_nyse_holiday(2012, 10, 31));
+        for &(year, month, day) in &HISTORICAL_CLOSURES {
+            assert!(is_nyse_holiday(year, month, day));
+            assert!((1..=5).contains(&day_of_week(days_from_civil(year, month, day))));
+        }
+
+        // Thursday 2025-01-09 12:00 PM EST, President Carter's funeral
+        let carter = get_nyse_market_state(1_736_442_000, None, None).unwrap();
+        assert_eq!(carter.status, MarketStatus::Holiday);
+    }
+
+    #[test]
+    fn weekday_of_month_helpers() {
+        let (sunday, monday, tuesday, thursday, friday) = (0, 1, 2, 4, 5);
+        assert_eq!(nth_weekday_of_month(2024, 1, monday, 3), 15);
+        assert_eq!(last_weekday_of_month(2024, 5, monday), 27);
+        assert_eq!(nth_weekday_of_month(2024, 11, thursday, 4), 28);
+        assert_eq!(nth_weekday_of_month(2024, 3, sunday, 2), 10);
+        // A month that starts or ends on the weekday itself
+        assert_eq!(nth_weekday_of_month(2024, 9, sunday, 1), 1);
+        assert_eq!(last_weekday_of_month(2024, 3, sunday), 31);
+        assert_eq!(nth_weekday_of_month(2024, 3, friday, 5), 29);
+        // February in and out of a leap year
+        assert_eq!(last_weekday_of_month(2024, 2, thursday), 29);
+        assert_eq!(last_weekday_of_month(2023, 2, tuesday), 28);
+
+        for year in 1987..2100 {
+            for month in 1..=12 {
+                let last_day = days_in_month(year, month);
+                for weekday in 0..7 {
+                    let on = |day| day_of_week(days_from_civil(year, month, day));
+                    let first = nth_weekday_of_month(year, month, weekday, 1);
+                    assert!((1..=7).contains(&first) && on(first) == weekday);
+                    assert_eq!(nth_weekday_of_month(year, month, weekday, 2), first + 7);
+                    let last = last_weekday_of_month(year, month, weekday);
+                    assert!(last + 7 > last_day && last <= last_day && on(last) == weekday);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn dst_rules_switch_in_2007() {
+        let noon_utc = |year, month, day| days_from_civil(year, month, day) * 86400 + 12 * 3600;
+
+        // 2006 ran on the old rules: 1st Sunday of April to last Sunday of October
+        assert!(is_daylight_saving_time(noon_utc(2006, 7, 15)));
+        assert!(!is_daylight_saving_time(noon_utc(2006, 3, 15)));
+        assert!(!is_daylight_saving_time(noon_utc(2006, 4, 1)));
+        assert!(is_daylight_saving_time(noon_utc(2006, 4, 2)));
+        assert!(is_daylight_saving_time(noon_utc(2006, 10, 28)));
+        assert!(!is_daylight_saving_time(noon_utc(2006, 10, 30)));
+
+        // 2007 moved to the 2nd Sunday of March and 1st Sunday of November
+        assert!(!is_daylight_saving_time(noon_utc(2007, 3, 10)));
+        assert!(is_daylight_saving_time(noon_utc(2007, 3, 12)));
+        assert!(is_daylight_saving_time(noon_utc(2007, 10, 30)));
+        assert!(!is_daylight_saving_time(noon_utc(2007, 11, 5)));
+
+        // The switch itself at 2:00 AM local on 2006-04-02
+        let spring_2006 = days_from_civil(2006, 4, 2) * 86400 + 7 * 3600;
+        assert!(!is_daylight_saving_time(spring_2006 - 1));
+        assert!(is_daylight_saving_time(spring_2006));
+    }
+
+    #[test]
+    fn dst_transition_weekends_of_2005() {
+        // Replays predate the hook's accepted clock range, so this goes
+        // through the calendar directly
+        use crate::calendar::{eastern_time, eastern_to_utc};
+        let wall_clock = |timestamp| {
+            let local = eastern_time(timestamp);
+            (
+                local.month,
+                local.day,
+                local.hour,
+                local.minute,
+                local.is_dst,
+            )
+        };
+
+        // Spring forward on 2005-04-03, the first Sunday in April
+        let spring = 1_112_511_600;
+        assert_eq!(wall_clock(spring - 1), (4, 3, 1, 59, false));
+        assert_eq!(wall_clock(spring), (4, 3, 3, 0, true));
+        assert_eq!(eastern_to_utc(2005, 4, 3, 3 * 3600), spring);
+        // Fall back on 2005-10-30, the last Sunday in October
+        let fall = 1_130_652_000;
+        assert_eq!(wall_clock(fall - 1), (10, 30, 1, 59, true));
+        assert_eq!(wall_clock(fall), (10, 30, 1, 0, false));
+        assert_eq!(eastern_to_utc(2005, 10, 30, 9 * 3600), fall + 8 * 3600);
+
+        // The Sundays the 2007 rules use were still standard time
+        assert!(!eastern_time(1_110_715_200).is_dst); // 2005-03-13 07:00 EST
+        assert!(!eastern_time(1_131_278_400).is_dst); // 2005-11-06 07:00 EST
+    }
+
+    #[test]
+    fn hook_stats_count_each_verdict() {
+        let mut stats = HookStats {
+            authority: Pubkey::new_unique(),
+            blocked_weekend: 0,
+            blocked_holiday: 0,
+            blocked_after_hours: 0,
+            allowed: 0,
+            allowed_extended: 0,
+            bump: 255,
+        };
+        // Tuesday 09:30 EST open, 17:00 after hours, then Saturday and MLK Day
+        let open = 1_704_810_600;
+        for (timestamp, allowed) in [
+            (open, true),
+            (open + 60, true),
+            (open + 450 * 60, false),
+            (open + 4 * 86_400, false),
+            (1_705_338_000, false),
+            // An allowlisted transfer on a holiday still counts as allowed
+            (1_705_338_000, true),
+        ] {
+            let state = get_nyse_market_state(timestamp, None, None).unwrap();
+            stats.record(&state, allowed);
+        }
+
+        assert_eq!(stats.allowed, 3);
+        assert_eq!(stats.blocked_after_hours, 1);
+        assert_eq!(stats.blocked_weekend, 1);
+        assert_eq!(stats.blocked_holiday, 1);
+
+        stats.reset();
+        assert_eq!(
+            (
+                stats.allowed,
+                stats.blocked_weekend,
+                stats.blocked_holiday,
+                stats.blocked_after_hours
+            ),
+            (0, 0, 0, 0)
+        );
+    }
+
+    #[test]
+    #[cfg(all(
+        not(feature = "testnet-calendar"),
+        feature = "hook-stats",
+        feature = "cooldown"
+    ))]
+    fn direct_calls_neither_count_nor_draw_on_the_reserve() {
+        install_test_runtime();
+        TEST_CLOCK.set(1_704_810_600 + 3600); // Tuesday 2024-01-09 10:30 EST
+        let mut config = config_with_hours(570, 960);
+        config.min_transfer_interval_seconds = 60;
+        let mut accounts = transfer_hook_accounts(&config);
+        let stats = HookStats {
+            authority: Pubkey::new_unique(),
+            blocked_weekend: 0,
+            blocked_holiday: 0,
+            blocked_after_hours: 0,
+            allowed: 0,
+            allowed_extended: 0,
+            bump: stored_bump(&accounts, HOOK_STATS_SLOT),
+        };
+        let mut data = vec![0; 8 + HookStats::INIT_SPACE];
+        stats.try_serialize(&mut &mut data[..]).unwrap();
+        let mut account = TestAccount::new(accounts[HOOK_STATS_SLOT].key, crate::ID, 5_000, &data);
+        account.is_writable = true;
+        accounts[HOOK_STATS_SLOT] = account;
+        let mut data = vec![0; 8 + RentReserve::INIT_SPACE];
+        RentReserve {
+            bump: stored_bump(&accounts, RENT_RESERVE_SLOT),
+        }
+        .try_serialize(&mut &mut data[..])
+        .unwrap();
+        let mut account = TestAccount::new(
+            accounts[RENT_RESERVE_SLOT].key,
+            crate::ID,
+            1_000_000_000,
+            &data,
+        );
+        account.is_writable = true;
+        accounts[RENT_RESERVE_SLOT] = account;
+        let allowed = |accounts: &[TestAccount]| {
+            HookStats::try_deserialize(&mut &accounts[HOOK_STATS_SLOT].data[8..])
+                .unwrap()
+                .allowed
+        };
+
+        // An owner the cooldown hasn't seen would get a LastTransfer paid
+        // from the reserve; a direct call naming them gets nothing
+        set_transferring(&mut accounts[0], false);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::NotTransferring.into()
+        );
+        assert_eq!(accounts[RENT_RESERVE_SLOT].lamports, 1_000_000_000);
+        assert_eq!(accounts[LAST_TRANSFER_SLOT].owner, System::id());
+        assert_eq!(allowed(&accounts), 0);
+
+        set_transferring(&mut accounts[0], true);
+        config.min_transfer_interval_seconds = 0;
+        store_config(&mut accounts, &config);
+        assert!(run_hook(&mut accounts, 100).is_ok());
+        assert_eq!(allowed(&accounts), 1);
+    }
+
+    #[test]
+    fn after_hours_transfer_counts_as_extended() {
+        let mut stats = HookStats {
+            authority: Pubkey::new_unique(),
+            blocked_weekend: 0,
+            blocked_holiday: 0,
+            blocked_after_hours: 0,
+            allowed: 0,
+            allowed_extended: 0,
+            bump: 255,
+        };
+        let mut config = config_with_hours(570, 960);
+        config.extended_hours = ExtendedHoursMode::ExtendedHours;
+        // Tuesday 2024-01-09: 11:00 AM and 5:00 PM EST
+        let regular = 1_704_816_000;
+        let after_hours = 1_704_837_600;
+
+        let state = get_nyse_market_state(after_hours, Some(&config), None).unwrap();
+        assert_eq!(
+            (state.is_open, state.status),
+            (true, MarketStatus::Extended)
+        );
+        stats.record(&state, true);
+        assert_eq!((stats.allowed, stats.allowed_extended), (0, 1));
+
+        let state = get_nyse_market_state(regular, Some(&config), None).unwrap();
+        stats.record(&state, true);
+        assert_eq!((stats.allowed, stats.allowed_extended), (1, 1));
+
+        stats.reset();
+        assert_eq!(stats.allowed_extended, 0);
+    }
+
+    #[test]
+    fn config_updates_must_name_the_current_version() {
+        install_test_runtime();
+        TEST_SLOT.set(250_000_000);
+        let mut config = config_with_hours(570, 960);
+
+        // A fresh update names version 0 and moves the config to 1
+        assert!(config.advance_version(0).is_ok());
+        assert_eq!(config.config_version, 1);
+        assert_eq!(config.config_applied_slot, 250_000_000);
+
+        // Replaying it, or one built before it, is stale and changes nothing
+        assert_eq!(
+            config.advance_version(0).unwrap_err(),
+            NyseError::StaleConfigVersion.into()
+        );
+        assert_eq!(
+            config.advance_version(5).unwrap_err(),
+            NyseError::StaleConfigVersion.into()
+        );
+        assert_eq!(config.config_version, 1);
+
+        assert_eq!(config.config_applied_slot, 250_000_000);
+
+        TEST_SLOT.set(250_000_007);
+        assert!(config.advance_version(1).is_ok());
+        assert_eq!(config.config_version, 2);
+        assert_eq!(config.config_applied_slot, 250_000_007);
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn transfer_after_a_pause_in_the_same_transaction_is_halted() {
+        use anchor_lang::InstructionData;
+
+        install_test_runtime();
+        TEST_CLOCK.set(1_704_810_600 + 3600); // Tuesday 2024-01-09 10:30 EST
+        TEST_SLOT.set(250_000_000);
+        let authority = Pubkey::new_unique();
+        let mut accounts = transfer_hook_accounts(&MarketConfig {
+            authority,
+            ..config_with_hours(570, 960)
+        });
+        #[cfg(feature = "state-cache")]
+        {
+            let cache = MarketStateCache {
+                minute: i64::MIN,
+                state: MarketState::HALTED,
+                bump: stored_bump(&accounts, MARKET_STATE_CACHE_SLOT),
+                config_version: 0,
+            };
+            let mut data = vec![0; 8 + MarketStateCache::INIT_SPACE];
+            cache.try_serialize(&mut &mut data[..]).unwrap();
+            accounts[MARKET_STATE_CACHE_SLOT] =
+                TestAccount::new(accounts[MARKET_STATE_CACHE_SLOT].key, crate::ID, 1, &data);
+            accounts[MARKET_STATE_CACHE_SLOT].is_writable = true;
+        }
+        accounts[MARKET_CONFIG_SLOT].is_writable = true;
+
+        // One transaction's instructions run in order over the same
+        // accounts, so each sees what the previous one committed
+        let update = |accounts: &mut Vec<TestAccount>, instruction: &dyn Fn(u64) -> Vec<u8>| {
+            let config =
+                MarketConfig::try_deserialize(&mut &accounts[MARKET_CONFIG_SLOT].data[8..])
+                    .unwrap();
+            let mut signer = TestAccount::new(authority, System::id(), 0, &[]);
+            signer.is_signer = true;
+            let placeholder = TestAccount::new(Pubkey::default(), System::id(), 0, &[]);
+            let market_config = std::mem::replace(&mut accounts[MARKET_CONFIG_SLOT], placeholder);
+            let mut setter = [signer, market_config];
+            let data = instruction(config.config_version);
+            entry(&crate::ID, &account_infos(&mut setter), &data).unwrap();
+            let [_, market_config] = setter;
+            accounts[MARKET_CONFIG_SLOT] = market_config;
+        };
+        let stored = |accounts: &Vec<TestAccount>| {
+            MarketConfig::try_deserialize(&mut &accounts[MARKET_CONFIG_SLOT].data[8..]).unwrap()
+        };
+
+        // The first transfer of the minute fills the cache with OPEN
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap(),
+            Some(AllowReason::MarketOpen)
+        );
+
+        // Pause, then transfer: the halt wins
+        TEST_SLOT.set(250_000_001);
+        update(&mut accounts, &|expected_version| {
+            instruction::SetPaused {
+                expected_version,
+                paused: true,
+            }
+            .data()
+        });
+        assert_eq!(stored(&accounts).config_applied_slot, 250_000_001);
+        let halted_at_version = stored(&accounts).config_version;
+        assert_eq!(stored(&accounts).halted_at_version, halted_at_version);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::MarketHalted.into()
+        );
+
+        // Unpause and move the close before 10:30 in the same minute: the
+        // cached OPEN is stale and the new hours decide
+        update(&mut accounts, &|expected_version| {
+            instruction::SetPaused {
+                expected_version,
+                paused: false,
+            }
+            .data()
+        });
+        assert_eq!(stored(&accounts).halted_at_version, 0);
+        update(&mut accounts, &|expected_version| {
+            instruction::UpdateMarketHours {
+                expected_version,
+                open_minute: 570,
+                close_minute: 600,
+            }
+            .data()
+        });
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::MarketClosedPostMarket.into()
+        );
+    }
+
+    #[test]
+    fn account_status_for_each_list() {
+        let market_maker = Pubkey::new_unique();
+        let sanctioned = Pubkey::new_unique();
+        let holder = Pubkey::new_unique();
+        let allowlist = Allowlist {
+            authority: Pubkey::new_unique(),
+            entries: vec![both_ways(market_maker)],
+            bump: 255,
+            programs: vec![],
+        };
+        let denylist = Denylist {
+            authority: Pubkey::new_unique(),
+            entries: vec![sanctioned],
+            bump: 255,
+        };
+        let status = |account| account_list_status(Some(&allowlist), Some(&denylist), account);
+
+        assert_eq!(status(&market_maker), AccountListStatus::Allowlisted);
+        assert_eq!(status(&sanctioned), AccountListStatus::Denylisted);
+        assert_eq!(status(&holder), AccountListStatus::Unlisted);
+        assert_eq!(
+            account_list_status(None, None, &market_maker),
+            AccountListStatus::Unlisted
+        );
+
+        // On both lists, the denylist decides, as it does in the hook
+        let mut both = allowlist.clone();
+        both.entries.push(both_ways(sanctioned));
+        assert_eq!(
+            account_list_status(Some(&both), Some(&denylist), &sanctioned),
+            AccountListStatus::Denylisted
+        );
+        assert_eq!(AccountListStatus::Denylisted.try_to_vec().unwrap(), [2]);
+    }
+
+    #[test]
+    fn batch_status_packs_two_bits_per_account() {
+        let market_maker = Pubkey::new_unique();
+        let sanctioned = Pubkey::new_unique();
+        let holder = Pubkey::new_unique();
+        let allowlist = Allowlist {
+            authority: Pubkey::new_unique(),
+            entries: vec![both_ways(market_maker), both_ways(sanctioned)],
+            bump: 255,
+            programs: vec![],
+        };
+        let denylist = Denylist {
+            authority: Pubkey::new_unique(),
+            entries: vec![sanctioned],
+            bump: 255,
+        };
+        let batch = [holder, market_maker, sanctioned, holder, market_maker];
+        let packed = packed_account_statuses(Some(&allowlist), Some(&denylist), &batch).unwrap();
+        // 0, 1, 2, 0 in the first byte, then 1 in the second
+        assert_eq!(packed, [0b00_10_01_00, 0b01]);
+        for (index, account) in batch.iter().enumerate() {
+            let code = (packed[index / 4] >> (2 * (index % 4))) & 0b11;
+            let expected = account_list_status(Some(&allowlist), Some(&denylist), account);
+            assert_eq!(code, expected as u8);
+        }
+
+        assert!(packed_account_statuses(None, None, &[]).unwrap().is_empty());
+        assert_eq!(packed_account_statuses(None, None, &[holder]).unwrap(), [0]);
+        let too_many = vec![holder; MAX_STATUS_BATCH + 1];
+        assert_eq!(
+            packed_account_statuses(None, None, &too_many).unwrap_err(),
+            NyseError::StatusBatchTooLarge.into()
+        );
+    }
+
+    #[test]
+    fn bulk_allowlist_of_fifty_wallets() {
+        let wallets: Vec<Pubkey> = (0..50).map(|_| Pubkey::new_unique()).collect();
+        let entries: Vec<AllowlistEntry> = wallets.iter().copied().map(both_ways).collect();
+        assert!(validate_allowlist(&entries).is_ok());
+
+        let allowlist = Allowlist {
+            authority: Pubkey::new_unique(),
+            entries: entries.clone(),
+            bump: 255,
+            programs: vec![],
+        };
+        assert!(allowlist.try_to_vec().unwrap().len() <= Allowlist::INIT_SPACE);
+        assert!(wallets.iter().all(|wallet| allowlist.contains(wallet)));
+        assert!(!allowlist.contains(&Pubkey::new_unique()));
+        assert!(is_exempt(
+            Some(&allowlist),
+            &wallets[49],
+            &Pubkey::new_unique()
+        ));
+
+        // Duplicates and oversized rosters are refused
+        let mut duplicated = entries.clone();
+        duplicated.push(AllowlistEntry {
+            direction: ExemptDirection::SendExempt,
+            ..entries[7]
+        });
+        assert_eq!(
+            validate_allowlist(&duplicated).unwrap_err(),
+            NyseError::AlreadyAllowlisted.into()
+        );
+        let oversized: Vec<AllowlistEntry> = (0..=MAX_ALLOWLIST_ENTRIES)
+            .map(|_| both_ways(Pubkey::new_unique()))
+            .collect();
+        assert_eq!(
+            validate_allowlist(&oversized).unwrap_err(),
+            NyseError::AllowlistFull.into()
+        );
+        assert!(validate_allowlist(&[]).is_ok());
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn same_tx_halt_grace_lets_the_halting_transaction_finish() {
+        use anchor_lang::InstructionData;
+        use sysvar::instructions::{BorrowedAccountMeta, BorrowedInstruction};
+
+        install_test_runtime();
+        TEST_CLOCK.set(1_704_810_600 + 3600); // Tuesday 2024-01-09 10:30 EST
+        let authority = Pubkey::new_unique();
+        // The mint is halted by `set_paused` calls naming these versions,
+        // the ones in `in_tx` sent earlier in the transfer's transaction
+        let run = |same_tx_halt_grace: bool, pauses: &[u64], in_tx: &[u64]| {
+            let mut accounts = transfer_hook_accounts(&MarketConfig {
+                authority,
+                same_tx_halt_grace,
+                ..config_with_hours(570, 960)
+            });
+            let set_paused = |expected_version| {
+                instruction::SetPaused {
+                    expected_version,
+                    paused: true,
+                }
+                .data()
+            };
+            for &expected_version in pauses {
+                let mut signer = TestAccount::new(authority, System::id(), 0, &[]);
+                signer.is_signer = true;
+                let placeholder = TestAccount::new(Pubkey::default(), System::id(), 0, &[]);
+                let config = std::mem::replace(&mut accounts[MARKET_CONFIG_SLOT], placeholder);
+                let mut setter = [signer, config];
+                entry(
+                    &crate::ID,
+                    &account_infos(&mut setter),
+                    &set_paused(expected_version),
+                )
+                .unwrap();
+                let [_, config] = setter;
+                accounts[MARKET_CONFIG_SLOT] = config;
+            }
+
+            let config_key = accounts[MARKET_CONFIG_SLOT].key;
+            let pause_data: Vec<Vec<u8>> = in_tx.iter().map(|&v| set_paused(v)).collect();
+            let mut instructions: Vec<BorrowedInstruction> = pause_data
+                .iter()
+                .map(|data| BorrowedInstruction {
+                    program_id: &crate::ID,
+                    accounts: vec![
+                        BorrowedAccountMeta {
+                            pubkey: &authority,
+                            is_signer: true,
+                            is_writable: false,
+                        },
+                        BorrowedAccountMeta {
+                            pubkey: &config_key,
+                            is_signer: false,
+                            is_writable: true,
+                        },
+                    ],
+                    data,
+                })
+                .collect();
+            // The transfer itself, which invokes the hook
+            instructions.push(BorrowedInstruction {
+                program_id: &anchor_spl::token_2022::ID,
+                accounts: vec![],
+                data: &[],
+            });
+            let mut data = sysvar::instructions::construct_instructions_data(&instructions);
+            sysvar::instructions::store_current_index(&mut data, in_tx.len() as u16);
+            accounts[INSTRUCTIONS_SYSVAR_SLOT] =
+                TestAccount::new(sysvar::instructions::ID, Pubkey::default(), 0, &data);
+            run_hook(&mut accounts, 100)
+        };
+        let halted = Error::from(NyseError::MarketHalted);
+
+        // Halted earlier in the same transaction: blocked by default, and
+        // checked as before the halt under the grace
+        assert_eq!(run(false, &[0], &[0]).unwrap_err(), halted);
+        assert_eq!(
+            run(true, &[0], &[0]).unwrap(),
+            Some(AllowReason::MarketOpen)
+        );
+
+        // A halt from an earlier transaction still blocks
+        assert_eq!(run(true, &[0], &[]).unwrap_err(), halted);
+        // So does one this transaction only repeated
+        assert_eq!(run(true, &[0, 1], &[1]).unwrap_err(), halted);
+    }
+
+    #[test]
+    fn another_mints_config_is_a_mint_mismatch() {
+        let mint = Pubkey::new_unique();
+        let other_mint = Pubkey::new_unique();
+        let pda = |prefix: &[u8], mint: &Pubkey| {
+            Pubkey::find_program_address(&[prefix, mint.as_ref()], &crate::ID)
+        };
+
+        for prefix in [&b"market-config"[..], b"allowlist"] {
+            let (address, bump) = pda(prefix, &mint);
+            assert!(is_stored_pda(&address, &[prefix, mint.as_ref()], bump));
+            // Another mint's account carries its own bump
+            let (other, other_bump) = pda(prefix, &other_mint);
+            assert!(!is_stored_pda(&other, &[prefix, mint.as_ref()], other_bump));
+        }
+        // Nor can the config stand in for the allowlist
+        let (config, bump) = pda(b"market-config", &mint);
+        assert!(!is_stored_pda(
+            &config,
+            &[b"allowlist", mint.as_ref()],
+            bump
+        ));
+        // A bump that isn't the one the address was found with fails
+        assert!(!is_stored_pda(
+            &config,
+            &[b"market-config", mint.as_ref()],
+            bump.wrapping_sub(1)
+        ));
+
+        // An account that was never created has nothing to compare
+        let key = Pubkey::new_unique();
+        let mut lamports = 0;
+        let info = AccountInfo::new(
+            &key,
+            false,
+            false,
+            &mut lamports,
+            &mut [],
+            &crate::ID,
+            false,
+            0,
+        );
+        assert!(check_stored_pda(&info, &[b"allowlist"], None, NyseError::MintMismatch).is_ok());
+        assert_eq!(
+            check_stored_pda(&info, &[b"allowlist"], Some(bump), NyseError::MintMismatch)
+                .unwrap_err(),
+            NyseError::MintMismatch.into()
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn hook_refuses_another_mints_accounts() {
+        install_test_runtime();
+        TEST_CLOCK.set(1_704_810_600 + 3600); // Tuesday 2024-01-09 10:30 EST
+        let config = config_with_hours(570, 960);
+        let mut accounts = transfer_hook_accounts(&config);
+        assert!(run_hook(&mut accounts, 100).is_ok());
+
+        // Another mint's config, created at its own address with its own bump
+        let (other_config, other_bump) = market_config_pda(&Pubkey::new_unique());
+        let mut data = vec![0; 8 + MarketConfig::INIT_SPACE];
+        MarketConfig {
+            bump: other_bump,
+            ..config.clone()
+        }
+        .try_serialize(&mut &mut data[..])
+        .unwrap();
+        let mut other = TestAccount::new(other_config, crate::ID, 1, &data);
+        other.is_writable = true;
+        let ours = std::mem::replace(&mut accounts[MARKET_CONFIG_SLOT], other);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::MintMismatch.into()
+        );
+        accounts[MARKET_CONFIG_SLOT] = ours;
+
+        // Ours, but claiming a bump it wasn't derived with
+        #[cfg(feature = "allowlist")]
+        {
+            let allowlist = Allowlist {
+                authority: Pubkey::new_unique(),
+                entries: vec![both_ways(accounts[3].key)],
+                bump: stored_bump(&accounts, ALLOWLIST_SLOT).wrapping_sub(1),
+                programs: vec![],
+            };
+            let mut data = vec![0; 8 + Allowlist::INIT_SPACE];
+            allowlist.try_serialize(&mut &mut data[..]).unwrap();
+            accounts[ALLOWLIST_SLOT] =
+                TestAccount::new(accounts[ALLOWLIST_SLOT].key, crate::ID, 5_000, &data);
+            assert_eq!(
+                run_hook(&mut accounts, 100).unwrap_err(),
+                NyseError::MintMismatch.into()
+            );
+        }
+    }
+
+    #[test]
+    fn missing_config_follows_the_program_policy() {
+        let config = config_with_hours(570, 960);
+        let mut settings = ProgramSettings {
+            authority: Pubkey::new_unique(),
+            require_config: false,
+            frozen: false,
+            super_authority: Pubkey::new_unique(),
+            bump: 255,
+        };
+
+        // Without settings, or with the requirement off, defaults apply
+        for settings in [None, Some(&settings)] {
+            assert!(check_config_policy(settings, Some(&config)).is_ok());
+            assert!(check_config_policy(settings, None).is_ok());
+        }
+
+        settings.require_config = true;
+        assert!(check_config_policy(Some(&settings), Some(&config)).is_ok());
+        assert_eq!(
+            check_config_policy(Some(&settings), None).unwrap_err(),
+            NyseError::ConfigMissing.into()
+        );
+    }
+
+    #[test]
+    fn transfers_wait_for_activation() {
+        let mut config = config_with_hours(570, 960);
+        config.activated = false;
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30:00 EST
+        assert!(
+            get_nyse_market_state(open, Some(&config), None)
+                .unwrap()
+                .is_open
+        );
+
+        // Blocked even in regular hours until the authority activates it
+        assert_eq!(
+            ensure_activated(Some(&config)).unwrap_err(),
+            NyseError::HookNotActivated.into()
+        );
+        config.activated = true;
+        assert!(ensure_activated(Some(&config)).is_ok());
+
+        // Mints without a config run on the defaults
+        assert!(ensure_activated(None).is_ok());
+    }
+
+    #[test]
+    fn pre_open_staging_admits_only_the_staging_account() {
+        let mut config = config_with_hours(570, 960);
+        let (staging, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30:00 EST
+        let staging_at = |config: &MarketConfig, timestamp, owner| {
+            let state = get_nyse_market_state(timestamp, Some(config), None).unwrap();
+            is_pre_open_staging(Some(config), || Ok(None), &state, timestamp, owner).unwrap()
+        };
+        let at_9_25 = open - 5 * 60;
+        assert!(
+            !get_nyse_market_state(at_9_25, Some(&config), None)
+                .unwrap()
+                .is_open
+        );
+        assert!(!staging_at(&config, at_9_25, &staging));
+
+        config.pre_open_staging_minutes = 10;
+        config.staging_account = staging;
+        assert!(staging_at(&config, at_9_25, &staging));
+        assert!(!staging_at(&config, at_9_25, &other));
+        assert!(staging_at(&config, open - 10 * 60, &staging));
+        assert!(!staging_at(&config, open - 10 * 60 - 1, &staging));
+        // Once open, the normal rules take over
+        assert!(!staging_at(&config, open, &staging));
+        // And only on trading days: the same hour on Saturday stays closed
+        assert!(!staging_at(&config, at_9_25 + 4 * 86400, &staging));
+        assert!(!is_pre_open_staging(
+            None,
+            || Ok(None),
+            &get_nyse_market_state(at_9_25, None, None).unwrap(),
+            at_9_25,
+            &staging
+        )
+        .unwrap());
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn global_freeze_blocks_an_open_unpaused_mint() {
+        install_test_runtime();
+        TEST_CLOCK.set(1_704_810_600 + 3600); // Tuesday 2024-01-09 10:30 EST
+        let mut accounts = transfer_hook_accounts(&config_with_hours(570, 960));
+        let mut settings = ProgramSettings {
+            authority: Pubkey::new_unique(),
+            require_config: false,
+            frozen: false,
+            super_authority: Pubkey::new_unique(),
+            bump: stored_bump(&accounts, PROGRAM_SETTINGS_SLOT),
+        };
+        let store = |accounts: &mut [TestAccount], settings: &ProgramSettings| {
+            let mut data = vec![0; 8 + ProgramSettings::INIT_SPACE];
+            settings.try_serialize(&mut &mut data[..]).unwrap();
+            accounts[PROGRAM_SETTINGS_SLOT] =
+                TestAccount::new(accounts[PROGRAM_SETTINGS_SLOT].key, crate::ID, 1, &data);
+        };
+        store(&mut accounts, &settings);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap(),
+            Some(AllowReason::MarketOpen)
+        );
+
+        settings.frozen = true;
+        store(&mut accounts, &settings);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::GloballyFrozen.into()
+        );
+        assert!(ensure_not_frozen(None).is_ok());
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn optional_subsystems_only_apply_when_built_in() {
+        install_test_runtime();
+        let tuesday = 1_704_810_600 + 3600; // Tuesday 2024-01-09 10:30 EST
+        let saturday = 1_705_165_200; // Saturday 2024-01-13 12:00 EST
+        let mlk_day = 1_705_336_200; // Monday 2024-01-15 11:30 EST
+        let config = config_with_hours(570, 960);
+        let run = |timestamp, setup: &dyn Fn(&mut Vec<TestAccount>)| {
+            TEST_CLOCK.set(timestamp);
+            let mut accounts = transfer_hook_accounts(&config);
+            setup(&mut accounts);
+            run_hook(&mut accounts, 100)
+        };
+        let plain = |_: &mut Vec<TestAccount>| {};
+
+        // Every build, the leanest included, enforces the NYSE hours and
+        // the built-in holidays
+        assert_eq!(run(tuesday, &plain).unwrap(), Some(AllowReason::MarketOpen));
+        assert_eq!(
+            run(saturday, &plain).unwrap_err(),
+            NyseError::MarketClosedWeekend.into()
+        );
+        assert_eq!(
+            run(mlk_day, &plain).unwrap_err(),
+            NyseError::MarketClosedHoliday.into()
+        );
+
+        // A compiled-out subsystem's accounts aren't even registered
+        let optional_accounts = [
+            cfg!(feature = "allowlist"),
+            cfg!(feature = "holiday-calendar"),
+            cfg!(feature = "state-cache"),
+            cfg!(feature = "denylist"),
+            cfg!(feature = "rent-reserve"),
+            cfg!(feature = "volume-tracking"),
+            OWNER_PDAS,
+            cfg!(feature = "transition-events"),
+            cfg!(feature = "hook-stats"),
+            cfg!(feature = "cooldown"),
+            cfg!(feature = "destination-age"),
+            cfg!(feature = "circuit-breaker"),
+            cfg!(feature = "transfer-vouchers"),
+            cfg!(feature = "daily-counter"),
+        ];
+        assert_eq!(
+            extra_account_metas().unwrap().len(),
+            3 + optional_accounts
+                .iter()
+                .filter(|&&built_in| built_in)
+                .count()
+        );
+
+        // A limit the build can't enforce refuses transfers rather than
+        // letting them through unchecked
+        let mut aged = config.clone();
+        aged.min_destination_age_seconds = 3600;
+        TEST_CLOCK.set(tuesday);
+        assert_eq!(
+            run_hook(&mut transfer_hook_accounts(&aged), 100).unwrap_err(),
+            if cfg!(feature = "destination-age") {
+                NyseError::DestinationTooNew.into()
+            } else {
+                NyseError::FeatureDisabled.into()
+            }
+        );
+
+        // The lists and the mint's own calendar count when built in
+        #[cfg(feature = "allowlist")]
+        {
+            let allowlisted = |accounts: &mut Vec<TestAccount>| {
+                let allowlist = Allowlist {
+                    authority: Pubkey::new_unique(),
+                    entries: vec![both_ways(accounts[3].key)],
+                    bump: stored_bump(accounts, ALLOWLIST_SLOT),
+                    programs: vec![],
+                };
+                let mut data = vec![0; 8 + Allowlist::INIT_SPACE];
+                allowlist.try_serialize(&mut &mut data[..]).unwrap();
+                accounts[ALLOWLIST_SLOT] =
+                    TestAccount::new(accounts[ALLOWLIST_SLOT].key, crate::ID, 5_000, &data);
+            };
+            assert_eq!(
+                run(saturday, &allowlisted),
+                Ok(Some(AllowReason::Allowlisted))
+            );
+        }
+        #[cfg(feature = "denylist")]
+        {
+            let denylisted = |accounts: &mut Vec<TestAccount>| {
+                let denylist = Denylist {
+                    authority: Pubkey::new_unique(),
+                    entries: vec![accounts[3].key],
+                    bump: stored_bump(accounts, DENYLIST_SLOT),
+                };
+                let mut data = vec![0; 8 + Denylist::INIT_SPACE];
+                denylist.try_serialize(&mut &mut data[..]).unwrap();
+                accounts[DENYLIST_SLOT] =
+                    TestAccount::new(accounts[DENYLIST_SLOT].key, crate::ID, 5_000, &data);
+            };
+            assert_eq!(
+                run(tuesday, &denylisted),
+                Err(NyseError::AddressBlocked.into())
+            );
+        }
+        #[cfg(feature = "holiday-calendar")]
+        {
+            let closed_tuesday = |accounts: &mut Vec<TestAccount>| {
+                let calendar = HolidayCalendar {
+                    authority: Pubkey::new_unique(),
+                    dates: vec![HolidayDate {
+                        year: 2024,
+                        month: 1,
+                        day: 9,
+                    }],
+                    early_closes: Vec::new(),
+                    bump: stored_bump(accounts, HOLIDAY_CALENDAR_SLOT),
+                };
+                let mut data = vec![0; 8 + HolidayCalendar::INIT_SPACE];
+                calendar.try_serialize(&mut &mut data[..]).unwrap();
+                accounts[HOLIDAY_CALENDAR_SLOT] =
+                    TestAccount::new(accounts[HOLIDAY_CALENDAR_SLOT].key, crate::ID, 5_000, &data);
+            };
+            assert_eq!(
+                run(tuesday, &closed_tuesday),
+                Err(NyseError::MarketClosedHoliday.into())
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(all(
+        not(feature = "testnet-calendar"),
+        feature = "allowlist",
+        feature = "denylist"
+    ))]
+    fn the_most_lasting_block_reason_wins() {
+        install_test_runtime();
+        let saturday = 1_705_165_200; // Saturday 2024-01-13 12:00 EST
+        let tuesday = 1_704_810_600 + 3600; // Tuesday 2024-01-09 10:30 EST
+        let run = |config: &MarketConfig, timestamp, frozen, denied| {
+            TEST_CLOCK.set(timestamp);
+            let mut accounts = transfer_hook_accounts(config);
+            let settings = ProgramSettings {
+                authority: Pubkey::new_unique(),
+                require_config: false,
+                frozen,
+                super_authority: Pubkey::new_unique(),
+                bump: stored_bump(&accounts, PROGRAM_SETTINGS_SLOT),
+            };
+            let mut data = vec![0; 8 + ProgramSettings::INIT_SPACE];
+            settings.try_serialize(&mut &mut data[..]).unwrap();
+            accounts[PROGRAM_SETTINGS_SLOT] =
+                TestAccount::new(accounts[PROGRAM_SETTINGS_SLOT].key, crate::ID, 1, &data);
+            // The sender is allowlisted, and denylisted as well when `denied`
+            let owner = accounts[3].key;
+            let allowlist = Allowlist {
+                authority: Pubkey::new_unique(),
+                entries: vec![both_ways(owner)],
+                bump: stored_bump(&accounts, ALLOWLIST_SLOT),
+                programs: vec![],
+            };
+            let mut data = vec![0; 8 + Allowlist::INIT_SPACE];
+            allowlist.try_serialize(&mut &mut data[..]).unwrap();
+            accounts[ALLOWLIST_SLOT] =
+                TestAccount::new(accounts[ALLOWLIST_SLOT].key, crate::ID, 5_000, &data);
+            if denied {
+                let denylist = Denylist {
+                    authority: Pubkey::new_unique(),
+                    entries: vec![owner],
+                    bump: stored_bump(&accounts, DENYLIST_SLOT),
+                };
+                let mut data = vec![0; 8 + Denylist::INIT_SPACE];
+                denylist.try_serialize(&mut &mut data[..]).unwrap();
+                accounts[DENYLIST_SLOT] =
+                    TestAccount::new(accounts[DENYLIST_SLOT].key, crate::ID, 5_000, &data);
+            }
+            run_hook(&mut accounts, 100)
+        };
+
+        // Everything at once on a weekend: the freeze, then each rule
+        // further down once the ones above it clear
+        let mut config = config_with_hours(570, 960);
+        config.activated = false;
+        config.delisting_timestamp = saturday - 3600;
+        config.paused = true;
+        config.maintenance_start = saturday - 3600;
+        config.maintenance_end = saturday + 3600;
+        assert_eq!(
+            run(&config, saturday, true, true).unwrap_err(),
+            NyseError::GloballyFrozen.into()
+        );
+        assert_eq!(
+            run(&config, saturday, false, true).unwrap_err(),
+            NyseError::HookNotActivated.into()
+        );
+        config.activated = true;
+        assert_eq!(
+            run(&config, saturday, false, true).unwrap_err(),
+            NyseError::Delisted.into()
+        );
+        config.delisting_timestamp = 0;
+        assert_eq!(
+            run(&config, saturday, false, true).unwrap_err(),
+            NyseError::MarketHalted.into()
+        );
+        config.paused = false;
+        assert_eq!(
+            run(&config, saturday, false, true).unwrap_err(),
+            NyseError::AddressBlocked.into()
+        );
+        // The allowlist waives maintenance but not the denylist. Once off the
+        // denylist, the sender gets past maintenance and the weekend alike
+        assert_eq!(
+            run(&config, saturday, false, false).unwrap(),
+            Some(AllowReason::Allowlisted)
+        );
+
+        // For everyone else, maintenance outranks the denylist and the
+        // weekend...
+        let mut config = config_with_hours(570, 960);
+        config.maintenance_start = saturday - 3600;
+        config.maintenance_end = saturday + 3600;
+        config.post_halt_cooldown_seconds = 3600;
+        config.resumed_at = saturday - 60;
+        config.delayed_open_until = saturday + 86_400 * 7;
+        config.min_transfer_amount = 1_000;
+        TEST_CLOCK.set(saturday);
+        let mut accounts = transfer_hook_accounts(&config);
+        let denylist = Denylist {
+            authority: Pubkey::new_unique(),
+            entries: vec![accounts[3].key],
+            bump: stored_bump(&accounts, DENYLIST_SLOT),
+        };
+        let mut data = vec![0; 8 + Denylist::INIT_SPACE];
+        denylist.try_serialize(&mut &mut data[..]).unwrap();
+        accounts[DENYLIST_SLOT] =
+            TestAccount::new(accounts[DENYLIST_SLOT].key, crate::ID, 5_000, &data);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::MaintenanceWindow.into()
+        );
+        let mut maintenance_over = config.clone();
+        maintenance_over.maintenance_end = saturday;
+        store_config(&mut accounts, &maintenance_over);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::AddressBlocked.into()
+        );
+        // ...and the weekend outranks the rules for open hours
+        config.maintenance_end = 0;
+        config.maintenance_start = 0;
+        let mut accounts = transfer_hook_accounts(&config);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::MarketClosedWeekend.into()
+        );
+
+        // On a trading day those rules apply in order
+        config.resumed_at = tuesday - 60;
+        config.delayed_open_until = tuesday + 3600;
+        TEST_CLOCK.set(tuesday);
+        let mut accounts = transfer_hook_accounts(&config);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::PostHaltCooldown.into()
+        );
+        config.post_halt_cooldown_seconds = 0;
+        let mut accounts = transfer_hook_accounts(&config);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::OpeningDelayed.into()
+        );
+        config.delayed_open_until = 0;
+        let mut accounts = transfer_hook_accounts(&config);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::TransferBelowMinimum.into()
+        );
+
+        // A weekend holiday reports the weekend
+        let calendar = HolidayCalendar {
+            authority: Pubkey::new_unique(),
+            dates: vec![HolidayDate {
+                year: 2024,
+                month: 1,
+                day: 13,
+            }],
+            early_closes: Vec::new(),
+            bump: 255,
+        };
+        let state = get_nyse_market_state(saturday, Some(&config), Some(&calendar)).unwrap();
+        assert_eq!(state.status, MarketStatus::Weekend);
+    }
+
+    #[test]
+    #[cfg(all(not(feature = "testnet-calendar"), feature = "transfer-vouchers"))]
+    fn transfer_voucher_lets_one_off_hours_transfer_through() {
+        install_test_runtime();
+        TEST_CLOCK.set(1_705_165_200); // Saturday 2024-01-13 12:00 EST
+        let mut accounts = transfer_hook_accounts(&config_with_hours(570, 960));
+        let (source, mint, destination) = (accounts[0].key, accounts[1].key, accounts[2].key);
+        let issue = |accounts: &mut [TestAccount], amount, expires_at| {
+            let voucher = TransferVoucher {
+                mint,
+                source,
+                destination,
+                amount,
+                expires_at,
+                bump: stored_bump(accounts, TRANSFER_VOUCHER_SLOT),
+            };
+            let mut data = vec![0; 8 + TransferVoucher::INIT_SPACE];
+            voucher.try_serialize(&mut &mut data[..]).unwrap();
+            let mut account =
+                TestAccount::new(accounts[TRANSFER_VOUCHER_SLOT].key, crate::ID, 5_000, &data);
+            account.is_writable = true;
+            accounts[TRANSFER_VOUCHER_SLOT] = account;
+        };
+        let weekend = Error::from(NyseError::MarketClosedWeekend);
+        assert_eq!(run_hook(&mut accounts, 100).unwrap_err(), weekend);
+
+        // A voucher only matches its exact amount, and not once expired
+        issue(&mut accounts, 100, 1_705_165_200 + 3600);
+        assert_eq!(run_hook(&mut accounts, 99).unwrap_err(), weekend);
+        assert_eq!(accounts[TRANSFER_VOUCHER_SLOT].lamports, 5_000);
+        issue(&mut accounts, 100, 1_705_165_200);
+        assert_eq!(run_hook(&mut accounts, 100).unwrap_err(), weekend);
+
+        // A direct call naming the voucher's transfer can't burn it
+        issue(&mut accounts, 100, 1_705_165_200 + 3600);
+        set_transferring(&mut accounts[0], false);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::NotTransferring.into()
+        );
+        assert_eq!(accounts[TRANSFER_VOUCHER_SLOT].lamports, 5_000);
+        set_transferring(&mut accounts[0], true);
+
+        // The matching transfer goes through and closes it into the reserve
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap(),
+            Some(AllowReason::Voucher)
+        );
+        assert_eq!(accounts[TRANSFER_VOUCHER_SLOT].lamports, 0);
+        assert_eq!(accounts[TRANSFER_VOUCHER_SLOT].owner, System::id());
+        assert_eq!(accounts[RENT_RESERVE_SLOT].lamports, 5_000);
+        assert_eq!(run_hook(&mut accounts, 100).unwrap_err(), weekend);
+    }
+
+    #[test]
+    #[cfg(all(not(feature = "testnet-calendar"), feature = "allowlist"))]
+    fn closing_cross_blocks_all_but_allowlisted_accounts() {
+        install_test_runtime();
+        TEST_CLOCK.set(1_704_834_000 - 15); // Tuesday 2024-01-09 15:59:45 EST
+        let mut config = config_with_hours(570, 960);
+        config.closing_cross_seconds = 30;
+        config.blocking_during_closing_cross = true;
+        let mut accounts = transfer_hook_accounts(&config);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::ClosingCrossBlocked.into()
+        );
+
+        // The account settling the auction is allowlisted
+        let allowlist = Allowlist {
+            authority: Pubkey::new_unique(),
+            entries: vec![both_ways(accounts[3].key)],
+            bump: stored_bump(&accounts, ALLOWLIST_SLOT),
+            programs: vec![],
+        };
+        let mut data = vec![0; 8 + Allowlist::INIT_SPACE];
+        allowlist.try_serialize(&mut &mut data[..]).unwrap();
+        let mut account = TestAccount::new(accounts[ALLOWLIST_SLOT].key, crate::ID, 5_000, &data);
+        account.is_writable = true;
+        accounts[ALLOWLIST_SLOT] = account;
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap(),
+            Some(AllowReason::Allowlisted)
+        );
+    }
+
+    #[test]
+    #[cfg(all(not(feature = "testnet-calendar"), feature = "allowlist"))]
+    fn allowlisted_owner_trades_off_hours_until_the_daily_cap() {
+        install_test_runtime();
+        let saturday = 1_705_165_200; // Saturday 2024-01-13 12:00 EST
+        TEST_CLOCK.set(saturday);
+        let mut config = config_with_hours(570, 960);
+        config.allowlist_daily_cap = 250;
+        let mut accounts = transfer_hook_accounts(&config);
+        let allowlist = Allowlist {
+            authority: Pubkey::new_unique(),
+            entries: vec![both_ways(accounts[3].key)],
+            bump: stored_bump(&accounts, ALLOWLIST_SLOT),
+            programs: vec![],
+        };
+        let mut data = vec![0; 8 + Allowlist::INIT_SPACE];
+        allowlist.try_serialize(&mut &mut data[..]).unwrap();
+        let mut account = TestAccount::new(accounts[ALLOWLIST_SLOT].key, crate::ID, 5_000, &data);
+        account.is_writable = true;
+        accounts[ALLOWLIST_SLOT] = account;
+        // The owner's counter, last touched on Friday
+        let counter = DailyCounter {
+            trading_day: trading_day(saturday, &config) - 1,
+            count: 0,
+            allowlist_volume: 250,
+            bump: stored_bump(&accounts, DAILY_COUNTER_SLOT),
+        };
+        let mut data = vec![0; 8 + DailyCounter::INIT_SPACE];
+        counter.try_serialize(&mut &mut data[..]).unwrap();
+        let mut account =
+            TestAccount::new(accounts[DAILY_COUNTER_SLOT].key, crate::ID, 5_000, &data);
+        account.is_writable = true;
+        accounts[DAILY_COUNTER_SLOT] = account;
+        let volume = |accounts: &[TestAccount]| {
+            DailyCounter::try_deserialize(&mut &accounts[DAILY_COUNTER_SLOT].data[8..])
+                .unwrap()
+                .allowlist_volume
+        };
+
+        // Direct calls don't spend the owner's allowance
+        set_transferring(&mut accounts[0], false);
+        assert_eq!(
+            run_hook(&mut accounts, 250).unwrap_err(),
+            NyseError::NotTransferring.into()
+        );
+        assert_eq!(volume(&accounts), 250);
+        set_transferring(&mut accounts[0], true);
+
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap(),
+            Some(AllowReason::Allowlisted)
+        );
+        assert_eq!(volume(&accounts), 100);
+        assert!(run_hook(&mut accounts, 150).is_ok());
+        assert_eq!(
+            run_hook(&mut accounts, 1).unwrap_err(),
+            NyseError::AllowlistCapExceeded.into()
+        );
+        assert_eq!(volume(&accounts), 250);
+
+        // The cap frees up on Sunday, and zero lifts it
+        TEST_CLOCK.set(saturday + 86_400);
+        assert!(run_hook(&mut accounts, 250).is_ok());
+        config.allowlist_daily_cap = 0;
+        store_config(&mut accounts, &config);
+        assert!(run_hook(&mut accounts, u64::MAX).is_ok());
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn weekend_and_after_close_blocks_report_mondays_open() {
+        install_test_runtime();
+        let config = config_with_hours(570, 960);
+        let monday_open = 1_705_933_800; // Monday 2024-01-22 09:30 EST
+        let blocked = |timestamp| {
+            TEST_CLOCK.set(timestamp);
+            TEST_LOG_DATA.take();
+            let mut accounts = transfer_hook_accounts(&config);
+            assert!(run_hook(&mut accounts, 100).is_err());
+            let data = TEST_RETURN_DATA.take();
+            let state = MarketState::try_from_slice(&data[..11]).unwrap();
+            let next_open = i64::from_le_bytes(data[11..].try_into().unwrap());
+            let event = TEST_LOG_DATA
+                .take()
+                .iter()
+                .find_map(|data| {
+                    let event = data.strip_prefix(TransferBlocked::DISCRIMINATOR)?;
+                    TransferBlocked::try_from_slice(event).ok()
+                })
+                .unwrap();
+            assert_eq!(event.next_open, next_open);
+            (state, next_open)
+        };
+
+        // Saturday 2024-01-20 15:00 EST, past Monday's pre-market start
+        let (state, next_open) = blocked(1_705_780_800);
+        assert_eq!(state.status, MarketStatus::Weekend);
+        assert_eq!(next_open, monday_open);
+        // Friday 2024-01-19 17:00 EST, after the close
+        let (state, next_open) = blocked(1_705_701_600);
+        assert_eq!(state.status, MarketStatus::AfterHours);
+        assert_eq!(next_open, monday_open);
+
+        // Saturday 2024-01-13 15:00 EST: Monday is MLK Day, so Tuesday
+        let (_, next_open) = blocked(1_705_176_000);
+        assert_eq!(next_open, 1_705_415_400);
+    }
+
+    #[test]
+    #[cfg(all(not(feature = "testnet-calendar"), feature = "allowlist"))]
+    fn pool_transfers_pass_off_hours_while_peer_trades_stay_blocked() {
+        install_test_runtime();
+        TEST_CLOCK.set(1_705_165_200); // Saturday 2024-01-13 12:00 EST
+        let mut config = config_with_hours(570, 960);
+        let run = |config: &MarketConfig, pool: Option<Pubkey>| {
+            let mut accounts = transfer_hook_accounts(config);
+            let destination_owner = Pubkey::try_from(&accounts[2].data[8 + 32..8 + 64]).unwrap();
+            let allowlist = Allowlist {
+                authority: Pubkey::new_unique(),
+                entries: vec![AllowlistEntry {
+                    wallet: pool.unwrap_or(destination_owner),
+                    direction: ExemptDirection::LiquidityPool,
+                }],
+                bump: stored_bump(&accounts, ALLOWLIST_SLOT),
+                programs: vec![],
+            };
+            let mut data = vec![0; 8 + Allowlist::INIT_SPACE];
+            allowlist.try_serialize(&mut &mut data[..]).unwrap();
+            let mut account =
+                TestAccount::new(accounts[ALLOWLIST_SLOT].key, crate::ID, 5_000, &data);
+            account.is_writable = true;
+            accounts[ALLOWLIST_SLOT] = account;
+            run_hook(&mut accounts, 100)
+        };
+
+        // Depositing into the pool's vault
+        assert_eq!(
+            run(&config, None).unwrap(),
+            Some(AllowReason::LiquidityOperation)
+        );
+        assert_eq!(AllowReason::LiquidityOperation as u8, 10);
+        // A direct trade between two wallets, with the pool uninvolved
+        assert_eq!(
+            run(&config, Some(Pubkey::new_unique())).unwrap_err(),
+            NyseError::MarketClosedWeekend.into()
+        );
+
+        // The tag isn't a plain exemption in either direction
+        let pool = Pubkey::new_unique();
+        assert!(!ExemptDirection::LiquidityPool.exempts_sending());
+        assert!(!ExemptDirection::LiquidityPool.exempts_receiving());
+        let allowlist = Allowlist {
+            authority: Pubkey::new_unique(),
+            entries: vec![AllowlistEntry {
+                wallet: pool,
+                direction: ExemptDirection::LiquidityPool,
+            }],
+            bump: 255,
+            programs: vec![],
+        };
+        let trader = Pubkey::new_unique();
+        assert!(!is_exempt(Some(&allowlist), &pool, &trader));
+        assert!(is_liquidity_operation(
+            Some(&config),
+            Some(&allowlist),
+            &pool,
+            &trader
+        ));
+
+        // Operators can hold pool transfers to the hours too
+        config.pool_transfers_follow_hours = true;
+        assert_eq!(
+            run(&config, None).unwrap_err(),
+            NyseError::MarketClosedWeekend.into()
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn corrupted_meta_list_fails_with_a_clear_error() {
+        install_test_runtime();
+        TEST_CLOCK.set(1_704_810_600); // Tuesday 2024-01-09 09:30 EST
+        let list = extra_account_meta_list_data().unwrap();
+        let with_list = |data: &[u8]| {
+            let mut accounts = transfer_hook_accounts(&config_with_hours(570, 960));
+            let mut account = TestAccount::new(accounts[4].key, crate::ID, 5_000, data);
+            account.is_writable = true;
+            accounts[4] = account;
+            accounts
+        };
+        assert!(run_hook(&mut with_list(&list), 100).is_ok());
+
+        // Truncated mid-entry, never initialized, a length prefix past the
+        // end of the account, and an entry of an unknown kind
+        let first_entry = 8 + 4 + 4;
+        let mut overlong = list.clone();
+        overlong[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+        let mut unknown = list.clone();
+        unknown[first_entry] = 7;
+        for data in [
+            list[..list.len() - 10].to_vec(),
+            vec![0; list.len()],
+            overlong,
+            unknown,
+        ] {
+            assert!(!meta_list_parses(&data));
+            assert_eq!(
+                run_hook(&mut with_list(&data), 100).unwrap_err(),
+                NyseError::CorruptedMetaList.into()
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(all(feature = "transition-events", not(feature = "testnet-calendar")))]
+    fn deduped_decision_events_are_full_once_per_status() {
+        install_test_runtime();
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        let run = |dedup_decision_events: bool, timestamps: &[i64]| {
+            let mut accounts = transfer_hook_accounts(&MarketConfig {
+                dedup_decision_events,
+                ..config_with_hours(570, 960)
+            });
+            let transition = MarketTransition {
+                is_open: false,
+                timestamp: 0,
+                last_decision_status: None,
+                bump: stored_bump(&accounts, MARKET_TRANSITION_SLOT),
+            };
+            let mut data = vec![0; 8 + MarketTransition::INIT_SPACE];
+            transition.try_serialize(&mut &mut data[..]).unwrap();
+            let mut account = TestAccount::new(
+                accounts[MARKET_TRANSITION_SLOT].key,
+                crate::ID,
+                5_000,
+                &data,
+            );
+            account.is_writable = true;
+            accounts[MARKET_TRANSITION_SLOT] = account;
+
+            // Full events and lightweight ones, in order
+            TEST_LOG_DATA.take();
+            for &timestamp in timestamps {
+                TEST_CLOCK.set(timestamp);
+                let _ = run_hook(&mut accounts, 100);
+            }
+            TEST_LOG_DATA
+                .take()
+                .iter()
+                .filter_map(|data| {
+                    if data.starts_with(TransferAllowed::DISCRIMINATOR) {
+                        Some("allowed")
+                    } else if data.starts_with(TransferBlocked::DISCRIMINATOR) {
+                        Some("blocked")
+                    } else if data.starts_with(TransferStatusUnchanged::DISCRIMINATOR) {
+                        Some("unchanged")
+                    } else {
+                        None
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let (at_10_30, at_17_00) = (open + 3600, open + 7 * 3600 + 1800);
+        let burst = [at_10_30, at_10_30 + 1, at_10_30 + 2, at_17_00, at_17_00 + 1];
+
+        // Each status gets its full event once. On chain a blocked
+        // transfer's write rolls back with it, which the harness doesn't do.
+        assert_eq!(
+            run(true, &burst),
+            ["allowed", "unchanged", "unchanged", "blocked", "unchanged"]
+        );
+        assert_eq!(
+            run(false, &burst),
+            ["allowed", "allowed", "allowed", "blocked", "blocked"]
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn soft_open_allows_and_flags_transfers_before_the_open() {
+        install_test_runtime();
+        TEST_LOG_DATA.take();
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        let mut config = config_with_hours(570, 960);
+        config.soft_open_minutes = 5;
+        let mut accounts = transfer_hook_accounts(&config);
+        let source_owner = accounts[3].key;
+        let flagged = || {
+            TEST_LOG_DATA
+                .take()
+                .iter()
+                .find_map(|data| data.strip_prefix(SoftOpenTransfer::DISCRIMINATOR))
+                .map(|body| SoftOpenTransfer::try_from_slice(body).unwrap())
+        };
+
+        // 9:28 is inside the five minutes before the open
+        TEST_CLOCK.set(open - 120);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap(),
+            Some(AllowReason::MarketOpen)
+        );
+        let event = flagged().unwrap();
+        assert_eq!(event.amount, 100);
+        assert_eq!(event.timestamp, open - 120);
+        assert_eq!(event.source_owner, source_owner);
+
+        // Before the window the pre-market is still blocked, and from the
+        // open on nothing is flagged
+        TEST_CLOCK.set(open - 301);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::MarketClosedPreMarket.into()
+        );
+        assert!(flagged().is_none());
+        TEST_CLOCK.set(open);
+        assert!(run_hook(&mut accounts, 100).is_ok());
+        assert!(flagged().is_none());
+    }
+
+    #[test]
+    #[cfg(all(not(feature = "testnet-calendar"), feature = "daily-limit"))]
+    fn daily_transfer_limit_resets_the_next_trading_day() {
+        install_test_runtime();
+        let tuesday = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        let mut config = config_with_hours(570, 960);
+        config.max_transfers_per_day = 2;
+        let mut accounts = transfer_hook_accounts(&config);
+
+        // An owner with one transfer already counted on Tuesday
+        let counter = DailyCounter {
+            trading_day: trading_day(tuesday, &config),
+            count: 1,
+            allowlist_volume: 0,
+            bump: stored_bump(&accounts, DAILY_COUNTER_SLOT),
+        };
+        let mut data = vec![0; 8 + DailyCounter::INIT_SPACE];
+        counter.try_serialize(&mut &mut data[..]).unwrap();
+        let mut account =
+            TestAccount::new(accounts[DAILY_COUNTER_SLOT].key, crate::ID, 5_000, &data);
+        account.is_writable = true;
+        accounts[DAILY_COUNTER_SLOT] = account;
+        let count = |accounts: &[TestAccount]| {
+            DailyCounter::try_deserialize(&mut &accounts[DAILY_COUNTER_SLOT].data[8..])
+                .unwrap()
+                .count
+        };
+
+        // Direct calls don't use up the owner's count
+        TEST_CLOCK.set(tuesday + 3600);
+        set_transferring(&mut accounts[0], false);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::NotTransferring.into()
+        );
+        assert_eq!(count(&accounts), 1);
+        set_transferring(&mut accounts[0], true);
+
+        assert!(run_hook(&mut accounts, 100).is_ok());
+        assert_eq!(count(&accounts), 2);
+        TEST_CLOCK.set(tuesday + 2 * 3600);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::DailyTransferLimitExceeded.into()
+        );
+        assert_eq!(count(&accounts), 2);
+
+        // Wednesday's open starts the count over
+        TEST_CLOCK.set(tuesday + 86_400);
+        assert!(run_hook(&mut accounts, 100).is_ok());
+        assert_eq!(count(&accounts), 1);
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn quiet_rejection_fails_with_one_code_and_no_event() {
+        install_test_runtime();
+        TEST_CLOCK.set(1_705_165_200); // Saturday 2024-01-13 12:00 EST
+        let blocked_events = || {
+            TEST_LOG_DATA
+                .take()
+                .iter()
+                .filter(|data| data.starts_with(TransferBlocked::DISCRIMINATOR))
+                .count()
+        };
+        let mut config = config_with_hours(570, 960);
+
+        TEST_LOG_DATA.take();
+        let mut accounts = transfer_hook_accounts(&config);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::MarketClosedWeekend.into()
+        );
+        assert_eq!(blocked_events(), 1);
+
+        // Every closed reason fails the same way; the transfer still fails
+        config.block_behavior = BlockBehavior::RejectQuietly;
+        let mut accounts = transfer_hook_accounts(&config);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::MarketClosedRejected.into()
+        );
+        assert_eq!(blocked_events(), 0);
+        TEST_CLOCK.set(1_704_810_600 - 3600); // Tuesday 08:30, pre-market
+        let mut accounts = transfer_hook_accounts(&config);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::MarketClosedRejected.into()
+        );
+
+        // Rules other than the hours keep their own errors, and WarnOnly
+        // still lets the transfer through
+        config.enforcement_mode = EnforcementMode::WarnOnly;
+        let state = get_nyse_market_state(1_705_165_200, Some(&config), None).unwrap();
+        assert!(enforce_closed(Some(&config), &state).is_ok());
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn dust_transfers_skip_the_checks_on_a_weekend() {
+        install_test_runtime();
+        TEST_CLOCK.set(1_705_165_200); // Saturday 2024-01-13 12:00 EST
+        let run = |config: &MarketConfig, amount| {
+            let mut accounts = transfer_hook_accounts(config);
+            run_hook(&mut accounts, amount)
+        };
+        let weekend = Error::from(NyseError::MarketClosedWeekend);
+
+        // Off by default
+        let mut config = config_with_hours(570, 960);
+        assert_eq!(run(&config, 99).unwrap_err(), weekend);
+
+        config.dust_exempt_below = 100;
+        assert_eq!(run(&config, 99).unwrap(), Some(AllowReason::DustExempt));
+        assert_eq!(run(&config, 1).unwrap(), Some(AllowReason::DustExempt));
+        assert_eq!(run(&config, 100).unwrap_err(), weekend);
+        assert_eq!(AllowReason::DustExempt as u8, 11);
+
+        // Ahead of every rule, a halt included
+        config.paused = true;
+        assert_eq!(run(&config, 99).unwrap(), Some(AllowReason::DustExempt));
+        config.paused = false;
+
+        // Zero amounts keep their own setting
+        config.allow_zero_amount = false;
+        assert_eq!(run(&config, 0).unwrap_err(), weekend);
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn burning_into_the_incinerator_works_on_a_weekend() {
+        use anchor_lang::solana_program::{incinerator, program_pack::Pack};
+        use anchor_spl::token_2022::spl_token_2022::state::Account;
+
+        install_test_runtime();
+        TEST_CLOCK.set(1_705_165_200); // Saturday 2024-01-13 12:00 EST
+        let run = |config: &MarketConfig| {
+            let mut accounts = transfer_hook_accounts(config);
+            let base = &mut accounts[2].data[8..8 + Account::LEN];
+            let mut destination = Account::unpack(base).unwrap();
+            destination.owner = incinerator::ID;
+            destination.pack_into_slice(base);
+            run_hook(&mut accounts, 100)
+        };
+
+        let mut config = config_with_hours(570, 960);
+        assert_eq!(run(&config).unwrap(), Some(AllowReason::Incinerator));
+        config.allow_incinerator = false;
+        assert_eq!(
+            run(&config).unwrap_err(),
+            NyseError::MarketClosedWeekend.into()
+        );
+
+        // The incinerator's own address as the token account counts too
+        assert!(is_incinerator_exempt(
+            None,
+            &incinerator::ID,
+            &Pubkey::new_unique()
+        ));
+        assert!(!is_incinerator_exempt(
+            None,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique()
+        ));
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn treasury_withdrawals_keep_their_own_narrow_window() {
+        use anchor_lang::solana_program::program_pack::Pack;
+        use anchor_spl::token_2022::spl_token_2022::state::Account;
+
+        install_test_runtime();
+        let treasury = Pubkey::new_unique();
+        let config = MarketConfig {
+            treasury,
+            // Deposits 07:00-18:00, withdrawals only 14:00-15:00
+            treasury_deposit_window: Some(SessionWindow {
+                open_minute: 420,
+                close_minute: 1080,
+            }),
+            treasury_withdraw_window: Some(SessionWindow {
+                open_minute: 840,
+                close_minute: 900,
+            }),
+            ..config_with_hours(570, 960)
+        };
+        // Transfer out of (slot 0) or into (slot 2) the treasury
+        let run = |slot: usize| {
+            let mut accounts = transfer_hook_accounts(&config);
+            let base = &mut accounts[slot].data[8..8 + Account::LEN];
+            let mut token = Account::unpack(base).unwrap();
+            token.owner = treasury;
+            token.pack_into_slice(base);
+            if slot == 0 {
+                accounts[3].key = treasury;
+            }
+            run_hook(&mut accounts, 100)
+        };
+        let (withdraw, deposit) = (0, 2);
+        let at_9_30 = 1_704_810_600; // Tuesday 2024-01-09 09:30:00 EST
+
+        // At 10:30 the market is open, but a withdrawal waits for 14:00
+        // while a deposit goes through
+        TEST_CLOCK.set(at_9_30 + 3600);
+        assert_eq!(
+            run(withdraw).unwrap_err(),
+            NyseError::MarketClosedPreMarket.into()
+        );
+        assert_eq!(run(deposit).unwrap(), Some(AllowReason::MarketOpen));
+
+        // The windows replace the market hours: deposits at 07:30, before
+        // the open, and withdrawals until 15:00 only
+        TEST_CLOCK.set(at_9_30 - 2 * 3600);
+        assert_eq!(run(deposit).unwrap(), Some(AllowReason::MarketOpen));
+        TEST_CLOCK.set(at_9_30 + 5 * 3600);
+        assert_eq!(run(withdraw).unwrap(), Some(AllowReason::MarketOpen));
+        TEST_CLOCK.set(at_9_30 + 5 * 3600 + 30 * 60);
+        assert_eq!(
+            run(withdraw).unwrap_err(),
+            NyseError::MarketClosedPostMarket.into()
+        );
+
+        // Weekends stay closed in either direction
+        TEST_CLOCK.set(at_9_30 + 4 * 86400 + 5 * 3600);
+        assert_eq!(
+            run(deposit).unwrap_err(),
+            NyseError::MarketClosedWeekend.into()
+        );
+
+        // Without a window, a direction follows the market hours
+        let state = get_nyse_market_state(at_9_30, Some(&config), None).unwrap();
+        let other = Pubkey::new_unique();
+        assert!(treasury_market_state(at_9_30, Some(&config), &state, &other, &other).is_none());
+        let deposits_only = MarketConfig {
+            treasury_withdraw_window: None,
+            ..config.clone()
+        };
+        assert!(
+            treasury_market_state(at_9_30, Some(&deposits_only), &state, &treasury, &other)
+                .is_none()
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn round_number_transfers_are_flagged_or_blocked() {
+        install_test_runtime();
+        TEST_CLOCK.set(1_704_810_600 + 3600); // Tuesday 2024-01-09 10:30 EST
+        let decimals = 6; // the test mint's
+        let tokens = |whole: u64| whole * 10u64.pow(decimals);
+        let run = |round_amount_policy, amount| {
+            let mut accounts = transfer_hook_accounts(&MarketConfig {
+                round_amount_threshold: 5_000,
+                round_amount_unit: 1_000,
+                round_amount_policy,
+                ..config_with_hours(570, 960)
+            });
+            TEST_LOG_DATA.take();
+            let result = run_hook(&mut accounts, amount);
+            let flagged = TEST_LOG_DATA
+                .take()
+                .iter()
+                .find_map(|data| data.strip_prefix(TransferAllowed::DISCRIMINATOR))
+                .map(|body| TransferAllowed::try_from_slice(body).unwrap().suspicious);
+            (result, flagged)
+        };
+
+        // Exactly 10,000.000000 tokens is above the threshold and round
+        let (allowed, flagged) = run(RoundAmountPolicy::Flag, tokens(10_000));
+        assert_eq!(allowed.unwrap(), Some(AllowReason::MarketOpen));
+        assert_eq!(flagged, Some(true));
+        let (blocked, flagged) = run(RoundAmountPolicy::Block, tokens(10_000));
+        assert_eq!(
+            blocked.unwrap_err(),
+            NyseError::SuspiciousRoundAmount.into()
+        );
+        assert_eq!(flagged, None);
+
+        // One base unit off, or round but not above the threshold, passes
+        for amount in [tokens(10_000) + 1, tokens(5_000), tokens(3_000)] {
+            let (allowed, flagged) = run(RoundAmountPolicy::Block, amount);
+            assert_eq!(allowed.unwrap(), Some(AllowReason::MarketOpen));
+            assert_eq!(flagged, Some(false));
+        }
+        // The units are whole tokens: 10,000 base units is 0.01 tokens
+        assert_eq!(run(RoundAmountPolicy::Block, 10_000).1, Some(false));
+
+        // A threshold or unit beyond a u64 at these decimals never matches
+        let config = MarketConfig {
+            round_amount_threshold: 1,
+            round_amount_unit: u64::MAX / 1_000,
+            ..config_with_hours(570, 960)
+        };
+        assert!(!is_suspicious_round_amount(
+            Some(&config),
+            decimals as u8,
+            u64::MAX
+        ));
+        assert!(!is_suspicious_round_amount(Some(&config), 30, u64::MAX));
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn no_op_transfers_short_circuit_with_a_hint() {
+        use anchor_lang::solana_program::program_pack::Pack;
+        use anchor_spl::token_2022::spl_token_2022::state::Account;
+
+        install_test_runtime();
+        TEST_CLOCK.set(1_705_165_200); // Saturday 2024-01-13 12:00 EST
+        let run = |config: &MarketConfig, balance, amount, commit| {
+            let mut accounts = transfer_hook_accounts(config);
+            let base = &mut accounts[0].data[8..8 + Account::LEN];
+            let mut source = Account::unpack(base).unwrap();
+            source.amount = balance;
+            source.pack_into_slice(base);
+            let infos = account_infos(&mut accounts);
+            let mut bumps = TransferHookBumps::default();
+            let hook = TransferHook::try_accounts(
+                &crate::ID,
+                &mut &infos[..],
+                &[],
+                &mut bumps,
+                &mut std::collections::BTreeSet::new(),
+            )
+            .unwrap();
+            run_transfer_checks(&hook, amount, commit)
+        };
+        let weekend = Error::from(NyseError::MarketClosedWeekend);
+
+        // Off by default: an empty source gets the market-hours verdict
+        let mut config = config_with_hours(570, 960);
+        assert_eq!(run(&config, 0, 100, false).unwrap_err(), weekend);
+
+        config.short_circuit_no_ops = true;
+        assert_eq!(
+            run(&config, 0, 100, false).unwrap(),
+            Some(AllowReason::NoOp)
+        );
+        assert_eq!(
+            run(&config, 1_000, 0, true).unwrap(),
+            Some(AllowReason::NoOp)
+        );
+        assert_eq!(run(&config, 1_000, 100, false).unwrap_err(), weekend);
+        // In the hook the balance is already debited: sending the whole
+        // balance isn't a no-op
+        assert_eq!(run(&config, 0, 100, true).unwrap_err(), weekend);
+
+        assert_eq!(no_op_hint(Some(&config), 0, 0), Some(NoOpHint::ZeroAmount));
+        assert_eq!(
+            no_op_hint(Some(&config), 100, 0),
+            Some(NoOpHint::EmptySource)
+        );
+        assert_eq!(NoOpHint::EmptySource.try_to_vec().unwrap(), [1]);
+        assert_eq!(no_op_hint(None, 0, 0), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn pre_open_staging_through_the_hook() {
+        // A 9:25 AM transfer into the staging account passes while one to
+        // any other owner is blocked
+        let config = MarketConfig {
+            pre_open_staging_minutes: 10,
+            ..config_with_hours(570, 960)
+        };
+        let other = Pubkey::new_unique();
+        install_test_runtime();
+        TEST_CLOCK.set(1_704_810_300); // Tuesday 2024-01-09 09:25:00 EST
+        let mut accounts = transfer_hook_accounts(&config);
+        let destination_owner = Pubkey::try_from(&accounts[2].data[8 + 32..8 + 64]).unwrap();
+        let store = |accounts: &mut [TestAccount], staging_account| {
+            let config = MarketConfig {
+                staging_account,
+                ..config.clone()
+            };
+            store_config(accounts, &config);
+        };
+        store(&mut accounts, destination_owner);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap(),
+            Some(AllowReason::PreOpenStaging)
+        );
+        store(&mut accounts, other);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::MarketClosedPreMarket.into()
+        );
+    }
+
+    #[test]
+    fn same_owner_transfer_follows_the_config() {
+        let mut config = config_with_hours(570, 960);
+        let (owner, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        // Allowed by default, and always without a config
+        assert!(check_self_transfer(Some(&config), &owner, &owner).is_ok());
+        assert!(check_self_transfer(None, &owner, &owner).is_ok());
+
+        config.allow_self_transfer = false;
+        assert_eq!(
+            check_self_transfer(Some(&config), &owner, &owner).unwrap_err(),
+            NyseError::SelfTransferBlocked.into()
+        );
+        assert!(check_self_transfer(Some(&config), &owner, &other).is_ok());
+    }
+
+    #[test]
+    fn listing_window_bounds_transfers() {
+        let mut config = config_with_hours(570, 960);
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30:00 EST
+        assert!(check_listing(Some(&config), open).is_ok());
+        assert!(check_listing(None, open).is_ok());
+
+        // Lists at the open, delists a week later at the same minute
+        config.listing_timestamp = open;
+        config.delisting_timestamp = open + 7 * 86400;
+        assert_eq!(
+            check_listing(Some(&config), open - 1).unwrap_err(),
+            NyseError::NotYetListed.into()
+        );
+        assert!(check_listing(Some(&config), open).is_ok());
+        assert!(check_listing(Some(&config), open + 7 * 86400 - 1).is_ok());
+        assert_eq!(
+            check_listing(Some(&config), open + 7 * 86400).unwrap_err(),
+            NyseError::Delisted.into()
+        );
+        // Even though the market is open then
+        assert!(
+            get_nyse_market_state(open + 7 * 86400, Some(&config), None)
+                .unwrap()
+                .is_open
+        );
+
+        // Zero leaves the delisting side open
+        config.delisting_timestamp = 0;
+        assert!(check_listing(Some(&config), i64::MAX).is_ok());
+    }
+
+    #[test]
+    fn maintenance_window_blocks_transfers() {
+        let mut config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30:00 EST
+        assert!(check_maintenance(Some(&config), open).is_ok());
+
+        // 10:00-10:30 during the regular session
+        config.maintenance_start = open + 1800;
+        config.maintenance_end = open + 3600;
+        let inside = open + 2400;
+        assert!(
+            get_nyse_market_state(inside, Some(&config), None)
+                .unwrap()
+                .is_open
+        );
+        assert_eq!(
+            check_maintenance(Some(&config), inside).unwrap_err(),
+            NyseError::MaintenanceWindow.into()
+        );
+        assert_eq!(
+            check_maintenance(Some(&config), open + 1800).unwrap_err(),
+            NyseError::MaintenanceWindow.into()
+        );
+        assert!(check_maintenance(Some(&config), open + 1799).is_ok());
+        assert!(check_maintenance(Some(&config), open + 3600).is_ok());
+        assert!(check_maintenance(None, inside).is_ok());
+    }
+
+    #[test]
+    fn transfer_minimum_floor() {
+        let mut config = config_with_hours(570, 960);
+        // No floor by default, even for zero-amount transfers
+        assert!(check_transfer_minimum(Some(&config), 0).is_ok());
+        assert!(check_transfer_minimum(None, 0).is_ok());
+
+        config.min_transfer_amount = 1_000;
+        assert_eq!(
+            check_transfer_minimum(Some(&config), 999).unwrap_err(),
+            NyseError::TransferBelowMinimum.into()
+        );
+        assert!(check_transfer_minimum(Some(&config), 1_000).is_ok());
+        assert!(check_transfer_minimum(Some(&config), 1_001).is_ok());
+    }
+
+    #[test]
+    fn scheduled_early_close_override() {
+        let mut config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        config.early_close_override = Some(EarlyCloseOverride {
+            date: HolidayDate {
+                year: 2024,
+                month: 1,
+                day: 9,
+            },
+            close_minute: 12 * 60,
+        });
+        // Tuesday 2024-01-09, EST (UTC-5)
+        let at = |hour: i64, minute: i64| 1_704_758_400 + (hour + 5) * 3600 + minute * 60;
+        let verdict = |timestamp: i64| {
+            let state = get_nyse_market_state(timestamp, Some(&config), None).unwrap();
+            (state.is_open, state.reason)
+        };
+
+        assert_eq!(
+            verdict(at(11, 59)),
+            (true, MarketReason::RegularTradingHours)
+        );
+        assert_eq!(verdict(at(12, 0)), (false, MarketReason::EarlyClose));
+        assert_eq!(verdict(at(15, 0)), (false, MarketReason::EarlyClose));
+        // The following Tuesday is a normal session again
+        assert_eq!(
+            verdict(at(15, 0) + 7 * 86_400),
+            (true, MarketReason::RegularTradingHours)
+        );
+    }
+
+    #[test]
+    fn config_authority_handoff() {
+        let mut config = config_with_hours(570, 960);
+        let original = config.authority;
+        let successor = Pubkey::new_unique();
+
+        // Nobody can accept before a proposal
+        assert_eq!(
+            config.accept_authority(&successor).unwrap_err(),
+            NyseError::Unauthorized.into()
+        );
+
+        config.pending_authority = Some(successor);
+        // The wrong key (including the current authority) can't accept
+        for wrong in [Pubkey::new_unique(), original] {
+            assert_eq!(
+                config.accept_authority(&wrong).unwrap_err(),
+                NyseError::Unauthorized.into()
+            );
+        }
+        assert_eq!(config.authority, original);
+
+        assert!(config.accept_authority(&successor).is_ok());
+        assert_eq!(config.authority, successor);
+        assert_eq!(config.pending_authority, None);
+    }
+
+    #[test]
+    fn soft_open_reports_open_before_the_bell() {
+        let mut config = config_with_hours(570, 960);
+        config.soft_open_minutes = 5;
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        let state = |timestamp| get_nyse_market_state(timestamp, Some(&config), None).unwrap();
+
+        let early = state(open - 120);
+        assert!(early.is_open);
+        assert_eq!(early.status, MarketStatus::Open);
+        assert_eq!(early.reason, MarketReason::SoftPreOpen);
+        assert_eq!(early.next_transition, open);
+        let window_start = state(open - 300);
+        assert_eq!(window_start.reason, MarketReason::SoftPreOpen);
+        let before = state(open - 301);
+        assert_eq!(
+            (before.is_open, before.reason),
+            (false, MarketReason::PreMarket)
+        );
+        assert_eq!(before.next_transition, open - 300);
+        assert_eq!(state(open).reason, MarketReason::RegularTradingHours);
+
+        // Weekends stay closed
+        let saturday = open + 4 * 86_400;
+        assert_eq!(state(saturday - 120).reason, MarketReason::Weekend);
+    }
+
+    #[test]
+    fn grace_period_after_the_close() {
+        let mut config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        config.open_grace_seconds = 60;
+        config.close_grace_seconds = 60;
+        let close = 1_704_834_000; // Tuesday 2024-01-09 16:00:00 EST
+        let open = close - 390 * 60;
+        let verdict = |timestamp: i64| {
+            let state = get_nyse_market_state(timestamp, Some(&config), None).unwrap();
+            (state.is_open, state.reason)
+        };
+
+        assert_eq!(verdict(close + 30), (true, MarketReason::GracePeriod));
+        assert_eq!(verdict(close + 60), (false, MarketReason::AfterMarket));
+        assert_eq!(verdict(open - 60), (true, MarketReason::GracePeriod));
+        assert_eq!(verdict(open - 61), (false, MarketReason::PreMarket));
+        assert_eq!(verdict(open), (true, MarketReason::RegularTradingHours));
+
+        // No grace reaches into the weekend or a holiday (MLK Day)
+        assert_eq!(
+            get_nyse_market_state(open + 4 * 86_400 - 30, Some(&config), None)
+                .unwrap()
+                .status,
+            MarketStatus::Weekend
+        );
+        assert_eq!(
+            get_nyse_market_state(open + 6 * 86_400 - 30, Some(&config), None)
+                .unwrap()
+                .status,
+            MarketStatus::Holiday
+        );
+    }
+
+    #[test]
+    fn boundaries_at_930_and_30_seconds_by_precision() {
+        // Tuesday 2024-01-09 09:30:30 EST
+        let half_past = 1_704_810_630;
+        let mut config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        let state =
+            |config: &MarketConfig| get_nyse_market_state(half_past, Some(config), None).unwrap();
+
+        // With the open on a whole minute both precisions agree
+        for precision in [BoundaryPrecision::Second, BoundaryPrecision::Minute] {
+            config.boundary_precision = precision;
+            assert!(state(&config).is_open);
+        }
+
+        // A 9:31 open with 45 seconds of grace starts the grace at 09:30:15,
+        // which only the second precision sees by 09:30:30
+        config.open_minute = 9 * 60 + 31;
+        config.open_grace_seconds = 45;
+        config.boundary_precision = BoundaryPrecision::Second;
+        assert_eq!(state(&config).reason, MarketReason::GracePeriod);
+        config.boundary_precision = BoundaryPrecision::Minute;
+        let truncated = state(&config);
+        assert_eq!(truncated.reason, MarketReason::PreMarket);
+        // and the next change it reports is the whole minute it shows up at
+        assert_eq!(truncated.next_transition, half_past + 30);
+    }
+
+    #[test]
+    fn fixed_offset_skips_summer_time() {
+        // Tuesday 2024-07-09 13:45 UTC
+        let july = 1_720_532_700;
+        let mut config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        let observed = get_local_time_info(july, &config.timezone);
+        assert!(observed.is_dst);
+        assert_eq!((observed.hour, observed.minute), (9, 45));
+
+        // Arizona-style: EST's offset all year, no DST rule
+        config.timezone = TimezoneConfig::new(-5 * 3600, None);
+        assert!(validate_timezone(&config.timezone).is_ok());
+        let fixed = get_local_time_info(july, &config.timezone);
+        assert!(!fixed.is_dst);
+        assert_eq!((fixed.hour, fixed.minute), (8, 45));
+        assert_eq!(fixed.utc_offset_seconds, observed.utc_offset_seconds - 3600);
+
+        // An hour earlier on the wall clock, so the 9:30 open hasn't come yet
+        let state = get_nyse_market_state(july, Some(&config), None).unwrap();
+        assert_eq!(state.reason, MarketReason::PreMarket);
+        config.timezone = TimezoneConfig::US_EASTERN;
+        assert!(
+            get_nyse_market_state(july, Some(&config), None)
+                .unwrap()
+                .is_open
+        );
+    }
+
+    #[test]
+    fn sunday_to_thursday_trading_week() {
+        // 10:00-15:00 at UTC+3, trading Sunday through Thursday
+        let mut config = config_with_hours(10 * 60, 15 * 60);
+        config.timezone = TimezoneConfig::new(3 * 3600, None);
+        config.trading_days_bitmask = 0b0001_1111;
+        // Noon local on Friday 2024-01-12 plus `days`
+        let noon = |days: i64| 1_705_006_800 + days * 86_400 + 12 * 3600;
+        let status = |config: &MarketConfig, timestamp: i64| {
+            get_nyse_market_state(timestamp, Some(config), None)
+                .unwrap()
+                .status
+        };
+
+        assert_eq!(status(&config, noon(0)), MarketStatus::Weekend); // Friday
+        assert_eq!(status(&config, noon(1)), MarketStatus::Weekend); // Saturday
+        assert_eq!(status(&config, noon(2)), MarketStatus::Open); // Sunday
+        assert_eq!(status(&config, noon(6)), MarketStatus::Open); // Thursday
+
+        // An unset bitmask falls back to Monday-Friday
+        config.trading_days_bitmask = 0;
+        assert_eq!(status(&config, noon(0)), MarketStatus::Open);
+        assert_eq!(status(&config, noon(2)), MarketStatus::Weekend);
+    }
+
+    #[test]
+    fn lunch_break_between_sessions() {
+        // Tokyo: 09:00-11:30 and 12:30-15:00 JST (UTC+9, no DST)
+        let mut config = config_with_hours(9 * 60, 15 * 60);
+        config.timezone = TimezoneConfig::new(9 * 3600, None);
+        config.sessions = vec![
+            SessionWindow {
+                open_minute: 9 * 60,
+                close_minute: 11 * 60 + 30,
+            },
+            SessionWindow {
+                open_minute: 12 * 60 + 30,
+                close_minute: 15 * 60,
+            },
+        ];
+        assert!(validate_sessions(&config.sessions).is_ok());
+        // Tuesday 2024-01-09 JST
+        let at = |hour: i64, minute: i64| 1_704_726_000 + hour * 3600 + minute * 60;
+        let verdict = |timestamp: i64| {
+            let state = get_nyse_market_state(timestamp, Some(&config), None).unwrap();
+            (state.is_open, state.reason)
+        };
+
+        assert_eq!(verdict(at(8, 59)), (false, MarketReason::PreMarket));
+        assert_eq!(verdict(at(9, 0)), (true, MarketReason::RegularTradingHours));
+        assert_eq!(
+            verdict(at(11, 29)),
+            (true, MarketReason::RegularTradingHours)
+        );
+        assert_eq!(verdict(at(11, 30)), (false, MarketReason::LunchBreak));
+        assert_eq!(verdict(at(12, 29)), (false, MarketReason::LunchBreak));
+        assert_eq!(
+            verdict(at(12, 30)),
+            (true, MarketReason::RegularTradingHours)
+        );
+        assert_eq!(verdict(at(15, 0)), (false, MarketReason::AfterMarket));
+    }
+
+    #[test]
+    fn session_validation() {
+        let window = |open_minute: u16, close_minute: u16| SessionWindow {
+            open_minute,
+            close_minute,
+        };
+        assert!(validate_sessions(&[window(570, 960)]).is_ok());
+        assert!(validate_sessions(&[]).is_err());
+        // Sessions don't wrap past midnight
+        assert!(validate_sessions(&[window(20 * 60, 4 * 60)]).is_err());
+        // Overlapping or out of order
+        assert!(validate_sessions(&[window(540, 700), window(690, 900)]).is_err());
+        assert!(validate_sessions(&[window(750, 900), window(540, 690)]).is_err());
+        assert!(validate_sessions(&[window(0, 60); MAX_SESSIONS + 1]).is_err());
+    }
+
+    #[test]
+    fn custom_market_hours_from_config() {
+        let config = config_with_hours(10 * 60, 15 * 60);
+        // Tuesday 2024-01-09, EST (UTC-5)
+        let at = |hour: i64, minute: i64| 1_704_758_400 + (hour + 5) * 3600 + minute * 60;
+
+        assert!(
+            get_nyse_market_state(at(9, 45), None, None)
+                .unwrap()
+                .is_open
+        );
+        let before = get_nyse_market_state(at(9, 45), Some(&config), None).unwrap();
+        assert_eq!(before.reason, MarketReason::PreMarket);
+        assert!(
+            get_nyse_market_state(at(10, 0), Some(&config), None)
+                .unwrap()
+                .is_open
+        );
+        assert!(
+            get_nyse_market_state(at(14, 59), Some(&config), None)
+                .unwrap()
+                .is_open
+        );
+        let after = get_nyse_market_state(at(15, 0), Some(&config), None).unwrap();
+        assert_eq!(after.reason, MarketReason::AfterMarket);
+        assert!(
+            get_nyse_market_state(at(15, 30), None, None)
+                .unwrap()
+                .is_open
+        );
+    }
+
+    #[test]
+    fn halt_blocks_during_regular_hours() {
+        let mut config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        // Tuesday 2024-01-09 12:00 PM EST, inside the session
+        let noon = 1_704_758_400 + 17 * 3600;
+
+        config.paused = true;
+        assert_eq!(
+            ensure_not_halted(Some(&config)).unwrap_err(),
+            NyseError::MarketHalted.into()
+        );
+
+        config.paused = false;
+        assert!(ensure_not_halted(Some(&config)).is_ok());
+        assert!(
+            get_nyse_market_state(noon, Some(&config), None)
+                .unwrap()
+                .is_open
+        );
+        assert!(ensure_not_halted(None).is_ok());
+    }
+
+    #[test]
+    fn closure_message_round_trips_through_the_event() {
+        let mut config = config_with_hours(570, 960);
+        config.paused = true;
+        config.closure_message = closure_message_bytes("Closed: system-wide outage").unwrap();
+
+        let event = TransferBlocked::new(5, 1_704_826_800, &MarketState::HALTED)
+            .with_closure_message(closure_message(Some(&config)));
+        let decoded = TransferBlocked::try_from_slice(&event.try_to_vec().unwrap()).unwrap();
+        let text = std::str::from_utf8(&decoded.closure_message)
+            .unwrap()
+            .trim_end_matches('\0');
+        assert_eq!(text, "Closed: system-wide outage");
+        assert_eq!((decoded.status, decoded.reason_code), (4, 6));
+
+        // 64 bytes fit exactly; one more is refused
+        assert!(closure_message_bytes(&"x".repeat(CLOSURE_MESSAGE_LEN)).is_ok());
+        assert_eq!(
+            closure_message_bytes(&"x".repeat(CLOSURE_MESSAGE_LEN + 1)).unwrap_err(),
+            NyseError::ClosureMessageTooLong.into()
+        );
+        assert_eq!(closure_message(None), [0; CLOSURE_MESSAGE_LEN]);
+    }
+
+    #[test]
+    fn transfer_events_map_market_state_codes() {
+        // Sunday 2024-01-07 12:00 PM EST
+        let sunday = 1_704_585_600 + 17 * 3600;
+        let state = get_nyse_market_state(sunday, None, None).unwrap();
+        let blocked = TransferBlocked::new(42, sunday, &state);
+        assert_eq!((blocked.amount, blocked.timestamp), (42, sunday));
+        assert_eq!((blocked.status, blocked.reason_code), (1, 1));
+
+        let halted = TransferBlocked::new(1, sunday, &MarketState::HALTED);
+        assert_eq!((halted.status, halted.reason_code), (4, 6));
+        assert_eq!(halted.closure_message, [0; CLOSURE_MESSAGE_LEN]);
+
+        let tuesday = 1_704_758_400 + 17 * 3600;
+        let open = TransferAllowed::new(
+            7,
+            tuesday,
+            &get_nyse_market_state(tuesday, None, None).unwrap(),
+            AllowReason::MarketOpen,
+        );
+        assert_eq!((open.status, open.reason_code), (0, 0));
+    }
+
+    #[test]
+    fn burn_by_mint_authority_is_exempt_on_holiday() {
+        let issuer = Pubkey::new_unique();
+        let holder = Pubkey::new_unique();
+        // Christmas 2024, 12:00 PM EST
+        let christmas = 1_735_084_800 + 17 * 3600;
+        let state = get_nyse_market_state(christmas, None, None).unwrap();
+        assert_eq!(state.status, MarketStatus::Holiday);
+
+        // Holder redeeming tokens back to the issuer for burning
+        assert!(is_mint_burn(Some(&issuer), &holder, &issuer, &holder));
+        // Issuer distributing freshly minted tokens
+        assert!(is_mint_burn(Some(&issuer), &issuer, &holder, &issuer));
+        assert!(!is_mint_burn(Some(&issuer), &holder, &holder, &holder));
+        // Fixed-supply mints have no authority to exempt
+        assert!(!is_mint_burn(None, &holder, &issuer, &holder));
+    }
+
+    #[test]
+    fn denylisted_source_is_blocked_during_open_hours() {
+        let sanctioned = Pubkey::new_unique();
+        let trader = Pubkey::new_unique();
+        let denylist = Denylist {
+            authority: Pubkey::new_unique(),
+            entries: vec![sanctioned],
+            bump: 255,
+        };
+        // Tuesday 2024-01-09 10:00 AM EST
+        let tuesday = 1_704_758_400 + 15 * 3600;
+        assert!(get_nyse_market_state(tuesday, None, None).unwrap().is_open);
+
+        assert!(is_denied(Some(&denylist), &sanctioned, &trader));
+        assert!(is_denied(Some(&denylist), &trader, &sanctioned));
+        assert!(!is_denied(Some(&denylist), &trader, &trader));
+        assert!(!is_denied(None, &sanctioned, &trader));
+    }
+
+    #[test]
+    fn allowlisted_wallet_is_exempt_on_weekend() {
+        let treasury = Pubkey::new_unique();
+        let trader = Pubkey::new_unique();
+        let allowlist = Allowlist {
+            authority: Pubkey::new_unique(),
+            entries: vec![both_ways(treasury)],
+            bump: 255,
+            programs: vec![],
+        };
+        // Saturday 2024-01-06 12:00 PM EST
+        let saturday = 1_704_499_200 + 17 * 3600;
+        assert!(!get_nyse_market_state(saturday, None, None).unwrap().is_open);
+
+        assert!(is_exempt(Some(&allowlist), &treasury, &trader));
+        assert!(is_exempt(Some(&allowlist), &trader, &treasury));
+        assert!(!is_exempt(Some(&allowlist), &trader, &trader));
+        assert!(!is_exempt(None, &treasury, &trader));
+    }
+
+    #[test]
+    fn pda_of_an_allowlisted_program_sends_off_hours() {
+        let escrow_program = Pubkey::new_unique();
+        let other_program = Pubkey::new_unique();
+        let allowlist = Allowlist {
+            authority: Pubkey::new_unique(),
+            entries: vec![],
+            bump: 255,
+            programs: vec![escrow_program],
+        };
+        let vault = Pubkey::find_program_address(&[b"vault"], &escrow_program).0;
+        // The ed25519 base point: a key someone can sign for
+        let wallet = Pubkey::new_from_array([
+            0x58, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+            0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66, 0x66,
+            0x66, 0x66, 0x66, 0x66,
+        ]);
+        assert!(wallet.is_on_curve());
+        let exempt = |key: &Pubkey, owner: &Pubkey, source_owner: &Pubkey| {
+            let (mut lamports, mut data) = (1, vec![0u8; 8]);
+            let info =
+                AccountInfo::new(key, false, false, &mut lamports, &mut data, owner, false, 0);
+            is_protocol_account(Some(&allowlist), &info, source_owner)
+        };
+
+        assert!(exempt(&vault, &escrow_program, &vault));
+        // Another program's account, a signing key assigned to the program,
+        // and a delegate signing for the vault don't qualify
+        assert!(!exempt(&vault, &other_program, &vault));
+        assert!(!exempt(&wallet, &escrow_program, &wallet));
+        let delegate = Pubkey::find_program_address(&[b"delegate"], &escrow_program).0;
+        assert!(!exempt(&delegate, &escrow_program, &vault));
+        let unlisted = Allowlist {
+            programs: vec![],
+            ..allowlist.clone()
+        };
+        let (mut lamports, mut data) = (1, vec![0u8; 8]);
+        let info = AccountInfo::new(
+            &vault,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &escrow_program,
+            false,
+            0,
+        );
+        assert!(!is_protocol_account(Some(&unlisted), &info, &vault));
+        assert!(!is_protocol_account(None, &info, &vault));
+        assert_eq!(AllowReason::ProtocolAccount as u8, 8);
+
+        // A list written before `programs` existed ends in zeroed space
+        let mut old = vec![0u8; 8 + Allowlist::INIT_SPACE];
+        unlisted.try_serialize(&mut &mut old[..]).unwrap();
+        let used = 8 + 32 + 4 + 1;
+        old[used..].fill(0);
+        assert_eq!(
+            Allowlist::try_deserialize(&mut &old[..]).unwrap().programs,
+            vec![]
+        );
+    }
+
+    #[test]
+    fn receive_exempt_wallet_accepts_but_does_not_send_off_hours() {
+        let vault = Pubkey::new_unique();
+        let desk = Pubkey::new_unique();
+        let trader = Pubkey::new_unique();
+        let allowlist = Allowlist {
+            authority: Pubkey::new_unique(),
+            entries: vec![
+                AllowlistEntry {
+                    wallet: vault,
+                    direction: ExemptDirection::ReceiveExempt,
+                },
+                AllowlistEntry {
+                    wallet: desk,
+                    direction: ExemptDirection::SendExempt,
+                },
+            ],
+            bump: 255,
+            programs: vec![],
+        };
+
+        // The custody vault takes deposits at any hour but only pays out in
+        // market hours; the desk is the other way round
+        assert!(is_exempt(Some(&allowlist), &trader, &vault));
+        assert!(!is_exempt(Some(&allowlist), &vault, &trader));
+        assert!(is_exempt(Some(&allowlist), &desk, &trader));
+        assert!(!is_exempt(Some(&allowlist), &trader, &desk));
+        assert!(is_exempt(Some(&allowlist), &desk, &vault));
+        assert!(!is_exempt(Some(&allowlist), &vault, &desk));
+    }
+
+    #[test]
+    fn zero_amount_on_weekend_follows_the_config() {
+        // Saturday 2024-01-06 12:00 PM EST
+        let saturday = 1_704_499_200 + 17 * 3600;
+        let mut config = config_with_hours(570, 960);
+        assert!(
+            !get_nyse_market_state(saturday, Some(&config), None)
+                .unwrap()
+                .is_open
+        );
+
+        assert!(is_zero_amount_exempt(Some(&config), 0));
+        assert!(is_zero_amount_exempt(None, 0));
+        assert!(!is_zero_amount_exempt(Some(&config), 1));
+
+        config.allow_zero_amount = false;
+        assert!(!is_zero_amount_exempt(Some(&config), 0));
+    }
+
+    #[test]
+    fn next_holiday_from_mid_december() {
+        // Sunday 2024-12-15 12:00 PM EST
+        assert_eq!(next_holiday_after(1_734_282_000), (2024, 12, 25));
+        // Thursday 2024-12-26 12:00 PM EST
+        assert_eq!(next_holiday_after(1_735_232_400), (2025, 1, 1));
+        // 2024-12-25 04:59 UTC is still Christmas Eve in New York
+        assert_eq!(next_holiday_after(1_735_102_740), (2024, 12, 25));
+
+        // 2021: Christmas falls on a Saturday and is observed Friday the 24th
+        assert_eq!(next_holiday_after(1_639_587_600), (2021, 12, 24));
+        // New Year's Day 2022 is a Saturday with no Friday observance, so
+        // the next closure after Christmas is MLK Day
+        assert_eq!(next_holiday_after(1_640_451_600), (2022, 1, 17));
+    }
+
+    #[test]
+    fn eu_summer_time_transitions() {
+        // 2024: last Sunday of March is the 31st, of October the 27th
+        let start = 1_711_846_800; // 2024-03-31 01:00 UTC
+        let end = 1_729_990_800; // 2024-10-27 01:00 UTC
+        assert!(!is_eu_summer_time(start - 1));
+        assert!(is_eu_summer_time(start));
+        assert!(is_eu_summer_time(end - 1));
+        assert!(!is_eu_summer_time(end));
+
+        let frankfurt = get_local_time_info(start, &TimezoneConfig::FRANKFURT);
+        assert_eq!(
+            (frankfurt.hour, frankfurt.utc_offset_seconds),
+            (3, 2 * 3600)
+        );
+    }
+
+    #[test]
+    fn london_configured_mint_uses_london_hours() {
+        let mut config = config_with_hours(8 * 60, 16 * 60 + 30);
+        config.timezone = TimezoneConfig::LONDON;
+        let is_open = |ts: i64| {
+            get_nyse_market_state(ts, Some(&config), None)
+                .unwrap()
+                .is_open
+        };
+
+        // Tuesday 2024-01-09, GMT (UTC+0)
+        let winter = |hour: i64, minute: i64| 1_704_758_400 + hour * 3600 + minute * 60;
+        assert!(!is_open(winter(7, 59)));
+        assert!(is_open(winter(8, 0)));
+        assert!(is_open(winter(16, 29)));
+        assert!(!is_open(winter(16, 30)));
+        // 17:00 UTC is noon in New York, but London has already closed
+        assert!(!is_open(winter(17, 0)));
+
+        // Tuesday 2024-07-09, BST (UTC+1)
+        let summer = |hour: i64, minute: i64| 1_720_483_200 + (hour - 1) * 3600 + minute * 60;
+        assert!(!is_open(summer(7, 59)));
+        assert!(is_open(summer(8, 0)));
+        assert!(is_open(summer(16, 29)));
+        assert!(!is_open(summer(16, 30)));
+    }
+
+    #[test]
+    fn timezone_offset_validation() {
+        assert!(validate_timezone(&TimezoneConfig::FRANKFURT).is_ok());
+        let bogus = TimezoneConfig::new(15 * 3600, None);
+        assert!(validate_timezone(&bogus).is_err());
+    }
+
+    #[test]
+    fn unknown_dst_ruleset_fails_safe() {
+        // A ruleset id from newer tooling, on an otherwise valid config
+        let mut config = config_with_hours(570, 960);
+        config.timezone.dst_ruleset = 9;
+        let mut data = vec![0; 8 + MarketConfig::INIT_SPACE];
+        config.try_serialize(&mut &mut data[..]).unwrap();
+        let loaded = MarketConfig::try_deserialize(&mut &data[..]).unwrap();
+        assert_eq!(loaded.timezone.dst_ruleset, 9);
+
+        let unsupported = Error::from(NyseError::UnsupportedTimezoneConfig);
+        assert_eq!(loaded.timezone.dst_rule().unwrap_err(), unsupported);
+        assert_eq!(
+            validate_timezone(&loaded.timezone).unwrap_err(),
+            unsupported
+        );
+        let tuesday_open = 1_704_810_600;
+        assert_eq!(
+            get_nyse_market_state(tuesday_open, Some(&loaded), None).unwrap_err(),
+            unsupported
+        );
+
+        for timezone in [TimezoneConfig::US_EASTERN, TimezoneConfig::LONDON] {
+            let rule = timezone.dst_rule().unwrap().unwrap();
+            assert_eq!(timezone.dst_ruleset, rule.ruleset_id());
+        }
+    }
+
+    #[test]
+    fn extended_hours_mode_opens_pre_market_and_after_hours() {
+        let mut config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        // Tuesday 2024-01-09, EST (UTC-5)
+        let at = |hour: i64, minute: i64| 1_704_758_400 + (hour + 5) * 3600 + minute * 60;
+
+        let regular_only = get_nyse_market_state(at(4, 30), Some(&config), None).unwrap();
+        assert!(!regular_only.is_open);
+        assert_eq!(regular_only.status, MarketStatus::AfterHours);
+
+        config.extended_hours = ExtendedHoursMode::ExtendedHours;
+        let pre_market = get_nyse_market_state(at(4, 30), Some(&config), None).unwrap();
+        assert!(pre_market.is_open);
+        assert_eq!(pre_market.status, MarketStatus::Extended);
+        assert_eq!(pre_market.reason, MarketReason::PreMarket);
+
+        let after_hours = get_nyse_market_state(at(19, 59), Some(&config), None).unwrap();
+        assert!(after_hours.is_open);
+        assert_eq!(after_hours.reason, MarketReason::AfterMarket);
+
+        // Overnight stays closed
+        assert!(
+            !get_nyse_market_state(at(3, 59), Some(&config), None)
+                .unwrap()
+                .is_open
+        );
+        assert!(
+            !get_nyse_market_state(at(20, 0), Some(&config), None)
+                .unwrap()
+                .is_open
+        );
+    }
+
+    #[test]
+    fn observance_mode_picks_which_dates_a_weekend_holiday_closes() {
+        // Independence Day 2026 is a Saturday, observed on Friday July 3
+        let mut config = config_with_hours(570, 960);
+        let closed = |config: &MarketConfig| {
+            (2..=6)
+                .filter(|&day| is_market_holiday(Some(config), None, 2026, 7, day))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(config.observance_mode, ObservanceMode::ObservedOnly);
+        assert_eq!(closed(&config), [3]);
+        assert!(!is_market_holiday(None, None, 2026, 7, 4));
+
+        config.observance_mode = ObservanceMode::ActualAndObserved;
+        assert_eq!(closed(&config), [3, 4]);
+
+        // Which shows on markets that trade on Saturdays
+        config.trading_days_bitmask = 0b1111110;
+        let saturday_noon = 1_783_180_800; // 2026-07-04 12:00 EDT
+        let state = get_nyse_market_state(saturday_noon, Some(&config), None).unwrap();
+        assert_eq!(state.status, MarketStatus::Holiday);
+        config.observance_mode = ObservanceMode::ObservedOnly;
+        let state = get_nyse_market_state(saturday_noon, Some(&config), None).unwrap();
+        assert_eq!(state.status, MarketStatus::Open);
+    }
+
+    #[test]
+    fn calendar_closure_blocks_regular_session() {
+        let mut config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        let mut calendar = HolidayCalendar {
+            authority: Pubkey::new_unique(),
+            dates: Vec::new(),
+            early_closes: Vec::new(),
+            bump: 255,
+        };
+        // Tuesday 2024-01-09 12:00 PM EST
+        let noon = 1_704_758_400 + 17 * 3600;
+        let state = |config: &MarketConfig, calendar: &HolidayCalendar| {
+            get_nyse_market_state(noon, Some(config), Some(calendar)).unwrap()
+        };
+        assert!(state(&config, &calendar).is_open);
+
+        // One-off closure added after the fact
+        calendar.dates.push(HolidayDate {
+            year: 2024,
+            month: 1,
+            day: 9,
+        });
+        let closed = state(&config, &calendar);
+        assert!(!closed.is_open);
+        assert_eq!(closed.status, MarketStatus::Holiday);
+
+        // Ignored when the mint sticks to the built-in rules
+        config.holiday_mode = HolidayMode::BuiltIn;
+        assert!(state(&config, &calendar).is_open);
+
+        // CalendarOnly drops the built-in holidays: MLK Day 2024 trades
+        config.holiday_mode = HolidayMode::CalendarOnly;
+        let mlk_day = 1_705_276_800 + 17 * 3600;
+        assert!(
+            get_nyse_market_state(mlk_day, Some(&config), Some(&calendar))
+                .unwrap()
+                .is_open
+        );
+    }
+
+    #[test]
+    fn reason_codes_are_stable() {
+        // The codes documented in the README's return data table
+        let codes = [
+            (MarketReason::RegularTradingHours, 0),
+            (MarketReason::Weekend, 1),
+            (MarketReason::Holiday, 2),
+            (MarketReason::PreMarket, 3),
+            (MarketReason::AfterMarket, 4),
+            (MarketReason::EarlyClose, 5),
+            (MarketReason::Halted, 6),
+            (MarketReason::LunchBreak, 7),
+            (MarketReason::GracePeriod, 8),
+            (MarketReason::OpeningAuction, 9),
+            (MarketReason::ClosingAuction, 10),
+            (MarketReason::SoftPreOpen, 11),
+        ];
+        for (reason, code) in codes {
+            assert_eq!(reason as u8, code, "{:?}", reason);
+            assert_eq!(reason.try_to_vec().unwrap(), [code]);
+        }
+
+        // Each default-hours state reports its code in the return data
+        let reason_byte = |timestamp| {
+            get_nyse_market_state(timestamp, None, None)
+                .unwrap()
+                .try_to_vec()
+                .unwrap()[2]
+        };
+        let states = [
+            (1_704_812_400, 0), // Tuesday 2024-01-09 10:00 AM EST
+            (1_705_165_200, 1), // Saturday 2024-01-13 noon
+            (1_705_338_000, 2), // Martin Luther King Jr. Day 2024 noon
+            (1_704_805_200, 3), // Tuesday 8:00 AM
+            (1_704_837_600, 4), // Tuesday 5:00 PM
+            (1_720_029_600, 5), // Wednesday 2024-07-03 2:00 PM EDT
+        ];
+        for (timestamp, code) in states {
+            assert_eq!(reason_byte(timestamp), code, "at {}", timestamp);
+        }
+        assert_eq!(MarketState::HALTED.reason as u8, 6);
+    }
+
+    #[test]
+    fn error_codes_are_stable() {
+        for (error, code) in [
+            (NyseError::MarketClosedWeekend, 6000),
+            (NyseError::MarketClosedHoliday, 6001),
+            (NyseError::MarketClosedPreMarket, 6002),
+            (NyseError::InvalidTimestamp, 6003),
+            (NyseError::Unauthorized, 6004),
+            (NyseError::InvalidMarketHours, 6005),
+            (NyseError::MarketHalted, 6006),
+            (NyseError::AllowlistFull, 6007),
+            (NyseError::AlreadyAllowlisted, 6008),
+            (NyseError::NotAllowlisted, 6009),
+            (NyseError::InvalidTimezone, 6010),
+            (NyseError::HolidayCalendarFull, 6011),
+            (NyseError::HolidayAlreadyListed, 6012),
+            (NyseError::HolidayNotListed, 6013),
+            (NyseError::InvalidDate, 6014),
+            (NyseError::TransferExceedsLimit, 6015),
+            (NyseError::AddressBlocked, 6016),
+            (NyseError::DenylistFull, 6017),
+            (NyseError::AlreadyDenylisted, 6018),
+            (NyseError::NotDenylisted, 6019),
+            (NyseError::VolumeLimitExceeded, 6020),
+            (NyseError::RentReserveDepleted, 6021),
+            (NyseError::MarketClosedPostMarket, 6022),
+            (NyseError::InvalidTradingDays, 6023),
+            (NyseError::TransferBelowMinimum, 6024),
+            (NyseError::MaintenanceWindow, 6025),
+            (NyseError::ExtraAccountMetaListMismatch, 6026),
+            (NyseError::TimeOverrideDisabled, 6027),
+            (NyseError::LargeTradeRequiresApproval, 6028),
+            (NyseError::HookStillActive, 6029),
+            (NyseError::ClosureMessageTooLong, 6030),
+            (NyseError::AuctionWindow, 6031),
+            (NyseError::UnsupportedHookInstruction, 6032),
+            (NyseError::TransferCooldown, 6033),
+            (NyseError::ConfigMissing, 6034),
+            (NyseError::MintMismatch, 6035),
+            (NyseError::StaleConfigVersion, 6036),
+            (NyseError::DestinationTooNew, 6037),
+            (NyseError::CircuitBreakerTripped, 6038),
+            (NyseError::HookNotActivated, 6039),
+            (NyseError::SelfTransferBlocked, 6040),
+            (NyseError::NotYetListed, 6041),
+            (NyseError::Delisted, 6042),
+            (NyseError::GloballyFrozen, 6043),
+            (NyseError::MalformedHookInstruction, 6044),
+            (NyseError::WarmupLimitExceeded, 6045),
+            (NyseError::SnapshotTooSoon, 6046),
+            (NyseError::UnsupportedTimezoneConfig, 6047),
+            (NyseError::DailyTransferLimitExceeded, 6048),
+            (NyseError::CorruptedMetaList, 6049),
+            (NyseError::ClosingCrossBlocked, 6050),
+            (NyseError::StatusBatchTooLarge, 6051),
+            (NyseError::MarketClosedRejected, 6052),
+            (NyseError::AllowlistCapExceeded, 6053),
+            (NyseError::PostHaltCooldown, 6054),
+            (NyseError::OpeningDelayed, 6055),
+            (NyseError::FeatureDisabled, 6056),
+            (NyseError::NotTransferring, 6057),
+            (NyseError::SuspiciousRoundAmount, 6058),
+        ] {
+            assert_eq!(u32::from(error), code, "{error:?}");
+        }
+    }
+
+    #[test]
+    fn registered_extra_accounts_resolve_to_transfer_hook_pdas() {
+        let metas = extra_account_metas().unwrap();
+        let mut list = vec![0u8; ExtraAccountMetaList::size_of(metas.len()).unwrap()];
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut list, &metas).unwrap();
+
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let (source, destination) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pda = |seeds: &[&[u8]]| Pubkey::find_program_address(seeds, &crate::ID).0;
+        let per_mint = |prefix: &[u8]| pda(&[prefix, mint.as_ref()]);
+
+        // The accounts TransferHook expects, in field order, with its `mut` flags
+        let mut accounts = vec![
+            (source, false), // source_token
+            (mint, false),
+            (destination, false),
+            (owner, false),
+            (per_mint(b"extra-account-metas"), false),
+            (per_mint(b"market-config"), true),
+        ];
+        #[cfg(feature = "allowlist")]
+        accounts.push((per_mint(b"allowlist"), false));
+        #[cfg(feature = "holiday-calendar")]
+        accounts.push((per_mint(b"holiday-calendar"), false));
+        #[cfg(feature = "state-cache")]
+        accounts.push((per_mint(b"market-state-cache"), true));
+        #[cfg(feature = "denylist")]
+        accounts.push((per_mint(b"denylist"), false));
+        #[cfg(feature = "rent-reserve")]
+        accounts.push((per_mint(b"rent-reserve"), true));
+        #[cfg(feature = "volume-tracking")]
+        accounts.push((pda(&[b"volume", mint.as_ref(), owner.as_ref()]), true));
+        if OWNER_PDAS {
+            accounts.push((System::id(), false));
+        }
+        #[cfg(feature = "transition-events")]
+        accounts.push((per_mint(b"market-transition"), true));
+        #[cfg(feature = "hook-stats")]
+        accounts.push((per_mint(b"hook-stats"), true));
+        accounts.push((sysvar::instructions::ID, false));
+        #[cfg(feature = "cooldown")]
+        accounts.push((
+            pda(&[b"last-transfer", mint.as_ref(), owner.as_ref()]),
+            true,
+        ));
+        accounts.push((pda(&[b"program-settings"]), false));
+        #[cfg(feature = "destination-age")]
+        accounts.push((
+            pda(&[b"account-age", mint.as_ref(), destination.as_ref()]),
+            false,
+        ));
+        #[cfg(feature = "circuit-breaker")]
+        accounts.push((per_mint(b"circuit-breaker"), true));
+        #[cfg(feature = "transfer-vouchers")]
+        accounts.push((
+            pda(&[
+                b"transfer-voucher",
+                mint.as_ref(),
+                source.as_ref(),
+                destination.as_ref(),
+            ]),
+            true,
+        ));
+        #[cfg(feature = "daily-counter")]
+        accounts.push((
+            pda(&[b"daily-counter", mint.as_ref(), owner.as_ref()]),
+            true,
+        ));
+        // The source token account's owner lives at bytes 32..64
+        let mut source_data = vec![0u8; 165];
+        source_data[32..64].copy_from_slice(owner.as_ref());
+        let mut data: Vec<Vec<u8>> = vec![Vec::new(); accounts.len()];
+        data[0] = source_data;
+        let mut lamports = vec![0u64; accounts.len()];
+        let infos: Vec<AccountInfo> = accounts
+            .iter()
+            .zip(lamports.iter_mut())
+            .zip(data.iter_mut())
+            .map(|(((key, is_writable), lamports), data)| {
+                AccountInfo::new(
+                    key,
+                    false,
+                    *is_writable,
+                    lamports,
+                    data,
+                    &crate::ID,
+                    false,
+                    0,
+                )
+            })
+            .collect();
+
+        let execute = TransferHookInstruction::Execute { amount: 1 }.pack();
+        assert!(
+            ExtraAccountMetaList::check_account_infos::<ExecuteInstruction>(
+                &infos,
+                &execute,
+                &crate::ID,
+                &list,
+            )
+            .is_ok()
+        );
+
+        // A list registered before later PDAs existed no longer matches
+        let mut stale = vec![0u8; ExtraAccountMetaList::size_of(2).unwrap()];
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut stale, &metas[..2]).unwrap();
+        assert!(
+            ExtraAccountMetaList::check_account_infos::<ExecuteInstruction>(
+                &infos,
+                &execute,
+                &crate::ID,
+                &stale,
+            )
+            .is_err()
+        );
+    }
+
+    // An account for `invoke_entry`; like the runtime's input buffer, `data`
+    // is prefixed with its length, which `AccountInfo::realloc` rewrites
+    struct TestAccount {
+        key: Pubkey,
+        owner: Pubkey,
+        lamports: u64,
+        data: Vec<u8>,
+        is_signer: bool,
+        is_writable: bool,
+        executable: bool,
+    }
+
+    impl TestAccount {
+        fn new(key: Pubkey, owner: Pubkey, lamports: u64, data: &[u8]) -> Self {
+            Self {
+                key,
+                owner,
+                lamports,
+                data: [&(data.len() as u64).to_le_bytes()[..], data].concat(),
+                is_signer: false,
+                is_writable: false,
+                executable: false,
+            }
+        }
+    }
+
+    fn account_infos(accounts: &mut [TestAccount]) -> Vec<AccountInfo<'_>> {
+        accounts
+            .iter_mut()
+            .map(|account| {
+                AccountInfo::new(
+                    &account.key,
+                    account.is_signer,
+                    account.is_writable,
+                    &mut account.lamports,
+                    &mut account.data[8..],
+                    &account.owner,
+                    account.executable,
+                    0,
+                )
+            })
+            .collect()
+    }
+
+    fn invoke_entry(
+        instruction: impl anchor_lang::InstructionData,
+        accounts: &mut [TestAccount],
+    ) -> std::result::Result<(), ProgramError> {
+        entry(&crate::ID, &account_infos(accounts), &instruction.data())
+    }
+
+    // Dispatch check_market_state at `timestamp` for a mint with `config`
+    // (or none) and no calendar, and decode what it writes
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn check_market_state_at(
+        timestamp: i64,
+        config: Option<&MarketConfig>,
+    ) -> (MarketState, MarketCountdown) {
+        install_test_runtime();
+        TEST_CLOCK.set(timestamp);
+        let mint = Pubkey::new_unique();
+        // Anchor reads the program id in an optional account's slot as None
+        let absent = || TestAccount::new(crate::ID, Pubkey::default(), 0, &[]);
+        let market_config = config.map_or_else(absent, |config| {
+            let mut data = vec![0; 8 + MarketConfig::INIT_SPACE];
+            config.try_serialize(&mut &mut data[..]).unwrap();
+            TestAccount::new(market_config_pda(&mint).0, crate::ID, 1, &data)
+        });
+        let mut accounts = [
+            TestAccount::new(mint, System::id(), 0, &[]),
+            market_config,
+            absent(),
+        ];
+        invoke_entry(instruction::CheckMarketState {}, &mut accounts).unwrap();
+
+        let data = TEST_RETURN_DATA.take();
+        let mut payload = &data[..];
+        let state = MarketState::deserialize(&mut payload).unwrap();
+        let countdown = MarketCountdown::deserialize(&mut payload).unwrap();
+        assert!(payload.is_empty());
+        (state, countdown)
+    }
+
+    // Whether this build creates per-owner PDAs, and so takes the system
+    // program
+    const OWNER_PDAS: bool = cfg!(any(
+        feature = "volume-tracking",
+        feature = "cooldown",
+        feature = "daily-counter"
+    ));
+
+    // Where `transfer_hook_accounts` puts each TransferHook account. A build
+    // leaves out the accounts of the subsystems it doesn't include, which
+    // moves up the ones after them.
+    const fn after(slot: usize, present: bool) -> usize {
+        slot + present as usize
+    }
+    const MARKET_CONFIG_SLOT: usize = 5;
+    const ALLOWLIST_SLOT: usize = 6;
+    const HOLIDAY_CALENDAR_SLOT: usize = after(ALLOWLIST_SLOT, cfg!(feature = "allowlist"));
+    const MARKET_STATE_CACHE_SLOT: usize =
+        after(HOLIDAY_CALENDAR_SLOT, cfg!(feature = "holiday-calendar"));
+    const DENYLIST_SLOT: usize = after(MARKET_STATE_CACHE_SLOT, cfg!(feature = "state-cache"));
+    const RENT_RESERVE_SLOT: usize = after(DENYLIST_SLOT, cfg!(feature = "denylist"));
+    const VOLUME_TRACKER_SLOT: usize = after(RENT_RESERVE_SLOT, cfg!(feature = "rent-reserve"));
+    const SYSTEM_PROGRAM_SLOT: usize =
+        after(VOLUME_TRACKER_SLOT, cfg!(feature = "volume-tracking"));
+    const MARKET_TRANSITION_SLOT: usize = after(SYSTEM_PROGRAM_SLOT, OWNER_PDAS);
+    const HOOK_STATS_SLOT: usize =
+        after(MARKET_TRANSITION_SLOT, cfg!(feature = "transition-events"));
+    const INSTRUCTIONS_SYSVAR_SLOT: usize = after(HOOK_STATS_SLOT, cfg!(feature = "hook-stats"));
+    const LAST_TRANSFER_SLOT: usize = INSTRUCTIONS_SYSVAR_SLOT + 1;
+    const PROGRAM_SETTINGS_SLOT: usize = after(LAST_TRANSFER_SLOT, cfg!(feature = "cooldown"));
+    const DESTINATION_AGE_SLOT: usize = PROGRAM_SETTINGS_SLOT + 1;
+    const CIRCUIT_BREAKER_SLOT: usize =
+        after(DESTINATION_AGE_SLOT, cfg!(feature = "destination-age"));
+    const TRANSFER_VOUCHER_SLOT: usize =
+        after(CIRCUIT_BREAKER_SLOT, cfg!(feature = "circuit-breaker"));
+    const DAILY_COUNTER_SLOT: usize =
+        after(TRANSFER_VOUCHER_SLOT, cfg!(feature = "transfer-vouchers"));
+
+    // Accounts of a transfer between two wallets of a fresh mint whose
+    // MarketConfig is `config`, with every other optional PDA left empty
+    fn transfer_hook_accounts(config: &MarketConfig) -> Vec<TestAccount> {
+        use anchor_spl::token_2022::spl_token_2022::extension::{
+            transfer_hook::TransferHookAccount, BaseStateWithExtensionsMut, ExtensionType,
+            StateWithExtensionsMut,
+        };
+        use anchor_spl::token_2022::spl_token_2022::state::{Account, AccountState};
+
+        let mint = Pubkey::new_unique();
+        let (source_owner, destination_owner) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (source, destination) = (Pubkey::new_unique(), Pubkey::new_unique());
+        // Flagged as mid-transfer, as Token-2022 leaves them during the hook
+        let token_account = |key, owner| {
+            let len = ExtensionType::try_calculate_account_len::<Account>(&[
+                ExtensionType::TransferHookAccount,
+            ])
+            .unwrap();
+            let mut data = vec![0; len];
+            let mut state =
+                StateWithExtensionsMut::<Account>::unpack_uninitialized(&mut data).unwrap();
+            let extension = state.init_extension::<TransferHookAccount>(true).unwrap();
+            extension.transferring = true.into();
+            state.base = Account {
+                mint,
+                owner,
+                amount: 1_000,
+                state: AccountState::Initialized,
+                ..Default::default()
+            };
+            state.pack_base();
+            state.init_account_type().unwrap();
+            TestAccount::new(key, anchor_spl::token_2022::ID, 1, &data)
+        };
+        let empty = |seeds: &[&[u8]]| {
+            let key = Pubkey::find_program_address(seeds, &crate::ID).0;
+            TestAccount::new(key, System::id(), 0, &[])
+        };
+        let (config_key, config_bump) = market_config_pda(&mint);
+        let mut config_data = vec![0; 8 + MarketConfig::INIT_SPACE];
+        let config = MarketConfig {
+            bump: config_bump,
+            ..config.clone()
+        };
+        config.try_serialize(&mut &mut config_data[..]).unwrap();
+
+        let mut accounts = vec![
+            token_account(source, source_owner),
+            TestAccount::new(
+                mint,
+                anchor_spl::token_2022::ID,
+                1,
+                &hook_mint(Pubkey::new_unique(), Some(crate::ID)),
+            ),
+            token_account(destination, destination_owner),
+            TestAccount::new(source_owner, System::id(), 0, &[]),
+            empty(&[b"extra-account-metas", mint.as_ref()]),
+            TestAccount::new(config_key, crate::ID, 1, &config_data),
+        ];
+        #[cfg(feature = "allowlist")]
+        accounts.push(empty(&[b"allowlist", mint.as_ref()]));
+        #[cfg(feature = "holiday-calendar")]
+        accounts.push(empty(&[b"holiday-calendar", mint.as_ref()]));
+        #[cfg(feature = "state-cache")]
+        accounts.push(empty(&[b"market-state-cache", mint.as_ref()]));
+        #[cfg(feature = "denylist")]
+        accounts.push(empty(&[b"denylist", mint.as_ref()]));
+        #[cfg(feature = "rent-reserve")]
+        accounts.push(empty(&[b"rent-reserve", mint.as_ref()]));
+        #[cfg(feature = "volume-tracking")]
+        accounts.push(empty(&[b"volume", mint.as_ref(), source_owner.as_ref()]));
+        if OWNER_PDAS {
+            let mut system_program = TestAccount::new(System::id(), Pubkey::default(), 1, &[]);
+            system_program.executable = true;
+            accounts.push(system_program);
+        }
+        #[cfg(feature = "transition-events")]
+        accounts.push(empty(&[b"market-transition", mint.as_ref()]));
+        #[cfg(feature = "hook-stats")]
+        accounts.push(empty(&[b"hook-stats", mint.as_ref()]));
+        accounts.push(TestAccount::new(
+            sysvar::instructions::ID,
+            Pubkey::default(),
+            0,
+            &[],
+        ));
+        #[cfg(feature = "cooldown")]
+        accounts.push(empty(&[
+            b"last-transfer",
+            mint.as_ref(),
+            source_owner.as_ref(),
+        ]));
+        accounts.push(empty(&[b"program-settings"]));
+        #[cfg(feature = "destination-age")]
+        accounts.push(empty(&[
+            b"account-age",
+            mint.as_ref(),
+            destination.as_ref(),
+        ]));
+        #[cfg(feature = "circuit-breaker")]
+        accounts.push(empty(&[b"circuit-breaker", mint.as_ref()]));
+        #[cfg(feature = "transfer-vouchers")]
+        accounts.push(empty(&[
+            b"transfer-voucher",
+            mint.as_ref(),
+            source.as_ref(),
+            destination.as_ref(),
+        ]));
+        #[cfg(feature = "daily-counter")]
+        accounts.push(empty(&[
+            b"daily-counter",
+            mint.as_ref(),
+            source_owner.as_ref(),
+        ]));
+        for account in &mut accounts {
+            account.is_writable = true;
+        }
+        accounts
+    }
+
+    // The bump of the PDA `transfer_hook_accounts` put in `slot`, for the
+    // accounts tests create there
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn stored_bump(accounts: &[TestAccount], slot: usize) -> u8 {
+        let mint = accounts[1].key;
+        let (source, destination) = (accounts[0].key, accounts[2].key);
+        let owner = accounts[3].key;
+        [
+            market_config_pda(&mint),
+            allowlist_pda(&mint),
+            holiday_calendar_pda(&mint),
+            market_state_cache_pda(&mint),
+            denylist_pda(&mint),
+            rent_reserve_pda(&mint),
+            volume_tracker_pda(&mint, &owner),
+            market_transition_pda(&mint),
+            hook_stats_pda(&mint),
+            last_transfer_pda(&mint, &owner),
+            program_settings_pda(),
+            account_age_pda(&mint, &destination),
+            circuit_breaker_pda(&mint),
+            transfer_voucher_pda(&mint, &source, &destination),
+            daily_counter_pda(&mint, &owner),
+        ]
+        .into_iter()
+        .find_map(|(address, bump)| (address == accounts[slot].key).then_some(bump))
+        .expect("a PDA slot")
+    }
+
+    // Overwrite the config `transfer_hook_accounts` set up, keeping the bump
+    // it was stored with
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn store_config(accounts: &mut [TestAccount], config: &MarketConfig) {
+        let data = &mut accounts[MARKET_CONFIG_SLOT].data[8..];
+        let bump = MarketConfig::try_deserialize(&mut &data[..]).unwrap().bump;
+        MarketConfig {
+            bump,
+            ..config.clone()
+        }
+        .try_serialize(&mut &mut data[..])
+        .unwrap();
+    }
+
+    #[test]
+    fn pda_helpers_match_the_account_constraints() {
+        let mut accounts = transfer_hook_accounts(&config_with_hours(570, 960));
+        let mint = accounts[1].key;
+        let (source, destination) = (accounts[0].key, accounts[2].key);
+        let source_owner = accounts[3].key;
+        let expected = [
+            (true, 4, extra_account_meta_list_pda(&mint)),
+            (true, MARKET_CONFIG_SLOT, market_config_pda(&mint)),
+            (
+                cfg!(feature = "allowlist"),
+                ALLOWLIST_SLOT,
+                allowlist_pda(&mint),
+            ),
+            (
+                cfg!(feature = "holiday-calendar"),
+                HOLIDAY_CALENDAR_SLOT,
+                holiday_calendar_pda(&mint),
+            ),
+            (
+                cfg!(feature = "state-cache"),
+                MARKET_STATE_CACHE_SLOT,
+                market_state_cache_pda(&mint),
+            ),
+            (
+                cfg!(feature = "denylist"),
+                DENYLIST_SLOT,
+                denylist_pda(&mint),
+            ),
+            (
+                cfg!(feature = "rent-reserve"),
+                RENT_RESERVE_SLOT,
+                rent_reserve_pda(&mint),
+            ),
+            (
+                cfg!(feature = "volume-tracking"),
+                VOLUME_TRACKER_SLOT,
+                volume_tracker_pda(&mint, &source_owner),
+            ),
+            (
+                cfg!(feature = "transition-events"),
+                MARKET_TRANSITION_SLOT,
+                market_transition_pda(&mint),
+            ),
+            (
+                cfg!(feature = "hook-stats"),
+                HOOK_STATS_SLOT,
+                hook_stats_pda(&mint),
+            ),
+            (
+                cfg!(feature = "cooldown"),
+                LAST_TRANSFER_SLOT,
+                last_transfer_pda(&mint, &source_owner),
+            ),
+            (true, PROGRAM_SETTINGS_SLOT, program_settings_pda()),
+            (
+                cfg!(feature = "destination-age"),
+                DESTINATION_AGE_SLOT,
+                account_age_pda(&mint, &destination),
+            ),
+            (
+                cfg!(feature = "circuit-breaker"),
+                CIRCUIT_BREAKER_SLOT,
+                circuit_breaker_pda(&mint),
+            ),
+            (
+                cfg!(feature = "transfer-vouchers"),
+                TRANSFER_VOUCHER_SLOT,
+                transfer_voucher_pda(&mint, &source, &destination),
+            ),
+            (
+                cfg!(feature = "daily-counter"),
+                DAILY_COUNTER_SLOT,
+                daily_counter_pda(&mint, &source_owner),
+            ),
+        ];
+        for (built_in, slot, (address, _)) in expected {
+            if built_in {
+                assert_eq!(accounts[slot].key, address, "account {slot}");
+            }
+        }
+        assert_eq!(
+            accounts.len(),
+            5 + extra_account_metas().unwrap().len(),
+            "one account per registered meta"
+        );
+
+        // The address the seeds constraint accepts
+        let infos = account_infos(&mut accounts);
+        let mut bumps = TransferHookBumps::default();
+        TransferHook::try_accounts(
+            &crate::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut std::collections::BTreeSet::new(),
+        )
+        .unwrap();
+        assert_eq!(bumps.extra_account_meta_list, expected[0].2 .1);
+    }
+
+    // Raise or clear the flag Token-2022 sets on a token account while it
+    // runs the hook; cleared, the hook's accounts are those of a direct call
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn set_transferring(account: &mut TestAccount, transferring: bool) {
+        use anchor_spl::token_2022::spl_token_2022::extension::{
+            transfer_hook::TransferHookAccount, BaseStateWithExtensionsMut, StateWithExtensionsMut,
+        };
+        use anchor_spl::token_2022::spl_token_2022::state::Account;
+
+        let mut state = StateWithExtensionsMut::<Account>::unpack(&mut account.data[8..]).unwrap();
+        let extension = state.get_extension_mut::<TransferHookAccount>().unwrap();
+        extension.transferring = transferring.into();
+    }
+
+    // The hook's checks over `accounts`, as `transfer_hook` runs them
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn run_hook(accounts: &mut [TestAccount], amount: u64) -> Result<Option<AllowReason>> {
+        let infos = account_infos(accounts);
+        let mut bumps = TransferHookBumps::default();
+        let hook = TransferHook::try_accounts(
+            &crate::ID,
+            &mut &infos[..],
+            &[],
+            &mut bumps,
+            &mut std::collections::BTreeSet::new(),
+        )?;
+        run_transfer_checks(&hook, amount, true)
+    }
+
+    // Stand-in for the runtime's syscalls, shared because the stubs are
+    // process-wide: a settable clock, and emitted events and return data
+    // kept per test thread so tests running in parallel only see their own
+    thread_local! {
+        static TEST_CLOCK: std::cell::Cell<i64> = const { std::cell::Cell::new(0) };
+        static TEST_SLOT: std::cell::Cell<u64> = const { std::cell::Cell::new(0) };
+        static TEST_LOG_DATA: std::cell::RefCell<Vec<Vec<u8>>> =
+            const { std::cell::RefCell::new(Vec::new()) };
+        static TEST_RETURN_DATA: std::cell::RefCell<Vec<u8>> =
+            const { std::cell::RefCell::new(Vec::new()) };
+    }
+
+    struct TestRuntime;
+
+    impl anchor_lang::solana_program::program_stubs::SyscallStubs for TestRuntime {
+        fn sol_log_data(&self, fields: &[&[u8]]) {
+            TEST_LOG_DATA.with_borrow_mut(|data| data.extend(fields.iter().map(|f| f.to_vec())));
+        }
+
+        fn sol_set_return_data(&self, data: &[u8]) {
+            TEST_RETURN_DATA.set(data.to_vec());
+        }
+
+        fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+            let clock = Clock {
+                slot: TEST_SLOT.get(),
+                unix_timestamp: TEST_CLOCK.get(),
+                ..Clock::default()
+            };
+            unsafe { *(var_addr as *mut Clock) = clock };
+            0
+        }
+    }
+
+    fn install_test_runtime() {
+        static INSTALL: std::sync::Once = std::sync::Once::new();
+        INSTALL.call_once(|| {
+            anchor_lang::solana_program::program_stubs::set_syscall_stubs(Box::new(TestRuntime));
+        });
+    }
+
+    // Run ensure_extra_account_meta_list against a list account already
+    // owned by this program
+    fn ensure_existing_list(list: &mut Vec<u8>) -> std::result::Result<(), ProgramError> {
+        let mint = Pubkey::new_unique();
+        let list_key =
+            Pubkey::find_program_address(&[b"extra-account-metas", mint.as_ref()], &crate::ID).0;
+        let mut payer = TestAccount::new(Pubkey::new_unique(), System::id(), 1_000_000_000, &[]);
+        payer.is_signer = true;
+        payer.is_writable = true;
+        let mut list_account = TestAccount::new(list_key, crate::ID, 1_000_000, list);
+        list_account.is_writable = true;
+        let mut system_program = TestAccount::new(System::id(), Pubkey::default(), 1, &[]);
+        system_program.executable = true;
+
+        let mut accounts = [
+            payer,
+            list_account,
+            TestAccount::new(mint, System::id(), 0, &[]),
+            system_program,
+        ];
+        let result = invoke_entry(instruction::EnsureExtraAccountMetaList {}, &mut accounts);
+        *list = accounts[1].data[8..].to_vec();
+        result
+    }
+
+    #[test]
+    fn fallback_rejects_interface_initializer() {
+        let data = TransferHookInstruction::InitializeExtraAccountMetaList {
+            extra_account_metas: Vec::new(),
+        }
+        .pack();
+        assert_eq!(
+            entry(&crate::ID, &[], &data).unwrap_err(),
+            ProgramError::Custom(NyseError::UnsupportedHookInstruction.into())
+        );
+    }
+
+    #[test]
+    fn fallback_reports_malformed_instructions() {
+        let malformed = ProgramError::Custom(NyseError::MalformedHookInstruction.into());
+
+        // Neither an Anchor discriminator nor a transfer-hook interface one
+        let unknown = [0xde, 0xad, 0xbe, 0xef, 0, 0, 0, 0, 1, 2, 3];
+        assert_eq!(entry(&crate::ID, &[], &unknown).unwrap_err(), malformed);
+        assert!(entry(&crate::ID, &[], &[]).is_err());
+
+        // An Execute cut short anywhere in its amount
+        let execute = TransferHookInstruction::Execute { amount: 100 }.pack();
+        for length in 8..execute.len() {
+            assert_eq!(
+                entry(&crate::ID, &[], &execute[..length]).unwrap_err(),
+                malformed
+            );
+        }
+
+        // An interface initializer whose metas don't parse
+        let mut initialize = TransferHookInstruction::InitializeExtraAccountMetaList {
+            extra_account_metas: Vec::new(),
+        }
+        .pack();
+        initialize.extend_from_slice(&[0xff; 3]);
+        assert_eq!(
+            decode_hook_instruction(&initialize).unwrap_err(),
+            NyseError::MalformedHookInstruction.into()
+        );
+        assert_eq!(
+            decode_hook_instruction(&execute).unwrap(),
+            TransferHookInstruction::Execute { amount: 100 }
+        );
+    }
+
+    #[test]
+    fn hours_change_emits_config_updated() {
+        install_test_runtime();
+        TEST_LOG_DATA.take();
+
+        let authority = Pubkey::new_unique();
+        let mut config_data = vec![0; 8 + MarketConfig::INIT_SPACE];
+        MarketConfig {
+            authority,
+            ..config_with_hours(570, 960)
+        }
+        .try_serialize(&mut &mut config_data[..])
+        .unwrap();
+        let mut signer = TestAccount::new(authority, System::id(), 0, &[]);
+        signer.is_signer = true;
+        let mut config = TestAccount::new(Pubkey::new_unique(), crate::ID, 1, &config_data);
+        config.is_writable = true;
+        let config_key = config.key;
+        let update = instruction::UpdateMarketHours {
+            expected_version: 0,
+            open_minute: 600,
+            close_minute: 900,
+        };
+        invoke_entry(update, &mut [signer, config]).unwrap();
+
+        let event = TEST_LOG_DATA
+            .take()
+            .iter()
+            .find_map(|data| data.strip_prefix(ConfigUpdated::DISCRIMINATOR))
+            .map(|body| ConfigUpdated::try_from_slice(body).unwrap())
+            .unwrap();
+        assert_eq!(event.market_config, config_key);
+        assert_eq!(event.authority, authority);
+        assert_eq!(event.config.authority, authority);
+        assert_eq!(
+            (event.config.open_minute, event.config.close_minute),
+            (600, 900)
+        );
+        assert_eq!(event.config.config_version, 1);
+    }
+
+    #[test]
+    fn global_freeze_emits_program_settings_updated() {
+        install_test_runtime();
+        TEST_LOG_DATA.take();
+
+        let (authority, responder) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let settings_key = program_settings_pda().0;
+        let settings = |super_authority| {
+            let mut data = vec![0; 8 + ProgramSettings::INIT_SPACE];
+            ProgramSettings {
+                authority,
+                require_config: false,
+                frozen: false,
+                super_authority,
+                bump: program_settings_pda().1,
+            }
+            .try_serialize(&mut &mut data[..])
+            .unwrap();
+            let mut account = TestAccount::new(settings_key, crate::ID, 1, &data);
+            account.is_writable = true;
+            account
+        };
+        let signer = |key| {
+            let mut signer = TestAccount::new(key, System::id(), 0, &[]);
+            signer.is_signer = true;
+            signer
+        };
+        let event = || {
+            TEST_LOG_DATA
+                .take()
+                .iter()
+                .find_map(|data| data.strip_prefix(ProgramSettingsUpdated::DISCRIMINATOR))
+                .map(|body| ProgramSettingsUpdated::try_from_slice(body).unwrap())
+                .unwrap()
+        };
+
+        // Handing the freeze to an incident-response key is recorded...
+        let mut accounts = [signer(authority), settings(authority)];
+        invoke_entry(
+            instruction::SetSuperAuthority {
+                super_authority: responder,
+            },
+            &mut accounts,
+        )
+        .unwrap();
+        let handoff = event();
+        assert_eq!(handoff.program_settings, settings_key);
+        assert_eq!(handoff.authority, authority);
+        assert_eq!(handoff.super_authority, responder);
+        assert!(!handoff.frozen);
+
+        // ...and so is that key pulling the kill switch
+        let mut accounts = [signer(responder), settings(responder)];
+        invoke_entry(instruction::SetGlobalFreeze { frozen: true }, &mut accounts).unwrap();
+        let freeze = event();
+        assert_eq!(freeze.authority, responder);
+        assert!(freeze.frozen);
+        assert_eq!(freeze.super_authority, responder);
+    }
+
+    #[test]
+    fn denylist_edits_emit_denylist_updated() {
+        install_test_runtime();
+        TEST_LOG_DATA.take();
+
+        let authority = Pubkey::new_unique();
+        let mut denylist_data = vec![0; 8 + Denylist::INIT_SPACE];
+        Denylist {
+            authority,
+            entries: Vec::new(),
+            bump: 255,
+        }
+        .try_serialize(&mut &mut denylist_data[..])
+        .unwrap();
+        let mut signer = TestAccount::new(authority, System::id(), 0, &[]);
+        signer.is_signer = true;
+        let mut denylist = TestAccount::new(Pubkey::new_unique(), crate::ID, 1, &denylist_data);
+        denylist.is_writable = true;
+        let denylist_key = denylist.key;
+        let mut accounts = [signer, denylist];
+        let events = || -> Vec<DenylistUpdated> {
+            TEST_LOG_DATA
+                .take()
+                .iter()
+                .filter_map(|data| data.strip_prefix(DenylistUpdated::DISCRIMINATOR))
+                .map(|body| DenylistUpdated::try_from_slice(body).unwrap())
+                .collect()
+        };
+
+        let (sanctioned, frozen) = (Pubkey::new_unique(), Pubkey::new_unique());
+        for wallet in [sanctioned, frozen] {
+            invoke_entry(instruction::AddToDenylist { wallet }, &mut accounts).unwrap();
+        }
+        let added = events();
+        assert_eq!(added.len(), 2);
+        assert_eq!(added[1].denylist, denylist_key);
+        assert_eq!(added[1].authority, authority);
+        assert_eq!(added[1].entries, vec![sanctioned, frozen]);
+
+        invoke_entry(
+            instruction::RemoveFromDenylist { wallet: sanctioned },
+            &mut accounts,
+        )
+        .unwrap();
+        assert_eq!(events()[0].entries, vec![frozen]);
+
+        // A refused edit changes nothing and announces nothing
+        assert!(invoke_entry(
+            instruction::RemoveFromDenylist { wallet: sanctioned },
+            &mut accounts
+        )
+        .is_err());
+        assert!(events().is_empty());
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn plain_open_market_transfer_compute_units() {
+        // There is no SBF runtime here, so this counts the PDA hashes, at
+        // the 1,500 compute units the runtime charges for each. Deriving an
+        // address with `find_program_address` costs one per bump tried.
+        const HASH_UNITS: u64 = 1_500;
+        // Anchor derives each account with a `seeds` constraint that way and
+        // keeps its bump, so only the extra account list may have one
+        assert_eq!(std::mem::size_of::<TransferHookBumps>(), 1);
+        install_test_runtime();
+        TEST_CLOCK.set(1_704_812_400); // Tuesday 2024-01-09 10:00 AM EST
+        let mut accounts = transfer_hook_accounts(&config_with_hours(570, 960));
+        let list_units =
+            (256 - extra_account_meta_list_pda(&accounts[1].key).1 as u64) * HASH_UNITS;
+        let units = |accounts: &mut Vec<TestAccount>| {
+            PDA_HASHES.set(0);
+            assert_eq!(
+                run_hook(accounts, 100).unwrap(),
+                Some(AllowReason::MarketOpen)
+            );
+            list_units + PDA_HASHES.get() * HASH_UNITS
+        };
+
+        // Past the list, only the config exists, and it is checked once
+        assert_eq!(units(&mut accounts), list_units + HASH_UNITS);
+
+        // A subsystem the config doesn't use costs nothing, even once created
+        #[cfg(feature = "circuit-breaker")]
+        {
+            let breaker = CircuitBreaker {
+                volume: 0,
+                last_update: 0,
+                tripped_at: 0,
+                bump: stored_bump(&accounts, CIRCUIT_BREAKER_SLOT),
+            };
+            let mut data = vec![0; 8 + CircuitBreaker::INIT_SPACE];
+            breaker.try_serialize(&mut &mut data[..]).unwrap();
+            let mut account =
+                TestAccount::new(accounts[CIRCUIT_BREAKER_SLOT].key, crate::ID, 5_000, &data);
+            account.is_writable = true;
+            accounts[CIRCUIT_BREAKER_SLOT] = account;
+            assert_eq!(units(&mut accounts), list_units + HASH_UNITS);
+        }
+
+        // Each created PDA the transfer reads adds one hash, here the mint's
+        // calendar
+        #[cfg(feature = "holiday-calendar")]
+        {
+            let calendar = HolidayCalendar {
+                authority: Pubkey::new_unique(),
+                dates: vec![],
+                early_closes: vec![],
+                bump: stored_bump(&accounts, HOLIDAY_CALENDAR_SLOT),
+            };
+            let mut data = vec![0; 8 + HolidayCalendar::INIT_SPACE];
+            calendar.try_serialize(&mut &mut data[..]).unwrap();
+            accounts[HOLIDAY_CALENDAR_SLOT] =
+                TestAccount::new(accounts[HOLIDAY_CALENDAR_SLOT].key, crate::ID, 5_000, &data);
+            assert_eq!(units(&mut accounts), list_units + 2 * HASH_UNITS);
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn quiet_logging_cuts_the_hooks_log_cost() {
+        const TEST: &str = "tests::quiet_logging_cuts_the_hooks_log_cost";
+        // Tuesday 2024-01-09 10:00 AM EST, and the Saturday before at noon
+        let open = 1_704_812_400;
+        let saturday = 1_704_499_200 + 17 * 3600;
+
+        // Off-chain msg! prints straight to stdout, so each transfer runs in
+        // a child process of this test and the parent reads its output
+        if let Ok(scenario) = std::env::var("HOOK_LOG_SCENARIO") {
+            let (verbose_logging, timestamp) = match scenario.as_str() {
+                "quiet-open" => (false, open),
+                "verbose-open" => (true, open),
+                "quiet-saturday" => (false, saturday),
+                _ => (true, saturday),
+            };
+            let config = MarketConfig {
+                verbose_logging,
+                ..config_with_hours(570, 960)
+            };
+            let mut accounts = transfer_hook_accounts(&config);
+            install_test_runtime();
+            TEST_CLOCK.set(timestamp);
+            // Token-2022 calls the hook through the interface's Execute
+            let execute = TransferHookInstruction::Execute { amount: 100 }.pack();
+            println!("<<<");
+            let allowed = entry(&crate::ID, &account_infos(&mut accounts), &execute).is_ok();
+            println!(">>> {allowed}");
+            return;
+        }
+        let transfer = |scenario: &str| {
+            let output = std::process::Command::new(std::env::current_exe().unwrap())
+                .args(["--exact", TEST, "--nocapture", "--test-threads=1"])
+                .env("HOOK_LOG_SCENARIO", scenario)
+                .output()
+                .unwrap();
+            let stdout = String::from_utf8(output.stdout).unwrap();
+            let (logs, verdict) = stdout
+                .split_once("<<<\n")
+                .and_then(|(_, rest)| rest.split_once(">>> "))
+                .unwrap();
+            // Anchor's entrypoint reports every failed instruction in one
+            // "AnchorError occurred" line of its own; count only the hook's
+            let (hook_logs, anchor_errors): (Vec<String>, Vec<String>) = logs
+                .lines()
+                .map(String::from)
+                .partition(|line| !line.starts_with("AnchorError occurred"));
+            let allowed = verdict.starts_with("true");
+            assert_eq!(anchor_errors.len(), usize::from(!allowed));
+            (allowed, hook_logs)
+        };
+        // The runtime charges each log line max(100, its length) compute
+        // units, on top of formatting it
+        let log_units = |logs: &[String]| {
+            logs.iter()
+                .map(|line| line.len().max(100) as u64)
+                .sum::<u64>()
+        };
+
+        let (allowed, quiet) = transfer("quiet-open");
+        assert!(allowed && quiet.is_empty());
+        let (allowed, verbose) = transfer("verbose-open");
+        assert!(allowed && verbose.len() >= 5);
+        assert!(log_units(&verbose) >= 500);
+
+        let (allowed, quiet) = transfer("quiet-saturday");
+        assert!(!allowed);
+        assert_eq!(quiet.len(), 1);
+        assert!(quiet[0].contains("Transfer BLOCKED"));
+        let (allowed, verbose) = transfer("verbose-saturday");
+        assert!(!allowed && verbose.len() > quiet.len());
+        assert!(log_units(&verbose) >= log_units(&quiet) + 500);
+        assert_eq!(log_units(&quiet), 100);
+    }
+
+    #[test]
+    fn ensure_extra_account_meta_list_is_idempotent() {
+        let expected = extra_account_meta_list_data().unwrap();
+
+        // Running again over an initialized list succeeds and leaves it intact
+        let mut list = expected.clone();
+        ensure_existing_list(&mut list).unwrap();
+        ensure_existing_list(&mut list).unwrap();
+        assert_eq!(list, expected);
+
+        // A corrupted list is reported rather than silently accepted
+        let mut corrupted = expected.clone();
+        corrupted[20] ^= 0xff;
+        assert_eq!(
+            ensure_existing_list(&mut corrupted).unwrap_err(),
+            ProgramError::Custom(NyseError::ExtraAccountMetaListMismatch.into())
+        );
+        assert_ne!(corrupted, expected);
+    }
+
+    #[test]
+    fn validate_hook_setup_diagnoses_the_meta_list() {
+        let mint = Pubkey::new_unique();
+        let list_key =
+            Pubkey::find_program_address(&[b"extra-account-metas", mint.as_ref()], &crate::ID).0;
+        let expected = extra_account_meta_list_data().unwrap();
+        let report = |owner: Pubkey, lamports: u64, list: &[u8], hook: Option<Pubkey>| {
+            let mint_data = hook_mint(Pubkey::new_unique(), hook);
+            let mut accounts = [
+                TestAccount::new(list_key, owner, lamports, list),
+                TestAccount::new(mint, anchor_spl::token_2022::ID, 1, &mint_data),
+            ];
+            let infos = account_infos(&mut accounts);
+            hook_setup_report(&infos[0], &infos[1]).unwrap()
+        };
+
+        let ready = report(crate::ID, 1_000_000, &expected, Some(crate::ID));
+        assert_eq!(
+            ready,
+            HookSetupReport {
+                status: HookSetupStatus::Ready,
+                hook_attached: true,
+                expected_size: expected.len() as u32,
+                actual_size: expected.len() as u32,
+            }
+        );
+        assert_eq!(ready.try_to_vec().unwrap().len(), 10);
+
+        // A list registered by a build with fewer extra accounts
+        let metas = extra_account_metas().unwrap();
+        let mut stale = vec![0u8; ExtraAccountMetaList::size_of(metas.len() - 1).unwrap()];
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut stale, &metas[1..]).unwrap();
+        let undersized = report(crate::ID, 1_000_000, &stale, Some(crate::ID));
+        assert_eq!(undersized.status, HookSetupStatus::WrongSize);
+        assert_eq!(undersized.actual_size, stale.len() as u32);
+
+        let mut corrupted = expected.clone();
+        corrupted[20] ^= 0xff;
+        assert_eq!(
+            report(crate::ID, 1_000_000, &corrupted, Some(crate::ID)).status,
+            HookSetupStatus::WrongAccounts
+        );
+        assert_eq!(
+            report(System::id(), 0, &[], Some(crate::ID)).status,
+            HookSetupStatus::Missing
+        );
+        assert_eq!(
+            report(System::id(), 1_000_000, &expected, Some(crate::ID)).status,
+            HookSetupStatus::WrongOwner
+        );
+        // The list can be right while the mint's hook points elsewhere
+        let detached = report(crate::ID, 1_000_000, &expected, None);
+        assert_eq!(detached.status, HookSetupStatus::Ready);
+        assert!(!detached.hook_attached);
+    }
+
+    // Token-2022 mint whose transfer hook points at `program_id`
+    fn hook_mint(authority: Pubkey, program_id: Option<Pubkey>) -> Vec<u8> {
+        use anchor_spl::token_2022::spl_token_2022::extension::{
+            BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+        };
+        use anchor_spl::token_2022::spl_token_2022::state::Mint;
+
+        let len = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferHook])
+            .unwrap();
+        let mut data = vec![0; len];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        let extension = state.init_extension::<TransferHookExtension>(true).unwrap();
+        extension.authority = Some(authority).try_into().unwrap();
+        extension.program_id = program_id.try_into().unwrap();
+        state.base = Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    // `hook_mint` with a transfer fee of `basis_points`, capped at `maximum_fee`
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn fee_mint(basis_points: u16, maximum_fee: u64) -> Vec<u8> {
+        use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::{
+            TransferFee, TransferFeeConfig,
+        };
+        use anchor_spl::token_2022::spl_token_2022::extension::{
+            BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+        };
+        use anchor_spl::token_2022::spl_token_2022::state::Mint;
+
+        let len = ExtensionType::try_calculate_account_len::<Mint>(&[
+            ExtensionType::TransferHook,
+            ExtensionType::TransferFeeConfig,
+        ])
+        .unwrap();
+        let mut data = vec![0; len];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        let extension = state.init_extension::<TransferHookExtension>(true).unwrap();
+        extension.program_id = Some(crate::ID).try_into().unwrap();
+        let fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: maximum_fee.into(),
+            transfer_fee_basis_points: basis_points.into(),
+        };
+        let fee_config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        fee_config.older_transfer_fee = fee;
+        fee_config.newer_transfer_fee = fee;
+        state.base = Mint {
+            decimals: 6,
+            is_initialized: true,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn net_amount_limits_use_what_the_destination_receives() {
+        install_test_runtime();
+        TEST_CLOCK.set(1_704_810_600 + 3600); // Tuesday 2024-01-09 10:30 EST
+        let mut config = config_with_hours(570, 960);
+        config.min_transfer_amount = 1_000;
+        // A 10% fee: sending 1,050 delivers 945
+        let with_fee = |config: &MarketConfig| {
+            let mut accounts = transfer_hook_accounts(config);
+            accounts[1] = TestAccount::new(
+                accounts[1].key,
+                anchor_spl::token_2022::ID,
+                1,
+                &fee_mint(1_000, u64::MAX),
+            );
+            accounts
+        };
+        let mut gross = with_fee(&config);
+        assert_eq!(
+            run_hook(&mut gross, 1_050).unwrap(),
+            Some(AllowReason::MarketOpen)
+        );
+
+        config.limits_on_net_amount = true;
+        let mut net = with_fee(&config);
+        assert_eq!(
+            run_hook(&mut net, 1_050).unwrap_err(),
+            NyseError::TransferBelowMinimum.into()
+        );
+        assert_eq!(
+            run_hook(&mut net, 1_112).unwrap(),
+            Some(AllowReason::MarketOpen)
+        );
+
+        // A mint without the fee extension delivers the gross amount
+        let mut plain = transfer_hook_accounts(&config);
+        let infos = account_infos(&mut plain[1..2]);
+        assert_eq!(
+            limited_amount(Some(&config), &infos[0], 0, 1_050).unwrap(),
+            1_050
+        );
+        let infos = account_infos(&mut net[1..2]);
+        assert_eq!(
+            limited_amount(Some(&config), &infos[0], 0, 1_050).unwrap(),
+            945
+        );
+        assert_eq!(limited_amount(None, &infos[0], 0, 1_050).unwrap(), 1_050);
+    }
+
+    #[test]
+    fn retired_mint_reclaims_extra_account_meta_list_rent() {
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let list =
+            Pubkey::find_program_address(&[b"extra-account-metas", mint.as_ref()], &crate::ID).0;
+        let list_data = extra_account_meta_list_data().unwrap();
+
+        let accounts = |program_id: Option<Pubkey>| {
+            let mut signer = TestAccount::new(authority, System::id(), 0, &[]);
+            signer.is_signer = true;
+            let mut recipient = TestAccount::new(Pubkey::new_unique(), System::id(), 5, &[]);
+            recipient.is_writable = true;
+            let mut list = TestAccount::new(list, crate::ID, 2_000_000, &list_data);
+            list.is_writable = true;
+            let mint = TestAccount::new(
+                mint,
+                anchor_spl::token_2022::ID,
+                1,
+                &hook_mint(authority, program_id),
+            );
+            [signer, recipient, list, mint]
+        };
+
+        // Still the mint's hook: nothing is closed
+        let mut live = accounts(Some(crate::ID));
+        assert_eq!(
+            invoke_entry(instruction::CloseExtraAccountMetaList {}, &mut live).unwrap_err(),
+            ProgramError::Custom(NyseError::HookStillActive.into())
+        );
+        assert_eq!((live[1].lamports, live[2].lamports), (5, 2_000_000));
+
+        // Detached: the rent moves to the recipient and the PDA is released
+        let mut retired = accounts(None);
+        invoke_entry(instruction::CloseExtraAccountMetaList {}, &mut retired).unwrap();
+        assert_eq!((retired[1].lamports, retired[2].lamports), (2_000_005, 0));
+        assert_eq!(retired[2].owner, System::id());
+        assert_eq!(retired[2].data[..8], 0u64.to_le_bytes());
+
+        // Someone else can't close it
+        let mut stranger = accounts(None);
+        stranger[0] = TestAccount::new(Pubkey::new_unique(), System::id(), 0, &[]);
+        stranger[0].is_signer = true;
+        assert_eq!(
+            invoke_entry(instruction::CloseExtraAccountMetaList {}, &mut stranger).unwrap_err(),
+            ProgramError::Custom(NyseError::Unauthorized.into())
+        );
+    }
+
+    #[test]
+    fn retired_mint_reclaims_market_config_rent() {
+        let authority = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        let (config_key, bump) =
+            Pubkey::find_program_address(&[b"market-config", mint.as_ref()], &crate::ID);
+        let mut config = config_with_hours(570, 960);
+        config.authority = authority;
+        config.bump = bump;
+        let mut config_data = vec![0; 8 + MarketConfig::INIT_SPACE];
+        config.try_serialize(&mut &mut config_data[..]).unwrap();
+
+        let accounts = |program_id: Option<Pubkey>| {
+            let mut signer = TestAccount::new(authority, System::id(), 0, &[]);
+            signer.is_signer = true;
+            let mut recipient = TestAccount::new(Pubkey::new_unique(), System::id(), 0, &[]);
+            recipient.is_writable = true;
+            let mut config = TestAccount::new(config_key, crate::ID, 3_000_000, &config_data);
+            config.is_writable = true;
+            let mint = TestAccount::new(
+                mint,
+                anchor_spl::token_2022::ID,
+                1,
+                &hook_mint(Pubkey::new_unique(), program_id),
+            );
+            [signer, recipient, config, mint]
+        };
+
+        let mut live = accounts(Some(crate::ID));
+        assert_eq!(
+            invoke_entry(instruction::CloseMarketConfig {}, &mut live).unwrap_err(),
+            ProgramError::Custom(NyseError::HookStillActive.into())
+        );
+
+        let mut retired = accounts(None);
+        invoke_entry(instruction::CloseMarketConfig {}, &mut retired).unwrap();
+        assert_eq!((retired[1].lamports, retired[2].lamports), (3_000_000, 0));
+        assert_eq!(retired[2].owner, System::id());
+        assert_eq!(retired[2].data[..8], 0u64.to_le_bytes());
+    }
+
+    #[test]
+    fn trading_days_bitmap_covers_a_month() {
+        let january = HolidayDate {
+            year: 2024,
+            month: 1,
+            day: 1,
+        };
+        let open_days = |bitmap: &[u8]| {
+            (0..bitmap.len() * 8)
+                .filter(|&i| bitmap[i / 8] & (1 << (i % 8)) != 0)
+                .map(|i| i as u32 + 1)
+                .collect::<Vec<_>>()
+        };
+
+        // January 2024 without New Year's Day (the 1st) and MLK Day (the 15th)
+        let bitmap = trading_days_bitmap(None, None, january, 31).unwrap();
+        assert_eq!(bitmap.len(), 4);
+        assert_eq!(
+            open_days(&bitmap),
+            [2, 3, 4, 5, 8, 9, 10, 11, 12, 16, 17, 18, 19, 22, 23, 24, 25, 26, 29, 30, 31]
+        );
+
+        // A calendar closure drops out too
+        let config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        let calendar = HolidayCalendar {
+            authority: Pubkey::new_unique(),
+            dates: vec![HolidayDate { day: 9, ..january }],
+            early_closes: Vec::new(),
+            bump: 255,
+        };
+        let bitmap = trading_days_bitmap(Some(&config), Some(&calendar), january, 31).unwrap();
+        assert!(!open_days(&bitmap).contains(&9));
+        assert_eq!(open_days(&bitmap).len(), 20);
+
+        assert_eq!(
+            trading_days_bitmap(None, None, january, MAX_RANGE_DAYS + 1).unwrap_err(),
+            NyseError::InvalidDate.into()
+        );
+        assert_eq!(
+            trading_days_bitmap(None, None, january, 0).unwrap_err(),
+            NyseError::InvalidDate.into()
+        );
+    }
+
+    #[test]
+    fn calendar_half_day_closes_early() {
+        let config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        let mut calendar = HolidayCalendar {
+            authority: Pubkey::new_unique(),
+            dates: Vec::new(),
+            early_closes: Vec::new(),
+            bump: 255,
+        };
+        // Tuesday 2024-01-09 2:00 PM EST
+        let two_pm = 1_704_758_400 + 19 * 3600;
+        let state = |calendar: &HolidayCalendar, timestamp: i64| {
+            get_nyse_market_state(timestamp, Some(&config), Some(calendar)).unwrap()
+        };
+        assert!(state(&calendar, two_pm).is_open);
+
+        calendar.early_closes.push(EarlyCloseOverride {
+            date: HolidayDate {
+                year: 2024,
+                month: 1,
+                day: 9,
+            },
+            close_minute: 13 * 60,
+        });
+        let blocked = state(&calendar, two_pm);
+        assert!(!blocked.is_open);
+        assert_eq!(blocked.reason, MarketReason::EarlyClose);
+        assert!(state(&calendar, two_pm - 3601).is_open);
+
+        // Without an entry the built-in 1:00 PM half days still apply
+        // (Wednesday 2024-07-03 at 2:00 PM EDT)
+        let july_3 = 1_720_029_600;
+        assert_eq!(state(&calendar, july_3).reason, MarketReason::EarlyClose);
+    }
+
+    #[test]
+    fn calendar_half_days_keep_their_own_close() {
+        let config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        let half_day = |month, day, close_minute| EarlyCloseOverride {
+            date: HolidayDate {
+                year: 2024,
+                month,
+                day,
+            },
+            close_minute,
+        };
+        // Noon the day after Thanksgiving, 1:30 PM on Christmas Eve
+        let calendar = HolidayCalendar {
+            authority: Pubkey::new_unique(),
+            dates: Vec::new(),
+            early_closes: vec![half_day(11, 29, 12 * 60), half_day(12, 24, 13 * 60 + 30)],
+            bump: 255,
+        };
+        let state = |timestamp: i64| {
+            get_nyse_market_state(timestamp, Some(&config), Some(&calendar)).unwrap()
+        };
+
+        // Friday 2024-11-29 12:30 PM EST is past that day's noon close
+        let black_friday = 1_732_901_400;
+        assert_eq!(state(black_friday).reason, MarketReason::EarlyClose);
+        assert!(state(black_friday - 31 * 60).is_open);
+
+        // Tuesday 2024-12-24 1:00 PM EST is still open, unlike the built-in
+        // 1:00 PM rule, and the 1:30 PM close applies
+        let christmas_eve = 1_735_063_200;
+        assert!(state(christmas_eve).is_open);
+        assert_eq!(
+            state(christmas_eve + 30 * 60).reason,
+            MarketReason::EarlyClose
+        );
+
+        let schedule = |month, day| {
+            schedule_for_date(
+                Some(&config),
+                Some(&calendar),
+                HolidayDate {
+                    year: 2024,
+                    month,
+                    day,
+                },
+            )
+            .unwrap()
+        };
+        assert_eq!(schedule(11, 29).close_minute, 12 * 60);
+        assert_eq!(schedule(12, 24).close_minute, 13 * 60 + 30);
+    }
+
+    #[test]
+    fn holiday_date_validation() {
+        let date = |year, month, day| HolidayDate { year, month, day };
+        assert!(validate_holiday_date(&date(2024, 2, 29)).is_ok());
+        assert!(validate_holiday_date(&date(2023, 2, 29)).is_err());
+        assert!(validate_holiday_date(&date(2024, 13, 1)).is_err());
+        assert!(validate_holiday_date(&date(2024, 4, 0)).is_err());
+        // Sorted storage keeps chronological order across fields
+        assert!(date(2024, 12, 31) < date(2025, 1, 1));
+    }
+
+    #[test]
+    fn closing_window_transfer_limit() {
+        let mut config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        // Tuesday 2024-01-09, EST (UTC-5)
+        let at = |hour: i64, minute: i64| 1_704_758_400 + (hour + 5) * 3600 + minute * 60;
+
+        // Unlimited by default
+        assert!(check_transfer_limit(at(15, 55), Some(&config), None, u64::MAX).is_ok());
+
+        config.max_transfer_amount = 1_000;
+        config.limit_window_minutes = 10;
+        assert!(check_transfer_limit(at(15, 55), Some(&config), None, 999).is_ok());
+        assert!(check_transfer_limit(at(15, 55), Some(&config), None, 1_000).is_ok());
+        assert_eq!(
+            check_transfer_limit(at(15, 55), Some(&config), None, 1_001).unwrap_err(),
+            NyseError::TransferExceedsLimit.into()
+        );
+        // Outside the window the cap doesn't apply
+        assert!(check_transfer_limit(at(15, 49), Some(&config), None, 1_001).is_ok());
+    }
+
+    #[test]
+    fn closing_cross_blocks_the_final_seconds() {
+        let mut config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        // Tuesday 2024-01-09, EST (UTC-5)
+        let at = |hour: i64, minute: i64, second: i64| {
+            1_704_758_400 + (hour + 5) * 3600 + minute * 60 + second
+        };
+        config.closing_cross_seconds = 30;
+
+        // Configured but not blocking
+        assert!(check_closing_cross(at(15, 59, 45), Some(&config), None).is_ok());
+
+        config.blocking_during_closing_cross = true;
+        assert_eq!(
+            check_closing_cross(at(15, 59, 45), Some(&config), None).unwrap_err(),
+            NyseError::ClosingCrossBlocked.into()
+        );
+        assert!(check_closing_cross(at(15, 59, 30), Some(&config), None).is_err());
+        assert!(check_closing_cross(at(15, 59, 29), Some(&config), None).is_ok());
+        // Seconds count even when the market state only moves on whole minutes
+        config.boundary_precision = BoundaryPrecision::Minute;
+        assert!(check_closing_cross(at(15, 59, 45), Some(&config), None).is_err());
+        assert!(check_closing_cross(at(15, 59, 29), Some(&config), None).is_ok());
+    }
+
+    #[test]
+    fn warmup_ramp_caps_transfers_after_the_open() {
+        let mut config = config_with_hours(MARKET_OPEN_MINUTES as u16, MARKET_CLOSE_MINUTES as u16);
+        // Tuesday 2024-01-09, EST (UTC-5)
+        let at = |hour: i64, minute: i64| 1_704_758_400 + (hour + 5) * 3600 + minute * 60;
+        let large = 10_000;
+
+        // Off by default
+        assert!(check_warmup_limit(at(9, 31), Some(&config), u64::MAX).is_ok());
+
+        config.warmup_minutes = 30;
+        config.warmup_start_limit = 1_000;
+        assert!(check_warmup_limit(at(9, 30), Some(&config), 1_000).is_ok());
+        assert_eq!(
+            check_warmup_limit(at(9, 30), Some(&config), 1_001).unwrap_err(),
+            NyseError::WarmupLimitExceeded.into()
+        );
+        assert_eq!(
+            check_warmup_limit(at(9, 31), Some(&config), large).unwrap_err(),
+            NyseError::WarmupLimitExceeded.into()
+        );
+        // The limit grows each minute, then lifts when the ramp ends
+        assert!(check_warmup_limit(at(9, 39), Some(&config), large).is_ok());
+        assert_eq!(
+            check_warmup_limit(at(9, 59), Some(&config), 30_001).unwrap_err(),
+            NyseError::WarmupLimitExceeded.into()
+        );
+        assert!(check_warmup_limit(at(10, 0), Some(&config), u64::MAX).is_ok());
+        assert!(check_warmup_limit(at(10, 30), Some(&config), large).is_ok());
+    }
+
+    #[test]
+    fn rejects_impossible_timestamps() {
+        for timestamp in [
+            -1,
+            0,
+            MIN_VALID_TIMESTAMP - 1,
+            MAX_VALID_TIMESTAMP,
+            i64::MAX,
+        ] {
+            assert_eq!(
+                get_nyse_market_state(timestamp, None, None).unwrap_err(),
+                NyseError::InvalidTimestamp.into(),
+                "timestamp {timestamp}"
+            );
+        }
+        assert!(get_nyse_market_state(MIN_VALID_TIMESTAMP, None, None).is_ok());
+        assert!(get_nyse_market_state(MAX_VALID_TIMESTAMP - 1, None, None).is_ok());
+    }
+
+    #[test]
+    fn test_time_override_only_applies_to_time_override_builds() {
+        // Clock at Tuesday 2024-01-09 2:00 PM EST, override at Saturday noon
+        let saturday = 1_705_165_200;
+        let mut config = config_with_hours(570, 960);
+        config.test_time_override = Some(saturday);
+
+        let timestamp = effective_timestamp(1_704_826_800, Some(&config));
+        let state = get_nyse_market_state(timestamp, Some(&config), None).unwrap();
+        if cfg!(feature = "time-override") {
+            assert_eq!(timestamp, saturday);
+            assert_eq!(state.status, MarketStatus::Weekend);
+            assert!(!state.is_open);
+        } else {
+            assert_eq!(timestamp, effective_timestamp(1_704_826_800, None));
+        }
+
+        config.test_time_override = None;
+        assert_eq!(
+            effective_timestamp(1_704_826_800, Some(&config)),
+            effective_timestamp(1_704_826_800, None)
+        );
+    }
+
+    // Instructions sysvar data for a transaction with one instruction over
+    // `accounts` (key, is_signer)
+    fn instructions_sysvar_data(accounts: &[(Pubkey, bool)]) -> Vec<u8> {
+        use sysvar::instructions::{BorrowedAccountMeta, BorrowedInstruction};
+
+        let metas = accounts
+            .iter()
+            .map(|(pubkey, is_signer)| BorrowedAccountMeta {
+                pubkey,
+                is_signer: *is_signer,
+                is_writable: false,
+            })
+            .collect();
+        let program_id = Pubkey::new_unique();
+        sysvar::instructions::construct_instructions_data(&[BorrowedInstruction {
+            program_id: &program_id,
+            accounts: metas,
+            data: &[],
+        }])
+    }
+
+    #[test]
+    fn large_trade_requires_compliance_signature() {
+        let compliance = Pubkey::new_unique();
+        let mut config = config_with_hours(570, 960);
+        config.large_trade_threshold = 1_000;
+        config.compliance_authority = compliance;
+
+        let check = |config: &MarketConfig, amount: u64, accounts: &[(Pubkey, bool)]| {
+            let mut data = instructions_sysvar_data(accounts);
+            let mut lamports = 0;
+            let owner = Pubkey::default();
+            let info = AccountInfo::new(
+                &sysvar::instructions::ID,
+                false,
+                false,
+                &mut lamports,
+                &mut data,
+                &owner,
+                false,
+                0,
+            );
+            check_large_trade(Some(config), amount, &info)
+        };
+        let sender = (Pubkey::new_unique(), true);
+
+        // Below the threshold nobody else needs to sign
+        assert!(check(&config, 999, &[sender]).is_ok());
+
+        // At or above it, only a real compliance signature approves
+        assert_eq!(
+            check(&config, 1_000, &[sender]).unwrap_err(),
+            NyseError::LargeTradeRequiresApproval.into()
+        );
+        assert_eq!(
+            check(&config, 5_000, &[sender, (compliance, false)]).unwrap_err(),
+            NyseError::LargeTradeRequiresApproval.into()
+        );
+        assert!(check(&config, 5_000, &[sender, (compliance, true)]).is_ok());
+
+        // A zero threshold turns the check off
+        config.large_trade_threshold = 0;
+        assert!(check(&config, 5_000, &[sender]).is_ok());
+    }
+
+    #[test]
+    fn extreme_clocks_fail_cleanly() {
+        let invalid: Error = NyseError::InvalidTimestamp.into();
+        let mut config = config_with_hours(570, 960);
+        config.max_clock_rewind_seconds = 30;
+        config.last_seen_timestamp = 1_704_834_000;
+
+        for timestamp in [i64::MIN, i64::MIN + 1, -1, i64::MAX - 1, i64::MAX] {
+            assert_eq!(
+                get_nyse_market_state(timestamp, Some(&config), None).unwrap_err(),
+                invalid
+            );
+            assert_eq!(get_eastern_time_info(timestamp).unwrap_err(), invalid);
+            // Never becomes the high-water mark, and never overflows the
+            // rewind subtraction
+            assert_eq!(config.observe_clock(timestamp).unwrap_err(), invalid);
+            assert_eq!(config.last_seen_timestamp, 1_704_834_000);
+
+            // The raw conversions clamp instead of panicking or wrapping
+            let local = get_local_time_info(timestamp, &TimezoneConfig::US_EASTERN);
+            assert!((1..=12).contains(&local.month) && local.weekday < 7);
+            format_timestamp(timestamp);
+            next_holiday_after(timestamp);
+        }
+        assert_eq!(days_to_date(i64::MAX).0, i32::MAX);
+        assert_eq!(days_to_date(i64::MIN).0, i32::MIN);
+    }
+
+    #[test]
+    fn backward_clock_jump_beyond_tolerance_is_rejected() {
+        let mut config = config_with_hours(570, 960);
+
+        // Off by default: any reading is accepted and nothing is tracked
+        assert!(!config.observe_clock(1_704_834_000).unwrap());
+        assert!(!config.observe_clock(1_704_800_000).unwrap());
+
+        config.max_clock_rewind_seconds = 30;
+        assert!(config.observe_clock(1_704_834_000).unwrap());
+        assert_eq!(config.last_seen_timestamp, 1_704_834_000);
+
+        // Skew within the tolerance is evaluated but doesn't move the mark
+        assert!(!config.observe_clock(1_704_833_970).unwrap());
+        assert_eq!(config.last_seen_timestamp, 1_704_834_000);
+
+        // 16:00:00 was seen; a validator reporting 15:59:29 is refused
+        assert_eq!(
+            config.observe_clock(1_704_833_969).unwrap_err(),
+            NyseError::InvalidTimestamp.into()
+        );
+        assert!(config.observe_clock(1_704_834_001).unwrap());
+    }
+
+    #[test]
+    fn cache_computes_once_per_minute() {
+        let mut cache = MarketStateCache {
+            minute: i64::MIN,
+            state: MarketState::HALTED,
+            bump: 255,
+            config_version: 0,
+        };
+        // Friday 2024-01-12: 3:59:10 PM and 3:59:50 PM EST, then the 4:00 PM close
+        let first = 1_705_093_150;
+        let second = first + 40;
+        let close = 1_705_093_200;
+
+        let computations = std::cell::Cell::new(0);
+        let evaluate = |cache: &mut MarketStateCache, timestamp: i64| {
+            cached_market_state(Some(cache), timestamp, 0, false, || {
+                computations.set(computations.get() + 1);
+                get_nyse_market_state(timestamp, None, None)
+            })
+            .unwrap()
+        };
+
+        assert!(evaluate(&mut cache, first).is_open);
+        assert!(evaluate(&mut cache, second).is_open);
+        // The close starts a new minute and is recomputed
+        assert!(!evaluate(&mut cache, close).is_open);
+        assert_eq!(computations.get(), 2);
+        assert_eq!(cache.minute, close / 60);
+    }
+
+    #[test]
+    fn state_history_wraps_around_once_full() {
+        let mut history = StateHistory {
+            next: 0,
+            snapshots: Vec::new(),
+            bump: 255,
+        };
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        let snapshot = |minute: i64| StateSnapshot {
+            timestamp: open + minute * 60,
+            state: get_nyse_market_state(open + minute * 60, None, None).unwrap(),
+        };
+        assert!(history.latest().is_none());
+
+        for minute in 0..3 {
+            history.record(snapshot(minute)).unwrap();
+        }
+        assert_eq!(history.latest(), Some(&snapshot(2)));
+        // One a minute at most
+        assert_eq!(
+            history
+                .record(StateSnapshot {
+                    timestamp: open + 2 * 60 + 59,
+                    ..snapshot(2)
+                })
+                .unwrap_err(),
+            NyseError::SnapshotTooSoon.into()
+        );
+
+        // Past capacity, the oldest snapshots are overwritten in order
+        let recorded = STATE_HISTORY_LEN as i64 + 5;
+        for minute in 3..recorded {
+            history.record(snapshot(minute)).unwrap();
+        }
+        assert_eq!(history.snapshots.len(), STATE_HISTORY_LEN);
+        assert_eq!(history.next, 5);
+        assert_eq!(history.latest(), Some(&snapshot(recorded - 1)));
+        let minutes: Vec<i64> = history
+            .chronological()
+            .map(|snapshot| (snapshot.timestamp - open) / 60)
+            .collect();
+        assert_eq!(minutes, (5..recorded).collect::<Vec<_>>());
+        assert!(history.try_to_vec().unwrap().len() <= StateHistory::INIT_SPACE);
+    }
+
+    #[test]
+    fn transitions_recorded_once_across_the_open() {
+        let mut transition = MarketTransition {
+            is_open: false,
+            timestamp: 0,
+            last_decision_status: None,
+            bump: 255,
+        };
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30:00 EST
+
+        // Transfers every 10 seconds from 09:29 to 09:31
+        let flips: Vec<(i64, bool)> = (open - 60..=open + 60)
+            .step_by(10)
+            .filter_map(|timestamp| {
+                let state = get_nyse_market_state(timestamp, None, None).unwrap();
+                transition
+                    .observe(&state, timestamp)
+                    .map(|is_open| (timestamp, is_open))
+            })
+            .collect();
+        assert_eq!(flips, vec![(open, true)]);
+        assert_eq!((transition.is_open, transition.timestamp), (true, open));
+
+        // The close at 16:00 flips it back exactly once
+        let close = open + 390 * 60;
+        for timestamp in [close - 1, close, close + 1, close + 600] {
+            let state = get_nyse_market_state(timestamp, None, None).unwrap();
+            let flipped = transition.observe(&state, timestamp);
+            assert_eq!(flipped, (timestamp == close).then_some(false));
+        }
+    }
+
+    #[test]
+    fn warn_only_lets_weekend_transfers_through() {
+        // Saturday 2024-01-06 12:00 PM EST
+        let saturday = 1_704_499_200 + 17 * 3600;
+        let mut config = config_with_hours(570, 960);
+        let state = get_nyse_market_state(saturday, Some(&config), None).unwrap();
+        assert!(!state.is_open);
+
+        assert_eq!(
+            enforce_closed(Some(&config), &state).unwrap_err(),
+            NyseError::MarketClosedWeekend.into()
+        );
+        assert_eq!(
+            enforce_closed(None, &state).unwrap_err(),
+            NyseError::MarketClosedWeekend.into()
+        );
+
+        config.enforcement_mode = EnforcementMode::WarnOnly;
+        assert!(enforce_closed(Some(&config), &state).is_ok());
+        let event = TransferWouldBlock::new(10, saturday, &state);
+        assert_eq!((event.status, event.reason_code), (1, 1));
+    }
+
+    #[test]
+    fn continuous_mode_opens_a_wednesday_at_3am() {
+        // Wednesday 2024-01-10 03:00 AM EST
+        let wednesday = 1_704_873_600;
+        let mut config = config_with_hours(570, 960);
+        let state = get_nyse_market_state(wednesday, Some(&config), None).unwrap();
+        assert_eq!(
+            (state.is_open, state.status),
+            (false, MarketStatus::Overnight)
+        );
+
+        config.session_mode = SessionMode::Continuous;
+        let state = get_nyse_market_state(wednesday, Some(&config), None).unwrap();
+        assert_eq!((state.is_open, state.status), (true, MarketStatus::Open));
+        // Open straight through to Saturday 2024-01-13 00:00 EST
+        assert_eq!(state.next_transition, 1_705_122_000);
+
+        // Weekends and holidays still close; Monday reopens at midnight
+        let saturday = wednesday + 3 * 86_400;
+        let state = get_nyse_market_state(saturday, Some(&config), None).unwrap();
+        assert_eq!(
+            (state.is_open, state.status),
+            (false, MarketStatus::Weekend)
+        );
+        // Monday 2024-01-15 is MLK Day, so Tuesday 00:00 EST
+        assert_eq!(state.next_transition, 1_705_381_200);
+        let good_friday = 1_711_728_000; // 2024-03-29 noon EDT
+        assert_eq!(
+            get_nyse_market_state(good_friday, Some(&config), None)
+                .unwrap()
+                .status,
+            MarketStatus::Holiday
+        );
+    }
+
+    #[test]
+    fn transfer_at_932_is_flagged_as_opening_auction() {
+        // Tuesday 2024-01-09, EST (UTC-5)
+        let at = |hour: i64, minute: i64| 1_704_758_400 + (hour + 5) * 3600 + minute * 60;
+        let mut config = config_with_hours(570, 960);
+        config.opening_auction_minutes = 5;
+        config.closing_auction_minutes = 5;
+
+        let state = get_nyse_market_state(at(9, 32), Some(&config), None).unwrap();
+        assert_eq!(
+            (state.is_open, state.status, state.reason),
+            (true, MarketStatus::Auction, MarketReason::OpeningAuction)
+        );
+        assert_eq!(state.next_transition, at(9, 35));
+        assert_eq!(
+            get_nyse_market_state(at(9, 35), Some(&config), None)
+                .unwrap()
+                .status,
+            MarketStatus::Open
+        );
+        assert_eq!(
+            get_nyse_market_state(at(15, 56), Some(&config), None)
+                .unwrap()
+                .reason,
+            MarketReason::ClosingAuction
+        );
+
+        // Blocking the auctions closes those minutes with their own error
+        config.allow_auction_transfers = false;
+        let state = get_nyse_market_state(at(9, 32), Some(&config), None).unwrap();
+        assert!(!state.is_open);
+        assert_eq!(
+            Error::from(closed_error(&state)),
+            NyseError::AuctionWindow.into()
+        );
+
+        // No windows unless configured
+        let state = get_nyse_market_state(at(9, 32), None, None).unwrap();
+        assert_eq!(state.status, MarketStatus::Open);
+    }
+
+    #[test]
+    fn closed_error_tells_pre_from_post_market() {
+        // Tuesday 2024-01-09, EST (UTC-5)
+        let at = |hour: i64| 1_704_758_400 + (hour + 5) * 3600;
+        let error_at = |timestamp| {
+            Error::from(closed_error(
+                &get_nyse_market_state(timestamp, None, None).unwrap(),
+            ))
+        };
+
+        assert_eq!(error_at(at(8)), NyseError::MarketClosedPreMarket.into());
+        assert_eq!(error_at(at(17)), NyseError::MarketClosedPostMarket.into());
+        // Saturday and a holiday keep their own variants
+        assert_eq!(
+            error_at(at(12) + 4 * 86_400),
+            NyseError::MarketClosedWeekend.into()
+        );
+        assert_eq!(
+            error_at(1_705_338_000),
+            NyseError::MarketClosedHoliday.into()
+        );
+        // After a 1:00 PM early close the day is over
+        assert_eq!(
+            error_at(1_720_026_000),
+            NyseError::MarketClosedPostMarket.into()
+        );
+        assert_eq!(
+            Error::from(closed_error(&MarketState::HALTED)),
+            NyseError::MarketHalted.into()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "volume-tracking")]
+    fn rolling_volume_cap() {
+        let mut tracker = VolumeTracker {
+            last_update: 0,
+            volume: 0,
+            bump: 255,
+        };
+        let day = 86_400;
+        let start = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+
+        assert!(accumulate_volume(&mut tracker, start, 600, 1_000, day).is_ok());
+        // A second transfer an hour later pushes past the cap
+        assert_eq!(
+            accumulate_volume(&mut tracker, start + 3600, 500, 1_000, day).unwrap_err(),
+            NyseError::VolumeLimitExceeded.into()
+        );
+        assert_eq!((tracker.volume, tracker.last_update), (600, start));
+
+        // Half a window later half the volume has decayed
+        assert!(accumulate_volume(&mut tracker, start + day as i64 / 2, 700, 1_000, day).is_ok());
+        assert_eq!(tracker.volume, 1_000);
+
+        // After a full window of inactivity the cap resets cleanly
+        let later = start + day as i64 / 2 + day as i64;
+        assert!(accumulate_volume(&mut tracker, later, 1_000, 1_000, day).is_ok());
+        assert_eq!(tracker.volume, 1_000);
+    }
+
+    #[test]
+    #[cfg(all(not(feature = "testnet-calendar"), feature = "volume-tracking"))]
+    fn direct_calls_do_not_use_up_the_volume_cap() {
+        install_test_runtime();
+        let now = 1_704_810_600 + 3600; // Tuesday 2024-01-09 10:30 EST
+        TEST_CLOCK.set(now);
+        let mut config = config_with_hours(570, 960);
+        config.max_volume_per_window = 1_000;
+        config.window_seconds = 86_400;
+        let mut accounts = transfer_hook_accounts(&config);
+        let tracker = VolumeTracker {
+            last_update: now,
+            volume: 0,
+            bump: stored_bump(&accounts, VOLUME_TRACKER_SLOT),
+        };
+        let mut data = vec![0; 8 + VolumeTracker::INIT_SPACE];
+        tracker.try_serialize(&mut &mut data[..]).unwrap();
+        let mut account =
+            TestAccount::new(accounts[VOLUME_TRACKER_SLOT].key, crate::ID, 5_000, &data);
+        account.is_writable = true;
+        accounts[VOLUME_TRACKER_SLOT] = account;
+        let volume = |accounts: &[TestAccount]| {
+            VolumeTracker::try_deserialize(&mut &accounts[VOLUME_TRACKER_SLOT].data[8..])
+                .unwrap()
+                .volume
+        };
+
+        // Fake transfers can't fill the owner's cap for them
+        set_transferring(&mut accounts[0], false);
+        for _ in 0..3 {
+            assert_eq!(
+                run_hook(&mut accounts, 1_000).unwrap_err(),
+                NyseError::NotTransferring.into()
+            );
+        }
+        assert_eq!(volume(&accounts), 0);
+
+        set_transferring(&mut accounts[0], true);
+        assert!(run_hook(&mut accounts, 1_000).is_ok());
+        assert_eq!(volume(&accounts), 1_000);
+    }
+
+    #[test]
+    #[cfg(feature = "volume-tracking")]
+    fn simulated_volume_block_matches_the_real_transfer() {
+        let day = 86_400;
+        let start = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        let mut tracker = VolumeTracker {
+            last_update: start,
+            volume: 900,
+            bump: 255,
+        };
+        let charge = |tracker: &mut VolumeTracker, amount| {
+            accumulate_volume(tracker, start + 60, amount, 1_000, day)
+                .map(|()| Some(AllowReason::MarketOpen))
+        };
+
+        // The dry run charges a copy, so it never moves the tracker
+        let simulated = transfer_verdict(charge(&mut tracker.clone(), 200));
+        assert_eq!(
+            simulated,
+            TransferVerdict {
+                allowed: false,
+                allow_reason: None,
+                error_code: 6020,
+            }
+        );
+        assert_eq!(tracker.volume, 900);
+
+        // and the real transfer then fails with that same error
+        let real = charge(&mut tracker, 200).unwrap_err();
+        assert_eq!(real, NyseError::VolumeLimitExceeded.into());
+        assert_eq!(transfer_verdict(Err(real)), simulated);
+
+        let fits = transfer_verdict(charge(&mut tracker.clone(), 50));
+        assert!(fits.allowed);
+        assert_eq!(fits.allow_reason, Some(AllowReason::MarketOpen));
+        assert_eq!(
+            TransferVerdict::try_from_slice(&fits.try_to_vec().unwrap()).unwrap(),
+            fits
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "destination-age")]
+    fn freshly_created_destination_is_too_new() {
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        let hour = 3600;
+        let too_new = || Error::from(NyseError::DestinationTooNew);
+
+        // Disabled, any destination is fine
+        assert!(check_destination_age(None, open, 0).is_ok());
+
+        // Unregistered, or registered at the open and not yet an hour old
+        assert_eq!(
+            check_destination_age(None, open, hour).unwrap_err(),
+            too_new()
+        );
+        let age = AccountAge {
+            registered_at: open,
+            bump: 255,
+        };
+        assert_eq!(
+            check_destination_age(Some(&age), open + 60, hour).unwrap_err(),
+            too_new()
+        );
+        assert!(check_destination_age(Some(&age), open + hour as i64, hour).is_ok());
+    }
+
+    #[test]
+    fn post_halt_cooldown_follows_the_latest_resumption() {
+        let mut config = config_with_hours(570, 960);
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        config.resumed_at = open;
+        assert!(check_post_halt_cooldown(Some(&config), None, open).is_ok());
+
+        config.post_halt_cooldown_seconds = 60;
+        for at in [open, open + 59] {
+            assert_eq!(
+                check_post_halt_cooldown(Some(&config), None, at).unwrap_err(),
+                NyseError::PostHaltCooldown.into()
+            );
+        }
+        assert!(check_post_halt_cooldown(Some(&config), None, open + 60).is_ok());
+        assert!(check_post_halt_cooldown(None, None, open).is_ok());
+
+        // A breaker tripped at 10:00 with a 15-minute cooldown resumes at
+        // 10:15; before that the breaker itself does the blocking
+        config.resumed_at = 0;
+        config.breaker_cooldown_seconds = 900;
+        let breaker = CircuitBreaker {
+            volume: 0,
+            last_update: open + 1800,
+            tripped_at: open + 1800,
+            bump: 255,
+        };
+        let reset = open + 1800 + 900;
+        assert!(check_post_halt_cooldown(Some(&config), Some(&breaker), reset - 1).is_ok());
+        assert_eq!(
+            check_post_halt_cooldown(Some(&config), Some(&breaker), reset + 59).unwrap_err(),
+            NyseError::PostHaltCooldown.into()
+        );
+        assert!(check_post_halt_cooldown(Some(&config), Some(&breaker), reset + 60).is_ok());
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn delayed_open_blocks_regular_hours_until_it_lapses() {
+        install_test_runtime();
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        let mut config = config_with_hours(570, 960);
+        config.delayed_open_until = open + 45 * 60; // 10:15 EST
+        let mut accounts = transfer_hook_accounts(&config);
+
+        TEST_CLOCK.set(open + 30 * 60);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::OpeningDelayed.into()
+        );
+        // It lapses at the timestamp, with nothing to clear
+        TEST_CLOCK.set(open + 45 * 60);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap(),
+            Some(AllowReason::MarketOpen)
+        );
+        assert!(check_opening_delay(Some(&config), open + 86_400).is_ok());
+
+        // Off-hours keep their own reason, and the recurring schedule is untouched
+        TEST_CLOCK.set(open - 60);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::MarketClosedPreMarket.into()
+        );
+        assert!(check_opening_delay(None, open).is_ok());
+    }
+
+    #[test]
+    #[cfg(all(not(feature = "testnet-calendar"), feature = "allowlist"))]
+    fn only_allowlisted_wallets_transfer_right_after_unpause() {
+        install_test_runtime();
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        let mut config = config_with_hours(570, 960);
+        config.post_halt_cooldown_seconds = 120;
+        config.resumed_at = open + 3600;
+        let mut accounts = transfer_hook_accounts(&config);
+
+        TEST_CLOCK.set(open + 3600 + 5);
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap_err(),
+            NyseError::PostHaltCooldown.into()
+        );
+        TEST_CLOCK.set(open + 3600 + 120);
+        assert!(run_hook(&mut accounts, 100).is_ok());
+
+        // Exempt wallets aren't held back
+        TEST_CLOCK.set(open + 3600 + 5);
+        let allowlist = Allowlist {
+            authority: Pubkey::new_unique(),
+            entries: vec![both_ways(accounts[3].key)],
+            bump: stored_bump(&accounts, ALLOWLIST_SLOT),
+            programs: vec![],
+        };
+        let mut data = vec![0; 8 + Allowlist::INIT_SPACE];
+        allowlist.try_serialize(&mut &mut data[..]).unwrap();
+        let mut account = TestAccount::new(accounts[ALLOWLIST_SLOT].key, crate::ID, 5_000, &data);
+        account.is_writable = true;
+        accounts[ALLOWLIST_SLOT] = account;
+        assert_eq!(
+            run_hook(&mut accounts, 100).unwrap(),
+            Some(AllowReason::Allowlisted)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "circuit-breaker")]
+    fn circuit_breaker_trips_and_resets() {
+        let mut config = config_with_hours(570, 960);
+        config.breaker_threshold = 1_000;
+        config.breaker_window_seconds = 300;
+        config.breaker_cooldown_seconds = 900;
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        let mut breaker = CircuitBreaker {
+            volume: 0,
+            last_update: 0,
+            tripped_at: 0,
+            bump: 255,
+        };
+
+        // Up to the threshold nothing happens
+        assert!(!observe_breaker(&mut breaker, &config, open, 600).unwrap());
+        assert!(!observe_breaker(&mut breaker, &config, open + 10, 380).unwrap());
+
+        // The transfer that crosses it goes through and trips the breaker
+        assert!(observe_breaker(&mut breaker, &config, open + 20, 100).unwrap());
+        assert_eq!(breaker.tripped_at, open + 20);
+
+        // Everything during the cooldown is blocked, small or not
+        for at in [open + 21, open + 20 + 899] {
+            assert_eq!(
+                observe_breaker(&mut breaker, &config, at, 1).unwrap_err(),
+                NyseError::CircuitBreakerTripped.into()
+            );
+        }
+
+        // After the cooldown it re-arms with a clean window
+        assert!(!observe_breaker(&mut breaker, &config, open + 20 + 900, 1).unwrap());
+        assert_eq!((breaker.tripped_at, breaker.volume), (0, 1));
+
+        // Volume spread beyond the window decays instead of tripping it
+        let later = open + 3600;
+        assert!(!observe_breaker(&mut breaker, &config, later, 900).unwrap());
+        assert!(!observe_breaker(&mut breaker, &config, later + 300, 900).unwrap());
+    }
+
+    #[test]
+    #[cfg(not(feature = "testnet-calendar"))]
+    fn the_hook_refuses_direct_calls() {
+        install_test_runtime();
+        TEST_CLOCK.set(1_704_810_600 + 3600); // Tuesday 2024-01-09 10:30 EST
+        let mut accounts = transfer_hook_accounts(&config_with_hours(570, 960));
+
+        // Outside a transfer both entry points refuse
+        set_transferring(&mut accounts[0], false);
+        let refused = ProgramError::Custom(NyseError::NotTransferring.into());
+        assert_eq!(
+            invoke_entry(instruction::TransferHook { amount: 100 }, &mut accounts).unwrap_err(),
+            refused
+        );
+        let execute = TransferHookInstruction::Execute { amount: 100 }.pack();
+        assert_eq!(
+            entry(&crate::ID, &account_infos(&mut accounts), &execute).unwrap_err(),
+            refused
+        );
+
+        // The dry run stores nothing, so it still answers
+        TEST_RETURN_DATA.take();
+        invoke_entry(instruction::SimulateTransfer { amount: 100 }, &mut accounts).unwrap();
+        let verdict = TransferVerdict::try_from_slice(&TEST_RETURN_DATA.take()).unwrap();
+        assert!(verdict.allowed);
+
+        set_transferring(&mut accounts[0], true);
+        assert!(invoke_entry(instruction::TransferHook { amount: 100 }, &mut accounts).is_ok());
+    }
+
+    #[test]
+    #[cfg(all(not(feature = "testnet-calendar"), feature = "circuit-breaker"))]
+    fn direct_calls_cannot_trip_the_circuit_breaker() {
+        install_test_runtime();
+        TEST_CLOCK.set(1_704_810_600 + 3600); // Tuesday 2024-01-09 10:30 EST
+        let mut config = config_with_hours(570, 960);
+        config.breaker_threshold = 1_000;
+        config.breaker_window_seconds = 300;
+        config.breaker_cooldown_seconds = 900;
+        let mut accounts = transfer_hook_accounts(&config);
+        let breaker = CircuitBreaker {
+            volume: 0,
+            last_update: 0,
+            tripped_at: 0,
+            bump: stored_bump(&accounts, CIRCUIT_BREAKER_SLOT),
+        };
+        let mut data = vec![0; 8 + CircuitBreaker::INIT_SPACE];
+        breaker.try_serialize(&mut &mut data[..]).unwrap();
+        let mut account =
+            TestAccount::new(accounts[CIRCUIT_BREAKER_SLOT].key, crate::ID, 5_000, &data);
+        account.is_writable = true;
+        accounts[CIRCUIT_BREAKER_SLOT] = account;
+        let tripped_at = |accounts: &[TestAccount]| {
+            CircuitBreaker::try_deserialize(&mut &accounts[CIRCUIT_BREAKER_SLOT].data[8..])
+                .unwrap()
+                .tripped_at
+        };
+
+        // A direct call is refused before it records any volume
+        set_transferring(&mut accounts[0], false);
+        assert_eq!(
+            run_hook(&mut accounts, 5_000).unwrap_err(),
+            NyseError::NotTransferring.into()
+        );
+        assert_eq!(tripped_at(&accounts), 0);
+
+        // A real transfer of that size trips it
+        set_transferring(&mut accounts[0], true);
+        assert_eq!(
+            run_hook(&mut accounts, 5_000).unwrap(),
+            Some(AllowReason::MarketOpen)
+        );
+        assert_eq!(tripped_at(&accounts), TEST_CLOCK.get());
+    }
+
+    #[test]
+    #[cfg(all(not(feature = "testnet-calendar"), feature = "cooldown"))]
+    fn direct_calls_do_not_restart_the_cooldown() {
+        install_test_runtime();
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        let mut config = config_with_hours(570, 960);
+        config.min_transfer_interval_seconds = 60;
+        let mut accounts = transfer_hook_accounts(&config);
+        let last = LastTransfer {
+            timestamp: open,
+            bump: stored_bump(&accounts, LAST_TRANSFER_SLOT),
+        };
+        let mut data = vec![0; 8 + LastTransfer::INIT_SPACE];
+        last.try_serialize(&mut &mut data[..]).unwrap();
+        let mut account =
+            TestAccount::new(accounts[LAST_TRANSFER_SLOT].key, crate::ID, 5_000, &data);
+        account.is_writable = true;
+        accounts[LAST_TRANSFER_SLOT] = account;
+        let stamped = |accounts: &[TestAccount]| {
+            LastTransfer::try_deserialize(&mut &accounts[LAST_TRANSFER_SLOT].data[8..])
+                .unwrap()
+                .timestamp
+        };
+
+        // Calling the hook every minute can't keep the owner waiting
+        set_transferring(&mut accounts[0], false);
+        for minute in 1..4 {
+            TEST_CLOCK.set(open + minute * 60);
+            assert_eq!(
+                run_hook(&mut accounts, 100).unwrap_err(),
+                NyseError::NotTransferring.into()
+            );
+        }
+        assert_eq!(stamped(&accounts), open);
+
+        set_transferring(&mut accounts[0], true);
+        assert!(run_hook(&mut accounts, 100).is_ok());
+        assert_eq!(stamped(&accounts), open + 180);
+    }
+
+    #[test]
+    #[cfg(feature = "cooldown")]
+    fn back_to_back_transfers_under_a_cooldown() {
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+
+        // No record yet: the owner's first transfer goes through
+        assert!(check_cooldown(None, open, 60).is_ok());
+        let last = LastTransfer {
+            timestamp: open,
+            bump: 255,
+        };
+
+        // A second one 5 seconds later has to wait
+        assert_eq!(
+            check_cooldown(Some(&last), open + 5, 60).unwrap_err(),
+            NyseError::TransferCooldown.into()
+        );
+        assert_eq!(
+            check_cooldown(Some(&last), open + 59, 60).unwrap_err(),
+            NyseError::TransferCooldown.into()
+        );
+        assert!(check_cooldown(Some(&last), open + 60, 60).is_ok());
+    }
+
+    #[test]
+    fn interval_math_survives_a_clock_going_backwards() {
+        let open = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+
+        assert_eq!(interval::elapsed(open, open + 60), 60);
+        // A clock reading before the stored time measures no time at all
+        assert_eq!(interval::elapsed(open, open - 60), 0);
+        assert_eq!(interval::elapsed(i64::MAX, i64::MIN), 0);
+        assert_eq!(interval::elapsed(i64::MIN, i64::MAX), u64::MAX);
+        assert!(interval::has_elapsed(open, open + 60, 60));
+        assert!(!interval::has_elapsed(open, open - 3600, 60));
+        assert_eq!(interval::remaining(open, open + 60), 60);
+        assert_eq!(interval::remaining(open + 60, open), 0);
+        assert_eq!(interval::after(open, 60), open + 60);
+        assert_eq!(interval::after(i64::MAX - 1, 60), i64::MAX);
+        assert_eq!(interval::after(open, u64::MAX), i64::MAX);
+
+        // A rewound clock doesn't count as having waited out a cooldown
+        #[cfg(feature = "cooldown")]
+        {
+            let last = LastTransfer {
+                timestamp: open,
+                bump: 255,
+            };
+            assert_eq!(
+                check_cooldown(Some(&last), open - 3600, 60).unwrap_err(),
+                NyseError::TransferCooldown.into()
+            );
+        }
+        #[cfg(feature = "destination-age")]
+        {
+            let age = AccountAge {
+                registered_at: open,
+                bump: 255,
+            };
+            assert_eq!(
+                check_destination_age(Some(&age), open - 3600, 60).unwrap_err(),
+                NyseError::DestinationTooNew.into()
+            );
+        }
+        #[cfg(feature = "circuit-breaker")]
+        {
+            let config = MarketConfig {
+                breaker_threshold: 1_000,
+                breaker_window_seconds: 60,
+                breaker_cooldown_seconds: 300,
+                ..config_with_hours(570, 960)
+            };
+            let mut breaker = CircuitBreaker {
+                volume: 2_000,
+                last_update: open,
+                tripped_at: open,
+                bump: 255,
+            };
+            assert_eq!(
+                observe_breaker(&mut breaker, &config, open - 3600, 1).unwrap_err(),
+                NyseError::CircuitBreakerTripped.into()
+            );
+        }
+        // Nor does it decay recorded volume, at any distance
+        #[cfg(any(feature = "volume-tracking", feature = "circuit-breaker"))]
+        {
+            assert_eq!(decayed_volume(1_000, open, open - 3600, 60), 1_000);
+            assert_eq!(decayed_volume(1_000, i64::MAX, i64::MIN, 60), 1_000);
+            assert_eq!(decayed_volume(1_000, i64::MIN, i64::MAX, 60), 0);
+        }
+
+        // A resume the clock reads as still ahead starts the cooldown now
+        let config = MarketConfig {
+            post_halt_cooldown_seconds: 300,
+            resumed_at: open + 60,
+            ..config_with_hours(570, 960)
+        };
+        assert_eq!(
+            check_post_halt_cooldown(Some(&config), None, open).unwrap_err(),
+            NyseError::PostHaltCooldown.into()
+        );
+        assert!(check_post_halt_cooldown(Some(&config), None, open + 360).is_ok());
+
+        // Stored times a client chose can't overflow the rewind check
+        let mut config = MarketConfig {
+            max_clock_rewind_seconds: 60,
+            last_seen_timestamp: i64::MAX,
+            ..config_with_hours(570, 960)
+        };
+        assert_eq!(
+            config.observe_clock(open).unwrap_err(),
+            NyseError::InvalidTimestamp.into()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "daily-limit")]
+    fn daily_count_follows_the_eastern_trading_day() {
+        let config = config_with_hours(570, 960);
+        let tuesday = 1_704_810_600; // Tuesday 2024-01-09 09:30 EST
+        let today = trading_day(tuesday, &config);
+        assert_eq!(today, days_from_civil(2024, 1, 9));
+        // 23:59 EST is still Tuesday although it's already Wednesday in UTC
+        assert_eq!(trading_day(tuesday + 14 * 3600 + 29 * 60, &config), today);
+        assert_eq!(
+            trading_day(tuesday + 14 * 3600 + 30 * 60, &config),
+            today + 1
+        );
+
+        let counter = DailyCounter {
+            trading_day: today,
+            count: 2,
+            allowlist_volume: 0,
+            bump: 255,
+        };
+        assert_eq!(next_daily_count(None, today, 3).unwrap(), 1);
+        assert_eq!(next_daily_count(Some(&counter), today, 3).unwrap(), 3);
+        assert_eq!(
+            next_daily_count(Some(&counter), today, 2).unwrap_err(),
+            NyseError::DailyTransferLimitExceeded.into()
+        );
+        assert_eq!(next_daily_count(Some(&counter), today + 1, 2).unwrap(), 1);
+
+        // The small hours of an overnight session belong to the evening it opened
+        let overnight = config_with_hours(20 * 60, 4 * 60);
+        let wednesday_2am = 1_704_870_000;
+        assert_eq!(trading_day(wednesday_2am, &overnight), today);
+        assert_eq!(
+            trading_day(wednesday_2am + 18 * 3600, &overnight),
+            today + 1
+        );
+    }
+
+    #[test]
+    fn overnight_session_spans_midnight() {
+        let config = config_with_hours(20 * 60, 4 * 60);
+        let state = |timestamp| get_nyse_market_state(timestamp, Some(&config), None).unwrap();
+
+        // Wednesday 2024-01-10 02:00 EST, in Tuesday evening's session
+        let wednesday_2am = 1_704_870_000;
+        let at_2am = state(wednesday_2am);
+        assert!(at_2am.is_open);
+        assert_eq!(at_2am.status, MarketStatus::Open);
+        assert_eq!(at_2am.next_transition, wednesday_2am + 2 * 3600);
+
+        // Noon falls between the morning close and the evening open
+        let at_noon = state(wednesday_2am + 10 * 3600);
+        assert!(!at_noon.is_open);
+        assert_eq!(at_noon.reason, MarketReason::PreMarket);
+        assert_eq!(at_noon.next_transition, wednesday_2am + 18 * 3600);
+        assert!(state(wednesday_2am + 18 * 3600).is_open);
+
+        // Friday evening's session runs into Saturday; Sunday night's doesn't
+        // exist, so Monday 02:00 is still the weekend
+        let saturday_2am = wednesday_2am + 3 * 86400;
+        assert!(state(saturday_2am).is_open);
+        assert_eq!(state(saturday_2am + 2 * 3600).status, MarketStatus::Weekend);
+        assert_eq!(
+            state(saturday_2am + 2 * 86400).status,
+            MarketStatus::Weekend
+        );
+        // Monday is Martin Luther King Jr. Day, so its evening session, and
+        // the small hours of Tuesday with it, are a holiday
+        let mlk_evening = saturday_2am + 2 * 86400 + 18 * 3600;
+        assert_eq!(state(mlk_evening).status, MarketStatus::Holiday);
+        assert_eq!(state(mlk_evening + 6 * 3600).status, MarketStatus::Holiday);
+        assert!(state(mlk_evening + 86400).is_open);
+    }
+
+    #[test]
+    fn dst_fold_policy_picks_the_pass_of_the_repeated_hour() {
+        // A 00:30-01:30 session on Sunday 2024-11-03, when 01:00-02:00
+        // EDT is followed by 01:00-02:00 EST
+        let mut config = config_with_hours(30, 90);
+        config.trading_days_bitmask = 0b111_1111;
+        let sunday = 1_730_592_000;
+        let (first_pass, repeat) = (sunday + 5 * 3600, sunday + 6 * 3600);
+        let state = |config: &MarketConfig, timestamp| {
+            get_nyse_market_state(timestamp, Some(config), None).unwrap()
+        };
+
+        // Earliest: the close is 01:30 EDT and the repeat stays closed
+        assert_eq!(config.dst_fold_policy, DstFoldPolicy::Earliest);
+        let at_1_15_edt = state(&config, first_pass + 15 * 60);
+        assert!(at_1_15_edt.is_open);
+        assert_eq!(at_1_15_edt.next_transition, first_pass + 30 * 60);
+        assert!(!state(&config, first_pass + 45 * 60).is_open);
+        assert!(!state(&config, repeat + 15 * 60).is_open);
+
+        // Latest: the close is 01:30 EST, so the session runs on through the
+        // rest of the first pass and into the repeat
+        config.dst_fold_policy = DstFoldPolicy::Latest;
+        let at_1_15_edt = state(&config, first_pass + 15 * 60);
+        assert!(at_1_15_edt.is_open);
+        assert_eq!(at_1_15_edt.next_transition, repeat + 30 * 60);
+        assert!(state(&config, first_pass + 45 * 60).is_open);
+        assert!(state(&config, repeat + 15 * 60).is_open);
+        assert!(!state(&config, repeat + 45 * 60).is_open);
+
+        // Away from the fold both policies read the clock the same way
+        for policy in [DstFoldPolicy::Earliest, DstFoldPolicy::Latest] {
+            config.dst_fold_policy = policy;
+            assert!(!state(&config, sunday + 4 * 3600 + 15 * 60).is_open);
+            assert!(state(&config, sunday + 4 * 3600 + 45 * 60).is_open);
+        }
+    }
+
+    #[test]
+    fn program_info_decodes() {
+        let bytes = program_info().try_to_vec().unwrap();
+        let info = ProgramInfo::try_from_slice(&bytes).unwrap();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            (info.default_open_minute, info.default_close_minute),
+            (9 * 60 + 30, 16 * 60)
+        );
+        let always = FEATURE_MARKET_CONFIG | FEATURE_EXTENDED_HOURS;
+        assert_eq!(info.features & always, always);
+        for (enabled, bit) in [
+            (cfg!(feature = "allowlist"), FEATURE_ALLOWLIST),
+            (cfg!(feature = "denylist"), FEATURE_DENYLIST),
+            (cfg!(feature = "volume-tracking"), FEATURE_VOLUME_TRACKING),
+            (cfg!(feature = "circuit-breaker"), FEATURE_CIRCUIT_BREAKER),
+            (cfg!(feature = "holiday-calendar"), FEATURE_HOLIDAY_CALENDAR),
+            (cfg!(feature = "state-cache"), FEATURE_STATE_CACHE),
+            (
+                cfg!(feature = "transition-events"),
+                FEATURE_TRANSITION_EVENTS,
+            ),
+            (cfg!(feature = "hook-stats"), FEATURE_HOOK_STATS),
+            (cfg!(feature = "cooldown"), FEATURE_COOLDOWN),
+            (cfg!(feature = "destination-age"), FEATURE_DESTINATION_AGE),
+            (
+                cfg!(feature = "transfer-vouchers"),
+                FEATURE_TRANSFER_VOUCHERS,
+            ),
+            (cfg!(feature = "daily-limit"), FEATURE_DAILY_LIMIT),
+        ] {
+            assert_eq!(info.features & bit != 0, enabled);
+        }
+        assert_eq!(
+            info.features & FEATURE_TESTNET_CALENDAR != 0,
+            cfg!(feature = "testnet-calendar")
+        );
+        assert_eq!(
+            info.features & FEATURE_TIME_OVERRIDE != 0,
+            cfg!(feature = "time-override")
+        );
+    }
+
+    #[test]
+    fn market_hours_validation() {
+        assert!(validate_market_hours(10 * 60, 15 * 60).is_ok());
+        assert!(validate_market_hours(0, 24 * 60).is_ok());
+        // Overnight
+        assert!(validate_market_hours(20 * 60, 4 * 60).is_ok());
+        assert!(validate_market_hours(600, 600).is_err());
+        assert!(validate_market_hours(24 * 60, 60).is_err());
+        assert!(validate_market_hours(600, 24 * 60 + 1).is_err());
+    }
+
+    #[test]
+    fn dst_transitions_2024_are_exact() {
+        // Spring forward: 2024-03-10 07:00:00 UTC (2:00 AM EST -> 3:00 AM EDT)
+        let spring = 1_710_054_000;
+        assert!(!is_daylight_saving_time(spring - 1));
+        assert!(is_daylight_saving_time(spring));
+        assert_eq!(get_eastern_time_info(spring - 1).unwrap().hour, 1);
+        assert_eq!(get_eastern_time_info(spring).unwrap().hour, 3);
+
+        // Fall back: 2024-11-03 06:00:00 UTC (2:00 AM EDT -> 1:00 AM EST)
+        let fall = 1_730_613_600;
+        assert!(is_daylight_saving_time(fall - 1));
+        assert!(!is_daylight_saving_time(fall));
+        let before = get_eastern_time_info(fall - 1).unwrap();
+        let after = get_eastern_time_info(fall).unwrap();
+        assert_eq!((before.hour, before.minute, before.second), (1, 59, 59));
+        assert_eq!((after.hour, after.minute, after.second), (1, 0, 0));
+    }
+
+    #[test]
+    fn local_time_reports_offset_on_both_sides_of_dst() {
+        // 2024-01-15 17:00:00 UTC -> 12:00 EST (Monday)
+        let winter = get_eastern_time_info(1_705_338_000).unwrap();
+        assert_eq!((winter.year, winter.month, winter.day), (2024, 1, 15));
+        assert_eq!((winter.hour, winter.minute, winter.second), (12, 0, 0));
+        assert_eq!(winter.weekday, 1);
+        assert!(!winter.is_dst);
+        assert_eq!(winter.utc_offset_seconds, -5 * 3600);
+
+        // 2024-07-15 16:00:00 UTC -> 12:00 EDT (Monday)
+        let summer = get_eastern_time_info(1_721_059_200).unwrap();
+        assert_eq!((summer.year, summer.month, summer.day), (2024, 7, 15));
+        assert_eq!((summer.hour, summer.minute, summer.second), (12, 0, 0));
+        assert_eq!(summer.weekday, 1);
+        assert!(summer.is_dst);
+        assert_eq!(summer.utc_offset_seconds, -4 * 3600);
+
+        // Return data round-trips through Borsh
+        let bytes = summer.try_to_vec().unwrap();
+        assert_eq!(EasternTimeInfo::try_from_slice(&bytes).unwrap(), summer);
+    }
 }
@@ -0,0 +1,140 @@
+// Seeds and address helpers for every PDA the program owns or resolves.
+//
+// The `#[account(seeds = ...)]` constraints, the extra account metas and the
+// signer seeds all use these constants, so clients and CPI callers that
+// derive addresses here can't drift from what the program checks.
+
+use anchor_lang::prelude::Pubkey;
+
+/// `["extra-account-metas", mint]`: the TLV list Token-2022 resolves
+pub const EXTRA_ACCOUNT_METAS_SEED: &[u8] = b"extra-account-metas";
+/// `["market-config", mint]`: the mint's MarketConfig
+pub const MARKET_CONFIG_SEED: &[u8] = b"market-config";
+/// `["allowlist", mint]`: wallets exempt from the hours check
+pub const ALLOWLIST_SEED: &[u8] = b"allowlist";
+/// `["denylist", mint]`: wallets blocked at any hour
+pub const DENYLIST_SEED: &[u8] = b"denylist";
+/// `["holiday-calendar", mint]`: the mint's extra closures and half days
+pub const HOLIDAY_CALENDAR_SEED: &[u8] = b"holiday-calendar";
+/// `["market-state-cache", mint]`: the verdict reused within a minute
+pub const MARKET_STATE_CACHE_SEED: &[u8] = b"market-state-cache";
+/// `["rent-reserve", mint]`: lamports that fund per-owner PDAs
+pub const RENT_RESERVE_SEED: &[u8] = b"rent-reserve";
+/// `["market-transition", mint]`: the last open/close the hook announced
+pub const MARKET_TRANSITION_SEED: &[u8] = b"market-transition";
+/// `["hook-stats", mint]`: allowed and blocked transfer counters
+pub const HOOK_STATS_SEED: &[u8] = b"hook-stats";
+/// `["circuit-breaker", mint]`: the mint-wide volume breaker
+pub const CIRCUIT_BREAKER_SEED: &[u8] = b"circuit-breaker";
+/// `["volume", mint, owner]`: one source owner's rolling volume
+pub const VOLUME_TRACKER_SEED: &[u8] = b"volume";
+/// `["last-transfer", mint, owner]`: one source owner's cooldown
+pub const LAST_TRANSFER_SEED: &[u8] = b"last-transfer";
+/// `["daily-counter", mint, owner]`: one source owner's transfers today
+pub const DAILY_COUNTER_SEED: &[u8] = b"daily-counter";
+/// `["account-age", mint, token_account]`: when a token account registered
+pub const ACCOUNT_AGE_SEED: &[u8] = b"account-age";
+/// `["transfer-voucher", mint, source, destination]`: a one-off exemption
+/// for one transfer between two token accounts
+pub const TRANSFER_VOUCHER_SEED: &[u8] = b"transfer-voucher";
+/// `["state-history", mint]`: the ring buffer of market-state snapshots
+pub const STATE_HISTORY_SEED: &[u8] = b"state-history";
+/// `["program-settings"]`: deployment-wide settings, shared by every mint
+pub const PROGRAM_SETTINGS_SEED: &[u8] = b"program-settings";
+
+fn mint_pda(seed: &[u8], mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[seed, mint.as_ref()], &crate::ID)
+}
+
+pub fn extra_account_meta_list_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    mint_pda(EXTRA_ACCOUNT_METAS_SEED, mint)
+}
+
+pub fn market_config_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    mint_pda(MARKET_CONFIG_SEED, mint)
+}
+
+pub fn allowlist_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    mint_pda(ALLOWLIST_SEED, mint)
+}
+
+pub fn denylist_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    mint_pda(DENYLIST_SEED, mint)
+}
+
+pub fn holiday_calendar_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    mint_pda(HOLIDAY_CALENDAR_SEED, mint)
+}
+
+pub fn market_state_cache_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    mint_pda(MARKET_STATE_CACHE_SEED, mint)
+}
+
+pub fn rent_reserve_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    mint_pda(RENT_RESERVE_SEED, mint)
+}
+
+pub fn market_transition_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    mint_pda(MARKET_TRANSITION_SEED, mint)
+}
+
+pub fn hook_stats_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    mint_pda(HOOK_STATS_SEED, mint)
+}
+
+pub fn circuit_breaker_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    mint_pda(CIRCUIT_BREAKER_SEED, mint)
+}
+
+pub fn state_history_pda(mint: &Pubkey) -> (Pubkey, u8) {
+    mint_pda(STATE_HISTORY_SEED, mint)
+}
+
+/// Keyed by the owner of the source token account, not the account itself
+pub fn volume_tracker_pda(mint: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[VOLUME_TRACKER_SEED, mint.as_ref(), owner.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Keyed by the owner of the source token account, not the account itself
+pub fn last_transfer_pda(mint: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[LAST_TRANSFER_SEED, mint.as_ref(), owner.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Keyed by the owner of the source token account, not the account itself
+pub fn daily_counter_pda(mint: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[DAILY_COUNTER_SEED, mint.as_ref(), owner.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Keyed by the token account itself
+pub fn account_age_pda(mint: &Pubkey, token_account: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[ACCOUNT_AGE_SEED, mint.as_ref(), token_account.as_ref()],
+        &crate::ID,
+    )
+}
+
+/// Keyed by the source and destination token accounts
+pub fn transfer_voucher_pda(mint: &Pubkey, source: &Pubkey, destination: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            TRANSFER_VOUCHER_SEED,
+            mint.as_ref(),
+            source.as_ref(),
+            destination.as_ref(),
+        ],
+        &crate::ID,
+    )
+}
+
+pub fn program_settings_pda() -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[PROGRAM_SETTINGS_SEED], &crate::ID)
+}
@@ -0,0 +1,1136 @@
+use anchor_lang::prelude::*;
+
+use crate::{MarketState, MarketStatus};
+
+/// Per-mint trading configuration, PDA seeded by `["market-config", mint]`.
+/// Mints without one run on the built-in NYSE defaults.
+#[account]
+#[derive(InitSpace)]
+pub struct MarketConfig {
+    /// Signer allowed to change this config
+    pub authority: Pubkey,
+    /// Proposed successor; becomes `authority` once it signs `accept_authority`
+    pub pending_authority: Option<Pubkey>,
+    /// Bumped by every config-changing instruction, each of which must name
+    /// the current value; a replayed or reordered update sees a stale one
+    pub config_version: u64,
+    /// Slot of the instruction that last changed this config (creation
+    /// included). Transfers in later slots, and those after it in the same
+    /// transaction or slot, see the change.
+    pub config_applied_slot: u64,
+    /// Start of the pre-market session, in minutes after local midnight;
+    /// earlier is overnight. Capped at `open_minute`.
+    pub premarket_open_minute: u16,
+    /// Session open, in minutes after local midnight
+    pub open_minute: u16,
+    /// Session close (exclusive), in minutes after local midnight
+    pub close_minute: u16,
+    /// Trading windows when the day has a break, sorted and disjoint; empty
+    /// means the single `open_minute..close_minute` session. Kept spanning
+    /// exactly `open_minute..close_minute`.
+    #[max_len(MAX_SESSIONS)]
+    pub sessions: Vec<SessionWindow>,
+    /// Whether the session minutes apply, or trading days are open around
+    /// the clock
+    pub session_mode: SessionMode,
+    /// Whether boundaries apply to the second or only on whole minutes
+    pub boundary_precision: BoundaryPrecision,
+    /// Bit `n` set when weekday `n` (0 = Sunday) is a trading day; 0 means
+    /// the Monday-Friday default
+    pub trading_days_bitmask: u8,
+    /// Seconds before the open during which transfers are already accepted
+    pub open_grace_seconds: u16,
+    /// Seconds after the close during which transfers are still accepted,
+    /// absorbing block-time lag
+    pub close_grace_seconds: u16,
+    /// Minutes before the open during which transfers are accepted but
+    /// flagged as soft pre-open activity (0 = off)
+    pub soft_open_minutes: u16,
+    /// Minutes after the open flagged as the opening auction (0 = none)
+    pub opening_auction_minutes: u16,
+    /// Minutes before the close flagged as the closing auction (0 = none)
+    pub closing_auction_minutes: u16,
+    /// Whether transfers go through during the auction windows
+    pub allow_auction_transfers: bool,
+    /// Seconds before the close that make up the closing cross
+    pub closing_cross_seconds: u16,
+    /// Whether transfers other than allowlisted ones are blocked in the
+    /// closing cross
+    pub blocking_during_closing_cross: bool,
+    /// Minutes before the open during which transfers to `staging_account`
+    /// are already accepted (0 = off)
+    pub pre_open_staging_minutes: u16,
+    /// Owner of the holding account orders are staged into before the open
+    pub staging_account: Pubkey,
+    /// Owner of the issuer's treasury, whose deposits and withdrawals can
+    /// keep their own hours (`Pubkey::default()` = none)
+    pub treasury: Pubkey,
+    /// Hours transfers into `treasury` go through on trading days, in place
+    /// of the market hours (None = the market hours)
+    pub treasury_deposit_window: Option<SessionWindow>,
+    /// Hours transfers out of `treasury` go through on trading days, in
+    /// place of the market hours (None = the market hours)
+    pub treasury_withdraw_window: Option<SessionWindow>,
+    /// One-off early close for a single announced date
+    pub early_close_override: Option<EarlyCloseOverride>,
+    /// Planned maintenance from this UTC timestamp (inclusive); 0 with
+    /// `maintenance_end` 0 means none
+    pub maintenance_start: i64,
+    /// End of the maintenance window (exclusive), UTC timestamp
+    pub maintenance_end: i64,
+    /// One-off late open (an IPO, pending news): open-market transfers are
+    /// blocked before this UTC timestamp (0 = none)
+    pub delayed_open_until: i64,
+    /// UTC timestamp the token lists at; earlier transfers are blocked
+    /// (0 = listed from the start)
+    pub listing_timestamp: i64,
+    /// UTC timestamp the token delists at; from then on transfers are
+    /// blocked (0 = never)
+    pub delisting_timestamp: i64,
+    /// Emergency halt; blocks every transfer while set
+    pub paused: bool,
+    /// `config_version` the current halt took effect at (0 while running)
+    pub halted_at_version: u64,
+    /// Whether transfers later in the transaction whose `set_paused` began
+    /// the halt still see the market as it was before it
+    pub same_tx_halt_grace: bool,
+    /// Set by `activate`; until then every transfer is blocked, so a mint
+    /// isn't tradeable while it is still being configured
+    pub activated: bool,
+    /// Operator's explanation for a halt or maintenance, UTF-8 padded with
+    /// zeros; all zeros means none
+    pub closure_message: [u8; CLOSURE_MESSAGE_LEN],
+    /// Local time the session minutes are interpreted in
+    pub timezone: TimezoneConfig,
+    /// Which pass of the hour repeated when DST ends the session
+    /// boundaries inside it fall on
+    pub dst_fold_policy: DstFoldPolicy,
+    /// Whether pre-market and after-hours sessions accept transfers
+    pub extended_hours: ExtendedHoursMode,
+    /// Which closure rules apply: built-in NYSE, the HolidayCalendar, or both
+    pub holiday_mode: HolidayMode,
+    /// Whether a weekend fixed-date holiday also closes its actual date, on
+    /// top of the weekday it is observed on
+    pub observance_mode: ObservanceMode,
+    /// Exchange whose built-in holidays `holiday_mode` refers to
+    pub exchange_preset: ExchangePreset,
+    /// Whether closed-market verdicts block transfers or only warn
+    pub enforcement_mode: EnforcementMode,
+    /// How an enforced closed-market verdict fails the transfer
+    pub block_behavior: BlockBehavior,
+    /// Whether the hook logs every step of its checks; off, it logs only
+    /// the reason a transfer is blocked
+    pub verbose_logging: bool,
+    /// Whether a transfer emits its full decision event only when the
+    /// status differs from the last one emitted, and the lightweight
+    /// `TransferStatusUnchanged` otherwise
+    pub dedup_decision_events: bool,
+    /// Zone the timestamps in log lines are written in
+    pub display_timezone: DisplayTimezone,
+    /// Largest single transfer allowed in the closing window (0 = no cap)
+    pub max_transfer_amount: u64,
+    /// Length of the capped window before the close, in minutes (0 = off)
+    pub limit_window_minutes: u16,
+    /// Length of the ramp after the open during which single transfers are
+    /// capped, in minutes (0 = off)
+    pub warmup_minutes: u16,
+    /// Single-transfer cap in the first minute of the ramp; each later
+    /// minute allows another this much (0 = off)
+    pub warmup_start_limit: u64,
+    /// Smallest transfer accepted while the market is open (0 = no floor)
+    pub min_transfer_amount: u64,
+    /// Nonzero transfers below this amount skip every check, for
+    /// micropayments (0 = off)
+    pub dust_exempt_below: u64,
+    /// Whether the size limits look at the amount the destination receives
+    /// after the mint's transfer fee, instead of the gross amount sent
+    pub limits_on_net_amount: bool,
+    /// Whether zero-amount transfers skip the hours check
+    pub allow_zero_amount: bool,
+    /// Whether transfers into the SPL incinerator, which burn the tokens,
+    /// go through at any hour
+    pub allow_incinerator: bool,
+    /// Whether transfers to and from `LiquidityPool` allowlist entries
+    /// follow the market hours like any other trade
+    pub pool_transfers_follow_hours: bool,
+    /// Whether an owner may transfer between their own token accounts;
+    /// false blocks these as potential wash trades
+    pub allow_self_transfer: bool,
+    /// Whether transfers that move nothing skip the checks with a
+    /// `NoOpHint` instead of running them
+    pub short_circuit_no_ops: bool,
+    /// Most a single owner may send per rolling window (0 = no cap)
+    pub max_volume_per_window: u64,
+    /// Length of the rolling volume window in seconds (0 = off)
+    pub window_seconds: u32,
+    /// Shortest gap between two transfers from the same owner (0 = off)
+    pub min_transfer_interval_seconds: u32,
+    /// Most transfers one owner may make per trading day (0 = off)
+    pub max_transfers_per_day: u32,
+    /// Most one owner may send per trading day through the allowlist
+    /// exemption (0 = unlimited)
+    pub allowlist_daily_cap: u64,
+    /// Destination token accounts must have been registered at least this
+    /// long before they receive a transfer (0 = off)
+    pub min_destination_age_seconds: u32,
+    /// Aggregate volume within `breaker_window_seconds` that trips the
+    /// circuit breaker (0 = off)
+    pub breaker_threshold: u64,
+    /// Length of the breaker's rolling volume window in seconds
+    pub breaker_window_seconds: u32,
+    /// How long a tripped breaker blocks transfers before resetting
+    pub breaker_cooldown_seconds: u32,
+    /// After a halt is lifted or the breaker resets, only allowlisted
+    /// wallets transfer for this many seconds (0 = off)
+    pub post_halt_cooldown_seconds: u32,
+    /// When `set_paused(false)` last lifted a halt, or 0 if never
+    pub resumed_at: i64,
+    /// Transfers of at least this amount need `compliance_authority` to sign
+    /// the transaction (0 = off)
+    pub large_trade_threshold: u64,
+    /// Co-signer that approves large trades
+    pub compliance_authority: Pubkey,
+    /// Transfers above this many whole tokens that are an exact multiple of
+    /// `round_amount_unit` count as suspicious round amounts (0 = off)
+    pub round_amount_threshold: u64,
+    /// Whole tokens a suspicious round amount is a multiple of (0 = off)
+    pub round_amount_unit: u64,
+    /// Whether suspicious round amounts are only flagged or also blocked
+    pub round_amount_policy: RoundAmountPolicy,
+    /// Largest backward clock jump tolerated between transfers, in seconds
+    /// (0 = off)
+    pub max_clock_rewind_seconds: u32,
+    /// Latest cluster time seen by the hook while the rewind check is on
+    pub last_seen_timestamp: i64,
+    /// Time the hook evaluates transfers at instead of the clock; only
+    /// honoured by `time-override` builds
+    pub test_time_override: Option<i64>,
+    pub bump: u8,
+}
+
+impl MarketConfig {
+    /// Deserialize the config PDA passed to the transfer hook, or `None` when
+    /// the mint never created one (the PDA is still an empty system account)
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+
+    /// Write the config back after the hook updated `last_seen_timestamp`
+    pub fn store(&self, info: &AccountInfo) -> Result<()> {
+        store_account(self, info)
+    }
+
+    /// Check the cluster clock against the latest reading, erroring when it
+    /// went back by more than `max_clock_rewind_seconds`; returns whether
+    /// `last_seen_timestamp` advanced and needs storing
+    pub fn observe_clock(&mut self, unix_timestamp: i64) -> Result<bool> {
+        if self.max_clock_rewind_seconds == 0 {
+            return Ok(false);
+        }
+        // A broken reading must not become the high-water mark
+        require!(
+            crate::calendar::is_valid_timestamp(unix_timestamp),
+            crate::NyseError::InvalidTimestamp
+        );
+        require!(
+            crate::interval::elapsed(unix_timestamp, self.last_seen_timestamp)
+                <= self.max_clock_rewind_seconds as u64,
+            crate::NyseError::InvalidTimestamp
+        );
+        if unix_timestamp <= self.last_seen_timestamp {
+            return Ok(false);
+        }
+        self.last_seen_timestamp = unix_timestamp;
+        Ok(true)
+    }
+
+    /// Whether the market trades on `weekday` (0 = Sunday, ..., 6 = Saturday)
+    pub fn trades_on(&self, weekday: u32) -> bool {
+        let mask = match self.trading_days_bitmask {
+            0 => MONDAY_TO_FRIDAY,
+            mask => mask,
+        };
+        mask & (1 << weekday) != 0
+    }
+
+    /// Accept an update built against `expected_version` and move the
+    /// version on, so the same update can't apply twice. Stamps the current
+    /// slot into `config_applied_slot`.
+    pub fn advance_version(&mut self, expected_version: u64) -> Result<()> {
+        require!(
+            self.config_version == expected_version,
+            crate::NyseError::StaleConfigVersion
+        );
+        self.config_version = self.config_version.wrapping_add(1);
+        self.config_applied_slot = Clock::get()?.slot;
+        Ok(())
+    }
+
+    /// Complete a proposed handoff; only the proposed key can accept
+    pub fn accept_authority(&mut self, signer: &Pubkey) -> Result<()> {
+        require!(
+            self.pending_authority == Some(*signer),
+            crate::NyseError::Unauthorized
+        );
+        self.authority = *signer;
+        self.pending_authority = None;
+        Ok(())
+    }
+}
+
+/// `trading_days_bitmask` for a Monday-Friday week
+pub const MONDAY_TO_FRIDAY: u8 = 0b0011_1110;
+
+/// Maximum number of trading windows per day
+pub const MAX_SESSIONS: usize = 4;
+
+/// Bytes of UTF-8 text in `closure_message`
+pub const CLOSURE_MESSAGE_LEN: usize = 64;
+
+/// One trading window, in minutes after local midnight (close exclusive)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct SessionWindow {
+    pub open_minute: u16,
+    pub close_minute: u16,
+}
+
+/// How a trading day's hours are determined
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum SessionMode {
+    /// Open only within the configured session minutes
+    #[default]
+    Scheduled,
+    /// Open all day on every trading day ("24/5"), like FX; weekends and
+    /// holidays still close
+    Continuous,
+}
+
+/// When DST ends, an hour of local time happens twice (1:00-2:00 AM in
+/// New York). This picks which of the two passes a boundary inside that
+/// hour, such as a 1:30 AM close, takes effect on; the rest of the hour
+/// follows from it, so the market never flips back on the repeat.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum DstFoldPolicy {
+    /// On the first, daylight-time pass; the whole repeat counts as after
+    /// every boundary in the hour
+    #[default]
+    Earliest,
+    /// On the second, standard-time pass; the whole first pass counts as
+    /// before every boundary in the hour
+    Latest,
+}
+
+/// Granularity of the open, close, grace and auction boundaries
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum BoundaryPrecision {
+    /// Exact to the second, like the NYSE defaults: 09:29:59 is before the
+    /// open and 09:30:00 is after it
+    #[default]
+    Second,
+    /// Seconds are dropped, so the market state only changes on whole
+    /// minutes (a 30-second grace period starts at the next minute)
+    Minute,
+}
+
+/// Sessions the mint trades in
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum ExtendedHoursMode {
+    /// Regular session only
+    #[default]
+    RegularOnly,
+    /// Also pre-market from 4:00 AM and after-hours until 8:00 PM local
+    ExtendedHours,
+}
+
+/// Source of market holidays for a configured mint
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum HolidayMode {
+    /// Built-in NYSE rules only
+    BuiltIn,
+    /// Built-in NYSE rules plus any dates in the mint's HolidayCalendar
+    #[default]
+    BuiltInAndCalendar,
+    /// Only the dates in the mint's HolidayCalendar
+    CalendarOnly,
+}
+
+/// Which dates a fixed-date holiday landing on a weekend closes. Only
+/// matters to mints that trade on weekends (see `trading_days_bitmask`).
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum ObservanceMode {
+    /// Only the weekday the exchange observes it on, as NYSE does
+    #[default]
+    ObservedOnly,
+    /// The observed weekday and the holiday's own date
+    ActualAndObserved,
+}
+
+/// Exchange whose built-in holiday rules apply
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum ExchangePreset {
+    #[default]
+    Nyse,
+    Nasdaq,
+}
+
+/// Whether a closed market actually fails transfers
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum EnforcementMode {
+    /// Transfers outside market hours are rejected
+    #[default]
+    Enforce,
+    /// Transfers outside market hours go through with a `TransferWouldBlock`
+    /// event, for observing a rollout before enforcing it
+    WarnOnly,
+}
+
+/// How an enforced closed-market verdict fails the transfer. A transfer
+/// hook can't turn a transfer into a no-op: Token-2022 only finishes the
+/// transfer when the hook succeeds, the hook sees the token accounts
+/// read-only, and a failed CPI aborts the calling program's whole
+/// transaction. Quiet rejection therefore still fails, just uniformly.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum BlockBehavior {
+    /// Fail with the verdict's own error (`MarketClosedWeekend`, ...) and
+    /// emit `TransferBlocked`
+    #[default]
+    Error,
+    /// Fail with `MarketClosedRejected` whatever the reason, logging one
+    /// line and emitting no event, for integrators that match one code
+    RejectQuietly,
+}
+
+/// What the hook does with a large round-number transfer, a possible sign
+/// of structuring
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum RoundAmountPolicy {
+    /// Let it through with `suspicious` set in `TransferAllowed`
+    #[default]
+    Flag,
+    /// Fail it with `SuspiciousRoundAmount`
+    Block,
+}
+
+/// Zone for the human-readable timestamps in log lines. Only the display
+/// changes: the hours are always evaluated in the config's `timezone`.
+#[derive(
+    AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq, InitSpace,
+)]
+pub enum DisplayTimezone {
+    /// "2024-01-09 14:30:00 UTC"
+    #[default]
+    Utc,
+    /// The config's `timezone`, labelled EST/EDT for New York and with the
+    /// offset in force elsewhere: "2024-01-09 09:30:00 EST"
+    Exchange,
+}
+
+/// Daylight saving rules, as transition instants in UTC
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum DstRule {
+    /// 2nd Sunday of March 2:00 AM to 1st Sunday of November 2:00 AM local
+    /// (pre-2007: 1st Sunday of April to last Sunday of October)
+    UnitedStates,
+    /// Last Sunday of March to last Sunday of October, both at 01:00 UTC
+    EuropeanUnion,
+}
+
+/// `TimezoneConfig::dst_ruleset` of an offset that applies year-round
+pub const NO_DST_RULESET: u8 = 0;
+
+impl DstRule {
+    /// The rule's id in `TimezoneConfig::dst_ruleset`
+    pub const fn ruleset_id(self) -> u8 {
+        match self {
+            Self::UnitedStates => 1,
+            Self::EuropeanUnion => 2,
+        }
+    }
+}
+
+/// Exchange time zone: a standard UTC offset plus an optional DST ruleset,
+/// which adds one hour while in effect
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct TimezoneConfig {
+    /// Standard-time offset from UTC in seconds (e.g. -18000 for EST)
+    pub utc_offset_seconds: i32,
+    /// `NO_DST_RULESET` or a `DstRule::ruleset_id`. Kept as a raw id so a
+    /// config written for a ruleset this build doesn't know still loads,
+    /// and fails with `UnsupportedTimezoneConfig` when the time is read.
+    pub dst_ruleset: u8,
+}
+
+impl TimezoneConfig {
+    /// New York (EST/EDT), the default for mints without a config
+    pub const US_EASTERN: Self = Self::new(-5 * 3600, Some(DstRule::UnitedStates));
+
+    /// London (GMT/BST)
+    pub const LONDON: Self = Self::new(0, Some(DstRule::EuropeanUnion));
+
+    /// Frankfurt (CET/CEST)
+    pub const FRANKFURT: Self = Self::new(3600, Some(DstRule::EuropeanUnion));
+
+    pub const fn new(utc_offset_seconds: i32, dst_rule: Option<DstRule>) -> Self {
+        Self {
+            utc_offset_seconds,
+            dst_ruleset: match dst_rule {
+                Some(rule) => rule.ruleset_id(),
+                None => NO_DST_RULESET,
+            },
+        }
+    }
+
+    /// The DST rule `dst_ruleset` names, or `UnsupportedTimezoneConfig` for
+    /// an id this build doesn't know
+    pub fn dst_rule(&self) -> Result<Option<DstRule>> {
+        match self.dst_ruleset {
+            NO_DST_RULESET => Ok(None),
+            1 => Ok(Some(DstRule::UnitedStates)),
+            2 => Ok(Some(DstRule::EuropeanUnion)),
+            _ => err!(crate::NyseError::UnsupportedTimezoneConfig),
+        }
+    }
+}
+
+impl Default for TimezoneConfig {
+    fn default() -> Self {
+        Self::US_EASTERN
+    }
+}
+
+/// Maximum number of exempt wallets per mint
+pub const MAX_ALLOWLIST_ENTRIES: usize = 64;
+
+/// Maximum number of protocol programs whose PDAs are exempt per mint
+pub const MAX_ALLOWLISTED_PROGRAMS: usize = 8;
+
+/// Which side of a transfer an allowlist entry is exempt on
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum ExemptDirection {
+    /// Sends at any hour; receives only in market hours
+    SendExempt,
+    /// Receives at any hour (a custody vault); sends only in market hours
+    ReceiveExempt,
+    /// Sends and receives at any hour
+    BothExempt,
+    /// An AMM pool's vault authority: transfers to or from it are liquidity
+    /// operations, allowed at any hour unless the config says otherwise
+    LiquidityPool,
+}
+
+impl ExemptDirection {
+    pub fn exempts_sending(self) -> bool {
+        matches!(self, Self::SendExempt | Self::BothExempt)
+    }
+
+    pub fn exempts_receiving(self) -> bool {
+        matches!(self, Self::ReceiveExempt | Self::BothExempt)
+    }
+}
+
+/// One exempt wallet and the direction it is exempt in
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct AllowlistEntry {
+    pub wallet: Pubkey,
+    pub direction: ExemptDirection,
+}
+
+/// Wallets exempt from the market-hours check, PDA seeded by
+/// `["allowlist", mint]`. Matched against token account owners.
+#[account]
+#[derive(InitSpace)]
+pub struct Allowlist {
+    /// Signer allowed to add and remove entries
+    pub authority: Pubkey,
+    #[max_len(MAX_ALLOWLIST_ENTRIES)]
+    pub entries: Vec<AllowlistEntry>,
+    pub bump: u8,
+    /// Programs (a vesting or escrow program, say) whose PDAs may send at
+    /// any hour when they own the source token account. After `bump` so
+    /// lists created before it, whose spare space is zeroed, read it as empty.
+    #[max_len(MAX_ALLOWLISTED_PROGRAMS)]
+    pub programs: Vec<Pubkey>,
+}
+
+impl Allowlist {
+    /// Deserialize the allowlist PDA passed to the transfer hook, or `None`
+    /// when the mint never created one
+    #[cfg(feature = "allowlist")]
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+
+    /// Builds without the `allowlist` feature exempt no one
+    #[cfg(not(feature = "allowlist"))]
+    pub fn load(_info: &AccountInfo) -> Result<Option<Self>> {
+        Ok(None)
+    }
+
+    pub fn contains(&self, key: &Pubkey) -> bool {
+        self.direction(key).is_some()
+    }
+
+    /// The direction `key` is exempt in, or `None` when it isn't listed
+    pub fn direction(&self, key: &Pubkey) -> Option<ExemptDirection> {
+        self.entries
+            .iter()
+            .find(|entry| entry.wallet == *key)
+            .map(|entry| entry.direction)
+    }
+
+    pub fn lists_program(&self, program_id: &Pubkey) -> bool {
+        self.programs.contains(program_id)
+    }
+}
+
+/// Maximum number of blocked wallets per mint
+pub const MAX_DENYLIST_ENTRIES: usize = 32;
+
+/// Wallets barred from sending or receiving the mint at any time, PDA
+/// seeded by `["denylist", mint]`. Matched against token account owners.
+#[account]
+#[derive(InitSpace)]
+pub struct Denylist {
+    /// Signer allowed to add and remove entries
+    pub authority: Pubkey,
+    #[max_len(MAX_DENYLIST_ENTRIES)]
+    pub entries: Vec<Pubkey>,
+    pub bump: u8,
+}
+
+impl Denylist {
+    /// Deserialize the denylist PDA passed to the transfer hook, or `None`
+    /// when the mint never created one
+    #[cfg(feature = "denylist")]
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+
+    /// Builds without the `denylist` feature block no one
+    #[cfg(not(feature = "denylist"))]
+    pub fn load(_info: &AccountInfo) -> Result<Option<Self>> {
+        Ok(None)
+    }
+
+    pub fn contains(&self, key: &Pubkey) -> bool {
+        self.entries.contains(key)
+    }
+}
+
+/// Maximum number of closure dates per calendar
+pub const MAX_CALENDAR_ENTRIES: usize = 64;
+
+/// A local calendar date. Field order makes the derived ordering
+/// chronological.
+#[derive(
+    AnchorSerialize,
+    AnchorDeserialize,
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    InitSpace,
+)]
+pub struct HolidayDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+/// Ad-hoc early close for one local date, ahead of the normal close
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct EarlyCloseOverride {
+    pub date: HolidayDate,
+    /// Close on that date, in minutes after local midnight
+    pub close_minute: u16,
+}
+
+/// Maximum number of half days per calendar
+pub const MAX_EARLY_CLOSE_ENTRIES: usize = 16;
+
+/// Extra full-day closures and half days, PDA seeded by
+/// `["holiday-calendar", mint]`. Both lists are kept sorted by date so
+/// lookups can binary search.
+#[account]
+#[derive(InitSpace)]
+pub struct HolidayCalendar {
+    /// Signer allowed to add and remove dates
+    pub authority: Pubkey,
+    #[max_len(MAX_CALENDAR_ENTRIES)]
+    pub dates: Vec<HolidayDate>,
+    /// Half days with their close, replacing the built-in 1:00 PM rule on
+    /// those dates
+    #[max_len(MAX_EARLY_CLOSE_ENTRIES)]
+    pub early_closes: Vec<EarlyCloseOverride>,
+    pub bump: u8,
+}
+
+impl HolidayCalendar {
+    /// Deserialize the calendar PDA passed to the transfer hook, or `None`
+    /// when the mint never created one
+    #[cfg(feature = "holiday-calendar")]
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+
+    /// Builds without the `holiday-calendar` feature keep to the built-in
+    /// NYSE holidays
+    #[cfg(not(feature = "holiday-calendar"))]
+    pub fn load(_info: &AccountInfo) -> Result<Option<Self>> {
+        Ok(None)
+    }
+
+    pub fn contains(&self, date: &HolidayDate) -> bool {
+        self.dates.binary_search(date).is_ok()
+    }
+
+    /// Close minute of a listed half day
+    pub fn early_close(&self, date: &HolidayDate) -> Option<u16> {
+        self.early_closes
+            .binary_search_by_key(date, |early_close| early_close.date)
+            .ok()
+            .map(|index| self.early_closes[index].close_minute)
+    }
+}
+
+/// Last verdict computed by `transfer_hook`, PDA seeded by
+/// `["market-state-cache", mint]`. Every session boundary, DST switch and
+/// holiday falls on a whole minute, so a verdict holds for its whole minute,
+/// as long as the config it was computed from hasn't changed since.
+#[account]
+#[derive(InitSpace)]
+pub struct MarketStateCache {
+    /// `unix_timestamp / 60` the verdict was computed for
+    pub minute: i64,
+    pub state: MarketState,
+    pub bump: u8,
+    /// `config_version` of the MarketConfig the verdict was computed from
+    /// (0 for mints without one)
+    pub config_version: u64,
+}
+
+impl MarketStateCache {
+    /// Deserialize the cache PDA passed to the transfer hook, or `None`
+    /// when the mint never created one. Caches created before
+    /// `config_version` was added are too short to hold it and are bypassed.
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        if info.data_len() < 8 + Self::INIT_SPACE {
+            return Ok(None);
+        }
+        load_optional(info)
+    }
+
+    /// Write the cache back into its (already initialized) account
+    pub fn store(&self, info: &AccountInfo) -> Result<()> {
+        store_account(self, info)
+    }
+}
+
+/// Last market open/close transition announced for a mint, PDA seeded by
+/// `["market-transition", mint]`
+#[account]
+#[derive(InitSpace)]
+pub struct MarketTransition {
+    /// Whether the market was open at the last recorded transition
+    pub is_open: bool,
+    /// When that transition was observed
+    pub timestamp: i64,
+    /// Status of the last full decision event under `dedup_decision_events`
+    pub last_decision_status: Option<MarketStatus>,
+    pub bump: u8,
+}
+
+impl MarketTransition {
+    /// Deserialize the transition PDA passed to the transfer hook, or `None`
+    /// when the mint never created one
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+
+    /// Write the transition back into its (already initialized) account
+    pub fn store(&self, info: &AccountInfo) -> Result<()> {
+        store_account(self, info)
+    }
+
+    /// Note the market state at `timestamp`; returns the new `is_open` when
+    /// it differs from the last recorded transition
+    pub fn observe(&mut self, state: &MarketState, timestamp: i64) -> Option<bool> {
+        if state.is_open == self.is_open {
+            return None;
+        }
+        self.is_open = state.is_open;
+        self.timestamp = timestamp;
+        Some(state.is_open)
+    }
+
+    /// Note the status of a transfer's decision; returns whether it differs
+    /// from the last one noted, and so gets the full event
+    pub fn observe_decision(&mut self, status: MarketStatus) -> bool {
+        if self.last_decision_status == Some(status) {
+            return false;
+        }
+        self.last_decision_status = Some(status);
+        true
+    }
+}
+
+/// Transfer counters for a mint, PDA seeded by `["hook-stats", mint]`.
+/// A blocked transfer fails its transaction, which rolls its write back, so
+/// the blocked counters only move in simulations (such as wallet
+/// pre-flight); `TransferBlocked` events are the settled record.
+#[account]
+#[derive(InitSpace)]
+pub struct HookStats {
+    /// Signer allowed to reset the counters
+    pub authority: Pubkey,
+    pub blocked_weekend: u64,
+    pub blocked_holiday: u64,
+    pub blocked_after_hours: u64,
+    /// Transfers allowed outside the extended sessions
+    pub allowed: u64,
+    /// Transfers allowed in the pre-market or after-hours session
+    pub allowed_extended: u64,
+    pub bump: u8,
+}
+
+impl HookStats {
+    /// Deserialize the stats PDA passed to the transfer hook, or `None` when
+    /// the mint never created one
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+
+    /// Write the counters back into their (already initialized) account
+    pub fn store(&self, info: &AccountInfo) -> Result<()> {
+        store_account(self, info)
+    }
+
+    /// Count one transfer with verdict `state`
+    pub fn record(&mut self, state: &MarketState, allowed: bool) {
+        let counter = match (allowed, state.status) {
+            (true, MarketStatus::Extended) => &mut self.allowed_extended,
+            (true, _) => &mut self.allowed,
+            (false, MarketStatus::Weekend) => &mut self.blocked_weekend,
+            (false, MarketStatus::Holiday) => &mut self.blocked_holiday,
+            (false, _) => &mut self.blocked_after_hours,
+        };
+        *counter = counter.saturating_add(1);
+    }
+
+    pub fn reset(&mut self) {
+        self.blocked_weekend = 0;
+        self.blocked_holiday = 0;
+        self.blocked_after_hours = 0;
+        self.allowed = 0;
+        self.allowed_extended = 0;
+    }
+}
+
+/// Snapshots a StateHistory keeps before overwriting the oldest
+pub const STATE_HISTORY_LEN: usize = 32;
+
+/// Shortest gap between two snapshots, in seconds
+pub const SNAPSHOT_INTERVAL_SECONDS: i64 = 60;
+
+/// The market state as `record_state_snapshot` saw it at `timestamp`
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct StateSnapshot {
+    pub timestamp: i64,
+    pub state: MarketState,
+}
+
+/// On-chain audit trail of the mint's market state, PDA seeded by
+/// `["state-history", mint]`. A ring buffer of the last
+/// `STATE_HISTORY_LEN` snapshots, which outlives the event log.
+#[account]
+#[derive(InitSpace)]
+pub struct StateHistory {
+    /// Slot the next snapshot goes in: the oldest one once the buffer is full
+    pub next: u16,
+    #[max_len(STATE_HISTORY_LEN)]
+    pub snapshots: Vec<StateSnapshot>,
+    pub bump: u8,
+}
+
+impl StateHistory {
+    /// Append a snapshot, overwriting the oldest when full. Fails with
+    /// `SnapshotTooSoon` within `SNAPSHOT_INTERVAL_SECONDS` of the latest.
+    pub fn record(&mut self, snapshot: StateSnapshot) -> Result<()> {
+        if let Some(latest) = self.latest() {
+            require!(
+                crate::interval::has_elapsed(
+                    latest.timestamp,
+                    snapshot.timestamp,
+                    SNAPSHOT_INTERVAL_SECONDS as u64
+                ),
+                crate::NyseError::SnapshotTooSoon
+            );
+        }
+        let next = self.next as usize;
+        if next < self.snapshots.len() {
+            self.snapshots[next] = snapshot;
+        } else {
+            self.snapshots.push(snapshot);
+        }
+        self.next = ((next + 1) % STATE_HISTORY_LEN) as u16;
+        Ok(())
+    }
+
+    /// The most recent snapshot, if any
+    pub fn latest(&self) -> Option<&StateSnapshot> {
+        let last = (self.next as usize + STATE_HISTORY_LEN - 1) % STATE_HISTORY_LEN;
+        self.snapshots.get(last)
+    }
+
+    /// The snapshots from oldest to newest
+    pub fn chronological(&self) -> impl Iterator<Item = &StateSnapshot> {
+        let (newer, older) = self
+            .snapshots
+            .split_at((self.next as usize).min(self.snapshots.len()));
+        older.iter().chain(newer)
+    }
+}
+
+/// Deployment-wide policy, PDA seeded by `["program-settings"]`. Created by
+/// the program's upgrade authority; until then every mint falls back to the
+/// built-in defaults when its MarketConfig is missing.
+#[account]
+#[derive(InitSpace)]
+pub struct ProgramSettings {
+    /// Signer allowed to change the settings
+    pub authority: Pubkey,
+    /// Reject transfers of mints without a MarketConfig instead of running
+    /// them on the NYSE defaults
+    pub require_config: bool,
+    /// Emergency stop: blocks every transfer of every mint, ahead of any
+    /// per-mint config
+    pub frozen: bool,
+    /// Signer allowed to flip `frozen`, besides the settings authority
+    pub super_authority: Pubkey,
+    pub bump: u8,
+}
+
+impl ProgramSettings {
+    /// Deserialize the settings passed to the transfer hook, or `None`
+    /// before the deployment creates them
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+}
+
+/// Mint-wide rolling volume behind the circuit breaker, PDA seeded by
+/// `["circuit-breaker", mint]`. The transfer that pushes the volume past the
+/// threshold still goes through (a blocked one would roll the trip back);
+/// every transfer after it is blocked until the cooldown has passed.
+#[account]
+#[derive(InitSpace)]
+pub struct CircuitBreaker {
+    /// Volume at `last_update`, decaying linearly over the window
+    pub volume: u64,
+    pub last_update: i64,
+    /// When the breaker tripped, or 0 while it is armed
+    pub tripped_at: i64,
+    pub bump: u8,
+}
+
+impl CircuitBreaker {
+    /// Deserialize the breaker passed to the transfer hook, or `None` when
+    /// the mint never created one
+    #[cfg(feature = "circuit-breaker")]
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+
+    /// Builds without the `circuit-breaker` feature never trip
+    #[cfg(not(feature = "circuit-breaker"))]
+    pub fn load(_info: &AccountInfo) -> Result<Option<Self>> {
+        Ok(None)
+    }
+
+    /// Write the breaker back into its (already created) account
+    pub fn store(&self, info: &AccountInfo) -> Result<()> {
+        store_account(self, info)
+    }
+}
+
+/// Funds PDAs the transfer hook creates on the fly (extra accounts can't
+/// sign, so there is no payer). PDA seeded by `["rent-reserve", mint]`; top
+/// it up with a plain SOL transfer.
+#[account]
+#[derive(InitSpace)]
+pub struct RentReserve {
+    pub bump: u8,
+}
+
+impl RentReserve {
+    /// Deserialize the reserve passed to the transfer hook, or `None` when
+    /// the mint never created one
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+}
+
+/// Decaying send volume of one owner, PDA seeded by
+/// `["volume", mint, owner]`. Created by the hook on the owner's first
+/// capped transfer.
+#[account]
+#[derive(InitSpace)]
+pub struct VolumeTracker {
+    /// When `volume` was last brought up to date
+    pub last_update: i64,
+    /// Volume still counted against the cap as of `last_update`
+    pub volume: u64,
+    pub bump: u8,
+}
+
+impl VolumeTracker {
+    /// Deserialize the tracker passed to the transfer hook, or `None` before
+    /// the owner's first capped transfer
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+
+    /// Write the tracker back into its (already created) account
+    pub fn store(&self, info: &AccountInfo) -> Result<()> {
+        store_account(self, info)
+    }
+}
+
+/// When a token account was registered with `register_token_account`, PDA
+/// seeded by `["account-age", mint, token_account]`. SPL token accounts don't
+/// record their creation time, so this stands in for it; registration can
+/// only make an account look younger than it is, never older.
+#[account]
+#[derive(InitSpace)]
+pub struct AccountAge {
+    pub registered_at: i64,
+    pub bump: u8,
+}
+
+impl AccountAge {
+    /// Deserialize the record passed to the transfer hook, or `None` for an
+    /// account that was never registered
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+}
+
+/// When one owner last made a cooled-down transfer, PDA seeded by
+/// `["last-transfer", mint, owner]`. Created by the hook on the owner's first
+/// transfer under a cooldown.
+#[account]
+#[derive(InitSpace)]
+pub struct LastTransfer {
+    pub timestamp: i64,
+    pub bump: u8,
+}
+
+impl LastTransfer {
+    /// Deserialize the record passed to the transfer hook, or `None` before
+    /// the owner's first transfer under a cooldown
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+
+    /// Write the record back into its (already created) account
+    pub fn store(&self, info: &AccountInfo) -> Result<()> {
+        store_account(self, info)
+    }
+}
+
+/// How many transfers one owner has made on a trading day, PDA seeded by
+/// `["daily-counter", mint, owner]`. Created by the hook on the owner's first
+/// transfer under a daily limit; both tallies restart when the day changes.
+#[account]
+#[derive(InitSpace)]
+pub struct DailyCounter {
+    /// Local session date the count belongs to, in days since 1970-01-01
+    pub trading_day: i64,
+    pub count: u32,
+    /// Amount sent through the allowlist exemption on `trading_day`
+    pub allowlist_volume: u64,
+    pub bump: u8,
+}
+
+impl DailyCounter {
+    /// Deserialize the counter passed to the transfer hook, or `None` before
+    /// the owner's first transfer under a daily limit
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+
+    /// Write the counter back into its (already created) account
+    pub fn store(&self, info: &AccountInfo) -> Result<()> {
+        store_account(self, info)
+    }
+}
+
+/// One-time exemption for a single off-hours transfer (a corporate action,
+/// say), PDA seeded by `["transfer-voucher", mint, source, destination]`
+/// over the two token accounts. Issued by the config authority; the hook
+/// closes it on the transfer it lets through.
+#[account]
+#[derive(InitSpace)]
+pub struct TransferVoucher {
+    pub mint: Pubkey,
+    /// Source token account
+    pub source: Pubkey,
+    /// Destination token account
+    pub destination: Pubkey,
+    /// Exact amount the transfer must move
+    pub amount: u64,
+    /// UTC timestamp from which the voucher no longer applies
+    pub expires_at: i64,
+    pub bump: u8,
+}
+
+impl TransferVoucher {
+    /// Deserialize the voucher passed to the transfer hook, or `None` when
+    /// none was issued for this pair of accounts
+    pub fn load(info: &AccountInfo) -> Result<Option<Self>> {
+        load_optional(info)
+    }
+
+    /// Whether it lets a transfer of `amount` through at `timestamp`; the
+    /// seeds already pin the mint and the two accounts
+    pub fn covers(&self, amount: u64, timestamp: i64) -> bool {
+        self.amount == amount && timestamp < self.expires_at
+    }
+}
+
+fn store_account<T: AccountSerialize>(account: &T, info: &AccountInfo) -> Result<()> {
+    let mut data = info.try_borrow_mut_data()?;
+    account.try_serialize(&mut &mut data[..])
+}
+
+// Optional PDAs are still empty system accounts until their mint creates them
+fn load_optional<T: AccountDeserialize>(info: &AccountInfo) -> Result<Option<T>> {
+    if info.owner != &crate::ID || info.data_is_empty() {
+        return Ok(None);
+    }
+    let data = info.try_borrow_data()?;
+    Ok(Some(T::try_deserialize(&mut &data[..])?))
+}
@@ -0,0 +1,64 @@
+// Compressed trading calendar for devnet/testnet end-to-end testing.
+//
+// Every real minute of cluster time replays one full day of the 2024
+// calendar, so a test suite can walk through sessions, weekends and holidays
+// in minutes instead of waiting for them. Only compiled with the
+// `testnet-calendar` feature.
+
+/// 2024-01-01 00:00:00 UTC, the first simulated instant
+pub const SIMULATED_EPOCH: i64 = 1_704_067_200;
+
+/// Simulated seconds that elapse per real second (86400 / 60)
+pub const COMPRESSION_FACTOR: i64 = 1440;
+
+/// Real seconds before the simulated 366-day year restarts
+pub const REAL_SECONDS_PER_CYCLE: i64 = 366 * 60;
+
+/// Map a real cluster timestamp onto the compressed calendar
+pub fn compress(unix_timestamp: i64) -> i64 {
+    let offset = unix_timestamp.rem_euclid(REAL_SECONDS_PER_CYCLE);
+    SIMULATED_EPOCH + offset * COMPRESSION_FACTOR
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{get_nyse_market_state, MarketStatus};
+
+    // Real timestamp at the start of a cycle, i.e. simulated 2024-01-01 00:00 UTC
+    const CYCLE_START: i64 = REAL_SECONDS_PER_CYCLE * 80_000;
+
+    // 43 real seconds into a real minute lands at 17:12 UTC (12:12 PM EST)
+    fn real_midday(simulated_day: i64) -> i64 {
+        CYCLE_START + simulated_day * 60 + 43
+    }
+
+    #[test]
+    fn each_real_minute_is_one_day() {
+        assert_eq!(compress(CYCLE_START), SIMULATED_EPOCH);
+        assert_eq!(compress(CYCLE_START + 60), SIMULATED_EPOCH + 86_400);
+        assert_eq!(
+            compress(CYCLE_START + REAL_SECONDS_PER_CYCLE),
+            SIMULATED_EPOCH
+        );
+    }
+
+    #[test]
+    fn traverses_holiday_and_weekend_within_minutes() {
+        // Day 0 is New Year's Day 2024 (a Monday)
+        let new_years = get_nyse_market_state(compress(real_midday(0)), None, None).unwrap();
+        assert_eq!(new_years.status, MarketStatus::Holiday);
+
+        let tuesday = get_nyse_market_state(compress(real_midday(1)), None, None).unwrap();
+        assert!(tuesday.is_open);
+
+        let saturday = get_nyse_market_state(compress(real_midday(5)), None, None).unwrap();
+        assert_eq!(saturday.status, MarketStatus::Weekend);
+
+        let sunday = get_nyse_market_state(compress(real_midday(6)), None, None).unwrap();
+        assert_eq!(sunday.status, MarketStatus::Weekend);
+
+        let monday = get_nyse_market_state(compress(real_midday(7)), None, None).unwrap();
+        assert!(monday.is_open);
+    }
+}
@@ -0,0 +1,146 @@
+// Property tests for the calendar's time conversions, checked against
+// chrono as an independent reference. Compiles the module through #[path],
+// the way calendar_standalone.rs does.
+
+#[allow(dead_code)]
+#[path = "../src/calendar.rs"]
+mod calendar;
+
+use calendar::{
+    days_from_civil, days_in_month, days_to_date, eastern_time, eastern_to_utc,
+    is_daylight_saving_time, local_time, MAX_VALID_TIMESTAMP,
+};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, Timelike, Weekday};
+use proptest::prelude::*;
+
+// 1987-01-01 00:00:00 UTC, when the pre-2007 US DST rules took effect
+const US_DST_RULES_FROM: i64 = 536_457_600;
+
+// Timestamps the US rules implemented by the calendar cover
+fn covered_timestamp() -> impl Strategy<Value = i64> {
+    US_DST_RULES_FROM..MAX_VALID_TIMESTAMP
+}
+
+fn nth_sunday(year: i32, month: u32, n: u8) -> NaiveDate {
+    NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, n).unwrap()
+}
+
+fn last_sunday(year: i32, month: u32) -> NaiveDate {
+    let last = NaiveDate::from_ymd_opt(year, month + 1, 1).unwrap() - Duration::days(1);
+    last - Duration::days(last.weekday().num_days_from_sunday() as i64)
+}
+
+// US Eastern DST from chrono's calendar: 2:00 AM local on the transition
+// Sundays, i.e. 07:00 UTC in spring and 06:00 UTC in autumn
+fn reference_is_dst(utc: NaiveDateTime) -> bool {
+    let year = utc.year();
+    let (start, end) = if year >= 2007 {
+        (nth_sunday(year, 3, 2), nth_sunday(year, 11, 1))
+    } else {
+        (nth_sunday(year, 4, 1), last_sunday(year, 10))
+    };
+    let start = start.and_hms_opt(7, 0, 0).unwrap();
+    let end = end.and_hms_opt(6, 0, 0).unwrap();
+    (start..end).contains(&utc)
+}
+
+fn reference_eastern(timestamp: i64) -> (NaiveDateTime, bool) {
+    let utc = DateTime::from_timestamp(timestamp, 0).unwrap().naive_utc();
+    let is_dst = reference_is_dst(utc);
+    let offset_hours = if is_dst { -4 } else { -5 };
+    (utc + Duration::hours(offset_hours), is_dst)
+}
+
+proptest! {
+    #[test]
+    fn days_to_date_matches_chrono(days in -1_000_000i64..1_000_000) {
+        let expected = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap() + Duration::days(days);
+        let (year, month, day) = days_to_date(days);
+        prop_assert_eq!((year, month, day), (expected.year(), expected.month(), expected.day()));
+        prop_assert_eq!(days_from_civil(year, month, day), days);
+    }
+
+    #[test]
+    fn eastern_fields_stay_in_range(timestamp in covered_timestamp()) {
+        let local = eastern_time(timestamp);
+        prop_assert!((1..=12).contains(&local.month));
+        prop_assert!((1..=days_in_month(local.year, local.month)).contains(&local.day));
+        prop_assert!(local.hour < 24 && local.minute < 60 && local.second < 60);
+        prop_assert!(local.weekday < 7);
+        prop_assert_eq!(
+            local.utc_offset_seconds,
+            if local.is_dst { -4 * 3600 } else { -5 * 3600 }
+        );
+    }
+
+    #[test]
+    fn weekday_advances_once_per_day(timestamp in covered_timestamp(), is_dst: bool) {
+        // At a fixed offset every 86400 seconds is exactly one local day
+        let today = local_time(timestamp, -5 * 3600, is_dst);
+        let tomorrow = local_time(timestamp + 86_400, -5 * 3600, is_dst);
+        prop_assert_eq!(tomorrow.weekday, (today.weekday + 1) % 7);
+        prop_assert_eq!(
+            days_from_civil(tomorrow.year, tomorrow.month, tomorrow.day),
+            days_from_civil(today.year, today.month, today.day) + 1
+        );
+        prop_assert_eq!((tomorrow.hour, tomorrow.minute), (today.hour, today.minute));
+    }
+
+    #[test]
+    fn eastern_time_matches_chrono(timestamp in covered_timestamp()) {
+        let (expected, expected_dst) = reference_eastern(timestamp);
+        let local = eastern_time(timestamp);
+        prop_assert_eq!(is_daylight_saving_time(timestamp), expected_dst);
+        prop_assert_eq!(local.is_dst, expected_dst);
+        prop_assert_eq!(
+            (local.year, local.month, local.day),
+            (expected.year(), expected.month(), expected.day())
+        );
+        prop_assert_eq!(
+            (local.hour, local.minute, local.second),
+            (expected.hour(), expected.minute(), expected.second())
+        );
+        prop_assert_eq!(local.weekday, expected.weekday().num_days_from_sunday());
+    }
+
+    #[test]
+    fn dst_transitions_match_chrono(
+        year in 1987i32..2100,
+        spring in any::<bool>(),
+        offset in -7_200i64..7_200,
+    ) {
+        // Uniform timestamps rarely land on a switch, so aim at them directly
+        let (start, end) = if year >= 2007 {
+            (nth_sunday(year, 3, 2), nth_sunday(year, 11, 1))
+        } else {
+            (nth_sunday(year, 4, 1), last_sunday(year, 10))
+        };
+        let transition = if spring {
+            start.and_hms_opt(7, 0, 0).unwrap()
+        } else {
+            end.and_hms_opt(6, 0, 0).unwrap()
+        };
+        let timestamp = transition.and_utc().timestamp() + offset;
+        let (expected, expected_dst) = reference_eastern(timestamp);
+        let local = eastern_time(timestamp);
+        prop_assert_eq!(local.is_dst, expected_dst);
+        prop_assert_eq!(
+            (local.day, local.hour, local.minute),
+            (expected.day(), expected.hour(), expected.minute())
+        );
+    }
+
+    #[test]
+    fn eastern_to_utc_inverts_eastern_time(timestamp in covered_timestamp()) {
+        let local = eastern_time(timestamp);
+        let seconds_of_day = local.hour * 3600 + local.minute * 60 + local.second;
+        let round_trip = eastern_to_utc(local.year, local.month, local.day, seconds_of_day);
+        // Only the EST pass through the repeated fall hour is ambiguous, and
+        // it resolves to the earlier EDT instant an hour before
+        if round_trip != timestamp {
+            prop_assert!(!local.is_dst);
+            prop_assert_eq!(round_trip, timestamp - 3600);
+            prop_assert!(eastern_time(round_trip).is_dst);
+        }
+    }
+}
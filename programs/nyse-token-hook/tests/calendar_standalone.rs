@@ -0,0 +1,32 @@
+// Compiles the calendar module on its own, the way an off-chain client crate
+// would, to prove it has no Anchor or runtime dependency.
+
+// Like any client, this only calls part of the module
+#[allow(dead_code)]
+#[path = "../src/calendar.rs"]
+mod calendar;
+
+use calendar::{eastern_time, is_market_open, is_nyse_holiday};
+
+#[test]
+fn client_verdicts_without_program_context() {
+    // Tuesday 2024-01-09, 09:29:59 / 09:30:00 / 15:59:59 / 16:00:00 EST
+    assert!(!is_market_open(1_704_810_599));
+    assert!(is_market_open(1_704_810_600));
+    assert!(is_market_open(1_704_833_999));
+    assert!(!is_market_open(1_704_834_000));
+
+    // Saturday 2024-01-13 noon and Good Friday 2024 noon
+    assert!(!is_market_open(1_705_165_200));
+    assert!(!is_market_open(1_711_728_000));
+    assert!(is_nyse_holiday(2024, 3, 29));
+
+    // Out-of-range clocks are never open
+    assert!(!is_market_open(-1));
+
+    let local = eastern_time(1_710_054_000);
+    assert_eq!(
+        (local.hour, local.is_dst, local.utc_offset_seconds),
+        (3, true, -4 * 3600)
+    );
+}
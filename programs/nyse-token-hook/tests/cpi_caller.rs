@@ -0,0 +1,221 @@
+// A tiny caller program that gates its own logic on the hook's verdict by
+// invoking `query_market_state` and reading the return data. The syscall
+// stubs stand in for the runtime: `invoke_signed` dispatches straight into
+// the hook's entrypoint and return data is kept in memory.
+
+// The compressed testnet calendar remaps the stubbed clock
+#![cfg(not(feature = "testnet-calendar"))]
+
+use std::sync::Mutex;
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::program::{get_return_data, invoke_signed};
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::{InstructionData, ToAccountMetas};
+use nyse_token_hook::pda::market_config_pda;
+use nyse_token_hook::{MarketConfig, MarketState, MarketStatus, SessionMode};
+
+static UNIX_TIMESTAMP: Mutex<i64> = Mutex::new(0);
+static RETURN_DATA: Mutex<Option<(Pubkey, Vec<u8>)>> = Mutex::new(None);
+// The clock and return data are process-wide, so the tests take turns
+static SERIAL: Mutex<()> = Mutex::new(());
+
+struct Runtime;
+
+impl SyscallStubs for Runtime {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> std::result::Result<(), ProgramError> {
+        let accounts: Vec<AccountInfo> = instruction
+            .accounts
+            .iter()
+            .map(|meta| {
+                account_infos
+                    .iter()
+                    .find(|info| info.key == &meta.pubkey)
+                    .unwrap()
+                    .clone()
+            })
+            .collect();
+        *RETURN_DATA.lock().unwrap() = None;
+        nyse_token_hook::entry(
+            &instruction.program_id,
+            Vec::leak(accounts),
+            &instruction.data,
+        )
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: *UNIX_TIMESTAMP.lock().unwrap(),
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        0
+    }
+
+    fn sol_set_return_data(&self, data: &[u8]) {
+        *RETURN_DATA.lock().unwrap() = Some((nyse_token_hook::ID, data.to_vec()));
+    }
+
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        RETURN_DATA.lock().unwrap().clone()
+    }
+}
+
+// The caller: CPI into the hook and decode its typed verdict
+fn try_query_hook<'info>(
+    hook_program: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    market_config: Option<&AccountInfo<'info>>,
+) -> std::result::Result<MarketState, ProgramError> {
+    let instruction = Instruction {
+        program_id: nyse_token_hook::ID,
+        accounts: nyse_token_hook::accounts::CheckMarketState {
+            mint: *mint.key,
+            market_config: market_config.map(|config| *config.key),
+            holiday_calendar: None,
+        }
+        .to_account_metas(None),
+        data: nyse_token_hook::instruction::QueryMarketState {}.data(),
+    };
+    let mut infos = vec![hook_program.clone(), mint.clone()];
+    infos.extend(market_config.cloned());
+    invoke_signed(&instruction, &infos, &[])?;
+
+    let (program_id, data) = get_return_data().unwrap();
+    assert_eq!(program_id, nyse_token_hook::ID);
+    Ok(MarketState::try_from_slice(&data).unwrap())
+}
+
+fn query_hook<'info>(hook_program: &AccountInfo<'info>, mint: &AccountInfo<'info>) -> MarketState {
+    try_query_hook(hook_program, mint, None).unwrap()
+}
+
+// A config that trades around the clock on weekdays, as stored on chain
+fn continuous_config() -> Vec<u8> {
+    let mut data = MarketConfig::DISCRIMINATOR.to_vec();
+    data.resize(8 + MarketConfig::INIT_SPACE, 0);
+    let mut config = MarketConfig::try_deserialize(&mut &data[..]).unwrap();
+    config.session_mode = SessionMode::Continuous;
+    config.try_serialize(&mut &mut data[..]).unwrap();
+    data
+}
+
+#[test]
+fn caller_program_reads_the_verdict() {
+    let _serial = SERIAL.lock().unwrap();
+    set_syscall_stubs(Box::new(Runtime));
+
+    let mut lamports = 1;
+    let hook_key = nyse_token_hook::ID;
+    let loader = Pubkey::default();
+    let hook_program = AccountInfo::new(
+        &hook_key,
+        false,
+        false,
+        &mut lamports,
+        &mut [],
+        &loader,
+        true,
+        0,
+    );
+    let mint_key = Pubkey::new_unique();
+    let mut mint_lamports = 1;
+    let mint = AccountInfo::new(
+        &mint_key,
+        false,
+        false,
+        &mut mint_lamports,
+        &mut [],
+        &loader,
+        false,
+        0,
+    );
+
+    // Tuesday 2024-01-09, 09:30:00 EST and an hour before the open
+    *UNIX_TIMESTAMP.lock().unwrap() = 1_704_810_600;
+    let state = query_hook(&hook_program, &mint);
+    assert!(state.is_open);
+    assert_eq!(state.status, MarketStatus::Open);
+    assert_eq!(state.next_transition, 1_704_834_000);
+
+    *UNIX_TIMESTAMP.lock().unwrap() = 1_704_810_600 - 3600;
+    let state = query_hook(&hook_program, &mint);
+    assert!(!state.is_open);
+    assert_eq!(state.status, MarketStatus::AfterHours);
+}
+
+#[test]
+fn caller_program_refuses_another_mints_config() {
+    let _serial = SERIAL.lock().unwrap();
+    set_syscall_stubs(Box::new(Runtime));
+
+    let mut lamports = 1;
+    let hook_key = nyse_token_hook::ID;
+    let loader = Pubkey::default();
+    let hook_program = AccountInfo::new(
+        &hook_key,
+        false,
+        false,
+        &mut lamports,
+        &mut [],
+        &loader,
+        true,
+        0,
+    );
+    let mint_key = Pubkey::new_unique();
+    let mut mint_lamports = 1;
+    let mint = AccountInfo::new(
+        &mint_key,
+        false,
+        false,
+        &mut mint_lamports,
+        &mut [],
+        &loader,
+        false,
+        0,
+    );
+    // An hour before Tuesday's open, closed under the NYSE defaults
+    *UNIX_TIMESTAMP.lock().unwrap() = 1_704_810_600 - 3600;
+
+    // A continuous config at another mint's address would open the gate
+    let foreign_key = market_config_pda(&Pubkey::new_unique()).0;
+    let mut foreign_lamports = 1;
+    let mut foreign_data = continuous_config();
+    let foreign = AccountInfo::new(
+        &foreign_key,
+        false,
+        false,
+        &mut foreign_lamports,
+        &mut foreign_data,
+        &hook_key,
+        false,
+        0,
+    );
+    assert_eq!(
+        try_query_hook(&hook_program, &mint, Some(&foreign)).unwrap_err(),
+        ProgramError::Custom(ErrorCode::ConstraintSeeds.into())
+    );
+
+    // The same config at this mint's own address applies
+    let own_key = market_config_pda(&mint_key).0;
+    let mut own_lamports = 1;
+    let mut own_data = continuous_config();
+    let own = AccountInfo::new(
+        &own_key,
+        false,
+        false,
+        &mut own_lamports,
+        &mut own_data,
+        &hook_key,
+        false,
+        0,
+    );
+    let state = try_query_hook(&hook_program, &mint, Some(&own)).unwrap();
+    assert!(state.is_open);
+}